@@ -0,0 +1,381 @@
+//! ゲーム音声の字幕化（WASAPI ループバック → whisper.cpp）
+//!
+//! 既定の再生デバイスの音をループバックで録り、音量で発話の区切りを見つけて、1発話ずつ
+//! whisper.cpp の `whisper-cli` で文字に起こす。字幕のないボイス付きムービーなど、OCR では
+//! 読めない台詞用。デバイス全体の音を録るので、ゲーム以外の音（通話・動画など）も拾う。
+
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use tokio::process::Command;
+use tokio::sync::mpsc;
+use windows::Win32::Media::Audio::*;
+use windows::Win32::System::Com::*;
+use windows::Win32::System::Threading::CREATE_NO_WINDOW;
+
+use crate::config::AppConfig;
+use crate::lang::Language;
+
+/// Whisper の入力（16kHz モノラル）
+const SAMPLE_RATE: u32 = 16_000;
+/// 発話かどうかを判定する単位
+const FRAME_SAMPLES: usize = SAMPLE_RATE as usize * 30 / 1000;
+/// これより大きい RMS を声（音）とみなす
+const SPEECH_RMS: f32 = 0.01;
+/// この長さ静かになったら発話の終わり
+const END_SILENCE_FRAMES: usize = 700 / 30;
+/// これより短い音（効果音など）は起こさない
+const MIN_SPEECH_FRAMES: usize = 400 / 30;
+/// 長い発話はここで区切る（字幕が出るまで待たせすぎないように）
+const MAX_SPEECH_FRAMES: usize = 15_000 / 30;
+/// ループバックのバッファを読みに行く間隔
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+/// whisper-cli 1回の制限時間
+const TRANSCRIBE_TIMEOUT: Duration = Duration::from_secs(60);
+
+const WAVE_FORMAT_IEEE_FLOAT: u16 = 3;
+const WAVE_FORMAT_EXTENSIBLE: u16 = 0xFFFE;
+
+/// whisper.cpp の実行ファイルとモデル
+pub struct Whisper {
+    command: PathBuf,
+    model: PathBuf,
+    /// "en", "ja" など（"auto" = 自動検出）
+    language: String,
+    /// 発話を渡す一時ファイル（セッションごとに別）
+    wav_path: PathBuf,
+}
+
+/// ソース言語コードに対応する Whisper の言語（自動検出・不明な言語は "auto"）
+fn whisper_language(source_lang: &str) -> String {
+    match Language::parse(source_lang) {
+        Some(Language::Auto) | None => "auto".to_string(),
+        Some(l) => l.code().split('-').next().unwrap_or_default().to_lowercase(),
+    }
+}
+
+impl Whisper {
+    pub fn from_config(config: &AppConfig) -> Result<Self> {
+        static SESSION: AtomicU64 = AtomicU64::new(0);
+        let command = PathBuf::from(config.whisper_command.trim());
+        let model = PathBuf::from(config.whisper_model.trim());
+        anyhow::ensure!(command.is_file(), "whisper-cli not found: {}", command.display());
+        anyhow::ensure!(model.is_file(), "Whisper model not found: {}", model.display());
+        let wav_path = std::env::temp_dir().join(format!(
+            "game_translator_{}_{}.wav",
+            std::process::id(),
+            SESSION.fetch_add(1, Ordering::Relaxed)
+        ));
+        Ok(Self { command, model, language: whisper_language(&config.source_lang), wav_path })
+    }
+
+    /// 16kHz モノラルの発話を文字に起こす（何も聞き取れなければ空）
+    async fn transcribe(&self, samples: &[f32]) -> Result<String> {
+        // タイムアウト・起動失敗で途中で抜けても一時ファイルを残さない
+        struct TempWav<'a>(&'a Path);
+        impl Drop for TempWav<'_> {
+            fn drop(&mut self) {
+                let _ = std::fs::remove_file(self.0);
+            }
+        }
+        std::fs::write(&self.wav_path, wav_bytes(samples)).context("Failed to write audio")?;
+        let _wav = TempWav(&self.wav_path);
+        let output = Command::new(&self.command)
+            .arg("-m")
+            .arg(&self.model)
+            .arg("-f")
+            .arg(&self.wav_path)
+            .args(["-l", &self.language, "-nt", "-np"])
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .creation_flags(CREATE_NO_WINDOW.0)
+            .kill_on_drop(true)
+            .output();
+        let output = tokio::time::timeout(TRANSCRIBE_TIMEOUT, output)
+            .await
+            .context("whisper-cli timed out")?
+            .with_context(|| format!("Failed to start {}", self.command.display()))?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            anyhow::bail!("whisper-cli failed ({}): {}", output.status, stderr.lines().last().unwrap_or_default());
+        }
+        Ok(clean_transcript(&String::from_utf8_lossy(&output.stdout)))
+    }
+}
+
+/// 行をつなげ、[BLANK_AUDIO]・(音楽) のような音の注記を除く
+fn clean_transcript(text: &str) -> String {
+    let mut cleaned = String::new();
+    let mut depth = 0;
+    for c in text.chars() {
+        match c {
+            '[' | '(' | '（' => depth += 1,
+            ']' | ')' | '）' if depth > 0 => depth -= 1,
+            _ if depth == 0 => cleaned.push(if c == '\n' || c == '\r' { ' ' } else { c }),
+            _ => {}
+        }
+    }
+    cleaned.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// 16bit PCM の WAV ファイル
+fn wav_bytes(samples: &[f32]) -> Vec<u8> {
+    let data_len = (samples.len() * 2) as u32;
+    let mut out = Vec::with_capacity(44 + data_len as usize);
+    out.extend_from_slice(b"RIFF");
+    out.extend_from_slice(&(36 + data_len).to_le_bytes());
+    out.extend_from_slice(b"WAVEfmt ");
+    out.extend_from_slice(&16u32.to_le_bytes());
+    out.extend_from_slice(&1u16.to_le_bytes()); // PCM
+    out.extend_from_slice(&1u16.to_le_bytes()); // mono
+    out.extend_from_slice(&SAMPLE_RATE.to_le_bytes());
+    out.extend_from_slice(&(SAMPLE_RATE * 2).to_le_bytes());
+    out.extend_from_slice(&2u16.to_le_bytes());
+    out.extend_from_slice(&16u16.to_le_bytes());
+    out.extend_from_slice(b"data");
+    out.extend_from_slice(&data_len.to_le_bytes());
+    for s in samples {
+        out.extend_from_slice(&((s.clamp(-1.0, 1.0) * i16::MAX as f32) as i16).to_le_bytes());
+    }
+    out
+}
+
+/// 入力のサンプルを平均して 16kHz に落とす（音声認識には十分）
+struct Downsampler {
+    rate: u32,
+    phase: u32,
+    sum: f32,
+    count: u32,
+}
+
+impl Downsampler {
+    fn push(&mut self, sample: f32, out: &mut Vec<f32>) {
+        self.sum += sample;
+        self.count += 1;
+        self.phase += SAMPLE_RATE;
+        if self.phase >= self.rate {
+            self.phase -= self.rate;
+            out.push(self.sum / self.count as f32);
+            self.sum = 0.0;
+            self.count = 0;
+        }
+    }
+}
+
+/// 音量で発話を切り出す
+#[derive(Default)]
+struct Segmenter {
+    frame: Vec<f32>,
+    /// 発話の直前の静かなフレーム（語頭を切らないように足す）
+    lead_in: Vec<f32>,
+    speech: Vec<f32>,
+    speech_frames: usize,
+    silent_frames: usize,
+}
+
+impl Segmenter {
+    /// 発話が終わったらその音を返す
+    fn push(&mut self, sample: f32) -> Option<Vec<f32>> {
+        self.frame.push(sample);
+        if self.frame.len() < FRAME_SAMPLES {
+            return None;
+        }
+        let frame = std::mem::take(&mut self.frame);
+        let rms = (frame.iter().map(|s| s * s).sum::<f32>() / frame.len() as f32).sqrt();
+        let in_speech = self.speech_frames > 0;
+        if rms >= SPEECH_RMS {
+            if !in_speech {
+                self.speech = std::mem::take(&mut self.lead_in);
+            }
+            self.speech.extend_from_slice(&frame);
+            self.speech_frames += 1;
+            self.silent_frames = 0;
+        } else if in_speech {
+            self.speech.extend_from_slice(&frame);
+            self.silent_frames += 1;
+        } else {
+            self.lead_in = frame;
+            return None;
+        }
+        if self.silent_frames < END_SILENCE_FRAMES && self.speech_frames < MAX_SPEECH_FRAMES {
+            return None;
+        }
+        let speech_frames = std::mem::take(&mut self.speech_frames);
+        self.silent_frames = 0;
+        let speech = std::mem::take(&mut self.speech);
+        (speech_frames >= MIN_SPEECH_FRAMES).then_some(speech)
+    }
+}
+
+/// 既定の再生デバイスをループバックで録り、発話ごとに `utterances` へ送る（受け手がいなくなるまで）
+fn record_loopback(utterances: mpsc::Sender<Vec<f32>>) -> Result<()> {
+    unsafe {
+        let _ = CoInitializeEx(None, COINIT_MULTITHREADED);
+    }
+    struct ComGuard;
+    impl Drop for ComGuard {
+        fn drop(&mut self) {
+            unsafe { CoUninitialize(); }
+        }
+    }
+    let _com_guard = ComGuard;
+
+    let (client, capture, format) = unsafe {
+        let enumerator: IMMDeviceEnumerator = CoCreateInstance(&MMDeviceEnumerator, None, CLSCTX_ALL)
+            .context("Failed to create the audio device enumerator")?;
+        let device = enumerator
+            .GetDefaultAudioEndpoint(eRender, eConsole)
+            .context("No audio output device")?;
+        let client: IAudioClient = device.Activate(CLSCTX_ALL, None).context("Failed to open the audio device")?;
+        let mix_format = client.GetMixFormat().context("GetMixFormat failed")?;
+        let format = *mix_format;
+        let is_float = format.wFormatTag == WAVE_FORMAT_IEEE_FLOAT
+            || (format.wFormatTag == WAVE_FORMAT_EXTENSIBLE
+                && (*(mix_format as *const WAVEFORMATEXTENSIBLE)).SubFormat.data1 == WAVE_FORMAT_IEEE_FLOAT as u32);
+        let initialized = client.Initialize(
+            AUDCLNT_SHAREMODE_SHARED,
+            AUDCLNT_STREAMFLAGS_LOOPBACK,
+            10_000_000, // 1s buffer, in 100ns units
+            0,
+            mix_format,
+            None,
+        );
+        CoTaskMemFree(Some(mix_format as *const _));
+        initialized.context("Failed to start loopback capture")?;
+        let capture: IAudioCaptureClient = client.GetService().context("GetService failed")?;
+        client.Start().context("Failed to start loopback capture")?;
+        (client, capture, (format, is_float))
+    };
+    let (format, is_float) = format;
+    let channels = format.nChannels.max(1) as usize;
+    let block_align = format.nBlockAlign as usize;
+    let bits = format.wBitsPerSample;
+    anyhow::ensure!(is_float || bits == 16, "Unsupported audio format ({} bit)", bits);
+    crate::log_always(&format!(
+        "[AUDIO] ループバック録音を開始 ({}Hz, {}ch, {}bit)",
+        { format.nSamplesPerSec },
+        channels,
+        bits
+    ));
+
+    let mut downsampler = Downsampler { rate: format.nSamplesPerSec.max(1), phase: 0, sum: 0.0, count: 0 };
+    let mut segmenter = Segmenter::default();
+    let mut samples = Vec::new();
+    while !utterances.is_closed() {
+        std::thread::sleep(POLL_INTERVAL);
+        samples.clear();
+        let mut received = false;
+        unsafe {
+            while capture.GetNextPacketSize().context("Loopback capture failed")? > 0 {
+                let mut data = std::ptr::null_mut();
+                let mut frames = 0;
+                let mut flags = 0;
+                capture
+                    .GetBuffer(&mut data, &mut frames, &mut flags, None, None)
+                    .context("Loopback capture failed")?;
+                let silent = flags & AUDCLNT_BUFFERFLAGS_SILENT.0 as u32 != 0;
+                let bytes = std::slice::from_raw_parts(data, frames as usize * block_align);
+                for frame in bytes.chunks_exact(block_align) {
+                    let mono = if silent {
+                        0.0
+                    } else if is_float {
+                        frame.chunks_exact(4).take(channels).map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]])).sum::<f32>()
+                            / channels as f32
+                    } else {
+                        frame.chunks_exact(2).take(channels).map(|b| i16::from_le_bytes([b[0], b[1]]) as f32 / 32768.0).sum::<f32>()
+                            / channels as f32
+                    };
+                    downsampler.push(mono, &mut samples);
+                }
+                capture.ReleaseBuffer(frames).context("Loopback capture failed")?;
+                received = true;
+            }
+        }
+        // 何も再生されていない間はパケットが来ないので、その分を無音として数える
+        if !received {
+            samples.resize(SAMPLE_RATE as usize * POLL_INTERVAL.as_millis() as usize / 1000, 0.0);
+        }
+        for &sample in &samples {
+            if let Some(speech) = segmenter.push(sample) {
+                if utterances.blocking_send(speech).is_err() {
+                    break;
+                }
+            }
+        }
+    }
+    unsafe {
+        let _ = client.Stop();
+    }
+    Ok(())
+}
+
+/// ループバックの発話を文字に起こし、`lines` へ送り続ける（受け手がいなくなるまで）
+pub async fn transcribe_loopback(whisper: Whisper, lines: mpsc::Sender<String>) -> Result<()> {
+    let (utterances_tx, mut utterances) = mpsc::channel(4);
+    let recorder = std::thread::spawn(move || record_loopback(utterances_tx));
+    while let Some(speech) = utterances.recv().await {
+        let seconds = speech.len() as f32 / SAMPLE_RATE as f32;
+        // 1つの発話の失敗（タイムアウト等）では止めず、その発話だけ飛ばして聞き続ける
+        let text = match whisper.transcribe(&speech).await {
+            Ok(text) => text,
+            Err(e) => {
+                crate::log_always(&format!("[AUDIO] {:.1}s の発話を文字にできません: {:#}", seconds, e));
+                continue;
+            }
+        };
+        crate::log(&format!("[AUDIO] {:.1}s → \"{}\"", seconds, text));
+        if !text.is_empty() && lines.send(text).await.is_err() {
+            return Ok(());
+        }
+    }
+    // 録音スレッドが終わった（エラーで止まった）
+    match recorder.join() {
+        Ok(result) => result,
+        Err(_) => anyhow::bail!("Loopback capture thread panicked"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `frames` frames at a constant level; returns the length in frames of each utterance cut
+    fn feed(segmenter: &mut Segmenter, level: f32, frames: usize) -> Vec<usize> {
+        (0..frames * FRAME_SAMPLES)
+            .filter_map(|_| segmenter.push(level))
+            .map(|speech| speech.len() / FRAME_SAMPLES)
+            .collect()
+    }
+
+    #[test]
+    fn clean_transcript_drops_sound_annotations() {
+        assert_eq!(clean_transcript(" [BLANK_AUDIO]\n Hello (music) there\r\n（拍手）world "), "Hello there world");
+        assert_eq!(clean_transcript("[BLANK_AUDIO]"), "");
+    }
+
+    #[test]
+    fn utterance_ends_after_silence() {
+        let mut segmenter = Segmenter::default();
+        assert!(feed(&mut segmenter, 0.0, 5).is_empty());
+        assert!(feed(&mut segmenter, 0.1, 20).is_empty());
+        // One frame of lead-in, the speech and the trailing silence
+        assert_eq!(feed(&mut segmenter, 0.0, 30), vec![1 + 20 + END_SILENCE_FRAMES]);
+    }
+
+    #[test]
+    fn short_noises_are_dropped() {
+        let mut segmenter = Segmenter::default();
+        assert!(feed(&mut segmenter, 0.1, MIN_SPEECH_FRAMES - 1).is_empty());
+        assert!(feed(&mut segmenter, 0.0, 30).is_empty());
+    }
+
+    #[test]
+    fn long_speech_is_split() {
+        let mut segmenter = Segmenter::default();
+        assert_eq!(feed(&mut segmenter, 0.1, MAX_SPEECH_FRAMES + 1), vec![MAX_SPEECH_FRAMES]);
+    }
+}
@@ -0,0 +1,292 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+
+use crate::config::{AppConfig, TranslationEngine};
+use crate::lang::Language;
+
+/// キャッシュファイルのフォーマットバージョン
+const CACHE_VERSION: u32 = 2;
+
+/// 翻訳結果が有効な範囲（エンジン + 言語ペア）
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CacheScope {
+    /// None = ファン翻訳等から取り込んだ人手翻訳（全エンジン共通）
+    pub engine: Option<TranslationEngine>,
+    /// エンジンが Plugin の時のプラグイン名（プラグインごとに訳を分ける。それ以外は空）
+    pub plugin: String,
+    pub source_lang: String,
+    pub target_lang: String,
+}
+
+impl CacheScope {
+    pub fn new(engine: TranslationEngine, source_lang: &str, target_lang: &str) -> Self {
+        Self::with_engine(Some(engine), "", source_lang, target_lang)
+    }
+
+    /// 設定のエンジン（プラグインなら選択中のプラグイン）と言語ペアのスコープ
+    pub fn from_config(config: &AppConfig) -> Self {
+        Self::with_engine(
+            Some(config.translation_engine),
+            &config.plugin_name,
+            &config.source_lang,
+            &config.target_lang,
+        )
+    }
+
+    /// 人手翻訳（シード）用のスコープ
+    pub fn seed(source_lang: &str, target_lang: &str) -> Self {
+        Self::with_engine(None, "", source_lang, target_lang)
+    }
+
+    fn with_engine(engine: Option<TranslationEngine>, plugin: &str, source_lang: &str, target_lang: &str) -> Self {
+        // "en-US" と "EN" 等の表記揺れを同じスコープにまとめる
+        let normalize = |s: &str| {
+            Language::parse(s)
+                .map(|l| l.code().to_string())
+                .unwrap_or_else(|| s.trim().to_uppercase())
+        };
+        let plugin = match engine {
+            Some(TranslationEngine::Plugin) => plugin.trim().to_string(),
+            _ => String::new(),
+        };
+        Self {
+            engine,
+            plugin,
+            source_lang: normalize(source_lang),
+            target_lang: normalize(target_lang),
+        }
+    }
+
+    fn engine_name(&self) -> &'static str {
+        self.engine.map_or("", |e| e.as_str())
+    }
+}
+
+/// キャッシュの1エントリ（ファイル保存・エクスポート用のフラット表現）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CacheEntry {
+    /// None = 人手翻訳（シード）
+    #[serde(default)]
+    pub engine: Option<TranslationEngine>,
+    /// エンジンが Plugin の時のプラグイン名
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub plugin: String,
+    pub source_lang: String,
+    pub target_lang: String,
+    pub text: String,
+    pub translation: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CacheFile {
+    version: u32,
+    entries: Vec<CacheEntry>,
+}
+
+/// エンジン・言語ペアごとに分離された翻訳キャッシュ。
+/// 複数のセッションや事前翻訳が同時に同じファイルを使うので、保存時はファイルの最新の内容に
+/// 自分が追加・変更したエントリだけを重ねる（他の書き込みを消さない）。
+#[derive(Default)]
+pub struct TranslationCache {
+    scopes: HashMap<CacheScope, HashMap<String, String>>,
+    /// 前回の読み込み・保存から追加・変更したエントリ
+    changed: HashSet<(CacheScope, String)>,
+}
+
+/// 同じプロセス内の保存（読み直し → 書き込み）を1つずつにする
+static SAVE_LOCK: Mutex<()> = Mutex::new(());
+
+/// 保存先ファイルの内容
+enum Stored {
+    /// 現行形式（ファイルがなければ空）
+    Scopes(HashMap<CacheScope, HashMap<String, String>>),
+    /// 旧形式（原文→訳文のフラットなマップ）
+    Legacy(HashMap<String, String>),
+    /// 解釈できない（壊れたファイル）
+    Corrupt,
+}
+
+fn cache_file_path() -> &'static PathBuf {
+    static PATH: OnceLock<PathBuf> = OnceLock::new();
+    PATH.get_or_init(|| crate::config::data_dir().join("translation_cache.json"))
+}
+
+impl TranslationCache {
+    /// キャッシュを読み込む。
+    /// 旧形式（原文→訳文のフラットなマップ）は `legacy_scope` に属するものとして移行する。
+    pub fn load(legacy_scope: &CacheScope) -> Self {
+        let mut cache = Self::default();
+        match Self::read_stored(cache_file_path()) {
+            Ok(Stored::Scopes(scopes)) => cache.scopes = scopes,
+            Ok(Stored::Legacy(legacy)) => {
+                crate::log_always(&format!(
+                    "[CACHE] 旧形式キャッシュを移行: {}件 ({} {}→{})",
+                    legacy.len(),
+                    legacy_scope.engine_name(),
+                    legacy_scope.source_lang,
+                    legacy_scope.target_lang
+                ));
+                for (text, translation) in legacy {
+                    cache.insert(legacy_scope, text, translation);
+                }
+                cache.save();
+            }
+            // 次の保存で .broken に退避してから書き直す
+            Ok(Stored::Corrupt) => crate::log_always("[CACHE] キャッシュファイルが壊れているため読み込めません"),
+            Err(e) => crate::log_always(&format!("[CACHE] キャッシュファイルを読み込めません: {}", e)),
+        }
+        cache
+    }
+
+    /// ファイルがなければ空。読み取りエラーは Err（空として上書きするとキャッシュが消えるため）
+    fn read_stored(path: &Path) -> std::io::Result<Stored> {
+        let data = match std::fs::read_to_string(path) {
+            Ok(data) => data,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Stored::Scopes(HashMap::new())),
+            // UTF-8 として読めない
+            Err(e) if e.kind() == ErrorKind::InvalidData => return Ok(Stored::Corrupt),
+            Err(e) => return Err(e),
+        };
+        Ok(if let Some(scopes) = Self::parse(&data) {
+            Stored::Scopes(scopes)
+        } else if let Ok(legacy) = serde_json::from_str::<HashMap<String, String>>(&data) {
+            Stored::Legacy(legacy)
+        } else {
+            Stored::Corrupt
+        })
+    }
+
+    fn parse(data: &str) -> Option<HashMap<CacheScope, HashMap<String, String>>> {
+        let file = serde_json::from_str::<CacheFile>(data).ok()?;
+        let mut scopes: HashMap<CacheScope, HashMap<String, String>> = HashMap::new();
+        for e in file.entries {
+            let scope = CacheScope::with_engine(e.engine, &e.plugin, &e.source_lang, &e.target_lang);
+            scopes.entry(scope).or_default().insert(e.text, e.translation);
+        }
+        Some(scopes)
+    }
+
+    /// ファイルを読み直して変更分を重ね、一時ファイル経由で置き換える（書きかけのファイルを残さない）。
+    /// 読み直した内容は以後このキャッシュからも引ける。
+    /// 読み直せない時は書き込まず、変更分は次の保存に持ち越す。
+    pub fn save(&mut self) {
+        let _guard = SAVE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let path = cache_file_path();
+        let mut merged = match Self::read_stored(path) {
+            Ok(Stored::Scopes(scopes)) => scopes,
+            // 旧形式の内容は読み込み時に変更分として移行済み
+            Ok(Stored::Legacy(_)) => HashMap::new(),
+            Ok(Stored::Corrupt) => {
+                let broken = path.with_extension("json.broken");
+                if let Err(e) = std::fs::rename(path, &broken) {
+                    crate::log_always(&format!("[CACHE] 壊れたキャッシュファイルを退避できないため保存しません: {}", e));
+                    return;
+                }
+                crate::log_always(&format!("[CACHE] 壊れたキャッシュファイルを {} に退避しました", broken.display()));
+                HashMap::new()
+            }
+            Err(e) => {
+                crate::log_always(&format!("[CACHE] キャッシュファイルを読み直せないため保存を見送ります: {}", e));
+                return;
+            }
+        };
+        for (scope, text) in &self.changed {
+            if let Some(translation) = self.scopes.get(scope).and_then(|m| m.get(text)) {
+                merged.entry(scope.clone()).or_default().insert(text.clone(), translation.clone());
+            }
+        }
+        self.scopes = merged;
+
+        let file = CacheFile {
+            version: CACHE_VERSION,
+            entries: self.entries(),
+        };
+        let Ok(json) = serde_json::to_string(&file) else {
+            return;
+        };
+        let tmp = path.with_extension("json.tmp");
+        match std::fs::write(&tmp, json).and_then(|_| std::fs::rename(&tmp, path)) {
+            Ok(()) => self.changed.clear(),
+            Err(e) => crate::log_always(&format!("[CACHE] 保存できません: {}", e)),
+        }
+    }
+
+    /// 全エントリをフラットなリストとして取得
+    pub fn entries(&self) -> Vec<CacheEntry> {
+        let mut entries: Vec<CacheEntry> = self
+            .scopes
+            .iter()
+            .flat_map(|(scope, map)| {
+                map.iter().map(move |(text, translation)| CacheEntry {
+                    engine: scope.engine,
+                    plugin: scope.plugin.clone(),
+                    source_lang: scope.source_lang.clone(),
+                    target_lang: scope.target_lang.clone(),
+                    text: text.clone(),
+                    translation: translation.clone(),
+                })
+            })
+            .collect();
+        entries.sort_by(|a, b| {
+            let key = |e: &CacheEntry| e.engine.map_or("", |en| en.as_str());
+            (key(a), &a.plugin, &a.source_lang, &a.target_lang, &a.text)
+                .cmp(&(key(b), &b.plugin, &b.source_lang, &b.target_lang, &b.text))
+        });
+        entries
+    }
+
+    /// エントリを追加（既存の訳文は上書き）
+    pub fn insert_entry(&mut self, entry: CacheEntry) {
+        let scope = CacheScope::with_engine(entry.engine, &entry.plugin, &entry.source_lang, &entry.target_lang);
+        self.insert(&scope, entry.text, entry.translation);
+    }
+
+    /// 訳文を取得。同じ言語ペアの人手翻訳（シード）があればエンジンの訳より優先する。
+    pub fn get(&self, scope: &CacheScope, text: &str) -> Option<&String> {
+        let seed = CacheScope::seed(&scope.source_lang, &scope.target_lang);
+        self.scopes
+            .get(&seed)
+            .and_then(|m| m.get(text))
+            .or_else(|| self.scopes.get(scope).and_then(|m| m.get(text)))
+    }
+
+    /// 同じ言語ペアの人手翻訳（シード・履歴で固定した訳）があるか。あれば API の結果で上書きしない。
+    pub fn is_pinned(&self, scope: &CacheScope, text: &str) -> bool {
+        let seed = CacheScope::seed(&scope.source_lang, &scope.target_lang);
+        self.scopes.get(&seed).is_some_and(|m| m.contains_key(text))
+    }
+
+    pub fn contains(&self, scope: &CacheScope, text: &str) -> bool {
+        self.get(scope, text).is_some()
+    }
+
+    pub fn insert(&mut self, scope: &CacheScope, text: String, translation: String) {
+        self.changed.insert((scope.clone(), text.clone()));
+        self.scopes
+            .entry(scope.clone())
+            .or_default()
+            .insert(text, translation);
+    }
+
+    /// 全スコープの合計エントリ数
+    pub fn len(&self) -> usize {
+        self.scopes.values().map(|m| m.len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.scopes.values().all(|m| m.is_empty())
+    }
+
+    /// 指定スコープで参照可能なエントリ数（シードを含む）
+    pub fn scope_len(&self, scope: &CacheScope) -> usize {
+        let seed = CacheScope::seed(&scope.source_lang, &scope.target_lang);
+        let own = self.scopes.get(scope).map_or(0, |m| m.len());
+        if seed == *scope {
+            return own;
+        }
+        own + self.scopes.get(&seed).map_or(0, |m| m.len())
+    }
+}
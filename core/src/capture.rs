@@ -0,0 +1,433 @@
+use anyhow::{Context, Result};
+use std::mem;
+use std::os::windows::ffi::OsStrExt;
+use std::path::Path;
+use windows::Win32::Foundation::*;
+use windows::Win32::Graphics::Gdi::*;
+use windows::Win32::Graphics::Imaging::*;
+use windows::Win32::Storage::Xps::*;
+use windows::Win32::System::Com::{CoCreateInstance, CLSCTX_INPROC_SERVER, STATFLAG_NONAME, STATSTG, STREAM_SEEK_SET};
+use windows::Win32::UI::Shell::SHCreateMemStream;
+use windows::Win32::System::Threading::{
+    OpenProcess, QueryFullProcessImageNameW, PROCESS_NAME_WIN32, PROCESS_QUERY_LIMITED_INFORMATION,
+};
+use windows::Win32::UI::WindowsAndMessaging::*;
+use windows::core::{BOOL, PCWSTR};
+
+/// 対象ウィンドウのPrintWindowキャプチャ（DIB永続化版）
+pub struct WindowCapture {
+    target_hwnd: HWND,
+    width: u32,
+    height: u32,
+    memory_dc: HDC,
+    bitmap: HBITMAP,
+    old_bitmap: HGDIOBJ,
+    bits: *mut u8,
+}
+
+impl WindowCapture {
+    pub fn new(target_hwnd: HWND) -> Result<Self> {
+        Ok(Self {
+            target_hwnd,
+            width: 0,
+            height: 0,
+            memory_dc: HDC::default(),
+            bitmap: HBITMAP::default(),
+            old_bitmap: HGDIOBJ::default(),
+            bits: std::ptr::null_mut(),
+        })
+    }
+
+    /// (Re)create the DIB section and memory DC for the given dimensions.
+    fn ensure_dib(&mut self, width: u32, height: u32) -> Result<()> {
+        if self.width == width && self.height == height && !self.memory_dc.is_invalid() {
+            return Ok(());
+        }
+
+        // Free previous resources
+        self.free_dib();
+
+        unsafe {
+            let window_dc = GetDC(Some(self.target_hwnd));
+            if window_dc.is_invalid() {
+                anyhow::bail!("GetDC failed for target window");
+            }
+            let memory_dc = CreateCompatibleDC(Some(window_dc));
+            ReleaseDC(Some(self.target_hwnd), window_dc);
+            if memory_dc.is_invalid() {
+                anyhow::bail!("CreateCompatibleDC failed");
+            }
+
+            let bmi = BITMAPINFO {
+                bmiHeader: BITMAPINFOHEADER {
+                    biSize: mem::size_of::<BITMAPINFOHEADER>() as u32,
+                    biWidth: width as i32,
+                    biHeight: -(height as i32), // top-down
+                    biPlanes: 1,
+                    biBitCount: 32,
+                    biCompression: BI_RGB.0,
+                    ..Default::default()
+                },
+                ..Default::default()
+            };
+
+            let mut bits_ptr: *mut core::ffi::c_void = std::ptr::null_mut();
+            let bitmap = CreateDIBSection(
+                Some(memory_dc),
+                &bmi,
+                DIB_RGB_COLORS,
+                &mut bits_ptr,
+                None,
+                0,
+            )?;
+
+            let old_bitmap = SelectObject(memory_dc, HGDIOBJ(bitmap.0));
+
+            self.memory_dc = memory_dc;
+            self.bitmap = bitmap;
+            self.old_bitmap = old_bitmap;
+            self.bits = bits_ptr as *mut u8;
+            self.width = width;
+            self.height = height;
+        }
+
+        Ok(())
+    }
+
+    fn free_dib(&mut self) {
+        unsafe {
+            if !self.memory_dc.is_invalid() {
+                SelectObject(self.memory_dc, self.old_bitmap);
+                let _ = DeleteObject(HGDIOBJ(self.bitmap.0));
+                let _ = DeleteDC(self.memory_dc);
+                self.memory_dc = HDC::default();
+                self.bitmap = HBITMAP::default();
+                self.old_bitmap = HGDIOBJ::default();
+                self.bits = std::ptr::null_mut();
+            }
+        }
+    }
+
+    pub fn capture_frame(&mut self) -> Result<Option<Vec<u8>>> {
+        unsafe {
+            // 対象ウィンドウのクライアント領域サイズを取得
+            let mut rect = RECT::default();
+            GetClientRect(self.target_hwnd, &mut rect)?;
+
+            let width = (rect.right - rect.left) as u32;
+            let height = (rect.bottom - rect.top) as u32;
+
+            if width == 0 || height == 0 {
+                return Ok(None);
+            }
+
+            // Recreate DIB only when dimensions change
+            self.ensure_dib(width, height)?;
+
+            // PrintWindowでウィンドウ内容をキャプチャ（DXゲーム対応）
+            let window_dc = GetDC(Some(self.target_hwnd));
+            let pw_result = PrintWindow(self.target_hwnd, self.memory_dc, PRINT_WINDOW_FLAGS(2)); // PW_RENDERFULLCONTENT = 2
+
+            if !pw_result.as_bool() && !window_dc.is_invalid() {
+                // PrintWindow失敗時はBitBltにフォールバック
+                let _ = BitBlt(self.memory_dc, 0, 0, width as i32, height as i32, Some(window_dc), 0, 0, SRCCOPY);
+            }
+            if !window_dc.is_invalid() {
+                ReleaseDC(Some(self.target_hwnd), window_dc);
+            }
+
+            // ピクセルデータをコピー
+            let data_size = (width * height * 4) as usize;
+            let mut pixel_data = vec![0u8; data_size];
+            std::ptr::copy_nonoverlapping(self.bits, pixel_data.as_mut_ptr(), data_size);
+
+            Ok(Some(pixel_data))
+        }
+    }
+
+    /// 指定領域の平均相対輝度 (0.0〜1.0) をキャプチャ済みBGRAフレームから求める。
+    /// 全画素を見る必要はないので4px間隔で間引いてサンプリングする。
+    pub fn sample_luminance(frame: &[u8], width: u32, height: u32, x: i32, y: i32, w: i32, h: i32) -> Option<f32> {
+        let x0 = x.clamp(0, width as i32) as usize;
+        let y0 = y.clamp(0, height as i32) as usize;
+        let x1 = (x + w).clamp(0, width as i32) as usize;
+        let y1 = (y + h).clamp(0, height as i32) as usize;
+
+        let mut sum = 0.0;
+        let mut count = 0u32;
+        for py in (y0..y1).step_by(4) {
+            for px in (x0..x1).step_by(4) {
+                let i = (py * width as usize + px) * 4;
+                let Some(bgra) = frame.get(i..i + 4) else { continue };
+                let rgb = [bgra[2] as f32 / 255.0, bgra[1] as f32 / 255.0, bgra[0] as f32 / 255.0];
+                sum += crate::overlay::relative_luminance(rgb);
+                count += 1;
+            }
+        }
+        (count > 0).then(|| sum / count as f32)
+    }
+
+    /// BGRAフレームを factor 分の1に縮小する（factor×factor 画素の平均）。
+    /// ぼかし背景の元画像・GUIのプレビュー用なので精度より速さを優先する。
+    pub fn downscale(frame: &[u8], width: u32, height: u32, factor: u32) -> (Vec<u8>, u32, u32) {
+        let (w, h) = ((width / factor).max(1), (height / factor).max(1));
+        let mut out = vec![0u8; (w * h * 4) as usize];
+        for oy in 0..h {
+            for ox in 0..w {
+                let mut sum = [0u32; 3];
+                let mut count = 0;
+                for py in (oy * factor)..((oy + 1) * factor).min(height) {
+                    for px in (ox * factor)..((ox + 1) * factor).min(width) {
+                        let i = ((py * width + px) * 4) as usize;
+                        let Some(bgra) = frame.get(i..i + 3) else { continue };
+                        sum[0] += bgra[0] as u32;
+                        sum[1] += bgra[1] as u32;
+                        sum[2] += bgra[2] as u32;
+                        count += 1;
+                    }
+                }
+                let o = ((oy * w + ox) * 4) as usize;
+                for c in 0..3 {
+                    out[o + c] = (sum[c] / count.max(1)) as u8;
+                }
+                out[o + 3] = 255;
+            }
+        }
+        (out, w, h)
+    }
+
+    pub fn get_dimensions(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    /// 対象ウィンドウのスクリーン上の位置を取得
+    pub fn get_window_position(&self) -> (i32, i32) {
+        client_origin(self.target_hwnd)
+    }
+}
+
+impl Drop for WindowCapture {
+    fn drop(&mut self) {
+        self.free_dib();
+    }
+}
+
+/// クライアント領域の左上のスクリーン座標
+pub fn client_origin(hwnd: HWND) -> (i32, i32) {
+    unsafe {
+        let mut rect = RECT::default();
+        if GetWindowRect(hwnd, &mut rect).is_ok() {
+            let mut pt = POINT { x: 0, y: 0 };
+            let _ = ClientToScreen(hwnd, &mut pt);
+            (pt.x, pt.y)
+        } else {
+            (0, 0)
+        }
+    }
+}
+
+/// クライアント領域のスクリーン座標での矩形（最小化中などは空の矩形）
+pub fn client_rect(hwnd: HWND) -> RECT {
+    unsafe {
+        let mut rect = RECT::default();
+        if GetClientRect(hwnd, &mut rect).is_err() || IsIconic(hwnd).as_bool() {
+            return RECT::default();
+        }
+        let (x, y) = client_origin(hwnd);
+        RECT { left: x, top: y, right: x + rect.right, bottom: y + rect.bottom }
+    }
+}
+
+/// 実行中のウィンドウ一覧を取得
+pub fn list_windows() -> Vec<(isize, String)> {
+    let mut windows: Vec<(isize, String)> = Vec::new();
+
+    unsafe {
+        let _ = EnumWindows(
+            Some(enum_windows_callback),
+            LPARAM(&mut windows as *mut Vec<(isize, String)> as isize),
+        );
+    }
+
+    windows
+}
+
+/// ウィンドウを持つプロセスの実行ファイル名（"game.exe" など）。取得できなければ None
+pub fn window_exe_name(hwnd_raw: isize) -> Option<String> {
+    unsafe {
+        let mut pid = 0;
+        GetWindowThreadProcessId(HWND(hwnd_raw as *mut _), Some(&mut pid));
+        let process = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, pid).ok()?;
+        let mut buf = [0u16; 1024];
+        let mut len = buf.len() as u32;
+        let result = QueryFullProcessImageNameW(process, PROCESS_NAME_WIN32, windows::core::PWSTR(buf.as_mut_ptr()), &mut len);
+        let _ = CloseHandle(process);
+        result.ok()?;
+        let path = String::from_utf16_lossy(&buf[..len as usize]);
+        std::path::Path::new(&path).file_name().map(|n| n.to_string_lossy().into_owned())
+    }
+}
+
+/// このアプリ自身（GUI・オーバーレイ）のウィンドウか
+pub fn is_own_window(hwnd_raw: isize) -> bool {
+    let mut pid = 0;
+    unsafe { GetWindowThreadProcessId(HWND(hwnd_raw as *mut _), Some(&mut pid)) };
+    pid == std::process::id()
+}
+
+/// 仮想デスクトップ全体（全モニター）のスクリーンショット
+pub struct DesktopShot {
+    /// BGRA（上から下へ）
+    pub pixels: Vec<u8>,
+    pub width: u32,
+    pub height: u32,
+    /// 左上の画面座標（左や上にモニターがあると負になる）
+    pub origin: (i32, i32),
+}
+
+/// 全モニターをまとめて BitBlt で撮る（範囲を選んで翻訳する用）
+pub fn capture_desktop() -> Result<DesktopShot> {
+    unsafe {
+        let x = GetSystemMetrics(SM_XVIRTUALSCREEN);
+        let y = GetSystemMetrics(SM_YVIRTUALSCREEN);
+        let width = GetSystemMetrics(SM_CXVIRTUALSCREEN);
+        let height = GetSystemMetrics(SM_CYVIRTUALSCREEN);
+        anyhow::ensure!(width > 0 && height > 0, "Failed to get the virtual screen size");
+
+        let screen_dc = GetDC(None);
+        if screen_dc.is_invalid() {
+            anyhow::bail!("GetDC failed for the screen");
+        }
+        let memory_dc = CreateCompatibleDC(Some(screen_dc));
+        let bmi = BITMAPINFO {
+            bmiHeader: BITMAPINFOHEADER {
+                biSize: mem::size_of::<BITMAPINFOHEADER>() as u32,
+                biWidth: width,
+                biHeight: -height, // top-down
+                biPlanes: 1,
+                biBitCount: 32,
+                biCompression: BI_RGB.0,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let mut bits_ptr: *mut core::ffi::c_void = std::ptr::null_mut();
+        let result = CreateDIBSection(Some(memory_dc), &bmi, DIB_RGB_COLORS, &mut bits_ptr, None, 0)
+            .context("CreateDIBSection failed")
+            .and_then(|bitmap| {
+                let old_bitmap = SelectObject(memory_dc, HGDIOBJ(bitmap.0));
+                // CAPTUREBLT includes layered windows such as other apps' popups
+                let copied = BitBlt(memory_dc, 0, 0, width, height, Some(screen_dc), x, y, SRCCOPY | CAPTUREBLT);
+                let size = (width * height * 4) as usize;
+                let mut pixels = vec![0u8; size];
+                std::ptr::copy_nonoverlapping(bits_ptr as *const u8, pixels.as_mut_ptr(), size);
+                SelectObject(memory_dc, old_bitmap);
+                let _ = DeleteObject(HGDIOBJ(bitmap.0));
+                copied.context("BitBlt failed")?;
+                Ok(DesktopShot { pixels, width: width as u32, height: height as u32, origin: (x, y) })
+            });
+        let _ = DeleteDC(memory_dc);
+        ReleaseDC(None, screen_dc);
+        result
+    }
+}
+
+/// 画像ファイル（PNG・JPEG・BMP など WIC で読める形式）を BGRA で読み込む（COM の初期化が必要）。
+/// 複数フレームの画像は最初のフレームだけ
+pub fn decode_image(path: &Path) -> Result<(Vec<u8>, u32, u32)> {
+    let wide: Vec<u16> = path.as_os_str().encode_wide().chain(Some(0)).collect();
+    unsafe {
+        let wic: IWICImagingFactory = CoCreateInstance(&CLSID_WICImagingFactory, None, CLSCTX_INPROC_SERVER)?;
+        let decoder = wic
+            .CreateDecoderFromFilename(PCWSTR(wide.as_ptr()), None, GENERIC_READ, WICDecodeMetadataCacheOnDemand)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let frame = decoder.GetFrame(0)?;
+        let source = WICConvertBitmapSource(&GUID_WICPixelFormat32bppBGRA, &frame)?;
+        let (mut width, mut height) = (0, 0);
+        source.GetSize(&mut width, &mut height)?;
+        let mut pixels = vec![0u8; width as usize * height as usize * 4];
+        source.CopyPixels(std::ptr::null(), width * 4, &mut pixels)?;
+        Ok((pixels, width, height))
+    }
+}
+
+/// BGRA のキャプチャ画像から矩形 (x, y, 幅, 高さ) を切り出して PNG にする（WIC を使うので COM の初期化が必要）。
+/// 矩形は画像の内側に収め、空になるなら None
+pub fn encode_png_crop(pixels: &[u8], width: u32, height: u32, rect: (i32, i32, i32, i32)) -> Result<Option<Vec<u8>>> {
+    let (x, y, w, h) = rect;
+    let left = x.clamp(0, width as i32) as usize;
+    let top = y.clamp(0, height as i32) as usize;
+    let right = (x + w).clamp(0, width as i32) as usize;
+    let bottom = (y + h).clamp(0, height as i32) as usize;
+    if right <= left || bottom <= top || pixels.len() < width as usize * height as usize * 4 {
+        return Ok(None);
+    }
+    // Captures carry no meaningful alpha, so the crop is written as opaque 24-bit BGR
+    let (crop_w, crop_h) = (right - left, bottom - top);
+    let mut bgr = Vec::with_capacity(crop_w * crop_h * 3);
+    for row in top..bottom {
+        let start = (row * width as usize + left) * 4;
+        for px in pixels[start..start + crop_w * 4].chunks_exact(4) {
+            bgr.extend_from_slice(&px[..3]);
+        }
+    }
+    unsafe {
+        let wic: IWICImagingFactory = CoCreateInstance(&CLSID_WICImagingFactory, None, CLSCTX_INPROC_SERVER)?;
+        let memory = SHCreateMemStream(None).context("SHCreateMemStream failed")?;
+        let stream = wic.CreateStream()?;
+        stream.InitializeFromIStream(&memory)?;
+        let encoder = wic.CreateEncoder(&GUID_ContainerFormatPng, std::ptr::null())?;
+        encoder.Initialize(&stream, WICBitmapEncoderNoCache)?;
+        let mut frame = None;
+        let mut props = None;
+        encoder.CreateNewFrame(&mut frame, &mut props)?;
+        let frame = frame.context("CreateNewFrame returned no frame")?;
+        frame.Initialize(props.as_ref())?;
+        frame.SetSize(crop_w as u32, crop_h as u32)?;
+        let mut format = GUID_WICPixelFormat24bppBGR;
+        frame.SetPixelFormat(&mut format)?;
+        anyhow::ensure!(format == GUID_WICPixelFormat24bppBGR, "PNG encoder does not accept 24bpp BGR");
+        frame.WritePixels(crop_h as u32, crop_w as u32 * 3, &bgr)?;
+        frame.Commit()?;
+        encoder.Commit()?;
+
+        let mut stat = STATSTG::default();
+        memory.Stat(&mut stat, STATFLAG_NONAME)?;
+        let mut png = vec![0u8; stat.cbSize as usize];
+        memory.Seek(0, STREAM_SEEK_SET, None)?;
+        let mut read = 0;
+        memory.Read(png.as_mut_ptr().cast(), png.len() as u32, Some(&mut read)).ok()?;
+        png.truncate(read as usize);
+        Ok(Some(png))
+    }
+}
+
+unsafe extern "system" fn enum_windows_callback(hwnd: HWND, lparam: LPARAM) -> BOOL {
+    let windows = &mut *(lparam.0 as *mut Vec<(isize, String)>);
+
+    // 表示されてるウィンドウのみ
+    if !IsWindowVisible(hwnd).as_bool() {
+        return TRUE;
+    }
+
+    // タイトルがあるウィンドウのみ
+    let mut title = [0u16; 256];
+    let len = GetWindowTextW(hwnd, &mut title);
+    if len == 0 {
+        return TRUE;
+    }
+
+    let title = String::from_utf16_lossy(&title[..len as usize]);
+
+    // 自分自身のオーバーレイは除外
+    if title == "Game Translator Overlay" {
+        return TRUE;
+    }
+
+    // 空タイトルやシステム系を除外
+    if !title.trim().is_empty() {
+        windows.push((hwnd.0 as isize, title));
+    }
+
+    TRUE
+}
@@ -0,0 +1,93 @@
+//! Windows クリップボードのテキストの読み書き
+
+use anyhow::{Context, Result};
+use windows::Win32::Foundation::*;
+use windows::Win32::System::DataExchange::*;
+use windows::Win32::System::Memory::*;
+use windows::Win32::System::Ole::CF_UNICODETEXT;
+use windows::Win32::System::Threading::GetCurrentProcessId;
+use windows::Win32::UI::WindowsAndMessaging::GetWindowThreadProcessId;
+
+/// クリップボードを開いている間だけ保持し、必ず CloseClipboard する
+struct ClipboardGuard;
+
+impl ClipboardGuard {
+    fn open(owner: Option<HWND>) -> Result<Self> {
+        unsafe { OpenClipboard(owner) }.context("OpenClipboard failed")?;
+        Ok(Self)
+    }
+}
+
+impl Drop for ClipboardGuard {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = CloseClipboard();
+        }
+    }
+}
+
+/// UTF-16 テキストをクリップボードに設定する
+pub fn set_text(owner: HWND, text: &str) -> Result<()> {
+    let wide: Vec<u16> = text.encode_utf16().chain(Some(0)).collect();
+    let bytes = wide.len() * std::mem::size_of::<u16>();
+
+    let _guard = ClipboardGuard::open(Some(owner))?;
+    unsafe {
+        EmptyClipboard().context("EmptyClipboard failed")?;
+
+        let hmem = GlobalAlloc(GMEM_MOVEABLE, bytes).context("GlobalAlloc failed")?;
+        let dst = GlobalLock(hmem) as *mut u16;
+        if dst.is_null() {
+            let _ = GlobalFree(Some(hmem));
+            anyhow::bail!("GlobalLock failed");
+        }
+        std::ptr::copy_nonoverlapping(wide.as_ptr(), dst, wide.len());
+        // ロックカウントが0になると FALSE が返るため結果は無視する
+        let _ = GlobalUnlock(hmem);
+
+        // 成功時はメモリの所有権がシステムに移る
+        if let Err(e) = SetClipboardData(CF_UNICODETEXT.0 as u32, Some(HANDLE(hmem.0))) {
+            let _ = GlobalFree(Some(hmem));
+            return Err(e).context("SetClipboardData failed");
+        }
+    }
+    Ok(())
+}
+
+/// 内容が変わるたびに増える番号（変化の確認用。開かずに読める）
+pub fn sequence_number() -> u32 {
+    unsafe { GetClipboardSequenceNumber() }
+}
+
+/// 今の内容をこのアプリ自身が書き込んだか（訳文の出力・ボックスのコピー）
+pub fn is_own() -> bool {
+    let Ok(owner) = (unsafe { GetClipboardOwner() }) else {
+        return false;
+    };
+    let mut pid = 0;
+    unsafe { GetWindowThreadProcessId(owner, Some(&mut pid)) };
+    pid == unsafe { GetCurrentProcessId() }
+}
+
+/// クリップボードのテキストを読む（テキスト以外なら None）
+pub fn get_text() -> Result<Option<String>> {
+    if unsafe { IsClipboardFormatAvailable(CF_UNICODETEXT.0 as u32) }.is_err() {
+        return Ok(None);
+    }
+    let _guard = ClipboardGuard::open(None)?;
+    unsafe {
+        let handle = GetClipboardData(CF_UNICODETEXT.0 as u32).context("GetClipboardData failed")?;
+        let hmem = HGLOBAL(handle.0);
+        let src = GlobalLock(hmem) as *const u16;
+        if src.is_null() {
+            anyhow::bail!("GlobalLock failed");
+        }
+        // 終端の NUL まで（壊れたデータでもメモリの大きさを超えて読まない）
+        let len = GlobalSize(hmem) / std::mem::size_of::<u16>();
+        let wide = std::slice::from_raw_parts(src, len);
+        let end = wide.iter().position(|&c| c == 0).unwrap_or(len);
+        let text = String::from_utf16_lossy(&wide[..end]);
+        let _ = GlobalUnlock(hmem);
+        Ok(Some(text))
+    }
+}
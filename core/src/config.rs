@@ -0,0 +1,1017 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
+
+
+static DEBUG_LOG_ENABLED: AtomicBool = AtomicBool::new(false);
+
+pub fn set_debug_log(enabled: bool) {
+    DEBUG_LOG_ENABLED.store(enabled, Ordering::SeqCst);
+}
+
+pub fn is_debug_log() -> bool {
+    DEBUG_LOG_ENABLED.load(Ordering::SeqCst)
+}
+
+/// exe と同じフォルダにこの名前のファイルを置くとポータブルモード
+pub const PORTABLE_MARKER: &str = "portable.txt";
+
+/// 設定ファイルのバックアップを残す数（古いものから消す）
+const MAX_CONFIG_BACKUPS: usize = 10;
+
+/// 設定ファイルが壊れていて読めなかった時の情報（GUI で復元を提案する）
+#[derive(Debug, Clone)]
+pub struct ConfigLoadError {
+    pub message: String,
+    /// 壊れたファイルの退避先（上書き保存で失われないように）
+    pub broken_copy: Option<PathBuf>,
+    /// 読み込める最新のバックアップ
+    pub backup: Option<PathBuf>,
+}
+
+fn exe_dir() -> PathBuf {
+    std::env::current_exe()
+        .ok()
+        .and_then(|p| p.parent().map(|d| d.to_path_buf()))
+        .unwrap_or_else(|| PathBuf::from("."))
+}
+
+/// 設定・キャッシュ・ログの保存先。
+/// 通常は %APPDATA%\GameTranslator（Program Files 配下に置いても書き込めるように）、
+/// ポータブルモードでは exe と同じフォルダ。
+pub fn data_dir() -> &'static PathBuf {
+    static DIR: OnceLock<PathBuf> = OnceLock::new();
+    DIR.get_or_init(|| {
+        let exe_dir = exe_dir();
+        if exe_dir.join(PORTABLE_MARKER).exists() {
+            return exe_dir;
+        }
+        let Some(appdata) = std::env::var_os("APPDATA") else {
+            return exe_dir;
+        };
+        let dir = PathBuf::from(appdata).join("GameTranslator");
+        if std::fs::create_dir_all(&dir).is_err() {
+            return exe_dir;
+        }
+        migrate_data(&exe_dir, &dir);
+        dir
+    })
+}
+
+/// コマンドラインで指定された設定ファイル（未指定なら保存先の config.toml）
+static CONFIG_PATH: OnceLock<PathBuf> = OnceLock::new();
+
+/// 設定ファイルの場所を差し替える（GUI の起動前に一度だけ）
+pub fn set_config_path(path: PathBuf) {
+    if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+        let _ = std::fs::create_dir_all(dir);
+    }
+    let _ = CONFIG_PATH.set(path);
+}
+
+/// 以前のバージョンが exe の隣に保存した設定とキャッシュを、初回だけ新しい保存先へコピーする
+fn migrate_data(old: &Path, new: &Path) {
+    for name in ["config.toml", "translation_cache.json"] {
+        let (from, to) = (old.join(name), new.join(name));
+        if from.exists() && !to.exists() {
+            let _ = std::fs::copy(&from, &to);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum TranslationEngine {
+    DeepL,
+    LocalLLM,
+    Groq,
+    /// ネットワークを使わないテスト用エンジン（原文を加工して返す）
+    Echo,
+    /// 外部プロセスのプラグイン（どれを使うかは `plugin_name`）
+    Plugin,
+}
+
+impl TranslationEngine {
+    pub const ALL: [TranslationEngine; 5] = [Self::DeepL, Self::LocalLLM, Self::Groq, Self::Echo, Self::Plugin];
+
+    /// 設定ファイル・エクスポートで使う識別名
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::DeepL => "DeepL",
+            Self::LocalLLM => "LocalLLM",
+            Self::Groq => "Groq",
+            Self::Echo => "Echo",
+            Self::Plugin => "Plugin",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|e| e.as_str().eq_ignore_ascii_case(name.trim()))
+    }
+}
+
+/// ローカルLLMサーバーのAPI形式
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum LocalLlmApi {
+    /// /v1/models に応答すれば Chat、失敗時は Completions にフォールバック
+    Auto,
+    /// /v1/chat/completions（チャットテンプレートはサーバー側で適用）
+    Chat,
+    /// /v1/completions（プロンプトテンプレートをモデル名から推定）
+    Completions,
+}
+
+/// エンジンごとに追加送信するHTTPヘッダー
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CustomHeader {
+    pub engine: TranslationEngine,
+    pub name: String,
+    pub value: String,
+}
+
+/// GUI の表示言語の設定
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum UiLanguage {
+    /// OS の表示言語が日本語なら日本語、それ以外は英語
+    Auto,
+    Japanese,
+    English,
+}
+
+/// 用語集の一致のさせ方
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum GlossaryMatch {
+    /// 行全体が用語と同じ時だけ（APIに送らず訳語をそのまま使う）
+    Exact,
+    /// 前後が英数字でない時だけ（英語などの単語単位）
+    Word,
+    /// 行のどこに含まれていても
+    Substring,
+}
+
+impl GlossaryMatch {
+    pub const ALL: [GlossaryMatch; 3] = [Self::Exact, Self::Word, Self::Substring];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Exact => "Exact",
+            Self::Word => "Word",
+            Self::Substring => "Substring",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|m| m.as_str().eq_ignore_ascii_case(name.trim()))
+    }
+}
+
+/// 用語集の1項目。訳語が空なら翻訳せず原文のまま残す
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GlossaryEntry {
+    pub term: String,
+    pub translation: String,
+    #[serde(default = "default_glossary_match")]
+    pub mode: GlossaryMatch,
+}
+
+fn default_glossary_match() -> GlossaryMatch {
+    GlossaryMatch::Word
+}
+
+/// オーバーレイの見た目一式（フォント・色・縁取り・背景）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StylePreset {
+    pub name: String,
+    pub font_family: String,
+    pub text_color: [f32; 4],
+    pub bg_color: [f32; 4],
+    pub outline_enabled: bool,
+    pub outline_width: f32,
+    pub outline_color: [f32; 4],
+    pub corner_radius: f32,
+    pub border_width: f32,
+    pub border_color: [f32; 4],
+}
+
+impl StylePreset {
+    /// 組み込みプリセット
+    pub fn builtin() -> Vec<StylePreset> {
+        let standard = Self::from_config("標準", &AppConfig::default());
+        vec![
+            Self {
+                name: "字幕".to_string(),
+                text_color: [1.0, 1.0, 1.0, 1.0],
+                bg_color: [0.0, 0.0, 0.0, 0.0],
+                outline_enabled: true,
+                outline_width: 2.5,
+                corner_radius: 0.0,
+                ..standard.clone()
+            },
+            Self {
+                name: "コミック".to_string(),
+                font_family: "Comic Sans MS".to_string(),
+                text_color: [0.0, 0.0, 0.0, 1.0],
+                bg_color: [1.0, 1.0, 1.0, 0.95],
+                corner_radius: 14.0,
+                border_width: 2.0,
+                border_color: [0.0, 0.0, 0.0, 1.0],
+                ..standard.clone()
+            },
+            Self {
+                name: "ミニマル".to_string(),
+                font_family: "Segoe UI".to_string(),
+                text_color: [1.0, 1.0, 1.0, 1.0],
+                bg_color: [0.0, 0.0, 0.0, 0.55],
+                corner_radius: 4.0,
+                ..standard.clone()
+            },
+            standard,
+        ]
+    }
+
+    /// 現在の設定から見た目だけを取り出す
+    pub fn from_config(name: &str, config: &AppConfig) -> Self {
+        Self {
+            name: name.to_string(),
+            font_family: config.overlay_font_family.clone(),
+            text_color: config.overlay_text_color,
+            bg_color: config.overlay_bg_color,
+            outline_enabled: config.overlay_outline_enabled,
+            outline_width: config.overlay_outline_width,
+            outline_color: config.overlay_outline_color,
+            corner_radius: config.overlay_corner_radius,
+            border_width: config.overlay_border_width,
+            border_color: config.overlay_border_color,
+        }
+    }
+
+    /// 設定に適用（レイアウト関連の設定はそのまま）
+    pub fn apply(&self, config: &mut AppConfig) {
+        config.overlay_font_family = self.font_family.clone();
+        config.overlay_text_color = self.text_color;
+        config.overlay_bg_color = self.bg_color;
+        config.overlay_outline_enabled = self.outline_enabled;
+        config.overlay_outline_width = self.outline_width;
+        config.overlay_outline_color = self.outline_color;
+        config.overlay_corner_radius = self.corner_radius;
+        config.overlay_border_width = self.border_width;
+        config.overlay_border_color = self.border_color;
+    }
+}
+
+/// LLM の翻訳スタイル一式（モデル・温度・最大トークン数・プロンプト）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LlmPreset {
+    pub name: String,
+    /// 選択中のエンジン（ローカル・Groq）のモデル名（空 = 変えない）
+    #[serde(default)]
+    pub model: String,
+    #[serde(default)]
+    pub temperature: Option<f32>,
+    #[serde(default)]
+    pub max_tokens: u32,
+    pub system_prompt: String,
+    pub user_template: String,
+}
+
+impl LlmPreset {
+    /// 組み込みプリセット（モデルは変えない）
+    pub fn builtin() -> Vec<LlmPreset> {
+        let standard = Self {
+            name: "標準".to_string(),
+            model: String::new(),
+            temperature: None,
+            max_tokens: 0,
+            system_prompt: crate::translate::DEFAULT_SYSTEM_PROMPT.to_string(),
+            user_template: crate::translate::DEFAULT_USER_TEMPLATE.to_string(),
+        };
+        vec![
+            Self {
+                name: "直訳（速い）".to_string(),
+                temperature: Some(0.0),
+                system_prompt: crate::translate::LITERAL_SYSTEM_PROMPT.to_string(),
+                ..standard.clone()
+            },
+            Self {
+                name: "意訳（文学的）".to_string(),
+                temperature: Some(0.7),
+                system_prompt: crate::translate::LITERARY_SYSTEM_PROMPT.to_string(),
+                ..standard.clone()
+            },
+            standard,
+        ]
+    }
+
+    /// 現在の設定から取り出す（モデルは選択中のエンジンのもの）
+    pub fn from_config(name: &str, config: &AppConfig) -> Self {
+        Self {
+            name: name.to_string(),
+            model: config.llm_model().to_string(),
+            temperature: config.llm_temperature,
+            max_tokens: config.llm_max_tokens,
+            system_prompt: config.llm_system_prompt.clone(),
+            user_template: config.llm_user_template.clone(),
+        }
+    }
+
+    /// 設定に適用（モデルは選択中のエンジンに設定する）
+    pub fn apply(&self, config: &mut AppConfig) {
+        if !self.model.is_empty() {
+            match config.translation_engine {
+                TranslationEngine::Groq => config.groq_model = self.model.clone(),
+                _ => config.local_llm_model = self.model.clone(),
+            }
+        }
+        config.llm_temperature = self.temperature;
+        config.llm_max_tokens = self.max_tokens;
+        config.llm_system_prompt = self.system_prompt.clone();
+        config.llm_user_template = self.user_template.clone();
+    }
+}
+
+/// ゲーム画面に対する矩形（位置・サイズは 0.0〜1.0 の比率）
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct RelativeRect {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl RelativeRect {
+    /// 比率座標 (x, y) が矩形内か
+    pub fn contains(&self, x: f32, y: f32) -> bool {
+        x >= self.x && x < self.x + self.width && y >= self.y && y < self.y + self.height
+    }
+}
+
+/// 画面の一部（会話欄・メニュー・戦闘ログなど）だけ見た目を変える領域。
+/// 原文の領域の中心がゾーン内にあれば、そのゾーンのスタイルで表示する。
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StyleZone {
+    pub name: String,
+    /// ゲーム画面に対する位置とサイズ（0.0〜1.0 の比率）
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+    /// 適用するスタイルプリセット名（組み込み・保存済み、見つからなければ通常の見た目）
+    pub preset: String,
+    /// 文字サイズの倍率
+    pub font_scale: f32,
+}
+
+impl Default for StyleZone {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            x: 0.0,
+            y: 0.7,
+            width: 1.0,
+            height: 0.3,
+            preset: String::new(),
+            font_scale: 1.0,
+        }
+    }
+}
+
+impl StyleZone {
+    /// 比率座標 (x, y) がゾーン内か
+    pub fn contains(&self, x: f32, y: f32) -> bool {
+        x >= self.x && x < self.x + self.width && y >= self.y && y < self.y + self.height
+    }
+}
+
+/// Echoエンジンの出力形式
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum EchoStyle {
+    /// "[原文]"
+    Bracket,
+    /// 原文を逆順に
+    Reverse,
+}
+
+/// 翻訳の表示位置
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum DisplayMode {
+    /// 原文の位置に重ねて表示
+    Overlay,
+    /// ゲームウィンドウの右側に縦に並べて表示（ゲーム画面を隠さない）
+    PanelRight,
+    /// ゲームウィンドウの左側に縦に並べて表示
+    PanelLeft,
+}
+
+/// 原文の取り方
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum InputSource {
+    /// 画面をキャプチャして OCR で読む
+    Ocr,
+    /// Textractor / Agent がクリップボードに書き出した文を読む
+    Clipboard,
+    /// Textractor の WebSocket 拡張などのサーバーから文を受け取る
+    WebSocket,
+    /// ゲーム音声（再生デバイスのループバック）を whisper.cpp で文字に起こす
+    Audio,
+}
+
+/// 訳文をオーバーレイ以外にも出力する方法
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum ReadoutMode {
+    Off,
+    /// Windows の音声合成で読み上げる
+    Speech,
+    /// クリップボードに書き込む（NVDA などのクリップボード読み上げ機能で読む）
+    Clipboard,
+}
+
+/// 原文の読みの表示（日本語は JMdict のふりがな、中国語は CC-CEDICT の声調記号付きピンイン）
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum SourceReading {
+    Off,
+    /// 読み付きの原文を訳文の上に添える
+    WithTranslation,
+    /// 訳文の代わりに読み付きの原文だけを表示する
+    Instead,
+}
+
+/// 停止時に書き出すセッション記録の形式
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum TranscriptFormat {
+    /// 原文と訳文を並べた読み物用のテキスト
+    Txt,
+    Json,
+    Csv,
+}
+
+impl TranscriptFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Txt => "txt",
+            Self::Json => "json",
+            Self::Csv => "csv",
+        }
+    }
+}
+
+/// 縦書き表示
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum VerticalText {
+    /// 常に横書き
+    Off,
+    /// 原文の領域が縦長なら縦書き（翻訳先が日本語・中国語のとき）
+    Auto,
+    /// 常に縦書き（原文の上に表示する場合のみ）
+    Always,
+}
+
+/// 原文に対する訳文の配置（原文の上に表示する場合）
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Placement {
+    /// 原文に重ねる
+    Cover,
+    /// 原文のすぐ下
+    Below,
+    /// 原文の横（右に入らなければ左）
+    Beside,
+}
+
+/// 訳文の行揃え（横書きのみ）
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum TextAlign {
+    Left,
+    Center,
+    Right,
+}
+
+/// 訳文ボックスを原文の領域のどこに合わせるか（縦方向）
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum VerticalAnchor {
+    /// 上端を揃える
+    Top,
+    /// 中央を揃える
+    Middle,
+}
+
+/// キャプチャ間隔の下限 (ms)。手書きの config.toml で 0 にされても OCR を回し続けないように
+pub const MIN_POLL_INTERVAL_MS: u32 = 50;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AppConfig {
+    /// GUI の表示言語
+    pub ui_language: UiLanguage,
+    pub translation_engine: TranslationEngine,
+    pub deepl_api_key: String,
+    /// DeepL の今月の残り文字数がこれを下回ったら警告する
+    pub deepl_quota_warning: u64,
+    pub local_llm_endpoint: String,
+    pub local_llm_model: String,
+    pub local_llm_api: LocalLlmApi,
+    /// LAN内の推論サーバー向けに自己署名証明書を許可（Local LLMのみ）
+    pub local_llm_accept_invalid_certs: bool,
+    pub groq_api_key: String,
+    pub groq_model: String,
+    pub echo_style: EchoStyle,
+    /// エンジンが Plugin の時に使うプラグインの名前（マニフェストの name）
+    pub plugin_name: String,
+    /// LLM（ローカル・Groq）への指示文。{from} / {to} は言語名に置き換える（空 = 既定）
+    pub llm_system_prompt: String,
+    /// LLM へのユーザーメッセージ。{lines} は番号付きの原文に置き換える（空 = 既定）
+    pub llm_user_template: String,
+    /// LLM の温度（None = エンジンの既定: ローカル 0.1、Groq 0.3）
+    pub llm_temperature: Option<f32>,
+    /// LLM の応答の最大トークン数（0 = 行数から自動）
+    pub llm_max_tokens: u32,
+    /// GUIの「LLMプリセット」で保存したモデル・パラメータ・プロンプトの組み合わせ
+    pub llm_presets: Vec<LlmPreset>,
+    /// 用語集（固有名詞の訳語・翻訳しない語）。翻訳前に置き換え、訳文に戻す
+    pub glossary: Vec<GlossaryEntry>,
+    pub source_lang: String,
+    pub target_lang: String,
+    /// 登録したゲームのウィンドウが現れたら自動で開始し、閉じたら停止する
+    pub auto_start: bool,
+    /// 自動開始の対象ウィンドウのタイトル（部分一致）
+    pub auto_start_titles: Vec<String>,
+    /// Windows へのログイン時に起動する（HKCU の Run キーに登録）
+    pub launch_at_login: bool,
+    /// ログイン時の起動ではウィンドウを隠し、通知領域のアイコンだけにしておく
+    pub start_minimized: bool,
+    /// 起動時、前回翻訳していたウィンドウ（実行ファイル名かタイトルで判定）が現れたら自動で再開する
+    pub resume_last_window: bool,
+    /// 前回開始したウィンドウのタイトル（実行ファイル名が分からない・同じ exe のウィンドウが複数ある時に使う）
+    pub last_window_title: String,
+    /// 前回開始したウィンドウの実行ファイル名（タイトルが変わるゲームでも見つけられるように）
+    pub last_window_exe: String,
+    /// 翻訳中のエラー（APIキーの期限切れ・レート制限など）を Windows の通知で知らせる
+    pub error_notifications: bool,
+    /// 停止時にセッションの記録（原文・訳文・位置・時刻・エンジン）を transcripts フォルダへ書き出す
+    pub transcript_auto_export: bool,
+    pub transcript_format: TranscriptFormat,
+    /// 履歴に原文の領域の画像を残す（Anki のカードに付ける用。メモリを使うので既定はオフ）
+    pub history_screenshots: bool,
+    /// OCR 結果と訳文をローカルの WebSocket で配信する（外部ツール向け）
+    pub feed_enabled: bool,
+    /// 配信の待ち受けポート（127.0.0.1 のみ）
+    pub feed_port: u16,
+    /// 原文を OCR で読むか、テキストフック（Textractor / Agent）・ゲーム音声から受け取るか
+    pub input_source: InputSource,
+    /// input_source が WebSocket の時の接続先
+    pub texthook_url: String,
+    /// input_source が Audio の時に使う whisper.cpp の実行ファイル（whisper-cli.exe）
+    pub whisper_command: String,
+    /// Whisper のモデル（ggml-*.bin）
+    pub whisper_model: String,
+    /// コピーされた文を翻訳してポップアップで表示する（翻訳を開始していなくても動く）
+    pub clipboard_watch: bool,
+    /// 学習モード: 原文の語ごとに辞書の訳語を原文の下に表示し、訳文は Ctrl+Alt+T で切り替えた時だけ出す
+    /// （OCR で翻訳元が日本語・中国語の時。辞書はダウンロード済みのものを使う）
+    pub gloss_mode: bool,
+    pub source_reading: SourceReading,
+    /// 新しく表示された訳文の読み上げ・クリップボードへの出力（スクリーンリーダー向け）
+    pub readout_mode: ReadoutMode,
+    /// 読み上げの速さ（-10〜10、0 = 標準）
+    pub speech_rate: i32,
+    /// 翻訳する範囲（空 = 画面全体）。原文の領域の中心がどれかに入っていれば翻訳する
+    pub capture_regions: Vec<RelativeRect>,
+    /// 翻訳しない範囲（HUD・チャット欄など）。範囲の指定より優先
+    pub capture_masks: Vec<RelativeRect>,
+    /// 画面に変化がある間のキャプチャ間隔 (ms)
+    pub capture_interval_ms: u32,
+    /// 変化のないキャプチャがこの回数を超えたら idle_interval_ms に落とす
+    pub idle_after: u32,
+    pub idle_interval_ms: u32,
+    /// さらにこの回数を超えたら deep_idle_interval_ms に落とす
+    pub deep_idle_after: u32,
+    pub deep_idle_interval_ms: u32,
+    /// 対象ウィンドウが前面の時だけ翻訳する（false なら裏に回っても翻訳を続ける）
+    pub foreground_only: bool,
+    /// 対象ウィンドウが前面にない間・一時停止中に様子を見る間隔 (ms)
+    pub background_interval_ms: u32,
+    /// http:// / https:// / socks5:// 形式。空ならプロキシを使わない
+    pub proxy_url: String,
+    pub proxy_username: String,
+    pub proxy_password: String,
+    /// プロキシを経由しないホスト（カンマ区切り）
+    pub no_proxy: String,
+    pub custom_headers: Vec<CustomHeader>,
+    pub overlay_text_color: [f32; 4],
+    pub overlay_bg_color: [f32; 4],
+    /// オーバーレイのフォント（CJK文字が無いフォントでも日本語フォントへフォールバック）
+    pub overlay_font_family: String,
+    /// 文字の縁取り（背景ボックス無しでも明るい画面で読めるように）
+    pub overlay_outline_enabled: bool,
+    pub overlay_outline_width: f32,
+    pub overlay_outline_color: [f32; 4],
+    /// 背景の明るさに応じて文字色・背景色を自動で切り替える
+    pub overlay_auto_contrast: bool,
+    /// 背景ボックスの角丸半径 (px)
+    pub overlay_corner_radius: f32,
+    /// 枠線の太さ (px, 0 = 枠線なし)
+    pub overlay_border_width: f32,
+    pub overlay_border_color: [f32; 4],
+    /// 訳文の表示中、ゲーム画面全体を暗くする強さ (0.0 = オフ、原文の上に表示する場合のみ)
+    pub overlay_dim: f32,
+    /// 背景ボックスを背景色から2色目へのグラデーション（上→下）で塗る
+    pub overlay_bg_gradient: bool,
+    pub overlay_bg_color2: [f32; 4],
+    /// 背景ボックスのスキン画像（PNGなど、9分割で拡大。空 = 使わない）
+    pub overlay_skin_path: String,
+    /// スキン画像の角・辺として拡大しない幅 (画像のpx)
+    pub overlay_skin_slice: u32,
+    /// 背景ボックスをゲーム画面のぼかしで塗る（アクリル風。背景色は半透明の色味として重なる）
+    pub overlay_acrylic: bool,
+    /// オーバーレイ全体の不透明度 (0.0〜1.0)。実行中も変更可能
+    pub overlay_opacity: f32,
+    /// 録画・配信（OBSなど）の画面キャプチャにオーバーレイを映さない。実行中も変更可能
+    pub overlay_exclude_from_capture: bool,
+    /// 操作モードでクリックしてコピーした時にボックスを一瞬強調表示する
+    pub overlay_copy_flash: bool,
+    pub display_mode: DisplayMode,
+    /// サイドパネル表示時の幅 (px)
+    pub panel_width: f32,
+    /// 訳文が原文の領域に収まるまでフォントを縮小する
+    pub overlay_auto_fit: bool,
+    /// 最小フォントサイズ（自動縮小もここで止まる）
+    pub overlay_min_font_size: f32,
+    /// 最大フォントサイズ（大きな原文でも訳文が画面を覆わないように）
+    pub overlay_max_font_size: f32,
+    /// OCRの行の高さから決めたフォントサイズに掛ける倍率。実行中も変更可能
+    pub overlay_font_scale: f32,
+    /// 重なった翻訳ボックスを上下にずらして読めるようにする
+    pub overlay_avoid_overlap: bool,
+    /// 表示切り替え時のフェード時間 (ms, 0 = フェードなし)
+    pub overlay_fade_ms: u32,
+    /// 1ボックスの最大行数（超えた分は「…」で省略、操作モードではホバーで全文表示。0 = 無制限）
+    pub overlay_max_lines: u32,
+    /// 縦書き表示（右から左へ列を並べる）
+    pub overlay_vertical_text: VerticalText,
+    /// 訳文を原文に重ねるか、原文を隠さないよう隣に置くか
+    pub overlay_placement: Placement,
+    /// 行揃え（中央揃え・右揃えではボックスも原文の領域の中央・右端に合わせる）
+    pub overlay_text_align: TextAlign,
+    pub overlay_vertical_anchor: VerticalAnchor,
+    /// オーバーレイの隅に処理状態（翻訳中・APIエラーなど）を表示する
+    pub overlay_status_badge: bool,
+    /// 左上にパフォーマンス表示（キャプチャの FPS・OCR と翻訳の所要時間・翻訳待ち・APIエラー数）を出す。実行中は Ctrl+Alt+F で切り替え
+    pub overlay_perf_hud: bool,
+    /// 左下の履歴パネルに残す訳文の行数（0 = パネルを表示しない）
+    pub overlay_history_lines: u32,
+    /// 日本語向けの改行（句読点・閉じ括弧を行頭に置かない禁則処理、収まらない単語は途中で改行）
+    pub overlay_cjk_line_breaking: bool,
+    /// 訳文の折り返し幅（原文の領域の幅に対する倍率）
+    pub overlay_wrap_width_factor: f32,
+    /// 検出したテキスト領域を枠線で表示する（マスク・グループ化の調整用）
+    pub overlay_debug_regions: bool,
+    /// 実行中、GUI の下部にキャプチャ中の画面の縮小表示を出す
+    pub capture_thumbnail: bool,
+    /// ユーザーが保存したスタイルプリセット（組み込みプリセットは含まない）
+    pub overlay_style_presets: Vec<StylePreset>,
+    /// 領域ごとの見た目の上書き（先に書いたゾーンが優先）
+    pub overlay_zones: Vec<StyleZone>,
+    /// システム全体のホットキー（"Ctrl+Alt+S" 形式、空 = 無効）
+    pub hotkey_start_stop: String,
+    pub hotkey_toggle_overlay: String,
+    pub hotkey_retranslate: String,
+    pub hotkey_pause: String,
+    /// 範囲を選んで翻訳（翻訳のセッションとは別に、ドラッグした範囲を1回だけ読む）
+    pub hotkey_snip: String,
+}
+
+impl Default for AppConfig {
+    fn default() -> Self {
+        Self {
+            ui_language: UiLanguage::Auto,
+            translation_engine: TranslationEngine::DeepL,
+            deepl_api_key: String::new(),
+            deepl_quota_warning: 50_000,
+            local_llm_endpoint: "http://localhost:5000".to_string(),
+            local_llm_model: "default".to_string(),
+            local_llm_api: LocalLlmApi::Auto,
+            local_llm_accept_invalid_certs: false,
+            groq_api_key: String::new(),
+            groq_model: "llama-3.3-70b-versatile".to_string(),
+            echo_style: EchoStyle::Bracket,
+            plugin_name: String::new(),
+            llm_system_prompt: crate::translate::DEFAULT_SYSTEM_PROMPT.to_string(),
+            llm_user_template: crate::translate::DEFAULT_USER_TEMPLATE.to_string(),
+            llm_temperature: None,
+            llm_max_tokens: 0,
+            llm_presets: Vec::new(),
+            glossary: Vec::new(),
+            source_lang: "EN".to_string(),
+            target_lang: "JA".to_string(),
+            proxy_url: String::new(),
+            proxy_username: String::new(),
+            proxy_password: String::new(),
+            no_proxy: "localhost,127.0.0.1,::1".to_string(),
+            custom_headers: Vec::new(),
+            overlay_text_color: [1.0, 1.0, 0.0, 1.0], // Yellow
+            overlay_bg_color: [0.0, 0.0, 0.0, 0.85],   // Semi-transparent black
+            overlay_font_family: "Yu Gothic UI".to_string(),
+            overlay_outline_enabled: false,
+            overlay_outline_width: 2.0,
+            overlay_outline_color: [0.0, 0.0, 0.0, 1.0],
+            overlay_auto_contrast: false,
+            overlay_corner_radius: 6.0,
+            overlay_border_width: 0.0,
+            overlay_border_color: [1.0, 1.0, 1.0, 0.6],
+            overlay_dim: 0.0,
+            overlay_bg_gradient: false,
+            overlay_bg_color2: [0.1, 0.1, 0.2, 0.85],
+            overlay_skin_path: String::new(),
+            overlay_skin_slice: 16,
+            overlay_acrylic: false,
+            overlay_opacity: 1.0,
+            overlay_exclude_from_capture: false,
+            overlay_copy_flash: true,
+            display_mode: DisplayMode::Overlay,
+            panel_width: 400.0,
+            overlay_auto_fit: true,
+            overlay_min_font_size: 10.0,
+            overlay_max_font_size: 72.0,
+            overlay_font_scale: 1.0,
+            overlay_avoid_overlap: true,
+            overlay_fade_ms: 150,
+            overlay_max_lines: 8,
+            overlay_vertical_text: VerticalText::Off,
+            overlay_placement: Placement::Cover,
+            overlay_text_align: TextAlign::Left,
+            overlay_vertical_anchor: VerticalAnchor::Top,
+            overlay_status_badge: true,
+            overlay_perf_hud: false,
+            overlay_history_lines: 0,
+            overlay_cjk_line_breaking: true,
+            overlay_wrap_width_factor: 1.3,
+            overlay_debug_regions: false,
+            capture_thumbnail: true,
+            overlay_style_presets: Vec::new(),
+            overlay_zones: Vec::new(),
+            auto_start: false,
+            auto_start_titles: Vec::new(),
+            launch_at_login: false,
+            start_minimized: true,
+            resume_last_window: false,
+            last_window_title: String::new(),
+            last_window_exe: String::new(),
+            error_notifications: true,
+            transcript_auto_export: false,
+            transcript_format: TranscriptFormat::Txt,
+            history_screenshots: false,
+            feed_enabled: false,
+            feed_port: 8765,
+            input_source: InputSource::Ocr,
+            texthook_url: "ws://127.0.0.1:6677".to_string(),
+            whisper_command: String::new(),
+            whisper_model: String::new(),
+            clipboard_watch: false,
+            gloss_mode: false,
+            source_reading: SourceReading::Off,
+            readout_mode: ReadoutMode::Off,
+            speech_rate: 0,
+            capture_regions: Vec::new(),
+            capture_masks: Vec::new(),
+            capture_interval_ms: 200,
+            idle_after: 5,
+            idle_interval_ms: 1000,
+            deep_idle_after: 10,
+            deep_idle_interval_ms: 2000,
+            foreground_only: true,
+            background_interval_ms: 500,
+            hotkey_start_stop: "Ctrl+Alt+S".to_string(),
+            hotkey_toggle_overlay: "Ctrl+Alt+H".to_string(),
+            hotkey_retranslate: "Ctrl+Alt+R".to_string(),
+            hotkey_pause: "Ctrl+Alt+P".to_string(),
+            hotkey_snip: "Ctrl+Alt+X".to_string(),
+        }
+    }
+}
+
+impl AppConfig {
+    /// 名前でプリセットを探す（保存済みが組み込みより優先）
+    pub fn find_preset(&self, name: &str) -> Option<StylePreset> {
+        self.overlay_style_presets
+            .iter()
+            .find(|p| p.name == name)
+            .cloned()
+            .or_else(|| StylePreset::builtin().into_iter().find(|p| p.name == name))
+    }
+
+    /// 選択中の LLM エンジンのモデル名（Groq 以外はローカル）
+    pub fn llm_model(&self) -> &str {
+        match self.translation_engine {
+            TranslationEngine::Groq => &self.groq_model,
+            _ => &self.local_llm_model,
+        }
+    }
+
+    /// 変化のないキャプチャが `no_change_count` 回続いた時の次のキャプチャまでの間隔 (ms)
+    pub fn poll_interval_ms(&self, no_change_count: u32) -> u32 {
+        let interval = if no_change_count > self.deep_idle_after {
+            self.deep_idle_interval_ms
+        } else if no_change_count > self.idle_after {
+            self.idle_interval_ms
+        } else {
+            self.capture_interval_ms
+        };
+        interval.max(MIN_POLL_INTERVAL_MS)
+    }
+
+    /// 設定ファイルの場所（--config / --profile で変えていればそのパス）
+    pub fn config_path() -> PathBuf {
+        CONFIG_PATH.get().cloned().unwrap_or_else(|| data_dir().join("config.toml"))
+    }
+
+    /// 設定ファイルを読み込む。壊れていれば既定値とともにエラーの情報を返す
+    /// （壊れたファイルは別名で残し、次の保存で上書きされても失われないようにする）
+    pub fn load() -> (Self, Option<ConfigLoadError>) {
+        let path = Self::config_path();
+        let mut error = None;
+        if path.exists() {
+            match Self::load_from(&path) {
+                Ok(config) => return (config, None),
+                Err(e) => {
+                    let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+                    let timestamp = chrono::Local::now().format("%Y%m%d-%H%M%S");
+                    let broken = path.with_file_name(format!("{}.broken-{}", file_name, timestamp));
+                    error = Some(ConfigLoadError {
+                        message: format!("{:#}", e),
+                        broken_copy: std::fs::copy(&path, &broken).is_ok().then_some(broken),
+                        backup: Self::latest_backup(),
+                    });
+                }
+            }
+        }
+        (Self::default_with_env_key(), error)
+    }
+
+    /// 既定値。以前の .env に DeepL の APIキーがあれば引き継ぐ
+    fn default_with_env_key() -> Self {
+        // Try loading API key from .env for migration
+        let mut config = Self::default();
+        if config.deepl_api_key.is_empty() {
+            if let Ok(key) = std::env::var("DEEPL_API_KEY") {
+                if !key.is_empty() {
+                    config.deepl_api_key = key;
+                }
+            }
+            if config.deepl_api_key.is_empty() {
+                let env_path = std::path::Path::new(".env");
+                if env_path.exists() {
+                    if let Ok(content) = std::fs::read_to_string(env_path) {
+                        for line in content.lines() {
+                            if let Some(val) = line.strip_prefix("DEEPL_API_KEY=") {
+                                let val = val.trim().trim_matches('"');
+                                if !val.is_empty() {
+                                    config.deepl_api_key = val.to_string();
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        config
+    }
+
+    /// 一時ファイルに書いてから置き換え（書き込み中に落ちても壊れないように）、
+    /// 内容が変わっていればバックアップにも残す
+    pub fn save(&self) -> Result<()> {
+        let path = Self::config_path();
+        let content = toml::to_string_pretty(self)?;
+        let tmp = path.with_extension("toml.tmp");
+        std::fs::write(&tmp, &content)?;
+        std::fs::rename(&tmp, &path)?;
+        if let Err(e) = Self::write_backup(&content) {
+            crate::log_always(&format!("[CONFIG] バックアップを作成できません: {:#}", e));
+        }
+        Ok(())
+    }
+
+    fn backup_dir() -> PathBuf {
+        let path = Self::config_path();
+        path.parent().unwrap_or(Path::new(".")).join("backups")
+    }
+
+    /// この設定ファイルのバックアップ（古い順）。名前は "<ファイル名>-YYYYMMDD-HHMMSS.toml"
+    fn backups() -> Vec<PathBuf> {
+        let stem = Self::config_path().file_stem().unwrap_or_default().to_string_lossy().into_owned();
+        let prefix = format!("{}-", stem);
+        let Ok(entries) = std::fs::read_dir(Self::backup_dir()) else {
+            return Vec::new();
+        };
+        let mut backups: Vec<PathBuf> = entries
+            .filter_map(|e| e.ok().map(|e| e.path()))
+            .filter(|p| {
+                p.file_stem()
+                    .and_then(|s| s.to_str())
+                    .and_then(|s| s.strip_prefix(&prefix))
+                    .is_some_and(|ts| ts.len() == "YYYYMMDD-HHMMSS".len())
+            })
+            .collect();
+        backups.sort();
+        backups
+    }
+
+    fn write_backup(content: &str) -> Result<()> {
+        let mut backups = Self::backups();
+        if backups.last().and_then(|p| std::fs::read_to_string(p).ok()).as_deref() == Some(content) {
+            return Ok(());
+        }
+        let dir = Self::backup_dir();
+        std::fs::create_dir_all(&dir)?;
+        let stem = Self::config_path().file_stem().unwrap_or_default().to_string_lossy().into_owned();
+        let timestamp = chrono::Local::now().format("%Y%m%d-%H%M%S");
+        let path = dir.join(format!("{}-{}.toml", stem, timestamp));
+        std::fs::write(&path, content)?;
+        if !backups.contains(&path) {
+            backups.push(path);
+        }
+        let excess = backups.len().saturating_sub(MAX_CONFIG_BACKUPS);
+        for old in &backups[..excess] {
+            let _ = std::fs::remove_file(old);
+        }
+        Ok(())
+    }
+
+    /// 読み込める最新のバックアップ
+    pub fn latest_backup() -> Option<PathBuf> {
+        Self::backups().into_iter().rev().find(|p| Self::load_from(p).is_ok())
+    }
+
+    /// バックアップを読み込み、設定ファイルとして書き戻す
+    pub fn restore_backup(backup: &Path) -> Result<Self> {
+        let config = Self::load_from(backup)?;
+        config.save()?;
+        Ok(config)
+    }
+
+    /// 指定した設定ファイルを読み込む（プロファイル・追加セッション用）。読めなければエラー。
+    pub fn load_from(path: &Path) -> Result<Self> {
+        Ok(toml::from_str(&std::fs::read_to_string(path)?)?)
+    }
+
+    /// 他のPCと共有するために設定一式を書き出す。
+    /// `include_secrets` が false なら APIキー・プロキシのパスワード・カスタムヘッダーの値を空にし、
+    /// URL のユーザー情報・クエリを除く。
+    pub fn export(&self, path: &Path, include_secrets: bool) -> Result<()> {
+        let config = if include_secrets { self.clone() } else { self.without_secrets() };
+        std::fs::write(path, toml::to_string_pretty(&config)?)?;
+        Ok(())
+    }
+
+    /// APIキー・プロキシのパスワード・カスタムヘッダーの値を空にし、URL に埋め込んだ
+    /// ユーザー情報・クエリ（トークン等）を除いた複製（共有・診断情報用）
+    pub fn without_secrets(&self) -> Self {
+        let mut config = self.clone();
+        config.deepl_api_key.clear();
+        config.groq_api_key.clear();
+        config.proxy_password.clear();
+        for h in &mut config.custom_headers {
+            h.value.clear();
+        }
+        for url in [&mut config.proxy_url, &mut config.texthook_url, &mut config.local_llm_endpoint] {
+            *url = strip_url_secrets(url);
+        }
+        config
+    }
+
+    /// 書き出した設定を読み込む。空の秘密情報と、このPC固有の設定（ログイン時の起動）は今の値を引き継ぐ。
+    pub fn import(&self, path: &Path) -> Result<Self> {
+        let mut config = Self::load_from(path)?;
+        let keep = |imported: &mut String, current: &String| {
+            if imported.is_empty() {
+                imported.clone_from(current);
+            }
+        };
+        keep(&mut config.deepl_api_key, &self.deepl_api_key);
+        keep(&mut config.groq_api_key, &self.groq_api_key);
+        keep(&mut config.proxy_password, &self.proxy_password);
+        for h in &mut config.custom_headers {
+            if let Some(current) = self.custom_headers.iter().find(|c| c.engine == h.engine && c.name == h.name) {
+                keep(&mut h.value, &current.value);
+            }
+        }
+        config.launch_at_login = self.launch_at_login;
+        config.start_minimized = self.start_minimized;
+        Ok(config)
+    }
+}
+
+/// URL からユーザー情報（`user:pass@`）とクエリ・フラグメントを除く（それ以外の表記はそのまま）
+fn strip_url_secrets(url: &str) -> String {
+    let url = url.split(['?', '#']).next().unwrap_or_default();
+    let Some((scheme, rest)) = url.split_once("://") else {
+        return url.to_string();
+    };
+    let authority_end = rest.find('/').unwrap_or(rest.len());
+    let (authority, path) = rest.split_at(authority_end);
+    let host = authority.rsplit_once('@').map_or(authority, |(_, host)| host);
+    format!("{}://{}{}", scheme, host, path)
+}
@@ -0,0 +1,550 @@
+//! 辞書引き（JMdict / CC-CEDICT）
+//!
+//! 操作モードでオーバーレイのボックスを Ctrl+クリックすると、フロントエンドがその原文を辞書の
+//! ポップアップに出し、クリックした文字から始まる語を引く。学習モードでは原文を語に区切り
+//! （[`Dictionary::segment`]）、語ごとの訳語を原文の下に表示する。翻訳元が日本語なら JMdict、
+//! 中国語なら CC-CEDICT を使う。原文の読み（[`Dictionary::with_readings`]）には
+//! それぞれの読み（ふりがな・ピンイン）を使う。
+//! 辞書ファイルは初めて使う時にダウンロードし、保存先の
+//! dictionaries フォルダに置く（圧縮したまま保存し、読み込み時に展開する）。
+
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+
+use crate::config::AppConfig;
+use crate::lang::Language;
+
+/// これより長い語は引かない（文の残りを全部試さないように）
+const MAX_WORD_CHARS: usize = 16;
+/// 1回の検索で返す、長さの違う一致の数
+const MAX_MATCHES: usize = 3;
+const DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(300);
+/// 学習モードの訳語の最大文字数
+const MAX_GLOSS_CHARS: usize = 24;
+
+/// 読み込んだ辞書（ポップアップとセッションで共有し、アプリを閉じるまで持っておく）
+static LOADED: Mutex<Vec<Arc<Dictionary>>> = Mutex::new(Vec::new());
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DictionaryKind {
+    JMdict,
+    CcCedict,
+}
+
+impl DictionaryKind {
+    /// 翻訳元の言語に使う辞書（日本語・中国語以外は None）
+    pub fn for_language(source_lang: &str) -> Option<Self> {
+        match Language::parse(source_lang)? {
+            Language::Japanese => Some(Self::JMdict),
+            Language::ChineseSimplified | Language::ChineseTraditional => Some(Self::CcCedict),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::JMdict => "JMdict",
+            Self::CcCedict => "CC-CEDICT",
+        }
+    }
+
+    /// 配布元（ライセンス表示用）
+    pub fn homepage(self) -> &'static str {
+        match self {
+            Self::JMdict => "https://www.edrdg.org/jmdict/j_jmdict.html",
+            Self::CcCedict => "https://www.mdbg.net/chinese/dictionary?page=cc-cedict",
+        }
+    }
+
+    fn url(self) -> &'static str {
+        match self {
+            Self::JMdict => "https://ftp.edrdg.org/pub/Nihongo/JMdict_e.gz",
+            Self::CcCedict => "https://www.mdbg.net/chinese/export/cedict/cedict_1_0_ts_utf-8_mdbg.txt.gz",
+        }
+    }
+
+    pub fn path(self) -> PathBuf {
+        let file = match self {
+            Self::JMdict => "JMdict_e.gz",
+            Self::CcCedict => "cedict_ts.u8.gz",
+        };
+        crate::config::data_dir().join("dictionaries").join(file)
+    }
+
+    pub fn is_downloaded(self) -> bool {
+        self.path().exists()
+    }
+
+    /// 辞書ファイルを設定のプロキシ経由でダウンロードして保存する。
+    /// 途中で失敗したり gzip として読めなかったりした時は、今のファイルを置き換えない。
+    pub fn download(self, config: &AppConfig) -> Result<()> {
+        let path = self.path();
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+        }
+        let mut builder = reqwest::blocking::Client::builder().timeout(DOWNLOAD_TIMEOUT);
+        if let Some(proxy) = crate::translate::proxy(config)? {
+            builder = builder.proxy(proxy);
+        }
+        let client = builder.build().context("Failed to build HTTP client")?;
+        let bytes = client
+            .get(self.url())
+            .send()
+            .and_then(|r| r.error_for_status())
+            .and_then(|r| r.bytes())
+            .with_context(|| format!("Failed to download {}", self.url()))?;
+        // エラーページ・途中で切れたファイルを弾く（最後まで展開できるか）
+        std::io::copy(&mut flate2::read::GzDecoder::new(&bytes[..]), &mut std::io::sink())
+            .with_context(|| format!("{} is not a valid gzip file", self.url()))?;
+        let partial = path.with_extension("part");
+        std::fs::write(&partial, &bytes).with_context(|| format!("Failed to write {}", partial.display()))?;
+        std::fs::rename(&partial, &path).with_context(|| format!("Failed to write {}", path.display()))?;
+        crate::log_always(&format!("[DICT] {} をダウンロード ({} KB)", self.name(), bytes.len() / 1024));
+        Ok(())
+    }
+}
+
+/// 辞書の1項目
+#[derive(Debug, Clone)]
+pub struct DictEntry {
+    /// 見出し語（漢字表記。CC-CEDICT は簡体字・繁体字）
+    pub headwords: Vec<String>,
+    /// 読み（かな・ピンイン）
+    pub readings: Vec<String>,
+    /// 語義（"[品詞] 訳; 訳" の形）
+    pub senses: Vec<String>,
+}
+
+/// 区切った語（文字単位の位置）と、その短い訳語（辞書にない文字は None）
+pub struct Segment {
+    pub start: usize,
+    pub len: usize,
+    pub gloss: Option<String>,
+}
+
+/// 原文のある位置から始まる語の一致
+pub struct LookupMatch<'a> {
+    /// 一致した原文の長さ（文字数）
+    pub len: usize,
+    /// 引いた形（活用を戻した辞書形のこともある）
+    pub form: String,
+    pub entries: Vec<&'a DictEntry>,
+}
+
+pub struct Dictionary {
+    pub kind: DictionaryKind,
+    entries: Vec<DictEntry>,
+    /// 見出し語・読み → entries の位置
+    index: HashMap<String, Vec<u32>>,
+}
+
+impl Dictionary {
+    /// ダウンロード済みの辞書を読み込む（JMdict は数秒かかる）
+    pub fn load(kind: DictionaryKind) -> Result<Self> {
+        let path = kind.path();
+        let file = std::fs::File::open(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+        let mut content = String::new();
+        flate2::read::GzDecoder::new(file)
+            .read_to_string(&mut content)
+            .with_context(|| format!("Failed to decompress {}", path.display()))?;
+        let entries = match kind {
+            DictionaryKind::JMdict => parse_jmdict(&content),
+            DictionaryKind::CcCedict => parse_cedict(&content),
+        };
+        anyhow::ensure!(!entries.is_empty(), "{} has no entries", path.display());
+        let mut index: HashMap<String, Vec<u32>> = HashMap::new();
+        for (i, entry) in entries.iter().enumerate() {
+            for key in entry.headwords.iter().chain(&entry.readings) {
+                let ids = index.entry(key.clone()).or_default();
+                if !ids.contains(&(i as u32)) {
+                    ids.push(i as u32);
+                }
+            }
+        }
+        crate::log_always(&format!("[DICT] {} を読み込み ({} 項目)", kind.name(), entries.len()));
+        Ok(Self { kind, entries, index })
+    }
+
+    /// 読み込み済みならそれを、なければ読み込んで返す
+    pub fn shared(kind: DictionaryKind) -> Result<Arc<Self>> {
+        if let Some(dictionary) = LOADED.lock().unwrap().iter().find(|d| d.kind == kind) {
+            return Ok(dictionary.clone());
+        }
+        // Loaded without holding the lock; a second caller racing in only costs a duplicate load
+        let dictionary = Arc::new(Self::load(kind)?);
+        let mut loaded = LOADED.lock().unwrap();
+        if let Some(existing) = loaded.iter().find(|d| d.kind == kind) {
+            return Ok(existing.clone());
+        }
+        loaded.push(dictionary.clone());
+        Ok(dictionary)
+    }
+
+    /// 文を辞書の最長一致で語に区切る
+    pub fn segment(&self, text: &str) -> Vec<Segment> {
+        let count = text.chars().count();
+        let mut segments = Vec::new();
+        let mut start = 0;
+        while start < count {
+            let best = self.lookup(text, start).into_iter().next();
+            let segment = match best {
+                Some(m) => Segment {
+                    start,
+                    len: m.len,
+                    gloss: m.entries.first().and_then(|e| e.senses.first()).map(|s| short_gloss(s)),
+                },
+                None => Segment { start, len: 1, gloss: None },
+            };
+            start += segment.len;
+            segments.push(segment);
+        }
+        segments
+    }
+
+    /// 文の漢字を含む語に読み（JMdict はふりがな、CC-CEDICT は声調記号付きのピンイン）を付け、
+    /// ルビ記法（`｜漢字《かんじ》`）にして返す
+    pub fn with_readings(&self, text: &str) -> String {
+        let chars: Vec<char> = text.chars().collect();
+        let mut out = String::new();
+        let mut start = 0;
+        while start < chars.len() {
+            let best = self.lookup(text, start).into_iter().next();
+            let len = best.as_ref().map_or(1, |m| m.len);
+            let surface: String = chars[start..start + len].iter().collect();
+            let annotated = best.filter(|_| surface.chars().any(crate::ruby::is_kanji)).and_then(|m| {
+                // Entries matched by their reading have no kanji form to annotate
+                let entry = m.entries.iter().find(|e| e.headwords.contains(&m.form))?;
+                let reading = entry.readings.first()?;
+                match self.kind {
+                    DictionaryKind::JMdict => furigana(&surface, &m.form, reading),
+                    DictionaryKind::CcCedict => Some(pinyin(&surface, reading)),
+                }
+            });
+            out.push_str(annotated.as_deref().unwrap_or(&surface));
+            start += len;
+        }
+        out
+    }
+
+    /// `text` の `start` 文字目から始まる語を、長い一致から順に引く
+    pub fn lookup(&self, text: &str, start: usize) -> Vec<LookupMatch<'_>> {
+        let chars: Vec<char> = text.chars().collect();
+        let mut matches = Vec::new();
+        let mut seen: Vec<u32> = Vec::new();
+        let longest = chars.len().saturating_sub(start).min(MAX_WORD_CHARS);
+        for len in (1..=longest).rev() {
+            let surface: String = chars[start..start + len].iter().collect();
+            let mut forms = vec![surface.clone()];
+            if self.kind == DictionaryKind::JMdict {
+                forms.extend(deinflect(&surface));
+            }
+            for form in forms {
+                let Some(ids) = self.index.get(&form) else {
+                    continue;
+                };
+                let entries: Vec<&DictEntry> = ids
+                    .iter()
+                    .filter(|id| !seen.contains(id))
+                    .map(|&id| &self.entries[id as usize])
+                    .collect();
+                seen.extend(ids);
+                if !entries.is_empty() {
+                    matches.push(LookupMatch { len, form, entries });
+                }
+            }
+            if matches.len() >= MAX_MATCHES {
+                break;
+            }
+        }
+        matches
+    }
+}
+
+/// 原文の語 `surface`（辞書形 `form`、読み `reading`）のルビ記法。送り仮名などの読みと共通の
+/// かなは親文字から外す（"食べた" / "食べる" / "たべる" → "｜食《た》べた"）
+fn furigana(surface: &str, form: &str, reading: &str) -> Option<String> {
+    let form: Vec<char> = form.chars().collect();
+    let reading: Vec<char> = reading.chars().collect();
+    let surface: Vec<char> = surface.chars().collect();
+    let shared = form.len().min(reading.len());
+    let prefix = form.iter().zip(&reading).take_while(|(f, r)| f == r).count();
+    let suffix = form
+        .iter()
+        .rev()
+        .zip(reading.iter().rev())
+        .take_while(|(f, r)| f == r)
+        .count()
+        .min(shared - prefix);
+    let base = &form[prefix..form.len() - suffix];
+    let ruby = &reading[prefix..reading.len() - suffix];
+    // Inflection only changes the tail, so the surface starts with the form's kana prefix and base
+    let head = prefix + base.len();
+    if base.is_empty() || ruby.is_empty() || surface.len() < head || surface[..head] != form[..head] {
+        return None;
+    }
+    let collect = |chars: &[char]| chars.iter().collect::<String>();
+    Some(format!(
+        "{}｜{}《{}》{}",
+        collect(&surface[..prefix]),
+        collect(base),
+        collect(ruby),
+        collect(&surface[head..])
+    ))
+}
+
+/// 原文の語 `surface` に CC-CEDICT の読み（"ni3 hao3"）からピンインを付けたルビ記法。
+/// 音節と文字の数が合えば1字ずつ、合わなければ語全体に付ける
+fn pinyin(surface: &str, reading: &str) -> String {
+    let syllables: Vec<String> = reading.split(' ').map(tone_marked).collect();
+    let chars: Vec<char> = surface.chars().collect();
+    if syllables.len() != chars.len() {
+        return format!("｜{}《{}》", surface, syllables.join(" "));
+    }
+    chars
+        .iter()
+        .zip(&syllables)
+        .map(|(&c, syllable)| if crate::ruby::is_kanji(c) { format!("｜{}《{}》", c, syllable) } else { c.to_string() })
+        .collect()
+}
+
+/// 数字で声調を書いた音節（"hao3"、"lu:4"）を声調記号付きに（"hǎo"、"lǜ"）。軽声（5）は記号なし
+fn tone_marked(syllable: &str) -> String {
+    const MARKS: [(char, [char; 4]); 6] = [
+        ('a', ['ā', 'á', 'ǎ', 'à']),
+        ('e', ['ē', 'é', 'ě', 'è']),
+        ('i', ['ī', 'í', 'ǐ', 'ì']),
+        ('o', ['ō', 'ó', 'ǒ', 'ò']),
+        ('u', ['ū', 'ú', 'ǔ', 'ù']),
+        ('ü', ['ǖ', 'ǘ', 'ǚ', 'ǜ']),
+    ];
+    let syllable = syllable.replace("u:", "ü").replace("U:", "Ü");
+    let Some(tone) = syllable.chars().last().and_then(|c| c.to_digit(10)) else {
+        return syllable;
+    };
+    let base = &syllable[..syllable.len() - 1];
+    if !(1..=4).contains(&tone) {
+        return base.to_string();
+    }
+    // The mark goes on a or e, on the o of ou, and otherwise on the last vowel
+    let lower = base.to_lowercase();
+    let target = lower
+        .find('a')
+        .or_else(|| lower.find('e'))
+        .or_else(|| lower.find("ou"))
+        .or_else(|| lower.rfind(['i', 'o', 'u', 'ü']));
+    base.char_indices()
+        .map(|(i, c)| {
+            let lower_c = c.to_lowercase().next().unwrap_or(c);
+            match MARKS.iter().find(|(v, _)| *v == lower_c).filter(|_| Some(i) == target) {
+                Some((_, marks)) => {
+                    let marked = marks[tone as usize - 1];
+                    if c.is_uppercase() { marked.to_uppercase().next().unwrap_or(marked) } else { marked }
+                }
+                None => c,
+            }
+        })
+        .collect()
+}
+
+/// 語義の最初の訳だけを短く（"[n] tradition; custom" → "tradition"）
+fn short_gloss(sense: &str) -> String {
+    let sense = if sense.starts_with('[') { sense.split_once("] ").map_or(sense, |(_, s)| s) } else { sense };
+    let first = sense.split("; ").next().unwrap_or(sense);
+    let mut gloss: String = first.chars().take(MAX_GLOSS_CHARS).collect();
+    if first.chars().count() > MAX_GLOSS_CHARS {
+        gloss.push('…');
+    }
+    gloss
+}
+
+/// 活用した形から辞書形の候補を作る（辞書にない候補は引く時に落ちるので、品詞は見ない）
+fn deinflect(word: &str) -> Vec<String> {
+    // (い段, あ段, え段, お段, て形, た形) → 終止形
+    const GODAN: [(&str, &str, &str, &str, &str, &str, &str); 9] = [
+        ("い", "わ", "え", "お", "って", "った", "う"),
+        ("き", "か", "け", "こ", "いて", "いた", "く"),
+        ("ぎ", "が", "げ", "ご", "いで", "いだ", "ぐ"),
+        ("し", "さ", "せ", "そ", "して", "した", "す"),
+        ("ち", "た", "て", "と", "って", "った", "つ"),
+        ("に", "な", "ね", "の", "んで", "んだ", "ぬ"),
+        ("び", "ば", "べ", "ぼ", "んで", "んだ", "ぶ"),
+        ("み", "ま", "め", "も", "んで", "んだ", "む"),
+        ("り", "ら", "れ", "ろ", "って", "った", "る"),
+    ];
+    const ICHIDAN: [&str; 14] = [
+        "ます", "ました", "ません", "ない", "なかった", "た", "て", "られる", "させる", "よう", "れば", "たい", "ろ", "ず",
+    ];
+    const ADJECTIVE: [&str; 7] = ["く", "くて", "かった", "くない", "くなかった", "さ", "ければ"];
+    const SURU: [&str; 8] = ["します", "しました", "しません", "して", "した", "しない", "される", "させる"];
+
+    let mut rules: Vec<(String, &str)> = Vec::new();
+    for (i, a, e, o, te, ta, u) in GODAN {
+        for suffix in ["ます", "ました", "ません", "たい"] {
+            rules.push((format!("{}{}", i, suffix), u));
+        }
+        for suffix in ["ない", "なかった", "れる", "せる", "ず"] {
+            rules.push((format!("{}{}", a, suffix), u));
+        }
+        rules.push((format!("{}ば", e), u));
+        rules.push((format!("{}る", e), u));
+        rules.push((format!("{}う", o), u));
+        rules.push((te.to_string(), u));
+        rules.push((ta.to_string(), u));
+    }
+    rules.extend(ICHIDAN.iter().map(|s| (s.to_string(), "る")));
+    rules.extend(ADJECTIVE.iter().map(|s| (s.to_string(), "い")));
+
+    let mut forms = Vec::new();
+    for suffix in SURU {
+        if let Some(stem) = word.strip_suffix(suffix) {
+            forms.push(format!("{}する", stem));
+        }
+    }
+    for (suffix, ending) in &rules {
+        if let Some(stem) = word.strip_suffix(suffix.as_str()).filter(|s| !s.is_empty()) {
+            let form = format!("{}{}", stem, ending);
+            if !forms.contains(&form) {
+                forms.push(form);
+            }
+        }
+    }
+    forms
+}
+
+/// JMdict の XML から項目を取り出す（見出し・読み・語義だけを読む簡易パーサー）
+fn parse_jmdict(xml: &str) -> Vec<DictEntry> {
+    // Skip the DTD, whose comments describe the elements
+    let body = xml.find("<JMdict>").map_or(xml, |i| &xml[i..]);
+    let mut entries = Vec::new();
+    for entry in body.split("<entry>").skip(1) {
+        let entry = entry.split("</entry>").next().unwrap_or(entry);
+        let headwords: Vec<String> = tag_values(entry, "keb").map(decode_xml).collect();
+        let readings: Vec<String> = tag_values(entry, "reb").map(decode_xml).collect();
+        let senses: Vec<String> = entry
+            .split("<sense>")
+            .skip(1)
+            .filter_map(|sense| {
+                let glosses: Vec<String> = tag_values(sense, "gloss").map(decode_xml).collect();
+                if glosses.is_empty() {
+                    return None;
+                }
+                // Parts of speech are entity references such as &v5r;
+                let pos: Vec<&str> = tag_values(sense, "pos").map(|p| p.trim_matches(['&', ';'])).collect();
+                Some(if pos.is_empty() {
+                    glosses.join("; ")
+                } else {
+                    format!("[{}] {}", pos.join(", "), glosses.join("; "))
+                })
+            })
+            .collect();
+        if (!headwords.is_empty() || !readings.is_empty()) && !senses.is_empty() {
+            entries.push(DictEntry { headwords, readings, senses });
+        }
+    }
+    entries
+}
+
+/// CC-CEDICT の行（"繁體 简体 [pin1 yin1] /訳/訳/"）から項目を取り出す
+fn parse_cedict(text: &str) -> Vec<DictEntry> {
+    let mut entries = Vec::new();
+    for line in text.lines().filter(|l| !l.starts_with('#')) {
+        let Some((traditional, rest)) = line.split_once(' ') else {
+            continue;
+        };
+        let Some((simplified, rest)) = rest.split_once(' ') else {
+            continue;
+        };
+        let Some((reading, glosses)) = rest.strip_prefix('[').and_then(|r| r.split_once(']')) else {
+            continue;
+        };
+        let senses: Vec<String> = glosses.split('/').map(str::trim).filter(|g| !g.is_empty()).map(str::to_string).collect();
+        if senses.is_empty() {
+            continue;
+        }
+        let mut headwords = vec![simplified.to_string()];
+        if traditional != simplified {
+            headwords.push(traditional.to_string());
+        }
+        entries.push(DictEntry { headwords, readings: vec![reading.to_string()], senses });
+    }
+    entries
+}
+
+/// `<tag>…</tag>`（属性付きも可）の中身を順に返す
+fn tag_values<'a>(s: &'a str, tag: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+    let open = format!("<{}", tag);
+    let close = format!("</{}>", tag);
+    let mut rest = s;
+    std::iter::from_fn(move || loop {
+        let start = rest.find(&open)?;
+        let after = &rest[start + open.len()..];
+        // <keb> but not <keb_pri>
+        if !after.starts_with(['>', ' ']) {
+            rest = after;
+            continue;
+        }
+        let content_start = after.find('>')? + 1;
+        let content = &after[content_start..];
+        let end = content.find(&close)?;
+        rest = &content[end + close.len()..];
+        return Some(&content[..end]);
+    })
+}
+
+fn decode_xml(s: &str) -> String {
+    s.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deinflect_finds_dictionary_forms() {
+        assert!(deinflect("食べた").contains(&"食べる".to_string()));
+        assert!(deinflect("書いて").contains(&"書く".to_string()));
+        assert!(deinflect("読まない").contains(&"読む".to_string()));
+        assert!(deinflect("勉強しました").contains(&"勉強する".to_string()));
+        assert!(deinflect("高かった").contains(&"高い".to_string()));
+        assert!(deinflect("た").is_empty());
+    }
+
+    #[test]
+    fn parse_cedict_reads_entries() {
+        let text = "# CC-CEDICT\n中國 中国 [Zhong1 guo2] /China/Middle Kingdom/\n你好 你好 [ni3 hao3] /hello/\nbroken line\n";
+        let entries = parse_cedict(text);
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].headwords, vec!["中国", "中國"]);
+        assert_eq!(entries[0].readings, vec!["Zhong1 guo2"]);
+        assert_eq!(entries[0].senses, vec!["China", "Middle Kingdom"]);
+        assert_eq!(entries[1].headwords, vec!["你好"]);
+    }
+
+    #[test]
+    fn furigana_leaves_okurigana_outside_the_base() {
+        assert_eq!(furigana("食べた", "食べる", "たべる").as_deref(), Some("｜食《た》べた"));
+        assert_eq!(furigana("お茶", "お茶", "おちゃ").as_deref(), Some("お｜茶《ちゃ》"));
+        assert_eq!(furigana("東京", "東京", "とうきょう").as_deref(), Some("｜東京《とうきょう》"));
+        assert_eq!(furigana("たべる", "たべる", "たべる"), None);
+    }
+
+    #[test]
+    fn tone_marked_places_the_mark() {
+        assert_eq!(tone_marked("hao3"), "hǎo");
+        assert_eq!(tone_marked("gou3"), "gǒu");
+        assert_eq!(tone_marked("liu2"), "liú");
+        assert_eq!(tone_marked("lu:4"), "lǜ");
+        assert_eq!(tone_marked("Zhong1"), "Zhōng");
+        assert_eq!(tone_marked("ma5"), "ma");
+        assert_eq!(tone_marked("xx"), "xx");
+    }
+}
@@ -0,0 +1,302 @@
+//! OCR 結果と訳文のライブ配信（ローカルの WebSocket）
+//!
+//! 有効にすると `ws://127.0.0.1:<port>` で待ち受け、接続したクライアントへイベントを1つずつ
+//! JSON のテキストメッセージで送る（配信用オーバーレイ・ログ・読み上げなどの外部ツール向け）。
+//! クライアントからのメッセージは無視する。座標はゲームのクライアント領域のピクセル。
+//!
+//! ```text
+//! {"time":"2025-01-01T12:00:00.000+09:00","type":"ocr","lines":[{"text":"Hello","box":{"x":10,"y":20,"width":100,"height":16}}]}
+//! {"time":"...","type":"translation","engine":"DeepL","source_lang":"EN","target_lang":"JA","lines":[{"text":"Hello","translation":"こんにちは","box":{...}}]}
+//! {"time":"...","type":"clear"}
+//! ```
+//!
+//! `ocr` は画面の文字が変わるたび、`translation` は新しく表示した訳文ごと、`clear` は
+//! オーバーレイを消した時に送る。
+//!
+//! 同じポートへの普通の HTTP リクエストには、このイベントを字幕として表示するページ
+//! （`subtitles.html`、OBS のブラウザソース用）を返す。
+
+use std::net::{Ipv4Addr, SocketAddr};
+use std::sync::OnceLock;
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use futures::{SinkExt, StreamExt};
+use serde::Serialize;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{broadcast, oneshot};
+use tokio_tungstenite::tungstenite::Message;
+
+use crate::ocr::TextRegion;
+
+/// 読み切れていないイベントがこれを超えたクライアントは古いものから読み飛ばす
+const FEED_BUFFER: usize = 256;
+/// リクエストヘッダーの上限と、届くまで待つ時間
+const MAX_REQUEST_HEAD: usize = 8192;
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+/// ヘッダーの続きを待つ間隔（届かないほど長くする）
+const PEEK_RETRY_MIN: Duration = Duration::from_millis(10);
+const PEEK_RETRY_MAX: Duration = Duration::from_millis(200);
+
+/// OBS のブラウザソースに指定する字幕ページ
+const SUBTITLES_PAGE: &str = include_str!("subtitles.html");
+
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum FeedEvent<'a> {
+    Ocr {
+        lines: Vec<FeedLine<'a>>,
+    },
+    Translation {
+        engine: &'a str,
+        source_lang: &'a str,
+        target_lang: &'a str,
+        lines: Vec<FeedLine<'a>>,
+    },
+    Clear,
+}
+
+#[derive(Serialize)]
+pub struct FeedLine<'a> {
+    pub text: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub translation: Option<&'a str>,
+    #[serde(rename = "box")]
+    pub bounds: FeedBox,
+}
+
+impl<'a> FeedLine<'a> {
+    pub fn new(region: &'a TextRegion, translation: Option<&'a str>) -> Self {
+        Self {
+            text: &region.text,
+            translation,
+            bounds: FeedBox { x: region.x, y: region.y, width: region.width, height: region.block_height },
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct FeedBox {
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+}
+
+#[derive(Serialize)]
+struct Envelope<'a> {
+    time: String,
+    #[serde(flatten)]
+    event: FeedEvent<'a>,
+}
+
+fn sender() -> &'static broadcast::Sender<String> {
+    static SENDER: OnceLock<broadcast::Sender<String>> = OnceLock::new();
+    SENDER.get_or_init(|| broadcast::channel(FEED_BUFFER).0)
+}
+
+/// 接続中のクライアントへイベントを送る（誰も接続していなければ何もしない）
+pub fn publish(event: FeedEvent) {
+    let sender = sender();
+    if sender.receiver_count() == 0 {
+        return;
+    }
+    let envelope = Envelope {
+        time: chrono::Local::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, false),
+        event,
+    };
+    if let Ok(json) = serde_json::to_string(&envelope) {
+        let _ = sender.send(json);
+    }
+}
+
+/// 待ち受け中の WebSocket サーバー。drop で止める
+pub struct FeedServer {
+    port: u16,
+    shutdown: Option<oneshot::Sender<()>>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl FeedServer {
+    /// 127.0.0.1 だけで待ち受ける（ポートが使用中ならエラー）
+    pub fn start(port: u16) -> Result<Self> {
+        let listener = std::net::TcpListener::bind((Ipv4Addr::LOCALHOST, port))
+            .with_context(|| format!("Failed to listen on port {}", port))?;
+        listener.set_nonblocking(true)?;
+        let (shutdown, mut shutdown_rx) = oneshot::channel();
+        let handle = std::thread::spawn(move || {
+            let rt = match tokio::runtime::Builder::new_current_thread().enable_all().build() {
+                Ok(rt) => rt,
+                Err(e) => {
+                    crate::log_always(&format!("[FEED] 配信を開始できません: {}", e));
+                    return;
+                }
+            };
+            // 止める時はランタイムごと捨てるので、接続ごとのタスクもここで終わる
+            rt.block_on(async move {
+                let Ok(listener) = TcpListener::from_std(listener) else {
+                    return;
+                };
+                loop {
+                    tokio::select! {
+                        _ = &mut shutdown_rx => break,
+                        accepted = listener.accept() => match accepted {
+                            Ok((stream, addr)) => {
+                                tokio::spawn(serve(stream, addr, port));
+                            }
+                            Err(e) => crate::log(&format!("[FEED] accept failed: {}", e)),
+                        },
+                    }
+                }
+            });
+        });
+        crate::log_always(&format!("[FEED] ws://127.0.0.1:{} で配信開始", port));
+        Ok(Self { port, shutdown: Some(shutdown), handle: Some(handle) })
+    }
+
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+}
+
+impl Drop for FeedServer {
+    fn drop(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+        crate::log_always(&format!("[FEED] ポート {} の配信を停止", self.port));
+    }
+}
+
+/// WebSocket のアップグレードならイベントを流し、それ以外は字幕ページを返す
+async fn serve(stream: TcpStream, addr: SocketAddr, port: u16) {
+    match tokio::time::timeout(REQUEST_TIMEOUT, peek_request_head(&stream)).await {
+        Ok(Ok((head, len))) if is_websocket_upgrade(&head) && !is_allowed_origin(&head, port) => {
+            crate::log_always(&format!("[FEED] {} を拒否 (Origin: {})", addr, header(&head, "origin").unwrap_or_default()));
+            reject(stream, len).await;
+        }
+        Ok(Ok((head, _))) if is_websocket_upgrade(&head) => stream_events(stream, addr).await,
+        Ok(Ok((head, len))) => serve_page(stream, &head, len).await,
+        Ok(Err(e)) => crate::log(&format!("[FEED] {} read failed: {}", addr, e)),
+        Err(_) => crate::log(&format!("[FEED] {} sent no request", addr)),
+    }
+}
+
+/// リクエストヘッダーを読み取らずに覗く（WebSocket ならハンドシェイクにそのまま渡すため）。
+/// ヘッダーと、空行までを含めた受信バイト数を返す
+async fn peek_request_head(stream: &TcpStream) -> std::io::Result<(String, usize)> {
+    let mut buf = vec![0; MAX_REQUEST_HEAD];
+    let mut delay = PEEK_RETRY_MIN;
+    loop {
+        stream.readable().await?;
+        let n = stream.peek(&mut buf).await?;
+        if n == 0 {
+            return Err(std::io::ErrorKind::UnexpectedEof.into());
+        }
+        // 不正な UTF-8 を置換すると長さが変わるので、区切りはバイト列のまま探す
+        if let Some(end) = buf[..n].windows(4).position(|w| w == b"\r\n\r\n") {
+            return Ok((String::from_utf8_lossy(&buf[..end]).into_owned(), end + 4));
+        }
+        if n == buf.len() {
+            return Err(std::io::Error::other("request head too large"));
+        }
+        // peek は読み取らないので、途中まで届いた状態では読み取り可能のまますぐ戻ってくる。
+        // 遅いクライアントでランタイムを回し続けないよう、間隔を広げながら続きを待つ
+        tokio::time::sleep(delay).await;
+        delay = (delay * 2).min(PEEK_RETRY_MAX);
+    }
+}
+
+/// リクエストヘッダーの値（名前は大文字小文字を区別しない）
+fn header<'a>(head: &'a str, name: &str) -> Option<&'a str> {
+    head.lines().skip(1).find_map(|line| {
+        let (key, value) = line.split_once(':')?;
+        key.trim().eq_ignore_ascii_case(name).then(|| value.trim())
+    })
+}
+
+fn is_websocket_upgrade(head: &str) -> bool {
+    header(head, "upgrade").is_some_and(|value| value.eq_ignore_ascii_case("websocket"))
+}
+
+/// ブラウザで開いている他のページからは読ませない。Origin のない接続（ブラウザ以外のツール）と、
+/// このサーバーが返した字幕ページからの接続だけ受け付ける
+fn is_allowed_origin(head: &str, port: u16) -> bool {
+    let Some(origin) = header(head, "origin") else {
+        return true;
+    };
+    [format!("http://127.0.0.1:{}", port), format!("http://localhost:{}", port)]
+        .iter()
+        .any(|allowed| origin.eq_ignore_ascii_case(allowed))
+}
+
+async fn reject(mut stream: TcpStream, head_len: usize) {
+    let mut consumed = vec![0; head_len];
+    if stream.read_exact(&mut consumed).await.is_err() {
+        return;
+    }
+    let _ = stream
+        .write_all(b"HTTP/1.1 403 Forbidden\r\nContent-Length: 0\r\nConnection: close\r\n\r\n")
+        .await;
+    let _ = stream.shutdown().await;
+}
+
+async fn serve_page(mut stream: TcpStream, head: &str, head_len: usize) {
+    // 覗いただけのヘッダーを読み捨てる
+    let mut consumed = vec![0; head_len];
+    if stream.read_exact(&mut consumed).await.is_err() {
+        return;
+    }
+    let path = head.split_whitespace().nth(1).unwrap_or("/");
+    let response = if path == "/" || path.starts_with("/?") {
+        format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: text/html; charset=utf-8\r\nContent-Length: {}\r\nCache-Control: no-store\r\nConnection: close\r\n\r\n{}",
+            SUBTITLES_PAGE.len(),
+            SUBTITLES_PAGE
+        )
+    } else {
+        "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_string()
+    };
+    let _ = stream.write_all(response.as_bytes()).await;
+    let _ = stream.shutdown().await;
+}
+
+/// 1つのクライアントへイベントを流す
+async fn stream_events(stream: TcpStream, addr: SocketAddr) {
+    let ws = match tokio_tungstenite::accept_async(stream).await {
+        Ok(ws) => ws,
+        Err(e) => {
+            crate::log(&format!("[FEED] {} handshake failed: {}", addr, e));
+            return;
+        }
+    };
+    crate::log_always(&format!("[FEED] {} が接続", addr));
+    let (mut outgoing, mut incoming) = ws.split();
+    let mut events = sender().subscribe();
+    loop {
+        tokio::select! {
+            event = events.recv() => match event {
+                Ok(json) => {
+                    if outgoing.send(Message::text(json)).await.is_err() {
+                        break;
+                    }
+                }
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    crate::log(&format!("[FEED] {} が遅れているため {}件を読み飛ばし", addr, skipped));
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            },
+            // ping への応答は tungstenite が返す。それ以外のメッセージは読み捨てる
+            message = incoming.next() => match message {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+    crate::log_always(&format!("[FEED] {} が切断", addr));
+}
@@ -0,0 +1,154 @@
+//! 用語集の適用
+//!
+//! 翻訳前に原文中の用語をプレースホルダー（{{0}} など）に置き換えてエンジンに送り、
+//! 訳文のプレースホルダーを訳語（翻訳しない語なら原文の用語）に戻す。
+//! エンジンに依存しないので DeepL でも LLM でも同じように効く。
+//! エンジンがプレースホルダーを崩した行は、用語集なしで訳し直す（`Translator::translate_batch`）。
+
+use crate::config::{GlossaryEntry, GlossaryMatch};
+
+#[derive(Debug, Clone, Default)]
+pub struct Glossary {
+    /// 部分一致・単語一致の項目（長い用語から先に試す）
+    inline: Vec<GlossaryEntry>,
+    /// 行全体の一致の項目
+    exact: Vec<GlossaryEntry>,
+}
+
+impl Glossary {
+    /// 用語が空の項目は無視する
+    pub fn new(entries: &[GlossaryEntry]) -> Self {
+        let (exact, mut inline): (Vec<_>, Vec<_>) = entries
+            .iter()
+            .filter(|e| !e.term.trim().is_empty())
+            .cloned()
+            .partition(|e| e.mode == GlossaryMatch::Exact);
+        inline.sort_by_key(|e| std::cmp::Reverse(e.term.chars().count()));
+        Self { inline, exact }
+    }
+
+    /// 行全体が用語と一致すれば、その訳（API不要）
+    pub fn exact(&self, text: &str) -> Option<String> {
+        let text = text.trim();
+        self.exact
+            .iter()
+            .find(|e| e.term.trim() == text)
+            .map(|e| replacement(e).to_string())
+    }
+
+    /// 用語をプレースホルダーに置き換えた原文と、各プレースホルダーに戻す語
+    pub fn protect(&self, text: &str) -> (String, Vec<String>) {
+        if self.inline.is_empty() {
+            return (text.to_string(), Vec::new());
+        }
+        let mut out = String::with_capacity(text.len());
+        let mut replacements: Vec<String> = Vec::new();
+        let mut rest = text;
+        let mut prev: Option<char> = None;
+        'scan: while let Some(c) = rest.chars().next() {
+            for e in &self.inline {
+                let Some(after) = rest.strip_prefix(e.term.as_str()) else {
+                    continue;
+                };
+                if e.mode == GlossaryMatch::Word
+                    && (prev.is_some_and(char::is_alphanumeric) || after.chars().next().is_some_and(char::is_alphanumeric))
+                {
+                    continue;
+                }
+                let word = replacement(e).to_string();
+                let index = replacements.iter().position(|r| *r == word).unwrap_or_else(|| {
+                    replacements.push(word);
+                    replacements.len() - 1
+                });
+                out.push_str(&placeholder(index));
+                prev = e.term.chars().last();
+                rest = after;
+                continue 'scan;
+            }
+            out.push(c);
+            prev = Some(c);
+            rest = &rest[c.len_utf8()..];
+        }
+        (out, replacements)
+    }
+
+    /// 訳文のプレースホルダーを語に戻す。
+    /// エンジンがプレースホルダーを落とした・書き換えた時は None（用語が抜けた訳になるため）。
+    pub fn restore(translated: &str, replacements: &[String]) -> Option<String> {
+        let mut out = translated.to_string();
+        for (i, word) in replacements.iter().enumerate() {
+            let placeholder = placeholder(i);
+            if !out.contains(&placeholder) {
+                return None;
+            }
+            out = out.replace(&placeholder, word);
+        }
+        Some(out)
+    }
+}
+
+fn replacement(entry: &GlossaryEntry) -> &str {
+    if entry.translation.trim().is_empty() {
+        &entry.term
+    } else {
+        &entry.translation
+    }
+}
+
+fn placeholder(index: usize) -> String {
+    format!("{{{{{}}}}}", index)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(term: &str, translation: &str, mode: GlossaryMatch) -> GlossaryEntry {
+        GlossaryEntry { term: term.to_string(), translation: translation.to_string(), mode }
+    }
+
+    fn glossary() -> Glossary {
+        Glossary::new(&[
+            entry("Potion", "ポーション", GlossaryMatch::Word),
+            entry("HP", "", GlossaryMatch::Substring),
+            entry("Yes", "はい", GlossaryMatch::Exact),
+            entry("  ", "空", GlossaryMatch::Substring),
+        ])
+    }
+
+    #[test]
+    fn exact_matches_whole_lines_only() {
+        assert_eq!(glossary().exact("  Yes "), Some("はい".to_string()));
+        assert_eq!(glossary().exact("Yes!"), None);
+    }
+
+    #[test]
+    fn word_match_respects_word_boundaries() {
+        let (text, replacements) = glossary().protect("Drink a Potion. Potions?");
+        assert_eq!(text, "Drink a {{0}}. Potions?");
+        assert_eq!(replacements, vec!["ポーション"]);
+    }
+
+    #[test]
+    fn substring_match_and_shared_placeholders() {
+        let (text, replacements) = glossary().protect("MaxHP: Potion or Potion");
+        assert_eq!(text, "Max{{0}}: {{1}} or {{1}}");
+        // An empty translation keeps the term untranslated
+        assert_eq!(replacements, vec!["HP", "ポーション"]);
+    }
+
+    #[test]
+    fn restore_replaces_placeholders() {
+        let replacements = vec!["ポーション".to_string()];
+        assert_eq!(Glossary::restore("{{0}}を飲む", &replacements), Some("ポーションを飲む".to_string()));
+        assert_eq!(Glossary::restore("{{0}}と{{0}}", &replacements), Some("ポーションとポーション".to_string()));
+        assert_eq!(Glossary::restore("訳文", &[]), Some("訳文".to_string()));
+    }
+
+    #[test]
+    fn restore_rejects_lost_placeholders() {
+        let replacements = vec!["ポーション".to_string()];
+        assert_eq!(Glossary::restore("薬を飲む", &replacements), None);
+        assert_eq!(Glossary::restore("{{ 0 }}を飲む", &replacements), None);
+    }
+}
@@ -0,0 +1,284 @@
+//! 言語コードの正規化とエンジンごとの表記変換
+
+use crate::config::TranslationEngine;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Language {
+    /// 自動検出（翻訳元のみ）
+    Auto,
+    English,
+    Japanese,
+    ChineseSimplified,
+    ChineseTraditional,
+    Korean,
+    French,
+    German,
+    Spanish,
+    Italian,
+    PortugueseBrazil,
+    PortuguesePortugal,
+    Russian,
+    Polish,
+    Dutch,
+    Turkish,
+    Ukrainian,
+    Indonesian,
+    Arabic,
+    Hebrew,
+}
+
+impl Language {
+    pub const ALL: [Language; 20] = [
+        Self::Auto,
+        Self::English,
+        Self::Japanese,
+        Self::ChineseSimplified,
+        Self::ChineseTraditional,
+        Self::Korean,
+        Self::French,
+        Self::German,
+        Self::Spanish,
+        Self::Italian,
+        Self::PortugueseBrazil,
+        Self::PortuguesePortugal,
+        Self::Russian,
+        Self::Polish,
+        Self::Dutch,
+        Self::Turkish,
+        Self::Ukrainian,
+        Self::Indonesian,
+        Self::Arabic,
+        Self::Hebrew,
+    ];
+
+    /// 設定ファイルに保存する正規コード
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Auto => "AUTO",
+            Self::English => "EN",
+            Self::Japanese => "JA",
+            Self::ChineseSimplified => "ZH-HANS",
+            Self::ChineseTraditional => "ZH-HANT",
+            Self::Korean => "KO",
+            Self::French => "FR",
+            Self::German => "DE",
+            Self::Spanish => "ES",
+            Self::Italian => "IT",
+            Self::PortugueseBrazil => "PT-BR",
+            Self::PortuguesePortugal => "PT-PT",
+            Self::Russian => "RU",
+            Self::Polish => "PL",
+            Self::Dutch => "NL",
+            Self::Turkish => "TR",
+            Self::Ukrainian => "UK",
+            Self::Indonesian => "ID",
+            Self::Arabic => "AR",
+            Self::Hebrew => "HE",
+        }
+    }
+
+    /// LLMのプロンプトで使う英語名
+    pub fn english_name(&self) -> &'static str {
+        match self {
+            Self::Auto => "the source language",
+            Self::English => "English",
+            Self::Japanese => "Japanese",
+            Self::ChineseSimplified => "Simplified Chinese",
+            Self::ChineseTraditional => "Traditional Chinese",
+            Self::Korean => "Korean",
+            Self::French => "French",
+            Self::German => "German",
+            Self::Spanish => "Spanish",
+            Self::Italian => "Italian",
+            Self::PortugueseBrazil => "Brazilian Portuguese",
+            Self::PortuguesePortugal => "European Portuguese",
+            Self::Russian => "Russian",
+            Self::Polish => "Polish",
+            Self::Dutch => "Dutch",
+            Self::Turkish => "Turkish",
+            Self::Ukrainian => "Ukrainian",
+            Self::Indonesian => "Indonesian",
+            Self::Arabic => "Arabic",
+            Self::Hebrew => "Hebrew",
+        }
+    }
+
+    /// GUI表示名
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            Self::Auto => "自動検出",
+            Self::English => "英語",
+            Self::Japanese => "日本語",
+            Self::ChineseSimplified => "中国語（簡体字）",
+            Self::ChineseTraditional => "中国語（繁体字）",
+            Self::Korean => "韓国語",
+            Self::French => "フランス語",
+            Self::German => "ドイツ語",
+            Self::Spanish => "スペイン語",
+            Self::Italian => "イタリア語",
+            Self::PortugueseBrazil => "ポルトガル語（ブラジル）",
+            Self::PortuguesePortugal => "ポルトガル語（ポルトガル）",
+            Self::Russian => "ロシア語",
+            Self::Polish => "ポーランド語",
+            Self::Dutch => "オランダ語",
+            Self::Turkish => "トルコ語",
+            Self::Ukrainian => "ウクライナ語",
+            Self::Indonesian => "インドネシア語",
+            Self::Arabic => "アラビア語",
+            Self::Hebrew => "ヘブライ語",
+        }
+    }
+
+    /// DeepL の source_lang（Auto は None = 自動検出）。DeepL は翻訳元で地域差を区別しない。
+    /// DeepL にない言語も None（`supported_by` で弾く）。
+    pub fn deepl_source(&self) -> Option<&'static str> {
+        match self {
+            Self::Auto | Self::Hebrew => None,
+            Self::ChineseSimplified | Self::ChineseTraditional => Some("ZH"),
+            Self::PortugueseBrazil | Self::PortuguesePortugal => Some("PT"),
+            other => Some(other.code()),
+        }
+    }
+
+    /// DeepL の target_lang（DeepL にない言語は None）。翻訳先の "EN" は非推奨のため "EN-US" を使う。
+    pub fn deepl_target(&self) -> Option<&'static str> {
+        match self {
+            Self::Auto | Self::Hebrew => None,
+            Self::English => Some("EN-US"),
+            other => Some(other.code()),
+        }
+    }
+
+    /// 縦書きで組める言語か
+    pub fn supports_vertical(&self) -> bool {
+        matches!(self, Self::Japanese | Self::ChineseSimplified | Self::ChineseTraditional)
+    }
+
+    /// エンジンが翻訳元・翻訳先として扱えるか。
+    /// LLM 系は言語名で指示するのでどれでもよく、DeepL は対応する言語コードがあるものだけ。
+    pub fn supported_by(&self, engine: TranslationEngine, as_target: bool) -> bool {
+        match (engine, as_target) {
+            (_, true) if *self == Self::Auto => false,
+            (TranslationEngine::DeepL, false) => *self == Self::Auto || self.deepl_source().is_some(),
+            (TranslationEngine::DeepL, true) => self.deepl_target().is_some(),
+            _ => true,
+        }
+    }
+
+    /// 右から左へ書く言語か
+    pub fn is_rtl(&self) -> bool {
+        matches!(self, Self::Arabic | Self::Hebrew)
+    }
+
+    /// 言語コード・言語名を解釈。"en-US" のような地域付きタグも受け付ける。
+    pub fn parse(s: &str) -> Option<Self> {
+        let norm = s.trim().to_uppercase().replace('_', "-");
+        let found = Self::ALL.into_iter().find(|l| {
+            l.code() == norm
+                || l.english_name().eq_ignore_ascii_case(s.trim())
+                || l.display_name() == s.trim()
+        });
+        if found.is_some() {
+            return found;
+        }
+        match norm.as_str() {
+            "ZH" | "ZH-CN" | "ZH-SG" => Some(Self::ChineseSimplified),
+            "ZH-TW" | "ZH-HK" => Some(Self::ChineseTraditional),
+            "PT" => Some(Self::PortugueseBrazil),
+            // 旧ISOコード
+            "IW" => Some(Self::Hebrew),
+            "" => None,
+            _ => {
+                // 地域サブタグを除いて再判定（"EN-GB" → "EN"）
+                let primary = norm.split('-').next().unwrap_or("");
+                if primary != norm {
+                    Self::parse(primary)
+                } else {
+                    None
+                }
+            }
+        }
+    }
+}
+
+/// よくある誤記（国コード）に対する正しい言語コードの提案
+fn suggestion(s: &str) -> Option<&'static str> {
+    match s.trim().to_uppercase().as_str() {
+        "JP" => Some("JA"),
+        "CN" => Some("ZH-HANS"),
+        "TW" => Some("ZH-HANT"),
+        "KR" => Some("KO"),
+        "BR" => Some("PT-BR"),
+        "UA" => Some("UK"),
+        "GB" | "US" => Some("EN"),
+        _ => None,
+    }
+}
+
+/// 言語ペア検証で見つかった問題。表示用の文言は GUI 側で組み立てる。
+#[derive(Debug, Clone, PartialEq)]
+pub enum PairError {
+    /// 言語コードを解釈できない。`hint` はよくある誤記に対する提案
+    UnknownCode { is_target: bool, code: String, hint: Option<&'static str> },
+    /// ターゲット言語に自動検出が指定された
+    AutoTarget,
+    /// ソース言語とターゲット言語が同じ
+    SameLanguage,
+    /// DeepL が対応していない組み合わせ
+    UnsupportedByDeepL(Language, Language),
+}
+
+fn parse_with_hint(s: &str, is_target: bool) -> Result<Language, PairError> {
+    Language::parse(s).ok_or_else(|| PairError::UnknownCode {
+        is_target,
+        code: s.trim().to_string(),
+        hint: suggestion(s),
+    })
+}
+
+/// 開始前の言語ペア検証
+pub fn validate_pair(engine: TranslationEngine, source: &str, target: &str) -> Result<(Language, Language), PairError> {
+    let src = parse_with_hint(source, false)?;
+    let tgt = parse_with_hint(target, true)?;
+
+    if tgt == Language::Auto {
+        return Err(PairError::AutoTarget);
+    }
+    if src == tgt {
+        return Err(PairError::SameLanguage);
+    }
+    if engine == TranslationEngine::DeepL && (!src.supported_by(engine, false) || !tgt.supported_by(engine, true)) {
+        return Err(PairError::UnsupportedByDeepL(src, tgt));
+    }
+    Ok((src, tgt))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_accepts_codes_tags_and_names() {
+        assert_eq!(Language::parse("en"), Some(Language::English));
+        assert_eq!(Language::parse(" en-US "), Some(Language::English));
+        assert_eq!(Language::parse("en_GB"), Some(Language::English));
+        assert_eq!(Language::parse("Japanese"), Some(Language::Japanese));
+        assert_eq!(Language::parse("auto"), Some(Language::Auto));
+        assert_eq!(Language::parse("pt-PT"), Some(Language::PortuguesePortugal));
+    }
+
+    #[test]
+    fn parse_maps_aliases() {
+        assert_eq!(Language::parse("zh"), Some(Language::ChineseSimplified));
+        assert_eq!(Language::parse("zh-TW"), Some(Language::ChineseTraditional));
+        assert_eq!(Language::parse("pt"), Some(Language::PortugueseBrazil));
+        assert_eq!(Language::parse("iw"), Some(Language::Hebrew));
+    }
+
+    #[test]
+    fn parse_rejects_unknown() {
+        assert_eq!(Language::parse(""), None);
+        assert_eq!(Language::parse("xx"), None);
+        assert_eq!(Language::parse("xx-YY"), None);
+    }
+}
@@ -0,0 +1,74 @@
+//! Game Translator のコア: ゲーム画面のキャプチャ → OCR → 翻訳 → オーバーレイ表示
+//!
+//! GUI（`game_translator`）はこのクレートの薄いフロントエンド。CLI や別の GUI からも
+//! 同じパイプラインを使える。
+//!
+//! - [`config`] — 設定（`config.toml`）の読み書き
+//! - [`capture`] — 対象ウィンドウのキャプチャ
+//! - [`ocr`] — Windows OCR によるテキスト領域の検出
+//! - [`texthook`] — OCR の代わりに Textractor / Agent から文を受け取る入力
+//! - [`audio`] — ゲーム音声を whisper.cpp で文字に起こす入力
+//! - [`translate`] — DeepL / Groq / ローカル LLM での翻訳
+//! - [`plugin`] — 外部プロセスの翻訳エンジン（`plugins` フォルダのマニフェストで追加）
+//! - [`cache`] — 翻訳キャッシュ
+//! - [`dictionary`] — 原文の語を JMdict / CC-CEDICT で引く
+//! - [`overlay`] — 訳文を重ねる透明ウィンドウと、フロントエンドとの共有状態（[`overlay::OverlayControls`]）
+//! - [`feed`] — OCR 結果と訳文を外部ツールへ流すローカルの WebSocket サーバー
+//! - [`pipeline`] — 1つの対象ウィンドウに対してすべてを動かす [`pipeline::run_overlay_thread`]
+//!
+//! セッションは [`pipeline::run_overlay_thread`] を専用スレッドで呼んで始め、
+//! 停止フラグを立ててオーバーレイへ `WM_CLOSE` を送ると終わる。
+
+pub mod audio;
+pub mod cache;
+pub mod capture;
+pub mod clipboard;
+pub mod config;
+pub mod dictionary;
+pub mod feed;
+pub mod glossary;
+pub mod lang;
+pub mod linebreak;
+pub mod ocr;
+pub mod overlay;
+pub mod pipeline;
+pub mod plugin;
+pub mod readout;
+pub mod ruby;
+pub mod texthook;
+pub mod translate;
+
+use std::io::Write;
+use std::sync::OnceLock;
+
+pub fn write_log(path: &std::path::Path, msg: &str) {
+    if let Ok(mut f) = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+    {
+        let _ = writeln!(f, "{}", msg);
+    }
+}
+
+pub fn debug_log_path() -> &'static std::path::PathBuf {
+    static PATH: OnceLock<std::path::PathBuf> = OnceLock::new();
+    PATH.get_or_init(|| {
+        let now = chrono::Local::now();
+        let filename = now.format("debug_%Y.%m.%d_%H.%M.%S.log").to_string();
+        crate::config::data_dir().join(filename)
+    })
+}
+
+/// デバッグフラグON時のみ出力
+pub fn log(msg: &str) {
+    if !crate::config::is_debug_log() {
+        return;
+    }
+    write_log(debug_log_path(), msg);
+}
+
+/// 常に出力（エラー・起動・停止など重要イベント）
+pub fn log_always(msg: &str) {
+    write_log(debug_log_path(), msg);
+}
@@ -0,0 +1,62 @@
+//! 日本語の禁則処理
+//!
+//! DirectWrite の改行位置に任せると句読点や閉じ括弧が行頭に来ることがあるため、
+//! 禁則文字の前後に WORD JOINER (U+2060) を挟んでその位置での改行を禁止する。
+//! WORD JOINER は幅を持たないので表示には影響しない。
+
+const WORD_JOINER: char = '\u{2060}';
+
+/// 行頭に置かない文字（句読点・閉じ括弧・小書きの仮名・長音など）
+fn is_no_leading(c: char) -> bool {
+    matches!(c,
+        '、' | '。' | '，' | '．' | '・' | '：' | '；' | '？' | '！' | '‼' | '⁉'
+        | 'ゝ' | 'ゞ' | 'ヽ' | 'ヾ' | '々' | 'ー' | '〜' | '～' | '…' | '‥'
+        | 'ぁ' | 'ぃ' | 'ぅ' | 'ぇ' | 'ぉ' | 'っ' | 'ゃ' | 'ゅ' | 'ょ' | 'ゎ' | 'ゕ' | 'ゖ'
+        | 'ァ' | 'ィ' | 'ゥ' | 'ェ' | 'ォ' | 'ッ' | 'ャ' | 'ュ' | 'ョ' | 'ヮ' | 'ヵ' | 'ヶ'
+        | '）' | '」' | '』' | '】' | '〕' | '〉' | '］' | '｝' | '〙' | '〗' | '”' | '’'
+        | ',' | '.' | '!' | '?' | ':' | ';' | ')' | ']' | '}')
+}
+
+/// 行末に置かない文字（開き括弧）
+fn is_no_trailing(c: char) -> bool {
+    matches!(c, '（' | '「' | '『' | '【' | '〔' | '〈' | '［' | '｛' | '〘' | '〖' | '“' | '‘' | '(' | '[' | '{')
+}
+
+/// 禁則文字の前後で改行されないよう WORD JOINER を挿入する。
+/// ルビ記法の「｜《》」は対象外なので `ruby::parse` の前に適用してよい。
+pub fn apply_kinsoku(text: &str) -> String {
+    let mut out = String::with_capacity(text.len() + text.len() / 4);
+    let mut prev: Option<char> = None;
+    for c in text.chars() {
+        if let Some(p) = prev {
+            if (is_no_leading(c) || is_no_trailing(p)) && !p.is_whitespace() && !c.is_whitespace() {
+                out.push(WORD_JOINER);
+            }
+        }
+        out.push(c);
+        prev = Some(c);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn joins_punctuation_to_the_previous_char() {
+        assert_eq!(apply_kinsoku("はい。"), "はい\u{2060}。");
+        assert_eq!(apply_kinsoku("Hello, world"), "Hello\u{2060}, world");
+    }
+
+    #[test]
+    fn joins_opening_brackets_to_the_next_char() {
+        assert_eq!(apply_kinsoku("「はい」"), "「\u{2060}はい\u{2060}」");
+    }
+
+    #[test]
+    fn leaves_whitespace_breakable() {
+        assert_eq!(apply_kinsoku("( a )"), "( a )");
+        assert_eq!(apply_kinsoku("ふつうの文"), "ふつうの文");
+    }
+}
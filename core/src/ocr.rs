@@ -4,6 +4,8 @@ use windows::Graphics::Imaging::*;
 use windows::Media::Ocr::*;
 use windows::Win32::System::WinRT::IMemoryBufferByteAccess;
 
+use crate::lang::Language;
+
 /// OCRの生の行データ
 struct RawLine {
     text: String,
@@ -11,23 +13,53 @@ struct RawLine {
     y: i32,
     width: i32,
     height: i32,
+    words: Vec<OcrWord>,
+}
+
+/// OCR が返した単語の枠（日本語・中国語では1〜数文字ずつ）
+#[derive(Clone)]
+pub struct OcrWord {
+    pub text: String,
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+    /// 段落の中で何行目か
+    pub line: usize,
 }
 
 /// 段落グループ化済みのテキスト領域
+#[derive(Clone)]
 pub struct TextRegion {
     pub text: String,
     pub x: i32,
     pub y: i32,
     pub width: i32,
+    /// 最も高い行の高さ（フォントサイズの基準）
     pub height: i32,
+    /// 段落全体の高さ（先頭行の上端〜最終行の下端）
+    pub block_height: i32,
+    /// 段落に含まれる単語の枠（語ごとの訳語表示用。テキストフック・音声の文では空）
+    pub words: Vec<OcrWord>,
 }
 
 pub struct OCREngine {
     engine: OcrEngine,
+    /// 使用中の認識言語タグ（"ja", "en-US" など）
+    language: String,
+}
+
+/// ソース言語コードに対応する OCR 言語タグの接頭辞（自動検出・不明な言語は英語）
+pub fn preferred_language(source_lang: &str) -> String {
+    match Language::parse(source_lang) {
+        Some(Language::Auto) | None => "en".to_string(),
+        Some(l) => l.code().to_lowercase(),
+    }
 }
 
 impl OCREngine {
-    pub fn new() -> Result<Self> {
+    /// `preferred` で始まる言語タグの認識言語を優先し、なければ英語、それもなければ先頭の言語を使う
+    pub fn new(preferred: &str) -> Result<Self> {
         let available_languages = OcrEngine::AvailableRecognizerLanguages()
             .map_err(|e| anyhow::anyhow!("Failed to get available OCR languages: {:?}", e))?;
 
@@ -38,36 +70,41 @@ impl OCREngine {
             anyhow::bail!("No OCR languages available.");
         }
 
-        let mut engine_opt = None;
+        let mut languages = Vec::new();
         for i in 0..count {
             let lang = available_languages.GetAt(i)
                 .map_err(|e| anyhow::anyhow!("Failed to get language at index {}: {:?}", i, e))?;
             let tag = lang.LanguageTag()
-                .map_err(|e| anyhow::anyhow!("Failed to get language tag: {:?}", e))?;
+                .map_err(|e| anyhow::anyhow!("Failed to get language tag: {:?}", e))?
+                .to_string();
 
             crate::log_always(&format!("Available OCR language: {}", tag));
-
-            if tag.to_string().to_lowercase().starts_with("en") {
-                engine_opt = OcrEngine::TryCreateFromLanguage(&lang).ok();
-                if engine_opt.is_some() {
-                    crate::log_always("Using English OCR");
-                    break;
-                }
-            }
+            languages.push((tag, lang));
         }
 
-        if engine_opt.is_none() {
-            let lang = available_languages.GetAt(0)?;
-            let tag = lang.LanguageTag()?;
-            crate::log_always(&format!("English not found, using: {}", tag));
-            engine_opt = OcrEngine::TryCreateFromLanguage(&lang).ok();
-        }
+        let preferred = preferred.to_lowercase();
+        let find = |prefix: &str| {
+            languages.iter().find_map(|(tag, lang)| {
+                if !tag.to_lowercase().starts_with(prefix) {
+                    return None;
+                }
+                OcrEngine::TryCreateFromLanguage(lang).ok().map(|engine| (tag.clone(), engine))
+            })
+        };
+        let (language, engine) = find(&preferred)
+            .or_else(|| {
+                crate::log_always(&format!("OCR language {} not found, trying English", preferred));
+                find("en")
+            })
+            .or_else(|| find(""))
+            .ok_or_else(|| anyhow::anyhow!("Failed to create OCR engine from any available language"))?;
+        crate::log_always(&format!("Using OCR language: {}", language));
 
-        let engine = engine_opt.ok_or_else(|| {
-            anyhow::anyhow!("Failed to create OCR engine from any available language")
-        })?;
+        Ok(Self { engine, language })
+    }
 
-        Ok(Self { engine })
+    pub fn language(&self) -> &str {
+        &self.language
     }
 
     /// 近い行を段落としてグループ化
@@ -77,6 +114,8 @@ impl OCREngine {
         }
 
         let mut paragraphs: Vec<TextRegion> = Vec::new();
+        let mut current_words = lines[0].words.clone();
+        let mut current_line = 0;
         let mut current_text = lines[0].text.clone();
         let mut current_x = lines[0].x;
         let mut current_y = lines[0].y;
@@ -94,6 +133,8 @@ impl OCREngine {
             if gap >= 0 && gap < threshold && x_diff < prev_height * 2 {
                 current_text.push(' ');
                 current_text.push_str(&line.text);
+                current_line += 1;
+                current_words.extend(line.words.iter().map(|w| OcrWord { line: current_line, ..w.clone() }));
                 if line.width > current_max_width {
                     current_max_width = line.width;
                 }
@@ -107,7 +148,11 @@ impl OCREngine {
                     y: current_y,
                     width: current_max_width,
                     height: current_max_height,
+                    block_height: prev_y + prev_height - current_y,
+                    words: std::mem::take(&mut current_words),
                 });
+                current_words = line.words.clone();
+                current_line = 0;
                 current_text = line.text.clone();
                 current_x = line.x;
                 current_y = line.y;
@@ -126,6 +171,8 @@ impl OCREngine {
             y: current_y,
             width: current_max_width,
             height: current_max_height,
+            block_height: prev_y + prev_height - current_y,
+            words: current_words,
         });
 
         paragraphs
@@ -174,9 +221,18 @@ impl OCREngine {
                     // 行全体の高さと幅を取得
                     let mut max_height = first_rect.Height;
                     let mut right_edge = first_rect.X + first_rect.Width;
+                    let mut word_boxes = Vec::new();
                     for w in 0..words.Size()? {
                         let word = words.GetAt(w)?;
                         let rect = word.BoundingRect()?;
+                        word_boxes.push(OcrWord {
+                            text: word.Text()?.to_string(),
+                            x: rect.X as i32,
+                            y: rect.Y as i32,
+                            width: rect.Width as i32,
+                            height: rect.Height as i32,
+                            line: 0,
+                        });
                         if rect.Height > max_height {
                             max_height = rect.Height;
                         }
@@ -192,6 +248,7 @@ impl OCREngine {
                         y: first_rect.Y as i32,
                         width: (right_edge - first_rect.X) as i32,
                         height: max_height as i32,
+                        words: word_boxes,
                     });
                 }
             }
@@ -0,0 +1,2325 @@
+use anyhow::Result;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::ops::RangeInclusive;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use crate::cache::CacheEntry;
+use crate::config::{AppConfig, DisplayMode, StylePreset, TextAlign, TranslationEngine, VerticalAnchor};
+use crate::lang::Language;
+use crate::linebreak;
+use crate::ocr::TextRegion;
+use crate::ruby::{self, RubySpan};
+use windows::Win32::Foundation::*;
+use windows::Win32::Graphics::Direct2D::Common::*;
+use windows::Win32::Graphics::Direct2D::*;
+use windows::Win32::Graphics::Direct3D::D3D_DRIVER_TYPE_HARDWARE;
+use windows::Win32::Graphics::Direct3D11::*;
+use windows::Win32::Graphics::DirectComposition::*;
+use windows::Win32::Graphics::DirectWrite::*;
+use windows::Win32::Graphics::Dxgi::Common::*;
+use windows::Win32::Graphics::Dxgi::*;
+use windows::Win32::Graphics::Gdi::*;
+use windows::Win32::Graphics::Imaging::*;
+use windows::Win32::System::Com::*;
+use windows::Win32::UI::WindowsAndMessaging::*;
+use windows::core::*;
+
+/// DirectComposition swap chain bound to an overlay window. D2D draws straight into the
+/// swap chain's back buffer, so frames never take the GDI / UpdateLayeredWindow round trip.
+struct CompositionSurface {
+    context: ID2D1DeviceContext,
+    swap_chain: IDXGISwapChain1,
+    dcomp_device: IDCompositionDevice,
+    /// Whole-window opacity (user opacity x fade), applied by the compositor
+    effect: IDCompositionEffectGroup,
+    // Kept alive for as long as the window shows the swap chain
+    _target: IDCompositionTarget,
+    _visual: IDCompositionVisual,
+}
+
+impl CompositionSurface {
+    fn new(factory: &ID2D1Factory1, hwnd: HWND, width: u32, height: u32) -> Result<Self> {
+        unsafe {
+            let mut d3d_device = None;
+            D3D11CreateDevice(
+                None,
+                D3D_DRIVER_TYPE_HARDWARE,
+                HMODULE::default(),
+                D3D11_CREATE_DEVICE_BGRA_SUPPORT,
+                None,
+                D3D11_SDK_VERSION,
+                Some(&mut d3d_device),
+                None,
+                None,
+            )?;
+            let d3d_device = d3d_device.ok_or_else(|| anyhow::anyhow!("D3D11CreateDevice returned no device"))?;
+            let dxgi_device: IDXGIDevice = d3d_device.cast()?;
+
+            let context = factory
+                .CreateDevice(&dxgi_device)?
+                .CreateDeviceContext(D2D1_DEVICE_CONTEXT_OPTIONS_NONE)?;
+            // Keep D2D units equal to pixels; the capture loop works in screen pixels
+            context.SetDpi(96.0, 96.0);
+
+            let dxgi_factory: IDXGIFactory2 = CreateDXGIFactory2(DXGI_CREATE_FACTORY_FLAGS(0))?;
+            let desc = DXGI_SWAP_CHAIN_DESC1 {
+                Width: width.max(1),
+                Height: height.max(1),
+                Format: DXGI_FORMAT_B8G8R8A8_UNORM,
+                SampleDesc: DXGI_SAMPLE_DESC { Count: 1, Quality: 0 },
+                BufferUsage: DXGI_USAGE_RENDER_TARGET_OUTPUT,
+                BufferCount: 2,
+                Scaling: DXGI_SCALING_STRETCH,
+                SwapEffect: DXGI_SWAP_EFFECT_FLIP_SEQUENTIAL,
+                AlphaMode: DXGI_ALPHA_MODE_PREMULTIPLIED,
+                ..Default::default()
+            };
+            let swap_chain = dxgi_factory.CreateSwapChainForComposition(&d3d_device, &desc, None)?;
+
+            let dcomp_device: IDCompositionDevice = DCompositionCreateDevice(&dxgi_device)?;
+            let target = dcomp_device.CreateTargetForHwnd(hwnd, true)?;
+            let visual = dcomp_device.CreateVisual()?;
+            let effect = dcomp_device.CreateEffectGroup()?;
+            visual.SetContent(&swap_chain)?;
+            visual.SetEffect(&effect)?;
+            target.SetRoot(&visual)?;
+            dcomp_device.Commit()?;
+
+            Ok(Self { context, swap_chain, dcomp_device, effect, _target: target, _visual: visual })
+        }
+    }
+
+    fn resize(&self, width: u32, height: u32) -> Result<()> {
+        unsafe {
+            // The back buffer must not be bound while the swap chain resizes
+            self.context.SetTarget(None);
+            self.swap_chain.ResizeBuffers(0, width.max(1), height.max(1), DXGI_FORMAT_UNKNOWN, DXGI_SWAP_CHAIN_FLAG(0))?;
+        }
+        Ok(())
+    }
+
+    /// Bind the current back buffer and start a frame.
+    fn begin_draw(&self) -> Result<()> {
+        unsafe {
+            let buffer: IDXGISurface = self.swap_chain.GetBuffer(0)?;
+            let props = D2D1_BITMAP_PROPERTIES1 {
+                pixelFormat: D2D1_PIXEL_FORMAT {
+                    format: DXGI_FORMAT_B8G8R8A8_UNORM,
+                    alphaMode: D2D1_ALPHA_MODE_PREMULTIPLIED,
+                },
+                dpiX: 96.0,
+                dpiY: 96.0,
+                bitmapOptions: D2D1_BITMAP_OPTIONS_TARGET | D2D1_BITMAP_OPTIONS_CANNOT_DRAW,
+                ..Default::default()
+            };
+            let bitmap = self.context.CreateBitmapFromDxgiSurface(&buffer, Some(&props))?;
+            self.context.SetTarget(&bitmap);
+            self.context.BeginDraw();
+            self.context.Clear(Some(&D2D1_COLOR_F { r: 0.0, g: 0.0, b: 0.0, a: 0.0 }));
+        }
+        Ok(())
+    }
+
+    fn end_draw(&self) -> Result<()> {
+        unsafe {
+            let result = self.context.EndDraw(None, None);
+            self.context.SetTarget(None);
+            result?;
+            self.swap_chain.Present(1, DXGI_PRESENT(0)).ok()?;
+        }
+        Ok(())
+    }
+
+    /// Blur `backdrop` once into an offscreen bitmap and wrap it in a brush that boxes can
+    /// be filled with. Call outside begin_draw/end_draw.
+    fn blurred_brush(&self, backdrop: &Backdrop, std_dev: f32) -> Result<ID2D1BitmapBrush1> {
+        unsafe {
+            let size = D2D_SIZE_U { width: backdrop.width, height: backdrop.height };
+            let mut props = D2D1_BITMAP_PROPERTIES1 {
+                pixelFormat: D2D1_PIXEL_FORMAT {
+                    format: DXGI_FORMAT_B8G8R8A8_UNORM,
+                    alphaMode: D2D1_ALPHA_MODE_PREMULTIPLIED,
+                },
+                dpiX: 96.0,
+                dpiY: 96.0,
+                ..Default::default()
+            };
+            let source = self.context.CreateBitmap(
+                size,
+                Some(backdrop.pixels.as_ptr() as *const _),
+                backdrop.width * 4,
+                &props,
+            )?;
+            props.bitmapOptions = D2D1_BITMAP_OPTIONS_TARGET;
+            let blurred = self.context.CreateBitmap(size, None, 0, &props)?;
+
+            let blur = self.context.CreateEffect(&CLSID_D2D1GaussianBlur)?;
+            blur.SetInput(0, &source, true);
+            blur.SetValue(
+                D2D1_GAUSSIANBLUR_PROP_STANDARD_DEVIATION.0 as u32,
+                D2D1_PROPERTY_TYPE_FLOAT,
+                &std_dev.to_le_bytes(),
+            )?;
+            // Hard edges keep the frame's borders from fading to transparent
+            blur.SetValue(
+                D2D1_GAUSSIANBLUR_PROP_BORDER_MODE.0 as u32,
+                D2D1_PROPERTY_TYPE_ENUM,
+                &(D2D1_BORDER_MODE_HARD.0 as u32).to_le_bytes(),
+            )?;
+
+            self.context.SetTarget(&blurred);
+            self.context.BeginDraw();
+            self.context.DrawImage(
+                &blur.GetOutput()?,
+                None,
+                None,
+                D2D1_INTERPOLATION_MODE_LINEAR,
+                D2D1_COMPOSITE_MODE_SOURCE_COPY,
+            );
+            let result = self.context.EndDraw(None, None);
+            self.context.SetTarget(None);
+            result?;
+
+            let brush_props = D2D1_BITMAP_BRUSH_PROPERTIES1 {
+                extendModeX: D2D1_EXTEND_MODE_CLAMP,
+                extendModeY: D2D1_EXTEND_MODE_CLAMP,
+                interpolationMode: D2D1_INTERPOLATION_MODE_LINEAR,
+            };
+            Ok(self.context.CreateBitmapBrush(&blurred, Some(&brush_props), None)?)
+        }
+    }
+
+    fn set_opacity(&self, opacity: f32) -> Result<()> {
+        unsafe {
+            self.effect.SetOpacity2(opacity)?;
+            self.dcomp_device.Commit()?;
+        }
+        Ok(())
+    }
+}
+
+/// D2DERR_RECREATE_TARGET from D2D, or the GPU going away under the swap chain
+fn is_device_lost(e: &anyhow::Error) -> bool {
+    e.downcast_ref::<windows::core::Error>().is_some_and(|we| {
+        we.code() == D2DERR_RECREATE_TARGET
+            || we.code() == DXGI_ERROR_DEVICE_REMOVED
+            || we.code() == DXGI_ERROR_DEVICE_RESET
+    })
+}
+
+#[derive(Clone)]
+pub struct TranslatedText {
+    /// OCR text this translation came from (copied alongside it on click)
+    pub source_text: String,
+    pub translated_text: String,
+    pub x: f32,
+    pub y: f32,
+    pub max_width: f32,
+    pub font_size: f32,
+    /// Height of the source block; auto-fit shrinks the font to stay within it (0 = unbounded).
+    /// For vertical text this is the column length instead.
+    pub max_height: f32,
+    /// Set top-to-bottom with columns flowing right-to-left
+    pub vertical: bool,
+    /// Centre of the source OCR region in game client coordinates (for hiding by position)
+    pub source_center: (i32, i32),
+    /// Height of the source block the box is anchored to (for middle anchoring; 0 = top)
+    pub anchor_height: f32,
+    /// Average relative luminance of the game pixels behind this text (for auto-contrast)
+    pub bg_luminance: Option<f32>,
+    /// Style zone the source lies in (index into `OverlayConfig::zones`; None = base look)
+    pub zone: Option<usize>,
+}
+
+/// Pipeline state shown as a small badge in the overlay's corner (none while idle)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PipelineStatus {
+    Translating,
+    ApiError,
+    RateLimited,
+    /// Paused from the GUI or a hotkey (OCR and translation are stopped)
+    Paused,
+}
+
+impl PipelineStatus {
+    fn label(&self) -> &'static str {
+        match self {
+            Self::Translating => "翻訳中…",
+            Self::ApiError => "APIエラー",
+            Self::RateLimited => "レート制限中",
+            Self::Paused => "一時停止中",
+        }
+    }
+
+    fn color(&self) -> [f32; 4] {
+        match self {
+            Self::Translating => [0.2, 0.45, 0.85, 0.85],
+            Self::ApiError => [0.8, 0.15, 0.15, 0.9],
+            Self::RateLimited => [0.85, 0.55, 0.1, 0.9],
+            Self::Paused => [0.4, 0.4, 0.4, 0.85],
+        }
+    }
+}
+
+/// Downscaled copy of the captured game frame, blurred behind the boxes when the acrylic
+/// background is on.
+pub struct Backdrop {
+    /// BGRA, top-down
+    pub pixels: Vec<u8>,
+    pub width: u32,
+    pub height: u32,
+    /// Screen pixels per backdrop pixel
+    pub scale: f32,
+}
+
+/// Background of the performance HUD
+const HUD_BACKGROUND: [f32; 4] = [0.0, 0.0, 0.0, 0.7];
+
+/// Outline colour of OCR regions in debug mode (magenta stands out on most game art)
+const DEBUG_REGION_COLOR: [f32; 4] = [1.0, 0.0, 1.0, 0.9];
+
+/// Blur strength of the acrylic background, in backdrop pixels
+const ACRYLIC_BLUR: f32 = 6.0;
+/// Share of the background colour's alpha kept as a tint over the blur
+const ACRYLIC_TINT: f32 = 0.6;
+
+/// One line of the on-screen history panel
+#[derive(Clone)]
+pub struct HistoryEntry {
+    /// Local time the line was first shown ("HH:MM:SS")
+    pub time: String,
+    pub text: String,
+}
+
+/// Rows the history panel shows at once; the rest is reached by scrolling
+const HISTORY_ROWS: usize = 6;
+const HISTORY_WIDTH: f32 = 480.0;
+const HISTORY_FONT_SIZE: f32 = 14.0;
+
+/// Configuration for overlay appearance
+#[derive(Clone, PartialEq)]
+pub struct OverlayConfig {
+    pub text_color: [f32; 4],  // RGBA
+    pub bg_color: [f32; 4],    // RGBA
+    /// Primary font family; CJK glyphs it lacks are resolved via the fallback chain
+    pub font_family: String,
+    /// Outline thickness in pixels (0 = no outline)
+    pub outline_width: f32,
+    pub outline_color: [f32; 4], // RGBA
+    /// Swap to a high-contrast text/background pair when the configured one is unreadable
+    pub auto_contrast: bool,
+    /// Background box corner radius in pixels (0 = sharp corners)
+    pub corner_radius: f32,
+    /// Border thickness in pixels (0 = no border)
+    pub border_width: f32,
+    pub border_color: [f32; 4], // RGBA
+    /// Briefly highlight a box after it was copied in interactive mode
+    pub copy_flash: bool,
+    /// Push each box below the previous one (side-panel mode, where boxes share a column)
+    pub stack_vertically: bool,
+    /// Move overlapping boxes apart (overlay mode)
+    pub avoid_overlap: bool,
+    /// Fade duration in milliseconds (0 = no animation)
+    pub fade_ms: u32,
+    /// Shrink the font until the translation fits its source region
+    pub auto_fit: bool,
+    /// Font size clamps applied after the user scale; the lower one also stops auto-fit
+    pub min_font_size: f32,
+    pub max_font_size: f32,
+    /// Maximum lines per box before truncating with "…" (0 = unlimited)
+    pub max_lines: u32,
+    /// Target language is written right-to-left (Arabic, Hebrew)
+    pub rtl: bool,
+    /// Show the pipeline status badge
+    pub status_badge: bool,
+    /// Translated lines kept in the history panel (0 = no panel)
+    pub history_lines: u32,
+    /// Japanese line breaking: kinsoku rules, and over-long words break mid-word
+    pub cjk_line_breaking: bool,
+    /// Fill boxes with the blurred game frame under a tint of `bg_color`
+    pub acrylic: bool,
+    /// Outline every OCR region (for tuning detection)
+    pub debug_regions: bool,
+    /// Black veil over the whole game area while boxes are shown (0.0 = off)
+    pub dim: f32,
+    /// Fill boxes with a top-to-bottom gradient from `bg_color` to `bg_color2`
+    pub bg_gradient: bool,
+    pub bg_color2: [f32; 4],
+    /// Image drawn as a 9-slice box skin instead of the fill (empty = none)
+    pub skin_path: String,
+    /// Width of the skin's fixed corners/edges, in image pixels
+    pub skin_slice: f32,
+    /// Line alignment inside horizontal boxes
+    pub text_align: TextAlign,
+    /// Align the box's top or middle with its source block
+    pub vertical_anchor: VerticalAnchor,
+    /// Per-zone looks, indexed by `TranslatedText::zone`
+    pub zones: Vec<BoxStyle>,
+}
+
+/// The look of one box: the base settings, or a style zone's preset
+#[derive(Clone, PartialEq)]
+pub struct BoxStyle {
+    pub font_family: String,
+    /// Multiplier on the box's font size
+    pub font_scale: f32,
+    pub text_color: [f32; 4],
+    pub bg_color: [f32; 4],
+    pub outline_width: f32,
+    pub outline_color: [f32; 4],
+    pub corner_radius: f32,
+    pub border_width: f32,
+    pub border_color: [f32; 4],
+}
+
+impl BoxStyle {
+    fn from_preset(preset: &StylePreset, font_scale: f32) -> Self {
+        Self {
+            font_family: preset.font_family.clone(),
+            font_scale,
+            text_color: preset.text_color,
+            bg_color: preset.bg_color,
+            outline_width: if preset.outline_enabled { preset.outline_width } else { 0.0 },
+            outline_color: preset.outline_color,
+            corner_radius: preset.corner_radius,
+            border_width: preset.border_width,
+            border_color: preset.border_color,
+        }
+    }
+}
+
+impl Default for OverlayConfig {
+    fn default() -> Self {
+        Self {
+            text_color: [1.0, 1.0, 0.0, 1.0],
+            bg_color: [0.0, 0.0, 0.0, 0.85],
+            font_family: "Yu Gothic UI".to_string(),
+            outline_width: 0.0,
+            outline_color: [0.0, 0.0, 0.0, 1.0],
+            auto_contrast: false,
+            corner_radius: 6.0,
+            border_width: 0.0,
+            border_color: [1.0, 1.0, 1.0, 0.6],
+            copy_flash: true,
+            stack_vertically: false,
+            avoid_overlap: true,
+            fade_ms: 150,
+            auto_fit: true,
+            min_font_size: 10.0,
+            max_font_size: 72.0,
+            max_lines: 8,
+            rtl: false,
+            status_badge: true,
+            history_lines: 0,
+            cjk_line_breaking: true,
+            acrylic: false,
+            debug_regions: false,
+            dim: 0.0,
+            bg_gradient: false,
+            bg_color2: [0.1, 0.1, 0.2, 0.85],
+            skin_path: String::new(),
+            skin_slice: 16.0,
+            text_align: TextAlign::Left,
+            vertical_anchor: VerticalAnchor::Top,
+            zones: Vec::new(),
+        }
+    }
+}
+
+impl OverlayConfig {
+    pub fn from_config(config: &AppConfig) -> Self {
+        Self {
+            text_color: config.overlay_text_color,
+            bg_color: config.overlay_bg_color,
+            font_family: config.overlay_font_family.clone(),
+            outline_width: if config.overlay_outline_enabled { config.overlay_outline_width } else { 0.0 },
+            outline_color: config.overlay_outline_color,
+            auto_contrast: config.overlay_auto_contrast,
+            corner_radius: config.overlay_corner_radius,
+            border_width: config.overlay_border_width,
+            border_color: config.overlay_border_color,
+            copy_flash: config.overlay_copy_flash,
+            stack_vertically: config.display_mode != DisplayMode::Overlay,
+            avoid_overlap: config.overlay_avoid_overlap,
+            fade_ms: config.overlay_fade_ms,
+            auto_fit: config.overlay_auto_fit,
+            min_font_size: config.overlay_min_font_size,
+            max_font_size: config.overlay_max_font_size.max(config.overlay_min_font_size),
+            max_lines: config.overlay_max_lines,
+            rtl: Language::parse(&config.target_lang).is_some_and(|l| l.is_rtl()),
+            status_badge: config.overlay_status_badge,
+            history_lines: config.overlay_history_lines,
+            cjk_line_breaking: config.overlay_cjk_line_breaking,
+            acrylic: config.overlay_acrylic,
+            debug_regions: config.overlay_debug_regions,
+            // Side panels sit beside the game, so there is nothing under them to dim
+            dim: if config.display_mode == DisplayMode::Overlay { config.overlay_dim.clamp(0.0, 1.0) } else { 0.0 },
+            bg_gradient: config.overlay_bg_gradient,
+            bg_color2: config.overlay_bg_color2,
+            skin_path: config.overlay_skin_path.clone(),
+            skin_slice: config.overlay_skin_slice as f32,
+            text_align: config.overlay_text_align,
+            vertical_anchor: config.overlay_vertical_anchor,
+            zones: config
+                .overlay_zones
+                .iter()
+                .map(|zone| {
+                    // An unknown preset keeps the base look, so the zone still applies its scale
+                    let preset = config
+                        .find_preset(&zone.preset)
+                        .unwrap_or_else(|| StylePreset::from_config(&zone.name, config));
+                    BoxStyle::from_preset(&preset, zone.font_scale)
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Range of the user font-scale multiplier applied on top of the OCR-derived size
+pub const FONT_SCALE_RANGE: RangeInclusive<f32> = 0.5..=3.0;
+
+/// A setting shared between the GUI sliders and the overlay thread (opacity, font scale),
+/// clamped to its range. Stored as f32 bits so both sides can update it lock-free.
+pub struct SharedF32 {
+    bits: AtomicU32,
+    range: RangeInclusive<f32>,
+}
+
+impl SharedF32 {
+    pub fn new(value: f32, range: RangeInclusive<f32>) -> Self {
+        let bits = AtomicU32::new(value.clamp(*range.start(), *range.end()).to_bits());
+        Self { bits, range }
+    }
+
+    pub fn get(&self) -> f32 {
+        f32::from_bits(self.bits.load(Ordering::SeqCst))
+    }
+
+    pub fn set(&self, value: f32) {
+        let value = value.clamp(*self.range.start(), *self.range.end());
+        self.bits.store(value.to_bits(), Ordering::SeqCst);
+    }
+}
+
+/// Runtime overlay state shared between the GUI and the overlay thread.
+/// Either side may change it; the other picks it up on its next message/frame.
+pub struct OverlayControls {
+    pub opacity: SharedF32,
+    /// Multiplier on every box's font size
+    pub font_scale: SharedF32,
+    /// true = overlay accepts mouse input (WS_EX_TRANSPARENT removed)
+    pub interactive: AtomicBool,
+    /// Regions dismissed with a right click, skipped by the capture loop
+    pub hidden: Mutex<HiddenRegions>,
+    /// true = overlay windows are left out of screen capture / recordings
+    pub exclude_from_capture: AtomicBool,
+    /// false = overlay windows are hidden (toggled by hotkey; capture keeps running)
+    pub visible: AtomicBool,
+    /// true = the capture loop skips OCR and translation and keeps the overlay clear
+    pub paused: AtomicBool,
+    /// Set to make the capture loop re-read the screen and translate it again, bypassing the cache
+    pub retranslate: AtomicBool,
+    /// true while the GUI's OCR preview is open, so the capture loop only builds one when needed
+    pub preview_enabled: AtomicBool,
+    /// Latest captured frame and its OCR results, for the GUI's preview
+    pub preview: Mutex<Option<OcrPreview>>,
+    /// Lines translated this session, oldest first, for the GUI's history tab
+    pub session_log: Mutex<SessionLog>,
+    /// Box Ctrl+clicked in interactive mode, for the GUI's dictionary popup
+    pub lookup: Mutex<Option<LookupRequest>>,
+    /// Gloss mode: true = full translations are shown instead of the word glosses (Ctrl+Alt+T)
+    pub gloss_show_translation: AtomicBool,
+    /// Pipeline counters for the GUI's statistics panel
+    pub stats: Mutex<SessionStats>,
+    /// true = the overlay shows a performance HUD built from `stats` (Ctrl+Alt+F)
+    pub perf_hud: AtomicBool,
+    /// Appearance edited in the GUI while running, picked up on WM_APPLY_CONFIG
+    pub pending_config: Mutex<Option<OverlayConfig>>,
+    /// Translations corrected and pinned in the GUI's history tab, merged into the cache by the capture loop
+    pub pending_pins: Mutex<Vec<CacheEntry>>,
+    /// Latest pipeline failure, shown as a banner and a notification by the GUI (cleared on recovery)
+    pub error: Mutex<Option<PipelineError>>,
+    errors_reported: AtomicU64,
+}
+
+impl OverlayControls {
+    pub fn new(opacity: f32, font_scale: f32, exclude_from_capture: bool) -> Self {
+        Self {
+            opacity: SharedF32::new(opacity, 0.0..=1.0),
+            font_scale: SharedF32::new(font_scale, FONT_SCALE_RANGE),
+            interactive: AtomicBool::new(false),
+            hidden: Mutex::new(HiddenRegions::default()),
+            exclude_from_capture: AtomicBool::new(exclude_from_capture),
+            visible: AtomicBool::new(true),
+            paused: AtomicBool::new(false),
+            retranslate: AtomicBool::new(false),
+            preview_enabled: AtomicBool::new(false),
+            preview: Mutex::new(None),
+            session_log: Mutex::new(SessionLog::default()),
+            lookup: Mutex::new(None),
+            gloss_show_translation: AtomicBool::new(false),
+            stats: Mutex::new(SessionStats::default()),
+            perf_hud: AtomicBool::new(false),
+            pending_config: Mutex::new(None),
+            pending_pins: Mutex::new(Vec::new()),
+            error: Mutex::new(None),
+            errors_reported: AtomicU64::new(0),
+        }
+    }
+
+    /// Record a failure for the GUI to surface
+    pub fn report_error(&self, message: String, fatal: bool) {
+        *self.error.lock().unwrap() = Some(PipelineError {
+            seq: self.errors_reported.fetch_add(1, Ordering::SeqCst) + 1,
+            time: chrono::Local::now().format("%H:%M:%S").to_string(),
+            message,
+            fatal,
+        });
+    }
+}
+
+/// A translation or capture failure in a running session
+#[derive(Clone)]
+pub struct PipelineError {
+    /// Increases with every report, so the GUI notifies once per failure
+    pub seq: u64,
+    /// Local time it happened ("HH:MM:SS")
+    pub time: String,
+    pub message: String,
+    /// true = the capture loop stopped because of it
+    pub fatal: bool,
+}
+
+/// Counters for one session, updated by the pipeline stages
+#[derive(Clone, Default)]
+pub struct SessionStats {
+    /// Frames captured (or lines received from a text source), counted by the capture stage
+    pub frames: u64,
+    /// Frames actually read, counted by the OCR stage after stale and unchanged frames are skipped
+    pub ocr_runs: u64,
+    /// Frames not read because they were unchanged or went stale waiting for OCR
+    pub frames_skipped: u64,
+    /// Changed lines that were already in the cache / had to be translated
+    pub cache_hits: u64,
+    pub cache_misses: u64,
+    pub api_calls: u64,
+    /// Source characters successfully translated by the API
+    pub chars_translated: u64,
+    /// Capture-to-render time summed over every frame that redrew the overlay
+    pub latency_total: Duration,
+    pub latency_samples: u32,
+    /// Result of the latest capture: None before the first one, Some(false) if nothing was captured
+    pub capture_ok: Option<bool>,
+    pub last_capture: Duration,
+    pub last_ocr: Duration,
+    /// Duration of the latest API request, successful or not
+    pub last_translation: Option<Duration>,
+    /// Lines sent in the API request currently in flight
+    pub queue_depth: usize,
+    /// Failed API requests (each retry counts)
+    pub api_errors: u64,
+}
+
+impl SessionStats {
+    pub fn cache_hit_rate(&self) -> Option<f32> {
+        let total = self.cache_hits + self.cache_misses;
+        (total > 0).then(|| self.cache_hits as f32 / total as f32)
+    }
+
+    pub fn average_latency(&self) -> Option<Duration> {
+        (self.latency_samples > 0).then(|| self.latency_total / self.latency_samples)
+    }
+}
+
+/// One line shown during a session
+#[derive(Clone)]
+pub struct SessionRecord {
+    /// Local time it first appeared ("YYYY-MM-DD HH:MM:SS")
+    pub time: String,
+    pub engine: TranslationEngine,
+    pub source: String,
+    /// Where the line was in the captured frame (x, y, width, height in pixels)
+    pub region: (i32, i32, i32, i32),
+    pub translation: String,
+    pub source_lang: String,
+    pub target_lang: String,
+    /// true = a human translation the API result never replaces
+    pub pinned: bool,
+    /// Time since the session started when it appeared, for subtitle export
+    pub shown_at: Duration,
+    /// Time since the session started when it left the screen (None while still shown)
+    pub hidden_at: Option<Duration>,
+    /// PNG of the line's region as it appeared (only with history_screenshots, OCR input only)
+    pub screenshot: Option<Arc<Vec<u8>>>,
+}
+
+/// Most lines the history keeps; the oldest go first (screenshots make long sessions heavy)
+const SESSION_LOG_LIMIT: usize = 5000;
+
+/// The session's lines, capped at `SESSION_LOG_LIMIT`. Lines are addressed by id (their
+/// number since the app started), which stays valid while older lines are dropped.
+#[derive(Default)]
+pub struct SessionLog {
+    /// Oldest first
+    pub records: Vec<SessionRecord>,
+    /// Lines dropped so far, i.e. the id of `records[0]`
+    dropped: usize,
+}
+
+impl SessionLog {
+    /// Append a line and return its id
+    pub fn push(&mut self, record: SessionRecord) -> usize {
+        if self.records.len() >= SESSION_LOG_LIMIT {
+            // Drop a batch at a time rather than shifting the whole log on every line
+            let excess = self.records.len() + 1 - SESSION_LOG_LIMIT + SESSION_LOG_LIMIT / 10;
+            self.records.drain(..excess);
+            self.dropped += excess;
+        }
+        self.records.push(record);
+        self.dropped + self.records.len() - 1
+    }
+
+    pub fn get(&self, id: usize) -> Option<&SessionRecord> {
+        self.records.get(id.checked_sub(self.dropped)?)
+    }
+
+    pub fn get_mut(&mut self, id: usize) -> Option<&mut SessionRecord> {
+        self.records.get_mut(id.checked_sub(self.dropped)?)
+    }
+
+    /// Lines with their ids, oldest first
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = (usize, &SessionRecord)> {
+        self.records.iter().enumerate().map(|(i, r)| (self.dropped + i, r))
+    }
+
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+
+    /// Forget every line (ids are not reused)
+    pub fn clear(&mut self) {
+        self.dropped += self.records.len();
+        self.records.clear();
+    }
+}
+
+/// A line to look up in the dictionary
+#[derive(Clone)]
+pub struct LookupRequest {
+    pub source: String,
+    pub translation: String,
+}
+
+/// A downscaled capture with the OCR regions found in it
+pub struct OcrPreview {
+    /// Increases with every capture, so the GUI only re-uploads changed frames
+    pub frame: u64,
+    /// BGRA thumbnail
+    pub pixels: Vec<u8>,
+    pub width: u32,
+    pub height: u32,
+    /// Thumbnail pixels per captured pixel
+    pub scale: f32,
+    /// Size of the captured frame
+    pub frame_width: u32,
+    pub frame_height: u32,
+    /// Every thumbnail pixel is (nearly) black, as with protected or minimized windows
+    pub blank: bool,
+    /// Regions in captured-frame pixels
+    pub regions: Vec<TextRegion>,
+}
+
+/// OCR regions the user dismissed in interactive mode, kept until the session stops.
+#[derive(Default)]
+pub struct HiddenRegions {
+    /// Exact OCR texts
+    pub texts: HashSet<String>,
+    /// Points in game client coordinates; any region containing one is hidden
+    pub points: Vec<(i32, i32)>,
+}
+
+impl HiddenRegions {
+    pub fn clear(&mut self) {
+        self.texts.clear();
+        self.points.clear();
+    }
+
+    pub fn hides(&self, region: &TextRegion) -> bool {
+        self.texts.contains(&region.text)
+            || self.points.iter().any(|&(x, y)| {
+                x >= region.x && x < region.x + region.width && y >= region.y && y < region.y + region.block_height
+            })
+    }
+}
+
+/// Unicode ranges routed to Japanese-capable fonts before the system fallback kicks in.
+/// Covers CJK punctuation, kana, CJK ideographs (incl. Ext. A / compatibility) and full-width forms.
+const CJK_RANGES: [DWRITE_UNICODE_RANGE; 6] = [
+    DWRITE_UNICODE_RANGE { first: 0x3000, last: 0x30FF },
+    DWRITE_UNICODE_RANGE { first: 0x31F0, last: 0x31FF },
+    DWRITE_UNICODE_RANGE { first: 0x3400, last: 0x4DBF },
+    DWRITE_UNICODE_RANGE { first: 0x4E00, last: 0x9FFF },
+    DWRITE_UNICODE_RANGE { first: 0xF900, last: 0xFAFF },
+    DWRITE_UNICODE_RANGE { first: 0xFF00, last: 0xFFEF },
+];
+
+/// Hangul syllables / jamo, which Japanese fonts do not cover.
+const HANGUL_RANGES: [DWRITE_UNICODE_RANGE; 2] = [
+    DWRITE_UNICODE_RANGE { first: 0x1100, last: 0x11FF },
+    DWRITE_UNICODE_RANGE { first: 0xAC00, last: 0xD7AF },
+];
+
+/// Emoji and pictographs, drawn in colour by Segoe UI Emoji.
+const EMOJI_RANGES: [DWRITE_UNICODE_RANGE; 4] = [
+    DWRITE_UNICODE_RANGE { first: 0x2600, last: 0x27BF },
+    DWRITE_UNICODE_RANGE { first: 0x2B00, last: 0x2BFF },
+    DWRITE_UNICODE_RANGE { first: 0x1F000, last: 0x1FAFF },
+    DWRITE_UNICODE_RANGE { first: 0xE0020, last: 0xE007F },
+];
+
+/// CJK ideograph extensions B-H and compatibility supplement, outside the BMP fonts.
+const RARE_CJK_RANGES: [DWRITE_UNICODE_RANGE; 2] = [
+    DWRITE_UNICODE_RANGE { first: 0x20000, last: 0x2FA1F },
+    DWRITE_UNICODE_RANGE { first: 0x30000, last: 0x323AF },
+];
+
+/// Build a fallback chain: CJK -> Japanese UI fonts, Hangul -> Malgun Gothic,
+/// emoji -> Segoe UI Emoji, rare ideographs -> the Ext-B/G fonts,
+/// everything else -> the system fallback. Requires Windows 8.1+ (IDWriteFactory2).
+fn create_font_fallback(write_factory: &IDWriteFactory) -> Result<IDWriteFontFallback> {
+    unsafe {
+        let factory2: IDWriteFactory2 = write_factory.cast()?;
+        let builder = factory2.CreateFontFallbackBuilder()?;
+
+        let cjk_fonts = [
+            w!("Yu Gothic UI").as_ptr(),
+            w!("Meiryo UI").as_ptr(),
+            w!("Meiryo").as_ptr(),
+            w!("MS Gothic").as_ptr(),
+        ];
+        builder.AddMapping(&CJK_RANGES, &cjk_fonts, None, w!("ja-JP"), PCWSTR::null(), 1.0)?;
+
+        let hangul_fonts = [w!("Malgun Gothic").as_ptr()];
+        builder.AddMapping(&HANGUL_RANGES, &hangul_fonts, None, w!("ko-KR"), PCWSTR::null(), 1.0)?;
+
+        let emoji_fonts = [w!("Segoe UI Emoji").as_ptr(), w!("Segoe UI Symbol").as_ptr()];
+        builder.AddMapping(&EMOJI_RANGES, &emoji_fonts, None, PCWSTR::null(), PCWSTR::null(), 1.0)?;
+
+        let rare_cjk_fonts = [
+            w!("Yu Gothic").as_ptr(),
+            w!("SimSun-ExtB").as_ptr(),
+            w!("SimSun-ExtG").as_ptr(),
+            w!("MingLiU-ExtB").as_ptr(),
+        ];
+        builder.AddMapping(&RARE_CJK_RANGES, &rare_cjk_fonts, None, w!("ja-JP"), PCWSTR::null(), 1.0)?;
+
+        builder.AddMappings(&factory2.GetSystemFontFallback()?)?;
+        Ok(builder.CreateFontFallback()?)
+    }
+}
+
+fn to_color_f(c: &[f32; 4]) -> D2D1_COLOR_F {
+    D2D1_COLOR_F { r: c[0], g: c[1], b: c[2], a: c[3] }
+}
+
+/// WCAG relative luminance of an sRGB color (components in 0.0..=1.0).
+pub fn relative_luminance(rgb: [f32; 3]) -> f32 {
+    let linear = |c: f32| {
+        if c <= 0.03928 {
+            c / 12.92
+        } else {
+            ((c + 0.055) / 1.055).powf(2.4)
+        }
+    };
+    0.2126 * linear(rgb[0]) + 0.7152 * linear(rgb[1]) + 0.0722 * linear(rgb[2])
+}
+
+fn contrast_ratio(l1: f32, l2: f32) -> f32 {
+    let (hi, lo) = if l1 > l2 { (l1, l2) } else { (l2, l1) };
+    (hi + 0.05) / (lo + 0.05)
+}
+
+/// WCAG AA threshold for normal-size text
+const MIN_CONTRAST: f32 = 4.5;
+
+/// Pick the (text, background) colors for a box drawn over pixels of the given luminance.
+/// Keeps the configured pair when it is readable; otherwise switches to dark-on-light
+/// or light-on-dark depending on the scene brightness.
+fn contrast_colors(style: &BoxStyle, auto_contrast: bool, bg_luminance: Option<f32>) -> ([f32; 4], [f32; 4]) {
+    let (text, bg) = (style.text_color, style.bg_color);
+    let Some(scene) = bg_luminance.filter(|_| auto_contrast) else {
+        return (text, bg);
+    };
+
+    // Semi-transparent box: the eye sees the box blended with the game pixels
+    let effective_bg = bg[3] * relative_luminance([bg[0], bg[1], bg[2]]) + (1.0 - bg[3]) * scene;
+    let text_lum = relative_luminance([text[0], text[1], text[2]]);
+    if contrast_ratio(text_lum, effective_bg) >= MIN_CONTRAST {
+        return (text, bg);
+    }
+
+    let alpha = bg[3].max(0.75);
+    if scene > 0.4 {
+        ([0.05, 0.05, 0.05, 1.0], [1.0, 1.0, 1.0, alpha])
+    } else {
+        ([1.0, 1.0, 1.0, 1.0], [0.0, 0.0, 0.0, alpha])
+    }
+}
+
+/// Ruby font size relative to the base text
+const RUBY_SCALE: f32 = 0.5;
+
+/// A laid-out ruby annotation, positioned relative to its base text's origin
+struct RubyRun {
+    layout: IDWriteTextLayout,
+    x: f32,
+    y: f32,
+}
+
+/// A translation ready to draw: the base layout plus any ruby above it
+struct TextBox {
+    layout: IDWriteTextLayout,
+    ruby: Vec<RubyRun>,
+}
+
+fn line_metrics(layout: &IDWriteTextLayout) -> Result<Vec<DWRITE_LINE_METRICS>> {
+    let mut count = 0u32;
+    let mut lines = vec![DWRITE_LINE_METRICS::default(); 16];
+    unsafe {
+        if layout.GetLineMetrics(Some(&mut lines), &mut count).is_err() {
+            // Buffer too small: `count` now holds the real line count
+            lines.resize(count as usize, DWRITE_LINE_METRICS::default());
+            layout.GetLineMetrics(Some(&mut lines), &mut count)?;
+        }
+    }
+    lines.truncate(count as usize);
+    Ok(lines)
+}
+
+/// Widen every line uniformly so there is room for ruby above the base glyphs.
+fn reserve_ruby_space(layout: &IDWriteTextLayout, font_size: f32) -> Result<()> {
+    let Some(first) = line_metrics(layout)?.into_iter().next() else {
+        return Ok(());
+    };
+    let ruby_height = font_size * RUBY_SCALE * 1.3;
+    unsafe {
+        layout.SetLineSpacing(
+            DWRITE_LINE_SPACING_METHOD_UNIFORM,
+            first.height + ruby_height,
+            first.baseline + ruby_height,
+        )?;
+    }
+    Ok(())
+}
+
+/// Right-to-left columns and RTL lines start at the layout's right edge; shrink it to
+/// the text so the text block starts at the origin like left-to-right text does.
+fn shrink_to_text(layout: &IDWriteTextLayout, metrics: &DWRITE_TEXT_METRICS) -> Result<()> {
+    // Round up so float error can't make the widest line wrap again
+    unsafe { layout.SetMaxWidth(metrics.width.ceil())? };
+    Ok(())
+}
+
+/// Decode an image file (PNG, etc.) into a D2D bitmap via WIC.
+fn load_bitmap(target: &ID2D1RenderTarget, path: &str) -> Result<ID2D1Bitmap> {
+    unsafe {
+        let wic: IWICImagingFactory = CoCreateInstance(&CLSID_WICImagingFactory, None, CLSCTX_INPROC_SERVER)?;
+        let path_w: Vec<u16> = path.encode_utf16().chain(Some(0)).collect();
+        let decoder = wic.CreateDecoderFromFilename(
+            PCWSTR(path_w.as_ptr()),
+            None,
+            GENERIC_READ,
+            WICDecodeMetadataCacheOnLoad,
+        )?;
+        let frame = decoder.GetFrame(0)?;
+        let converter = wic.CreateFormatConverter()?;
+        converter.Initialize(
+            &frame,
+            &GUID_WICPixelFormat32bppPBGRA,
+            WICBitmapDitherTypeNone,
+            None,
+            0.0,
+            WICBitmapPaletteTypeCustom,
+        )?;
+        Ok(target.CreateBitmapFromWicBitmap(&converter, None)?)
+    }
+}
+
+/// Stretch `bitmap` over `dest` as a 9-slice: corners keep their size, edges stretch
+/// along one axis and the centre along both. `slice` is the corner size in image pixels,
+/// reduced for boxes too small to fit two corners.
+fn draw_nine_slice(target: &ID2D1RenderTarget, bitmap: &ID2D1Bitmap, dest: &D2D_RECT_F, slice: f32) {
+    let size = unsafe { bitmap.GetSize() };
+    let s = slice.min(size.width / 2.0).min(size.height / 2.0);
+    let d = s.min((dest.right - dest.left) / 2.0).min((dest.bottom - dest.top) / 2.0);
+    let src_x = [0.0, s, size.width - s, size.width];
+    let src_y = [0.0, s, size.height - s, size.height];
+    let dst_x = [dest.left, dest.left + d, dest.right - d, dest.right];
+    let dst_y = [dest.top, dest.top + d, dest.bottom - d, dest.bottom];
+    for row in 0..3 {
+        for col in 0..3 {
+            let src = D2D_RECT_F { left: src_x[col], top: src_y[row], right: src_x[col + 1], bottom: src_y[row + 1] };
+            let dst = D2D_RECT_F { left: dst_x[col], top: dst_y[row], right: dst_x[col + 1], bottom: dst_y[row + 1] };
+            unsafe {
+                target.DrawBitmap(bitmap, Some(&dst), 1.0, D2D1_BITMAP_INTERPOLATION_MODE_LINEAR, Some(&src));
+            }
+        }
+    }
+}
+
+/// Minimum spacing between translation boxes after layout
+const BOX_GAP: f32 = 6.0;
+
+fn boxes_overlap(a: &D2D_RECT_F, b: &D2D_RECT_F, gap: f32) -> bool {
+    a.left < b.right + gap && b.left < a.right + gap && a.top < b.bottom + gap && b.top < a.bottom + gap
+}
+
+fn shift_y(r: &D2D_RECT_F, dy: f32) -> D2D_RECT_F {
+    D2D_RECT_F { top: r.top + dy, bottom: r.bottom + dy, ..*r }
+}
+
+/// Side-panel layout: boxes share one column, so each goes below the previous one.
+fn stack_boxes(boxes: &mut [D2D_RECT_F], gap: f32) {
+    let mut prev_bottom = f32::MIN;
+    for b in boxes.iter_mut() {
+        if b.top < prev_bottom + gap {
+            *b = shift_y(b, prev_bottom + gap - b.top);
+        }
+        prev_bottom = b.bottom;
+    }
+}
+
+/// Nudge overlapping boxes apart vertically. Boxes are placed top to bottom; a box that
+/// collides with an already-placed one is moved just below or just above the obstacle,
+/// whichever keeps it closer to its source region.
+fn separate_boxes(boxes: &mut [D2D_RECT_F], gap: f32) {
+    let mut order: Vec<usize> = (0..boxes.len()).collect();
+    order.sort_by(|&a, &b| boxes[a].top.total_cmp(&boxes[b].top));
+
+    let mut placed: Vec<D2D_RECT_F> = Vec::with_capacity(boxes.len());
+    for i in order {
+        let original = boxes[i];
+        let collides = |r: &D2D_RECT_F| placed.iter().any(|p| boxes_overlap(r, p, gap));
+
+        // Walk downward / upward past obstacles until a free slot is found
+        let search = |down: bool| {
+            let mut candidate = original;
+            for _ in 0..=placed.len() {
+                let Some(hit) = placed.iter().find(|p| boxes_overlap(&candidate, p, gap)) else {
+                    return Some(candidate);
+                };
+                let dy = if down {
+                    hit.bottom + gap - candidate.top
+                } else {
+                    hit.top - gap - candidate.bottom
+                };
+                candidate = shift_y(&candidate, dy);
+            }
+            (!collides(&candidate)).then_some(candidate)
+        };
+
+        let best = [search(true), search(false)]
+            .into_iter()
+            .flatten()
+            .min_by(|a, b| (a.top - original.top).abs().total_cmp(&(b.top - original.top).abs()))
+            .unwrap_or(original);
+        boxes[i] = best;
+        placed.push(best);
+    }
+}
+
+/// Offsets at which the text is re-drawn to form an outline of the given thickness.
+/// Thicker outlines use more samples so the ring stays round.
+fn outline_offsets(width: f32) -> Vec<(f32, f32)> {
+    if width <= 0.0 {
+        return Vec::new();
+    }
+    let steps = if width <= 1.5 { 8 } else { 16 };
+    (0..steps)
+        .map(|i| {
+            let angle = i as f32 * std::f32::consts::TAU / steps as f32;
+            (width * angle.cos(), width * angle.sin())
+        })
+        .collect()
+}
+
+/// Family names of all installed fonts (English name where available), sorted for display.
+pub fn list_font_families() -> Result<Vec<String>> {
+    unsafe {
+        let write_factory: IDWriteFactory = DWriteCreateFactory(DWRITE_FACTORY_TYPE_SHARED)?;
+        let mut collection: Option<IDWriteFontCollection> = None;
+        write_factory.GetSystemFontCollection(&mut collection, false)?;
+        let collection = collection.ok_or_else(|| anyhow::anyhow!("No system font collection"))?;
+
+        let mut families = Vec::new();
+        for i in 0..collection.GetFontFamilyCount() {
+            let names = collection.GetFontFamily(i)?.GetFamilyNames()?;
+            let mut index = 0u32;
+            let mut exists = BOOL::default();
+            names.FindLocaleName(w!("en-us"), &mut index, &mut exists)?;
+            if !exists.as_bool() {
+                index = 0;
+            }
+            let len = names.GetStringLength(index)? as usize;
+            let mut buf = vec![0u16; len + 1];
+            names.GetString(index, &mut buf)?;
+            families.push(String::from_utf16_lossy(&buf[..len]));
+        }
+        families.sort_by_key(|f| f.to_lowercase());
+        families.dedup();
+        Ok(families)
+    }
+}
+
+/// Renders font samples for the GUI's font picker. COM and the factories are set up once
+/// when the picker opens rather than on every redraw of the sample.
+pub struct FontPreviewRenderer {
+    wic: IWICImagingFactory,
+    factory: ID2D1Factory1,
+    write_factory: IDWriteFactory,
+    /// Whether `new` initialized COM on this thread and so must uninitialize it
+    com_initialized: bool,
+}
+
+impl FontPreviewRenderer {
+    pub fn new() -> Result<Self> {
+        unsafe {
+            let com_initialized = CoInitializeEx(None, COINIT_APARTMENTTHREADED).is_ok();
+            let created = (|| -> Result<(IWICImagingFactory, ID2D1Factory1, IDWriteFactory)> {
+                Ok((
+                    CoCreateInstance(&CLSID_WICImagingFactory, None, CLSCTX_INPROC_SERVER)?,
+                    D2D1CreateFactory(D2D1_FACTORY_TYPE_SINGLE_THREADED, None)?,
+                    DWriteCreateFactory(DWRITE_FACTORY_TYPE_SHARED)?,
+                ))
+            })();
+            match created {
+                Ok((wic, factory, write_factory)) => Ok(Self { wic, factory, write_factory, com_initialized }),
+                Err(e) => {
+                    if com_initialized {
+                        CoUninitialize();
+                    }
+                    Err(e)
+                }
+            }
+        }
+    }
+
+    /// Render `text` in the configured font and colors on a grey backdrop.
+    /// Returns `width` x `height` RGBA pixels.
+    pub fn render(&self, config: &OverlayConfig, text: &str, font_size: f32, width: u32, height: u32) -> Result<Vec<u8>> {
+        const PADDING: f32 = 12.0;
+        unsafe {
+            let bitmap = self.wic.CreateBitmap(width, height, &GUID_WICPixelFormat32bppPBGRA, WICBitmapCacheOnLoad)?;
+            let target = self.factory.CreateWicBitmapRenderTarget(&bitmap, &D2D1_RENDER_TARGET_PROPERTIES::default())?;
+
+            let write_factory = &self.write_factory;
+            let family: Vec<u16> = config.font_family.encode_utf16().chain(Some(0)).collect();
+            let format = write_factory.CreateTextFormat(
+                PCWSTR(family.as_ptr()),
+                None,
+                DWRITE_FONT_WEIGHT_BOLD,
+                DWRITE_FONT_STYLE_NORMAL,
+                DWRITE_FONT_STRETCH_NORMAL,
+                font_size,
+                w!("ja-JP"),
+            )?;
+            let text_brush = target.CreateSolidColorBrush(&to_color_f(&config.text_color), None)?;
+            let bg_brush = target.CreateSolidColorBrush(&to_color_f(&config.bg_color), None)?;
+
+            let (w, h) = (width as f32, height as f32);
+            let bg_rect = D2D_RECT_F { left: PADDING / 2.0, top: PADDING / 2.0, right: w - PADDING / 2.0, bottom: h - PADDING / 2.0 };
+            let radius = config.corner_radius.min((h - PADDING) / 2.0);
+            let utf16: Vec<u16> = text.encode_utf16().collect();
+            target.BeginDraw();
+            // Stands in for the game screen, so translucent backgrounds look as they would in-game
+            target.Clear(Some(&D2D1_COLOR_F { r: 0.3, g: 0.3, b: 0.3, a: 1.0 }));
+            target.FillRoundedRectangle(&D2D1_ROUNDED_RECT { rect: bg_rect, radiusX: radius, radiusY: radius }, &bg_brush);
+            target.DrawText(
+                &utf16,
+                &format,
+                &D2D_RECT_F { left: PADDING, top: PADDING, right: w - PADDING, bottom: h - PADDING },
+                &text_brush,
+                D2D1_DRAW_TEXT_OPTIONS_CLIP,
+                DWRITE_MEASURING_MODE_NATURAL,
+            );
+            target.EndDraw(None, None)?;
+
+            let stride = width * 4;
+            let mut pixels = vec![0u8; (stride * height) as usize];
+            bitmap.CopyPixels(std::ptr::null(), stride, &mut pixels)?;
+            // The backdrop is opaque, so premultiplied BGRA only needs its channels swapped
+            for p in pixels.chunks_exact_mut(4) {
+                p.swap(0, 2);
+            }
+            Ok(pixels)
+        }
+    }
+}
+
+impl Drop for FontPreviewRenderer {
+    fn drop(&mut self) {
+        if self.com_initialized {
+            unsafe { CoUninitialize(); }
+        }
+    }
+}
+
+/// A translation painted over its source region by [`render_annotated`]
+pub struct Annotation {
+    /// Source region (x, y, width, height) in image pixels
+    pub rect: (i32, i32, i32, i32),
+    pub text: String,
+    pub font_size: f32,
+}
+
+/// Paint each annotation in a box over its source region of a BGRA image, in the configured
+/// font and colors (batch screenshot translation). Returns opaque BGRA pixels.
+pub fn render_annotated(config: &OverlayConfig, pixels: &[u8], width: u32, height: u32, annotations: &[Annotation]) -> Result<Vec<u8>> {
+    const PADDING: f32 = 4.0;
+    let stride = width * 4;
+    // Screenshots carry no meaningful alpha; opaque pixels are valid premultiplied BGRA as they are
+    let mut opaque = pixels.to_vec();
+    for p in opaque.chunks_exact_mut(4) {
+        p[3] = 255;
+    }
+    unsafe {
+        let _ = CoInitializeEx(None, COINIT_APARTMENTTHREADED);
+        let wic: IWICImagingFactory = CoCreateInstance(&CLSID_WICImagingFactory, None, CLSCTX_INPROC_SERVER)?;
+        let bitmap = wic.CreateBitmapFromMemory(width, height, &GUID_WICPixelFormat32bppPBGRA, stride, &opaque)?;
+        let factory: ID2D1Factory1 = D2D1CreateFactory(D2D1_FACTORY_TYPE_SINGLE_THREADED, None)?;
+        let target = factory.CreateWicBitmapRenderTarget(&bitmap, &D2D1_RENDER_TARGET_PROPERTIES::default())?;
+
+        let write_factory: IDWriteFactory = DWriteCreateFactory(DWRITE_FACTORY_TYPE_SHARED)?;
+        let family: Vec<u16> = config.font_family.encode_utf16().chain(Some(0)).collect();
+        let text_brush = target.CreateSolidColorBrush(&to_color_f(&config.text_color), None)?;
+        let bg_brush = target.CreateSolidColorBrush(&to_color_f(&config.bg_color), None)?;
+
+        // Lay everything out first so an error can't leave the target mid-draw
+        let mut boxes = Vec::with_capacity(annotations.len());
+        for annotation in annotations {
+            let (x, y, w, h) = annotation.rect;
+            let format = write_factory.CreateTextFormat(
+                PCWSTR(family.as_ptr()),
+                None,
+                DWRITE_FONT_WEIGHT_BOLD,
+                DWRITE_FONT_STYLE_NORMAL,
+                DWRITE_FONT_STRETCH_NORMAL,
+                annotation.font_size,
+                w!("ja-JP"),
+            )?;
+            let utf16: Vec<u16> = annotation.text.encode_utf16().collect();
+            let max_width = (w as f32).max(annotation.font_size * 4.0);
+            let layout = write_factory.CreateTextLayout(&utf16, &format, max_width, f32::MAX)?;
+            let mut metrics = DWRITE_TEXT_METRICS::default();
+            layout.GetMetrics(&mut metrics)?;
+            let (x, y) = (x as f32, y as f32);
+            let rect = D2D_RECT_F {
+                left: x - PADDING,
+                top: y - PADDING,
+                right: x + metrics.width.max(w as f32) + PADDING,
+                bottom: y + metrics.height.max(h as f32) + PADDING,
+            };
+            boxes.push((rect, x, y, layout));
+        }
+
+        target.BeginDraw();
+        for (rect, x, y, layout) in &boxes {
+            let radius = config.corner_radius.min((rect.bottom - rect.top) / 2.0);
+            target.FillRoundedRectangle(&D2D1_ROUNDED_RECT { rect: *rect, radiusX: radius, radiusY: radius }, &bg_brush);
+            target.DrawTextLayout(windows_numerics::Vector2 { X: *x, Y: *y }, layout, &text_brush, D2D1_DRAW_TEXT_OPTIONS_NONE);
+        }
+        target.EndDraw(None, None)?;
+
+        let mut out = vec![0u8; (stride * height) as usize];
+        bitmap.CopyPixels(std::ptr::null(), stride, &mut out)?;
+        Ok(out)
+    }
+}
+
+pub struct Overlay {
+    factory: ID2D1Factory1,
+    surface: Option<CompositionSurface>,
+    /// Window the surface is bound to (needed to rebuild it after a device loss)
+    hwnd: HWND,
+    write_factory: IDWriteFactory,
+    bg_brush: Option<ID2D1SolidColorBrush>,
+    text_brush: Option<ID2D1SolidColorBrush>,
+    outline_brush: Option<ID2D1SolidColorBrush>,
+    border_brush: Option<ID2D1SolidColorBrush>,
+    /// Background gradient, repositioned onto each box before filling it
+    bg_gradient_brush: Option<ID2D1LinearGradientBrush>,
+    /// 9-slice box skin loaded from `config.skin_path`
+    skin: Option<ID2D1Bitmap>,
+    /// Custom CJK fallback chain (None on systems without IDWriteFactory2)
+    font_fallback: Option<IDWriteFontFallback>,
+    /// (font size quantized to integer, vertical) -> cached IDWriteTextFormat
+    /// Keyed by (size, vertical, style zone)
+    text_format_cache: HashMap<(u32, bool, Option<usize>), IDWriteTextFormat>,
+    width: u32,
+    height: u32,
+    origin_x: i32,
+    origin_y: i32,
+    config: OverlayConfig,
+    controls: Arc<OverlayControls>,
+    /// Texts of the current frame, kept for re-rendering (highlight) and hit-testing
+    last_texts: Vec<TranslatedText>,
+    /// Background box of each entry in `last_texts`, in window-local coordinates
+    drawn_boxes: Vec<D2D_RECT_F>,
+    /// Index into `last_texts` drawn with a highlight frame
+    highlight: Option<usize>,
+    /// Index into `last_texts` shown without the max-lines limit (hovered)
+    expanded: Option<usize>,
+    /// Fade multiplier applied on top of the user opacity (0.0 = invisible)
+    fade_factor: f32,
+    /// Content to show once the current frame has faded out (Some(None) = clear)
+    fade_pending: Option<Option<Vec<TranslatedText>>>,
+    last_fade_tick: Instant,
+    /// Badge shown in the top-right corner
+    status: Option<PipelineStatus>,
+    /// Performance readout shown in the top-left corner (None = HUD off)
+    hud: Option<String>,
+    /// Recently shown translations, oldest first, for the bottom-left history panel
+    history: VecDeque<HistoryEntry>,
+    /// How many entries the panel is scrolled back from the newest one
+    history_scroll: usize,
+    /// Where the history panel was last drawn, in window-local coordinates
+    history_rect: Option<D2D_RECT_F>,
+    /// Game frame behind the boxes and the screen position of its top-left corner
+    backdrop: Option<(Arc<Backdrop>, f32, f32)>,
+    /// `backdrop` blurred into a brush, built on the first frame after it changes
+    backdrop_brush: Option<ID2D1BitmapBrush1>,
+    /// OCR regions of the current frame in screen coordinates, outlined when debugging
+    debug_regions: Vec<D2D_RECT_F>,
+    /// The game's client area in screen coordinates; `dim` darkens only this part of the window
+    dim_area: Option<D2D_RECT_F>,
+}
+
+impl Overlay {
+    pub fn new(config: OverlayConfig, controls: Arc<OverlayControls>) -> Result<Self> {
+        unsafe {
+            let _ = CoInitializeEx(None, COINIT_APARTMENTTHREADED);
+
+            let factory: ID2D1Factory1 = D2D1CreateFactory(
+                D2D1_FACTORY_TYPE_SINGLE_THREADED,
+                None,
+            )?;
+
+            let write_factory: IDWriteFactory = DWriteCreateFactory(
+                DWRITE_FACTORY_TYPE_SHARED,
+            )?;
+
+            let font_fallback = match create_font_fallback(&write_factory) {
+                Ok(fallback) => Some(fallback),
+                Err(e) => {
+                    crate::log_always(&format!("[DWRITE] Font fallback unavailable, using system default: {}", e));
+                    None
+                }
+            };
+
+            Ok(Self {
+                factory,
+                surface: None,
+                hwnd: HWND::default(),
+                write_factory,
+                bg_brush: None,
+                text_brush: None,
+                outline_brush: None,
+                border_brush: None,
+                bg_gradient_brush: None,
+                skin: None,
+                font_fallback,
+                text_format_cache: HashMap::new(),
+                width: 0,
+                height: 0,
+                origin_x: 0,
+                origin_y: 0,
+                config,
+                controls,
+                last_texts: Vec::new(),
+                drawn_boxes: Vec::new(),
+                highlight: None,
+                expanded: None,
+                fade_factor: 1.0,
+                fade_pending: None,
+                last_fade_tick: Instant::now(),
+                status: None,
+                hud: None,
+                history: VecDeque::new(),
+                history_scroll: 0,
+                history_rect: None,
+                backdrop: None,
+                backdrop_brush: None,
+                debug_regions: Vec::new(),
+                dim_area: None,
+            })
+        }
+    }
+
+    /// The base look's settings, or the zone's when the text lies in one
+    fn box_style(&self, zone: Option<usize>) -> BoxStyle {
+        if let Some(style) = zone.and_then(|z| self.config.zones.get(z)) {
+            return style.clone();
+        }
+        let c = &self.config;
+        BoxStyle {
+            font_family: c.font_family.clone(),
+            font_scale: 1.0,
+            text_color: c.text_color,
+            bg_color: c.bg_color,
+            outline_width: c.outline_width,
+            outline_color: c.outline_color,
+            corner_radius: c.corner_radius,
+            border_width: c.border_width,
+            border_color: c.border_color,
+        }
+    }
+
+    fn get_or_create_text_format(&mut self, font_size: f32, vertical: bool, zone: Option<usize>) -> Result<IDWriteTextFormat> {
+        let key = (font_size.max(8.0) as u32, vertical, zone);
+        if let Some(fmt) = self.text_format_cache.get(&key) {
+            return Ok(fmt.clone());
+        }
+        let family: Vec<u16> = self.box_style(zone).font_family
+            .encode_utf16()
+            .chain(Some(0))
+            .collect();
+        unsafe {
+            let fmt = self.write_factory.CreateTextFormat(
+                PCWSTR(family.as_ptr()),
+                None,
+                DWRITE_FONT_WEIGHT_BOLD,
+                DWRITE_FONT_STYLE_NORMAL,
+                DWRITE_FONT_STRETCH_NORMAL,
+                key.0 as f32,
+                w!("ja-JP"),
+            )?;
+            if vertical {
+                // Columns read top-to-bottom and advance right-to-left, as in Japanese tategaki
+                fmt.SetFlowDirection(DWRITE_FLOW_DIRECTION_RIGHT_TO_LEFT)?;
+                fmt.SetReadingDirection(DWRITE_READING_DIRECTION_TOP_TO_BOTTOM)?;
+            } else if self.config.rtl {
+                // The paragraph direction drives DirectWrite's bidi analysis, so embedded
+                // numbers and Latin words keep their order; LEADING now aligns right
+                fmt.SetReadingDirection(DWRITE_READING_DIRECTION_RIGHT_TO_LEFT)?;
+            }
+            fmt.SetTextAlignment(DWRITE_TEXT_ALIGNMENT_LEADING)?;
+            fmt.SetParagraphAlignment(DWRITE_PARAGRAPH_ALIGNMENT_NEAR)?;
+            if self.config.cjk_line_breaking {
+                // Break inside a word only when it can't fit on a line by itself
+                fmt.SetWordWrapping(DWRITE_WORD_WRAPPING_EMERGENCY_BREAK)?;
+            }
+            if let Some(fallback) = &self.font_fallback {
+                let fmt1: IDWriteTextFormat1 = fmt.cast()?;
+                fmt1.SetFontFallback(fallback)?;
+            }
+            self.text_format_cache.insert(key, fmt.clone());
+            Ok(fmt)
+        }
+    }
+
+    fn create_brushes(&mut self, target: &ID2D1RenderTarget) -> Result<()> {
+        let solid = |c: &[f32; 4]| unsafe { target.CreateSolidColorBrush(&to_color_f(c), None) };
+        self.bg_brush = Some(solid(&self.config.bg_color)?);
+        self.text_brush = Some(solid(&self.config.text_color)?);
+        self.outline_brush = Some(solid(&self.config.outline_color)?);
+        self.border_brush = Some(solid(&self.config.border_color)?);
+        if self.config.bg_gradient {
+            unsafe {
+                let stops = [
+                    D2D1_GRADIENT_STOP { position: 0.0, color: to_color_f(&self.config.bg_color) },
+                    D2D1_GRADIENT_STOP { position: 1.0, color: to_color_f(&self.config.bg_color2) },
+                ];
+                let collection = target.CreateGradientStopCollection(&stops, D2D1_GAMMA_2_2, D2D1_EXTEND_MODE_CLAMP)?;
+                let props = D2D1_LINEAR_GRADIENT_BRUSH_PROPERTIES::default();
+                self.bg_gradient_brush = Some(target.CreateLinearGradientBrush(&props, None, &collection)?);
+            }
+        }
+        if !self.config.skin_path.is_empty() {
+            // A broken skin shouldn't take the overlay down; fall back to the plain fill
+            match load_bitmap(target, &self.config.skin_path) {
+                Ok(bitmap) => self.skin = Some(bitmap),
+                Err(e) => crate::log_always(&format!("[SKIN] {} を読み込めません: {}", self.config.skin_path, e)),
+            }
+        }
+        Ok(())
+    }
+
+    /// Lay out one translation (ruby markup already stripped). With auto-fit enabled
+    /// the font shrinks in steps until the text fits the source region (or hits the
+    /// minimum size, after which the box simply grows to fit).
+    /// Returns the layout and the font size it ended up with.
+    fn create_layout(&mut self, plain: &str, text: &TranslatedText, has_ruby: bool) -> Result<(IDWriteTextLayout, f32)> {
+        const FIT_STEP: f32 = 0.9;
+        let text_w: Vec<u16> = plain.encode_utf16().collect();
+        let wrap_width = text.max_width.max(150.0);
+        // Vertical text wraps at the column length and grows sideways instead
+        let (layout_width, layout_height) = if text.vertical {
+            (self.width as f32, text.max_height.max(150.0))
+        } else {
+            (wrap_width, self.height as f32)
+        };
+        let min_size = self.config.min_font_size;
+        let scale = self.controls.font_scale.get() * self.box_style(text.zone).font_scale;
+        let mut font_size = (text.font_size * scale).clamp(min_size, self.config.max_font_size);
+        loop {
+            let format = self.get_or_create_text_format(font_size, text.vertical, text.zone)?;
+            let layout = unsafe {
+                self.write_factory.CreateTextLayout(&text_w, &format, layout_width, layout_height)?
+            };
+            if !text.vertical {
+                let alignment = match self.config.text_align {
+                    TextAlign::Left => DWRITE_TEXT_ALIGNMENT_LEADING,
+                    TextAlign::Center => DWRITE_TEXT_ALIGNMENT_CENTER,
+                    TextAlign::Right => DWRITE_TEXT_ALIGNMENT_TRAILING,
+                };
+                unsafe { layout.SetTextAlignment(alignment)? };
+            }
+            if has_ruby {
+                reserve_ruby_space(&layout, font_size)?;
+            }
+            let mut metrics = DWRITE_TEXT_METRICS::default();
+            unsafe { layout.GetMetrics(&mut metrics)? };
+            let fits = if text.vertical {
+                metrics.width <= wrap_width
+            } else {
+                metrics.height <= text.max_height && metrics.width <= wrap_width
+            };
+            if fits || !self.config.auto_fit || text.max_height <= 0.0 || font_size <= min_size {
+                if text.vertical || self.config.rtl {
+                    shrink_to_text(&layout, &metrics)?;
+                }
+                return Ok((layout, font_size));
+            }
+            font_size = (font_size * FIT_STEP).max(min_size);
+        }
+    }
+
+    /// Lay out each ruby annotation centred above its base text (to the right of it
+    /// for vertical text). Annotations on lines cut off by `limit_lines` are dropped.
+    fn layout_ruby(&mut self, base: &IDWriteTextLayout, spans: &[RubySpan], font_size: f32, text: &TranslatedText) -> Result<Vec<RubyRun>> {
+        if spans.is_empty() {
+            return Ok(Vec::new());
+        }
+        let vertical = text.vertical;
+        let format = self.get_or_create_text_format(font_size * RUBY_SCALE, vertical, text.zone)?;
+        let (max_width, max_height) = unsafe { (base.GetMaxWidth(), base.GetMaxHeight()) };
+        let mut runs = Vec::with_capacity(spans.len());
+        for span in spans {
+            let mut count = 0u32;
+            let mut hits = vec![DWRITE_HIT_TEST_METRICS::default(); 4];
+            unsafe {
+                if base.HitTestTextRange(span.start, span.len, 0.0, 0.0, Some(&mut hits), &mut count).is_err() {
+                    hits.resize(count as usize, DWRITE_HIT_TEST_METRICS::default());
+                    base.HitTestTextRange(span.start, span.len, 0.0, 0.0, Some(&mut hits), &mut count)?;
+                }
+            }
+            // A base that wraps onto the next line keeps its ruby on the first part
+            let Some(rect) = hits.first().filter(|_| count > 0) else { continue };
+            if rect.top >= max_height || rect.left < 0.0 || rect.left >= max_width {
+                continue;
+            }
+            let ruby_w: Vec<u16> = span.ruby.encode_utf16().collect();
+            let layout = unsafe { self.write_factory.CreateTextLayout(&ruby_w, &format, 10_000.0, 10_000.0)? };
+            let mut metrics = DWRITE_TEXT_METRICS::default();
+            unsafe { layout.GetMetrics(&mut metrics)? };
+            let (x, y) = if vertical {
+                shrink_to_text(&layout, &metrics)?;
+                (rect.left + rect.width - metrics.width, rect.top + (rect.height - metrics.height) / 2.0)
+            } else {
+                (rect.left + (rect.width - metrics.width) / 2.0, rect.top)
+            };
+            runs.push(RubyRun { layout, x, y });
+        }
+        Ok(runs)
+    }
+
+    /// Cut the layout after `max_lines` lines and end it with an ellipsis.
+    fn limit_lines(&self, layout: &IDWriteTextLayout, vertical: bool) -> Result<()> {
+        let max_lines = self.config.max_lines as usize;
+        if max_lines == 0 {
+            return Ok(());
+        }
+        unsafe {
+            let lines = line_metrics(layout)?;
+            if lines.len() <= max_lines {
+                return Ok(());
+            }
+            // For vertical text the "lines" are columns, so cut the width instead
+            let visible: f32 = lines[..max_lines].iter().map(|l| l.height).sum();
+            if vertical {
+                layout.SetMaxWidth(visible)?;
+            } else {
+                layout.SetMaxHeight(visible)?;
+            }
+            let sign = self.write_factory.CreateEllipsisTrimmingSign(layout)?;
+            let trimming = DWRITE_TRIMMING {
+                granularity: DWRITE_TRIMMING_GRANULARITY_CHARACTER,
+                delimiter: 0,
+                delimiterCount: 0,
+            };
+            layout.SetTrimming(&trimming, &sign)?;
+        }
+        Ok(())
+    }
+
+    /// Show the full text of the hovered box (interactive mode). Redraws only on change.
+    pub fn set_expanded(&mut self, index: Option<usize>, hwnd: HWND) -> Result<()> {
+        if self.expanded == index {
+            return Ok(());
+        }
+        self.expanded = index;
+        self.redraw(hwnd)
+    }
+
+    fn recreate_render_resources(&mut self) -> Result<()> {
+        self.release_surface();
+        self.create_render_target(self.hwnd, self.width, self.height, self.origin_x, self.origin_y)
+    }
+
+    /// Free the swap chain and device-bound resources (before rebuilding them or on drop).
+    fn release_surface(&mut self) {
+        // Release D2D/DWrite resources before render target
+        self.bg_brush = None;
+        self.text_brush = None;
+        self.outline_brush = None;
+        self.border_brush = None;
+        self.bg_gradient_brush = None;
+        self.skin = None;
+        self.text_format_cache.clear();
+        self.backdrop_brush = None;
+        self.surface = None;
+    }
+
+    /// Move the surface to `rect` (screen coordinates) as it follows the game window.
+    /// Boxes keep their place relative to the surface; a size change reallocates it.
+    pub fn follow(&mut self, rect: &RECT, hwnd: HWND) -> Result<()> {
+        let (dx, dy) = (rect.left - self.origin_x, rect.top - self.origin_y);
+        self.shift(dx as f32, dy as f32);
+        self.place(rect, hwnd, false)
+    }
+
+    /// Move/resize the window and surface to `rect` (screen coordinates) without moving
+    /// the boxes on screen, e.g. after a DPI or display layout change.
+    pub fn set_bounds(&mut self, rect: &RECT, hwnd: HWND) -> Result<()> {
+        self.place(rect, hwnd, true)
+    }
+
+    /// `redraw_on_move`: boxes are drawn relative to the origin, so unless the caller shifted
+    /// them along with it, a move alone changes the frame too.
+    fn place(&mut self, rect: &RECT, hwnd: HWND, redraw_on_move: bool) -> Result<()> {
+        let (width, height) = ((rect.right - rect.left) as u32, (rect.bottom - rect.top) as u32);
+        let moved = (rect.left, rect.top) != (self.origin_x, self.origin_y);
+        let resized = (width, height) != (self.width, self.height);
+        if !moved && !resized {
+            return Ok(());
+        }
+
+        self.origin_x = rect.left;
+        self.origin_y = rect.top;
+        unsafe {
+            let flags = if resized { SWP_NOZORDER | SWP_NOACTIVATE } else { SWP_NOSIZE | SWP_NOZORDER | SWP_NOACTIVATE };
+            SetWindowPos(hwnd, None, rect.left, rect.top, width as i32, height as i32, flags)?;
+        }
+        if resized {
+            self.width = width;
+            self.height = height;
+            if let Some(surface) = &self.surface {
+                surface.resize(width, height)?;
+            }
+        }
+        if resized || (moved && redraw_on_move) {
+            return self.redraw(hwnd);
+        }
+        Ok(())
+    }
+
+    pub fn create_render_target(&mut self, hwnd: HWND, width: u32, height: u32, origin_x: i32, origin_y: i32) -> Result<()> {
+        self.hwnd = hwnd;
+        self.width = width;
+        self.height = height;
+        self.origin_x = origin_x;
+        self.origin_y = origin_y;
+
+        let surface = CompositionSurface::new(&self.factory, hwnd, width, height)?;
+        self.create_brushes(&surface.context)?;
+        surface.set_opacity(self.layer_opacity())?;
+        self.surface = Some(surface);
+        Ok(())
+    }
+
+    pub fn render(&mut self, texts: Vec<TranslatedText>, hwnd: HWND) -> Result<()> {
+        if self.config.fade_ms == 0 {
+            self.fade_factor = 1.0;
+            return self.show(texts, hwnd);
+        }
+        self.last_fade_tick = Instant::now();
+        if self.last_texts.is_empty() {
+            self.fade_factor = 0.0;
+            self.fade_pending = None;
+            return self.show(texts, hwnd);
+        }
+        self.fade_pending = Some(Some(texts));
+        Ok(())
+    }
+
+    fn show(&mut self, texts: Vec<TranslatedText>, hwnd: HWND) -> Result<()> {
+        self.last_texts = texts;
+        self.highlight = None;
+        self.expanded = None;
+        self.redraw(hwnd)
+    }
+
+    /// Move everything tied to a screen position (boxes, pending frame, backdrop, regions).
+    fn shift(&mut self, dx: f32, dy: f32) {
+        let pending = self.fade_pending.iter_mut().flatten().flatten();
+        for t in self.last_texts.iter_mut().chain(pending) {
+            t.x += dx;
+            t.y += dy;
+        }
+        if let Some((_, x, y)) = &mut self.backdrop {
+            *x += dx;
+            *y += dy;
+        }
+        for r in self.debug_regions.iter_mut().chain(&mut self.dim_area) {
+            r.left += dx;
+            r.right += dx;
+            r.top += dy;
+            r.bottom += dy;
+        }
+    }
+
+    /// Set the game's client area (screen coordinates) that `dim` darkens.
+    /// Takes effect with the next redraw.
+    pub fn set_dim_area(&mut self, area: Option<D2D_RECT_F>) {
+        self.dim_area = area;
+    }
+
+    /// Replace the OCR regions outlined in debug mode (screen coordinates).
+    /// Takes effect with the next redraw.
+    pub fn set_debug_regions(&mut self, regions: Vec<D2D_RECT_F>) {
+        if self.config.debug_regions {
+            self.debug_regions = regions;
+        }
+    }
+
+    /// Replace the game frame used for the acrylic background; `x`/`y` is its screen position.
+    /// Takes effect with the next redraw.
+    pub fn set_backdrop(&mut self, backdrop: Option<(Arc<Backdrop>, f32, f32)>) {
+        if !self.config.acrylic {
+            return;
+        }
+        self.backdrop = backdrop;
+        self.backdrop_brush = None;
+    }
+
+    /// Take box `index` off the screen right away (its region is hidden from now on).
+    pub fn dismiss(&mut self, index: usize, hwnd: HWND) -> Result<()> {
+        if index >= self.last_texts.len() {
+            return Ok(());
+        }
+        self.last_texts.remove(index);
+        self.highlight = None;
+        self.expanded = None;
+        self.redraw(hwnd)
+    }
+
+    /// Move everything on screen (and any frame waiting to fade in) by the given offset.
+    pub fn offset_texts(&mut self, dx: f32, dy: f32, hwnd: HWND) -> Result<()> {
+        self.shift(dx, dy);
+        if self.last_texts.is_empty() && self.debug_regions.is_empty() {
+            return Ok(());
+        }
+        self.redraw(hwnd)
+    }
+
+    /// True while a fade is in progress and `tick` should keep being called.
+    pub fn is_animating(&self) -> bool {
+        self.fade_pending.is_some() || (self.fade_factor < 1.0 && !self.last_texts.is_empty())
+    }
+
+    /// Advance the fade animation. Returns whether further ticks are needed.
+    pub fn tick(&mut self, hwnd: HWND) -> Result<bool> {
+        let now = Instant::now();
+        let step = now.duration_since(self.last_fade_tick).as_secs_f32() * 1000.0
+            / self.config.fade_ms.max(1) as f32;
+        self.last_fade_tick = now;
+
+        if self.fade_pending.is_some() {
+            self.fade_factor = (self.fade_factor - step).max(0.0);
+            if self.fade_factor <= 0.0 {
+                match self.fade_pending.take().flatten() {
+                    Some(texts) => self.show(texts, hwnd)?,
+                    None => self.clear_now(hwnd)?,
+                }
+                return Ok(self.is_animating());
+            }
+        } else {
+            self.fade_factor = (self.fade_factor + step).min(1.0);
+        }
+        self.apply_opacity()?;
+        Ok(self.is_animating())
+    }
+
+    fn layer_opacity(&self) -> f32 {
+        // A lone badge, HUD or history panel stays visible after the boxes faded out
+        let pinned = self.status.is_some() || self.hud.is_some() || !self.history.is_empty();
+        let fade = if self.last_texts.is_empty() && pinned { 1.0 } else { self.fade_factor };
+        self.controls.opacity.get() * fade
+    }
+
+    /// Re-render the current frame with `index` highlighted (None = no highlight).
+    pub fn set_highlight(&mut self, index: Option<usize>, hwnd: HWND) -> Result<()> {
+        self.highlight = index;
+        self.redraw(hwnd)
+    }
+
+    /// Topmost box containing the window-local point, if any.
+    pub fn hit_test(&self, x: f32, y: f32) -> Option<usize> {
+        self.drawn_boxes
+            .iter()
+            .rposition(|r| x >= r.left && x <= r.right && y >= r.top && y <= r.bottom)
+    }
+
+    pub fn text_at(&self, index: usize) -> Option<&TranslatedText> {
+        self.last_texts.get(index)
+    }
+
+    pub fn copy_flash_enabled(&self) -> bool {
+        self.config.copy_flash
+    }
+
+    /// Swap in appearance settings edited while running. Brushes, text formats and the skin
+    /// are built from the config, so they are recreated before the current boxes are redrawn.
+    pub fn apply_config(&mut self, config: OverlayConfig, hwnd: HWND) -> Result<()> {
+        self.config = config;
+        // The setters ignore these while their option is off, so drop what was kept from before
+        if !self.config.acrylic {
+            self.backdrop = None;
+            self.backdrop_brush = None;
+        }
+        if !self.config.debug_regions {
+            self.debug_regions.clear();
+        }
+        self.text_format_cache.clear();
+        self.bg_gradient_brush = None;
+        self.skin = None;
+        if let Some(context) = self.surface.as_ref().map(|s| s.context.clone()) {
+            self.create_brushes(&context)?;
+        }
+        self.redraw(hwnd)
+    }
+
+    /// Lay out and draw the current boxes again, e.g. after the font scale changed.
+    pub fn redraw(&mut self, hwnd: HWND) -> Result<()> {
+        if self.surface.is_none() {
+            return Ok(());
+        }
+
+        match self.render_inner(hwnd) {
+            Ok(()) => Ok(()),
+            Err(e) => {
+                if is_device_lost(&e) {
+                    crate::log_always("[D2D] Render target lost, recreating...");
+                    self.recreate_render_resources()?;
+                    self.render_inner(hwnd)
+                } else {
+                    Err(e)
+                }
+            }
+        }
+    }
+
+    fn render_inner(&mut self, hwnd: HWND) -> Result<()> {
+        // Build (and fit) text layouts before borrowing D2D resources
+        let texts = std::mem::take(&mut self.last_texts);
+        let expanded = self.expanded;
+        let layouts: Result<Vec<TextBox>> = texts
+            .iter()
+            .enumerate()
+            .map(|(i, t)| {
+                let (plain, spans) = if self.config.cjk_line_breaking {
+                    ruby::parse(&linebreak::apply_kinsoku(&t.translated_text))
+                } else {
+                    ruby::parse(&t.translated_text)
+                };
+                let (layout, font_size) = self.create_layout(&plain, t, !spans.is_empty())?;
+                if expanded != Some(i) {
+                    self.limit_lines(&layout, t.vertical)?;
+                }
+                let ruby = self.layout_ruby(&layout, &spans, font_size, t)?;
+                Ok(TextBox { layout, ruby })
+            })
+            .collect();
+        self.last_texts = texts;
+        let layouts = layouts?;
+        let badge = self.status.map(|status| self.badge_layout(status)).transpose()?;
+        let hud = self.hud.clone().map(|text| self.hud_layout(&text)).transpose()?;
+        let history = self.history_layout()?;
+
+        let styles: Vec<BoxStyle> = self.last_texts.iter().map(|t| self.box_style(t.zone)).collect();
+        // Larger corner radii need more room so glyphs don't touch the curve
+        let paddings: Vec<f32> = styles.iter().map(|s| 4.0_f32.max(s.corner_radius * 0.5)).collect();
+        let mut boxes = Vec::with_capacity(layouts.len());
+        // Where the text starts inside its layout box (non-zero for centred/right-aligned lines)
+        let mut text_offsets = Vec::with_capacity(layouts.len());
+        for ((text, text_box), &padding) in self.last_texts.iter().zip(layouts.iter()).zip(&paddings) {
+            let mut metrics = DWRITE_TEXT_METRICS::default();
+            unsafe { text_box.layout.GetMetrics(&mut metrics)? };
+            text_offsets.push(metrics.left);
+            let anchor_dy = match self.config.vertical_anchor {
+                VerticalAnchor::Middle if !self.config.stack_vertically && text.anchor_height > 0.0 => {
+                    (text.anchor_height - metrics.height) / 2.0
+                }
+                _ => 0.0,
+            };
+            let left = text.x - self.origin_x as f32 + metrics.left - padding;
+            let top = text.y - self.origin_y as f32 + anchor_dy - padding;
+            boxes.push(D2D_RECT_F {
+                left,
+                top,
+                right: left + metrics.width + padding * 2.0,
+                bottom: top + metrics.height + padding * 2.0,
+            });
+        }
+        let source_boxes = boxes.clone();
+        if self.config.stack_vertically {
+            stack_boxes(&mut boxes, BOX_GAP);
+        } else if self.config.avoid_overlap {
+            separate_boxes(&mut boxes, BOX_GAP);
+        }
+
+        unsafe {
+            let Some(surface) = &self.surface else {
+                return Ok(());
+            };
+            let target = &surface.context;
+            let bg_brush = match &self.bg_brush {
+                Some(b) => b,
+                None => return Ok(()),
+            };
+            let text_brush = match &self.text_brush {
+                Some(b) => b,
+                None => return Ok(()),
+            };
+            let outline_brush = match &self.outline_brush {
+                Some(b) => b,
+                None => return Ok(()),
+            };
+            let border_brush = match &self.border_brush {
+                Some(b) => b,
+                None => return Ok(()),
+            };
+
+            if let Some((backdrop, x, y)) = &self.backdrop {
+                if self.backdrop_brush.is_none() {
+                    self.backdrop_brush = Some(surface.blurred_brush(backdrop, ACRYLIC_BLUR)?);
+                }
+                if let Some(brush) = &self.backdrop_brush {
+                    brush.SetTransform(&windows_numerics::Matrix3x2 {
+                        M11: backdrop.scale,
+                        M12: 0.0,
+                        M21: 0.0,
+                        M22: backdrop.scale,
+                        M31: x - self.origin_x as f32,
+                        M32: y - self.origin_y as f32,
+                    });
+                }
+            }
+            let backdrop_brush = self.backdrop_brush.as_ref();
+
+            surface.begin_draw()?;
+
+            // The window may cover the whole monitor, so only the game itself is darkened
+            if let Some(area) = self.dim_area.filter(|_| self.config.dim > 0.0 && !self.last_texts.is_empty()) {
+                bg_brush.SetColor(&to_color_f(&[0.0, 0.0, 0.0, self.config.dim]));
+                let (ox, oy) = (self.origin_x as f32, self.origin_y as f32);
+                let rect = D2D_RECT_F { left: area.left - ox, top: area.top - oy, right: area.right - ox, bottom: area.bottom - oy };
+                target.FillRectangle(&rect, bg_brush);
+            }
+
+            if !self.debug_regions.is_empty() {
+                text_brush.SetColor(&to_color_f(&DEBUG_REGION_COLOR));
+                let (ox, oy) = (self.origin_x as f32, self.origin_y as f32);
+                for r in &self.debug_regions {
+                    let rect = D2D_RECT_F { left: r.left - ox, top: r.top - oy, right: r.right - ox, bottom: r.bottom - oy };
+                    target.DrawRectangle(&rect, text_brush, 1.0, None);
+                }
+            }
+
+            for (i, ((text, text_box), &bg_rect)) in
+                self.last_texts.iter().zip(layouts.iter()).zip(boxes.iter()).enumerate()
+            {
+                // Leader line back to the source region for boxes pushed well away from it
+                // (overlay mode only; the side panel is never next to its source)
+                let (style, padding) = (&styles[i], paddings[i]);
+                let source = &source_boxes[i];
+                let box_h = bg_rect.bottom - bg_rect.top;
+                if !self.config.stack_vertically && (bg_rect.top - source.top).abs() > box_h / 2.0 {
+                    let x = bg_rect.left + padding * 2.0;
+                    let (from_y, to_y) = if bg_rect.top > source.top {
+                        (bg_rect.top, source.top + padding)
+                    } else {
+                        (bg_rect.bottom, source.top + padding)
+                    };
+                    text_brush.SetColor(&to_color_f(&style.text_color));
+                    target.DrawLine(
+                        windows_numerics::Vector2 { X: x, Y: from_y },
+                        windows_numerics::Vector2 { X: x, Y: to_y },
+                        text_brush,
+                        1.0,
+                        None,
+                    );
+                }
+
+                let local_x = bg_rect.left + padding - text_offsets[i];
+                let local_y = bg_rect.top + padding;
+                let box_width = bg_rect.right - bg_rect.left;
+                let box_height = bg_rect.bottom - bg_rect.top;
+
+                let (text_color, mut bg_color) = contrast_colors(style, self.config.auto_contrast, text.bg_luminance);
+                let radius = style.corner_radius.min(box_width / 2.0).min(box_height / 2.0);
+                if let Some(brush) = backdrop_brush {
+                    target.FillRoundedRectangle(
+                        &D2D1_ROUNDED_RECT { rect: bg_rect, radiusX: radius, radiusY: radius },
+                        brush,
+                    );
+                    bg_color[3] *= ACRYLIC_TINT;
+                }
+                bg_brush.SetColor(&to_color_f(&bg_color));
+                text_brush.SetColor(&to_color_f(&text_color));
+                outline_brush.SetColor(&to_color_f(&style.outline_color));
+                border_brush.SetColor(&to_color_f(&style.border_color));
+
+                let rounded = D2D1_ROUNDED_RECT { rect: bg_rect, radiusX: radius, radiusY: radius };
+                match (&self.skin, &self.bg_gradient_brush) {
+                    (Some(skin), _) => draw_nine_slice(target, skin, &bg_rect, self.config.skin_slice),
+                    // Auto-contrast or a zone picked a different background, which the gradient can't follow
+                    (None, Some(gradient)) if bg_color[..3] == self.config.bg_color[..3] => {
+                        gradient.SetStartPoint(windows_numerics::Vector2 { X: bg_rect.left, Y: bg_rect.top });
+                        gradient.SetEndPoint(windows_numerics::Vector2 { X: bg_rect.left, Y: bg_rect.bottom });
+                        gradient.SetOpacity(if backdrop_brush.is_some() { ACRYLIC_TINT } else { 1.0 });
+                        target.FillRoundedRectangle(&rounded, gradient);
+                    }
+                    _ => target.FillRoundedRectangle(&rounded, bg_brush),
+                }
+                if style.border_width > 0.0 {
+                    // Inset by half the stroke so the border stays inside the box
+                    let inset = style.border_width / 2.0;
+                    let border_rect = D2D_RECT_F {
+                        left: bg_rect.left + inset,
+                        top: bg_rect.top + inset,
+                        right: bg_rect.right - inset,
+                        bottom: bg_rect.bottom - inset,
+                    };
+                    let border_radius = (radius - inset).max(0.0);
+                    target.DrawRoundedRectangle(
+                        &D2D1_ROUNDED_RECT { rect: border_rect, radiusX: border_radius, radiusY: border_radius },
+                        border_brush,
+                        style.border_width,
+                        None,
+                    );
+                }
+
+                if self.highlight == Some(i) {
+                    target.DrawRoundedRectangle(
+                        &D2D1_ROUNDED_RECT { rect: bg_rect, radiusX: radius, radiusY: radius },
+                        text_brush,
+                        3.0,
+                        None,
+                    );
+                }
+
+                let runs = std::iter::once((&text_box.layout, 0.0, 0.0))
+                    .chain(text_box.ruby.iter().map(|r| (&r.layout, r.x, r.y)));
+                for (layout, rx, ry) in runs {
+                    let (x, y) = (local_x + rx, local_y + ry);
+                    // Outline: stamp the same layout around the glyphs before the fill pass
+                    for (dx, dy) in outline_offsets(style.outline_width) {
+                        target.DrawTextLayout(
+                            windows_numerics::Vector2 { X: x + dx, Y: y + dy },
+                            layout,
+                            outline_brush,
+                            D2D1_DRAW_TEXT_OPTIONS_NONE,
+                        );
+                    }
+
+                    target.DrawTextLayout(
+                        windows_numerics::Vector2 { X: x, Y: y },
+                        layout,
+                        text_brush,
+                        D2D1_DRAW_TEXT_OPTIONS_ENABLE_COLOR_FONT,
+                    );
+                }
+            }
+
+            self.history_rect = match &history {
+                Some(layout) => Some(self.draw_history(target, layout, bg_brush, text_brush)?),
+                None => None,
+            };
+
+            if let (Some(status), Some(layout)) = (self.status, &badge) {
+                self.draw_badge(target, status, layout, bg_brush, text_brush)?;
+            }
+            if let Some(layout) = &hud {
+                self.draw_hud(target, layout, bg_brush, text_brush)?;
+            }
+
+            surface.end_draw()?;
+            surface.set_opacity(self.layer_opacity())?;
+        }
+        self.drawn_boxes = boxes;
+        self.update_hit_region(hwnd)
+    }
+
+    /// The window is only hit-testable where boxes are: a composition surface has no
+    /// per-pixel hit testing, so interactive mode clips the window region to the boxes.
+    /// (Leader lines outside the boxes are clipped with it while interactive.)
+    pub fn update_hit_region(&self, hwnd: HWND) -> Result<()> {
+        unsafe {
+            if !self.controls.interactive.load(Ordering::SeqCst) {
+                SetWindowRgn(hwnd, None, true);
+                return Ok(());
+            }
+            let region = CreateRectRgn(0, 0, 0, 0);
+            for b in self.drawn_boxes.iter().chain(&self.history_rect) {
+                let rect = CreateRectRgn(b.left as i32, b.top as i32, b.right.ceil() as i32, b.bottom.ceil() as i32);
+                CombineRgn(Some(region), Some(region), Some(rect), RGN_OR);
+                let _ = DeleteObject(rect.into());
+            }
+            // The system owns the region from here on
+            SetWindowRgn(hwnd, Some(region), true);
+        }
+        Ok(())
+    }
+
+    /// Re-apply the shared opacity to the already-rendered layer without redrawing.
+    pub fn apply_opacity(&self) -> Result<()> {
+        match &self.surface {
+            Some(surface) => surface.set_opacity(self.layer_opacity()),
+            None => Ok(()),
+        }
+    }
+
+    pub fn clear(&mut self, hwnd: HWND) -> Result<()> {
+        if self.config.fade_ms > 0 && !self.last_texts.is_empty() {
+            self.last_fade_tick = Instant::now();
+            self.fade_pending = Some(None);
+            return Ok(());
+        }
+        self.clear_now(hwnd)
+    }
+
+    fn clear_now(&mut self, hwnd: HWND) -> Result<()> {
+        self.fade_pending = None;
+        self.last_texts.clear();
+        self.drawn_boxes.clear();
+        self.debug_regions.clear();
+        self.highlight = None;
+        // Redrawing an empty frame keeps the status badge
+        self.redraw(hwnd)
+    }
+
+    /// Show or hide the status badge.
+    pub fn set_status(&mut self, status: Option<PipelineStatus>, hwnd: HWND) -> Result<()> {
+        let status = status.filter(|_| self.config.status_badge);
+        if status == self.status {
+            return Ok(());
+        }
+        self.status = status;
+        self.redraw(hwnd)
+    }
+
+    fn badge_layout(&mut self, status: PipelineStatus) -> Result<IDWriteTextLayout> {
+        let label: Vec<u16> = status.label().encode_utf16().collect();
+        let format = self.get_or_create_text_format(12.0, false, None)?;
+        Ok(unsafe { self.write_factory.CreateTextLayout(&label, &format, 300.0, 100.0)? })
+    }
+
+    fn draw_badge(
+        &self,
+        target: &ID2D1RenderTarget,
+        status: PipelineStatus,
+        layout: &IDWriteTextLayout,
+        bg_brush: &ID2D1SolidColorBrush,
+        text_brush: &ID2D1SolidColorBrush,
+    ) -> Result<()> {
+        const MARGIN: f32 = 8.0;
+        const PADDING: f32 = 4.0;
+        unsafe {
+            let mut metrics = DWRITE_TEXT_METRICS::default();
+            layout.GetMetrics(&mut metrics)?;
+            let right = self.width as f32 - MARGIN;
+            let rect = D2D_RECT_F {
+                left: right - metrics.width - PADDING * 3.0,
+                top: MARGIN,
+                right,
+                bottom: MARGIN + metrics.height + PADDING * 2.0,
+            };
+            bg_brush.SetColor(&to_color_f(&status.color()));
+            target.FillRoundedRectangle(&D2D1_ROUNDED_RECT { rect, radiusX: 4.0, radiusY: 4.0 }, bg_brush);
+            text_brush.SetColor(&to_color_f(&[1.0, 1.0, 1.0, 1.0]));
+            target.DrawTextLayout(
+                windows_numerics::Vector2 { X: rect.left + PADDING * 1.5, Y: rect.top + PADDING },
+                layout,
+                text_brush,
+                D2D1_DRAW_TEXT_OPTIONS_NONE,
+            );
+        }
+        Ok(())
+    }
+
+    /// Show, update or hide the performance HUD.
+    pub fn set_hud(&mut self, text: Option<String>, hwnd: HWND) -> Result<()> {
+        if text == self.hud {
+            return Ok(());
+        }
+        self.hud = text;
+        self.redraw(hwnd)
+    }
+
+    fn hud_layout(&mut self, text: &str) -> Result<IDWriteTextLayout> {
+        let text: Vec<u16> = text.encode_utf16().collect();
+        let format = self.get_or_create_text_format(12.0, false, None)?;
+        Ok(unsafe { self.write_factory.CreateTextLayout(&text, &format, 300.0, 200.0)? })
+    }
+
+    /// Draw the performance HUD in the top-left corner.
+    fn draw_hud(
+        &self,
+        target: &ID2D1RenderTarget,
+        layout: &IDWriteTextLayout,
+        bg_brush: &ID2D1SolidColorBrush,
+        text_brush: &ID2D1SolidColorBrush,
+    ) -> Result<()> {
+        const MARGIN: f32 = 8.0;
+        const PADDING: f32 = 6.0;
+        unsafe {
+            let mut metrics = DWRITE_TEXT_METRICS::default();
+            layout.GetMetrics(&mut metrics)?;
+            let rect = D2D_RECT_F {
+                left: MARGIN,
+                top: MARGIN,
+                right: MARGIN + metrics.width + PADDING * 2.0,
+                bottom: MARGIN + metrics.height + PADDING * 2.0,
+            };
+            bg_brush.SetColor(&to_color_f(&HUD_BACKGROUND));
+            target.FillRoundedRectangle(&D2D1_ROUNDED_RECT { rect, radiusX: 4.0, radiusY: 4.0 }, bg_brush);
+            text_brush.SetColor(&to_color_f(&[1.0, 1.0, 1.0, 1.0]));
+            target.DrawTextLayout(
+                windows_numerics::Vector2 { X: rect.left + PADDING, Y: rect.top + PADDING },
+                layout,
+                text_brush,
+                D2D1_DRAW_TEXT_OPTIONS_NONE,
+            );
+        }
+        Ok(())
+    }
+
+    /// Append newly shown translations to the history panel. While scrolled back the
+    /// panel keeps showing the same lines.
+    pub fn push_history(&mut self, entries: Vec<HistoryEntry>, hwnd: HWND) -> Result<()> {
+        let limit = self.config.history_lines as usize;
+        if limit == 0 || entries.is_empty() {
+            return Ok(());
+        }
+        if self.history_scroll > 0 {
+            self.history_scroll += entries.len();
+        }
+        self.history.extend(entries);
+        while self.history.len() > limit {
+            self.history.pop_front();
+        }
+        self.history_scroll = self.history_scroll.min(self.history.len().saturating_sub(HISTORY_ROWS));
+        self.redraw(hwnd)
+    }
+
+    /// Whether the window-local point is over the history panel.
+    pub fn history_hit_test(&self, x: f32, y: f32) -> bool {
+        self.history_rect
+            .is_some_and(|r| x >= r.left && x <= r.right && y >= r.top && y <= r.bottom)
+    }
+
+    /// Scroll the history panel; positive `lines` go back towards older entries.
+    pub fn scroll_history(&mut self, lines: i32, hwnd: HWND) -> Result<()> {
+        let max = self.history.len().saturating_sub(HISTORY_ROWS);
+        let scroll = (self.history_scroll as i64 + lines as i64).clamp(0, max as i64) as usize;
+        if scroll == self.history_scroll {
+            return Ok(());
+        }
+        self.history_scroll = scroll;
+        self.redraw(hwnd)
+    }
+
+    /// One line per entry (cut with "…"), under a header giving the visible range.
+    fn history_layout(&mut self) -> Result<Option<IDWriteTextLayout>> {
+        if self.history.is_empty() {
+            return Ok(None);
+        }
+        let end = self.history.len() - self.history_scroll;
+        let start = end.saturating_sub(HISTORY_ROWS);
+        let mut text = format!("履歴 {}–{} / {}", start + 1, end, self.history.len());
+        for entry in self.history.range(start..end) {
+            text.push_str(&format!("\n{}  {}", entry.time, ruby::strip(&entry.text).replace('\n', " ")));
+        }
+        let text_w: Vec<u16> = text.encode_utf16().collect();
+        let format = self.get_or_create_text_format(HISTORY_FONT_SIZE, false, None)?;
+        unsafe {
+            let layout = self.write_factory.CreateTextLayout(&text_w, &format, HISTORY_WIDTH, self.height as f32)?;
+            layout.SetWordWrapping(DWRITE_WORD_WRAPPING_NO_WRAP)?;
+            let sign = self.write_factory.CreateEllipsisTrimmingSign(&layout)?;
+            let trimming = DWRITE_TRIMMING {
+                granularity: DWRITE_TRIMMING_GRANULARITY_CHARACTER,
+                delimiter: 0,
+                delimiterCount: 0,
+            };
+            layout.SetTrimming(&trimming, &sign)?;
+            Ok(Some(layout))
+        }
+    }
+
+    /// Draw the history panel in the bottom-left corner and return its rectangle.
+    fn draw_history(
+        &self,
+        target: &ID2D1RenderTarget,
+        layout: &IDWriteTextLayout,
+        bg_brush: &ID2D1SolidColorBrush,
+        text_brush: &ID2D1SolidColorBrush,
+    ) -> Result<D2D_RECT_F> {
+        const MARGIN: f32 = 8.0;
+        const PADDING: f32 = 6.0;
+        unsafe {
+            let mut metrics = DWRITE_TEXT_METRICS::default();
+            layout.GetMetrics(&mut metrics)?;
+            let bottom = self.height as f32 - MARGIN;
+            let rect = D2D_RECT_F {
+                left: MARGIN,
+                top: bottom - metrics.height - PADDING * 2.0,
+                right: MARGIN + HISTORY_WIDTH + PADDING * 2.0,
+                bottom,
+            };
+            bg_brush.SetColor(&to_color_f(&self.config.bg_color));
+            text_brush.SetColor(&to_color_f(&self.config.text_color));
+            let radius = self.config.corner_radius;
+            target.FillRoundedRectangle(&D2D1_ROUNDED_RECT { rect, radiusX: radius, radiusY: radius }, bg_brush);
+            target.DrawTextLayout(
+                windows_numerics::Vector2 { X: rect.left + PADDING, Y: rect.top + PADDING },
+                layout,
+                text_brush,
+                D2D1_DRAW_TEXT_OPTIONS_ENABLE_COLOR_FONT,
+            );
+            Ok(rect)
+        }
+    }
+}
+
+impl Drop for Overlay {
+    fn drop(&mut self) {
+        self.release_surface();
+        unsafe { CoUninitialize() };
+    }
+}
@@ -0,0 +1,1969 @@
+//! The capture → OCR → translate → overlay pipeline for one target window.
+//!
+//! [`run_overlay_thread`] owns the overlay window and its message loop and drives the
+//! capture loop next to it; the `WM_APPLY_*` messages let a frontend push live changes.
+
+use anyhow::Result;
+use std::cell::{Cell, RefCell};
+use std::collections::HashSet;
+use std::hash::Hasher;
+use std::sync::atomic::{AtomicBool, AtomicIsize, Ordering};
+use std::sync::{mpsc, Arc};
+use std::time::{Duration, Instant};
+use tokio::sync::{mpsc as tokio_mpsc, watch};
+
+use windows::Win32::Foundation::*;
+use windows::Win32::Graphics::Direct2D::Common::D2D_RECT_F;
+use windows::Win32::Graphics::Gdi::*;
+use windows::Win32::System::Com::*;
+use windows::Win32::System::LibraryLoader::GetModuleHandleW;
+use windows::Win32::UI::Controls::WM_MOUSELEAVE;
+use windows::Win32::UI::HiDpi::*;
+use windows::Win32::UI::Input::KeyboardAndMouse::*;
+use windows::Win32::UI::WindowsAndMessaging::*;
+use windows::core::*;
+
+use crate::cache::{CacheScope, TranslationCache};
+use crate::capture::WindowCapture;
+use crate::config::{AppConfig, DisplayMode, InputSource, Placement, SourceReading, StyleZone, TextAlign, VerticalText};
+use crate::dictionary::{Dictionary, DictionaryKind};
+use crate::lang::Language;
+use crate::ocr::{OCREngine, TextRegion};
+use crate::overlay::{
+    Backdrop, HistoryEntry, LookupRequest, OcrPreview, Overlay, OverlayConfig, OverlayControls, PipelineStatus, SessionRecord,
+    SessionStats, TranslatedText,
+};
+use crate::translate::Translator;
+use crate::feed::{self, FeedEvent, FeedLine};
+use crate::audio::{self, Whisper};
+use crate::{capture, clipboard, log, log_always, ocr, readout, ruby, texthook, translate};
+
+const WM_RENDER: u32 = WM_USER + 1;
+/// Posted by the GUI when the opacity slider moves
+pub const WM_APPLY_OPACITY: u32 = WM_USER + 2;
+/// Posted by the GUI when the interactive-mode switch is toggled
+pub const WM_APPLY_INTERACTIVE: u32 = WM_USER + 3;
+/// Posted by the GUI when the font-scale slider moves
+pub const WM_APPLY_FONT_SCALE: u32 = WM_USER + 4;
+/// Posted by the GUI when "hide from screen capture" is toggled
+pub const WM_APPLY_CAPTURE_VISIBILITY: u32 = WM_USER + 5;
+/// Posted by the GUI when the show/hide overlay hotkey is pressed
+pub const WM_APPLY_VISIBILITY: u32 = WM_USER + 6;
+/// Posted by the GUI after it stored edited appearance settings in `OverlayControls::pending_config`
+pub const WM_APPLY_CONFIG: u32 = WM_USER + 7;
+
+/// Global hotkeys registered on the overlay window
+const HOTKEY_OPACITY_UP: i32 = 1;
+const HOTKEY_OPACITY_DOWN: i32 = 2;
+const HOTKEY_INTERACTIVE: i32 = 3;
+/// Gloss mode only: switch between the word glosses and the full translation
+const HOTKEY_GLOSS_TRANSLATION: i32 = 4;
+const HOTKEY_PERF_HUD: i32 = 5;
+/// (id, virtual key, description) — all combined with Ctrl+Alt
+const OVERLAY_HOTKEYS: [(i32, VIRTUAL_KEY, &str); 4] = [
+    (HOTKEY_OPACITY_UP, VK_UP, "Ctrl+Alt+↑"),
+    (HOTKEY_OPACITY_DOWN, VK_DOWN, "Ctrl+Alt+↓"),
+    (HOTKEY_INTERACTIVE, VK_I, "Ctrl+Alt+I"),
+    (HOTKEY_PERF_HUD, VK_F, "Ctrl+Alt+F"),
+];
+/// Timer that removes the "copied" highlight
+const TIMER_COPY_FLASH: usize = 1;
+const COPY_FLASH_MS: u32 = 600;
+/// Timer driving fade-in/out animations (~60fps)
+const TIMER_FADE: usize = 2;
+const FADE_TICK_MS: u32 = 16;
+/// Timer polling the game window position, independent of the OCR cadence
+const TIMER_TRACK: usize = 3;
+const TRACK_INTERVAL_MS: u32 = 33;
+/// Timer re-asserting TOPMOST for games that push themselves above the overlay
+const TIMER_TOPMOST: usize = 4;
+const TOPMOST_INTERVAL_MS: u32 = 250;
+/// Timer refreshing the performance HUD
+const TIMER_HUD: usize = 5;
+const HUD_INTERVAL_MS: u32 = 500;
+/// Opacity change per hotkey press
+const OPACITY_STEP: f32 = 0.1;
+
+/// Truncate a string to at most `max_chars` characters (safe for multi-byte UTF-8).
+fn truncate_str(s: &str, max_chars: usize) -> &str {
+    match s.char_indices().nth(max_chars) {
+        Some((idx, _)) => &s[..idx],
+        None => s,
+    }
+}
+
+/// Render command sent from background thread to overlay thread
+enum RenderCommand {
+    /// `origin` is the game window's client origin the coordinates were computed against;
+    /// `backdrop` is the captured frame for the acrylic background (placed at `origin`)
+    /// `regions` are the frame's OCR regions (screen coordinates), outlined in debug mode
+    Draw {
+        texts: Vec<TranslatedText>,
+        origin: (i32, i32),
+        backdrop: Option<Arc<Backdrop>>,
+        regions: Vec<D2D_RECT_F>,
+    },
+    Clear,
+    /// Pipeline status shown as a badge on the overlay covering the game
+    Status(Option<PipelineStatus>),
+}
+
+/// One layered overlay window, covering either a monitor or the game's client area
+struct MonitorOverlay {
+    hwnd: HWND,
+    monitor: HMONITOR,
+    overlay: Overlay,
+}
+
+/// Store receiver in window's user data.
+/// Shared by all overlay windows; the first one is the primary window that owns the
+/// hotkeys, the tracking timer and the render channel, and frees this state.
+struct WndState {
+    overlays: Vec<MonitorOverlay>,
+    rx: mpsc::Receiver<RenderCommand>,
+    controls: Arc<OverlayControls>,
+    target_hwnd: HWND,
+    /// Client origin of the game window that the overlay's texts are currently aligned to
+    anchor: (i32, i32),
+    /// Single window covering the game's client area that moves and resizes with it
+    follow_target: bool,
+    /// Foreground window at the last z-order check
+    last_foreground: HWND,
+    /// Translations in the previous frame, so only new lines go to the history panel
+    shown: HashSet<String>,
+    /// Frame count at the previous HUD refresh, for the capture rate
+    hud_frames: (u64, Instant),
+}
+
+impl WndState {
+    fn is_primary(&self, hwnd: HWND) -> bool {
+        self.overlays.first().is_some_and(|o| o.hwnd == hwnd)
+    }
+
+    fn overlay_for(&mut self, hwnd: HWND) -> Option<&mut MonitorOverlay> {
+        self.overlays.iter_mut().find(|o| o.hwnd == hwnd)
+    }
+
+    /// The window on the game's monitor, which carries the status badge and history panel.
+    fn game_overlay(&mut self) -> &mut MonitorOverlay {
+        let point = POINT { x: self.anchor.0, y: self.anchor.1 };
+        let monitor = unsafe { MonitorFromPoint(point, MONITOR_DEFAULTTONEAREST) };
+        let index = self.overlays.iter().position(|o| o.monitor == monitor).unwrap_or(0);
+        &mut self.overlays[index]
+    }
+
+    /// Refresh the performance HUD on the game's monitor from the session counters (hide it when off).
+    fn update_hud(&mut self) {
+        let text = self.controls.perf_hud.load(Ordering::SeqCst).then(|| {
+            let stats = self.controls.stats.lock().unwrap().clone();
+            let (frames, since) = self.hud_frames;
+            let fps = stats.frames.saturating_sub(frames) as f32 / since.elapsed().as_secs_f32().max(0.001);
+            self.hud_frames = (stats.frames, Instant::now());
+            let ms = |d: Option<Duration>| d.map_or_else(|| "-".to_string(), |d| format!("{} ms", d.as_millis()));
+            format!(
+                "キャプチャ {:.1} fps ({})\nOCR {}\n翻訳 {}\n翻訳待ち {}行\nAPIエラー {}",
+                fps,
+                ms(stats.capture_ok.map(|_| stats.last_capture)),
+                ms((stats.ocr_runs > 0).then_some(stats.last_ocr)),
+                ms(stats.last_translation),
+                stats.queue_depth,
+                stats.api_errors
+            )
+        });
+        let o = self.game_overlay();
+        if let Err(e) = o.overlay.set_hud(text, o.hwnd) {
+            log_always(&format!("HUD error: {:?}", e));
+        }
+    }
+
+    /// Split a frame's texts between the monitor windows by where each box starts.
+    fn draw(&mut self, texts: Vec<TranslatedText>) {
+        let time = chrono::Local::now().format("%H:%M:%S").to_string();
+        let new_lines: Vec<HistoryEntry> = texts
+            .iter()
+            .filter(|t| !self.shown.contains(&t.translated_text))
+            .map(|t| HistoryEntry { time: time.clone(), text: t.translated_text.clone() })
+            .collect();
+        self.shown = texts.iter().map(|t| t.translated_text.clone()).collect();
+        let o = self.game_overlay();
+        if let Err(e) = o.overlay.push_history(new_lines, o.hwnd) {
+            log_always(&format!("Render error: {:?}", e));
+        }
+
+        let mut per_monitor: Vec<Vec<TranslatedText>> = self.overlays.iter().map(|_| Vec::new()).collect();
+        for text in texts {
+            let point = POINT { x: text.x as i32, y: text.y as i32 };
+            let monitor = unsafe { MonitorFromPoint(point, MONITOR_DEFAULTTONEAREST) };
+            let index = self.overlays.iter().position(|o| o.monitor == monitor).unwrap_or(0);
+            per_monitor[index].push(text);
+        }
+        for (o, texts) in self.overlays.iter_mut().zip(per_monitor) {
+            if let Err(e) = o.overlay.render(texts, o.hwnd) {
+                log_always(&format!("Render error: {:?}", e));
+            }
+        }
+    }
+
+    /// Put the overlay windows back on top when the game has covered them — some games
+    /// re-assert TOPMOST themselves — or has just come to the foreground.
+    fn keep_on_top(&mut self) {
+        let foreground = unsafe { GetForegroundWindow() };
+        let gained_focus = foreground == self.target_hwnd && self.last_foreground != self.target_hwnd;
+        self.last_foreground = foreground;
+        for o in &self.overlays {
+            if gained_focus || is_below(o.hwnd, self.target_hwnd) {
+                unsafe {
+                    let _ = SetWindowPos(
+                        o.hwnd,
+                        Some(HWND_TOPMOST),
+                        0,
+                        0,
+                        0,
+                        0,
+                        SWP_NOMOVE | SWP_NOSIZE | SWP_NOACTIVATE | SWP_NOOWNERZORDER,
+                    );
+                }
+            }
+        }
+    }
+
+    /// Re-fit every window to the area it covers after a DPI or display layout change.
+    /// Boxes are kept in screen coordinates, so they stay put while the windows move under
+    /// them. A monitor that went away has its window emptied; newly attached monitors get
+    /// a window on the next start.
+    fn refit(&mut self) {
+        if self.follow_target {
+            self.track_target();
+            return;
+        }
+        let monitors = list_monitors();
+        for o in &mut self.overlays {
+            let rect = if o.monitor.is_invalid() {
+                Some(virtual_screen_rect())
+            } else {
+                monitors.iter().find(|(m, _)| *m == o.monitor).map(|(_, rect)| *rect)
+            };
+            let result = match rect {
+                Some(rect) => o.overlay.set_bounds(&rect, o.hwnd),
+                None => o.overlay.clear(o.hwnd),
+            };
+            if let Err(e) = result {
+                log_always(&format!("Render error: {:?}", e));
+            }
+        }
+    }
+
+    /// Follow the game window between OCR passes by shifting what is already on screen.
+    fn track_target(&mut self) {
+        if self.follow_target {
+            let rect = capture::client_rect(self.target_hwnd);
+            // Minimized: keep the overlay where it was until the game is restored
+            if rect.right > rect.left && rect.bottom > rect.top {
+                self.anchor = (rect.left, rect.top);
+                let o = &mut self.overlays[0];
+                if let Err(e) = o.overlay.follow(&rect, o.hwnd) {
+                    log_always(&format!("Render error: {:?}", e));
+                }
+            }
+            return;
+        }
+        let pos = capture::client_origin(self.target_hwnd);
+        if pos != self.anchor {
+            let (dx, dy) = (pos.0 - self.anchor.0, pos.1 - self.anchor.1);
+            self.anchor = pos;
+            for o in &mut self.overlays {
+                if let Err(e) = o.overlay.offset_texts(dx as f32, dy as f32, o.hwnd) {
+                    log_always(&format!("Render error: {:?}", e));
+                }
+            }
+        }
+    }
+}
+
+/// Whether `window` (or a window it owns) sits above `hwnd` in the z-order.
+fn is_below(hwnd: HWND, window: HWND) -> bool {
+    unsafe {
+        let mut above = GetWindow(hwnd, GW_HWNDPREV).ok();
+        while let Some(w) = above {
+            if IsWindowVisible(w).as_bool() && GetAncestor(w, GA_ROOTOWNER) == window {
+                return true;
+            }
+            above = GetWindow(w, GW_HWNDPREV).ok();
+        }
+        false
+    }
+}
+
+/// Hide the window from screen capture (OBS, screenshots, screen sharing) while it stays
+/// visible on the monitor. Needs Windows 10 2004 or later.
+fn set_capture_excluded(hwnd: HWND, excluded: bool) {
+    let affinity = if excluded { WDA_EXCLUDEFROMCAPTURE } else { WDA_NONE };
+    if let Err(e) = unsafe { SetWindowDisplayAffinity(hwnd, affinity) } {
+        log_always(&format!("SetWindowDisplayAffinity failed: {:?}", e));
+    }
+}
+
+/// Toggle WS_EX_TRANSPARENT. While it is off, the window region set by
+/// `Overlay::update_hit_region` keeps everything but the translation boxes click-through.
+fn set_click_through(hwnd: HWND, click_through: bool) {
+    unsafe {
+        let ex_style = GetWindowLongPtrW(hwnd, GWL_EXSTYLE);
+        let new_style = if click_through {
+            ex_style | WS_EX_TRANSPARENT.0 as isize
+        } else {
+            ex_style & !(WS_EX_TRANSPARENT.0 as isize)
+        };
+        if new_style != ex_style {
+            SetWindowLongPtrW(hwnd, GWL_EXSTYLE, new_style);
+            let _ = SetWindowPos(
+                hwnd,
+                None,
+                0,
+                0,
+                0,
+                0,
+                SWP_NOMOVE | SWP_NOSIZE | SWP_NOZORDER | SWP_NOACTIVATE | SWP_FRAMECHANGED,
+            );
+        }
+    }
+}
+
+unsafe extern "system" fn wndproc(
+    hwnd: HWND,
+    msg: u32,
+    wparam: WPARAM,
+    lparam: LPARAM,
+) -> LRESULT {
+    match msg {
+        WM_DESTROY => {
+            let ptr = GetWindowLongPtrW(hwnd, GWLP_USERDATA) as *mut WndState;
+            if !ptr.is_null() && (*ptr).is_primary(hwnd) {
+                for (id, _, _) in OVERLAY_HOTKEYS {
+                    let _ = UnregisterHotKey(Some(hwnd), id);
+                }
+                let _ = UnregisterHotKey(Some(hwnd), HOTKEY_GLOSS_TRANSLATION);
+                // Take the other monitors' windows down with the primary one
+                let secondary: Vec<HWND> = (&(*ptr).overlays)[1..].iter().map(|o| o.hwnd).collect();
+                for other in secondary {
+                    let _ = DestroyWindow(other);
+                }
+                PostQuitMessage(0);
+            }
+            LRESULT(0)
+        }
+        WM_NCDESTROY => {
+            // Reclaim and drop WndState stored in GWLP_USERDATA (owned by the primary window)
+            let ptr = GetWindowLongPtrW(hwnd, GWLP_USERDATA) as *mut WndState;
+            if !ptr.is_null() {
+                SetWindowLongPtrW(hwnd, GWLP_USERDATA, 0);
+                if (*ptr).is_primary(hwnd) {
+                    drop(Box::from_raw(ptr));
+                }
+            }
+            DefWindowProcW(hwnd, msg, wparam, lparam)
+        }
+        WM_DPICHANGED | WM_DISPLAYCHANGE => {
+            // Keep covering the same physical area instead of taking the suggested DPI-scaled
+            // rect; the surface is drawn in raw pixels, so only its bounds need refreshing.
+            let ptr = GetWindowLongPtrW(hwnd, GWLP_USERDATA) as *mut WndState;
+            if !ptr.is_null() && (msg == WM_DPICHANGED || (*ptr).is_primary(hwnd)) {
+                (*ptr).refit();
+            }
+            LRESULT(0)
+        }
+        WM_PAINT => {
+            let mut ps = PAINTSTRUCT::default();
+            let _hdc = BeginPaint(hwnd, &mut ps);
+            let _ = EndPaint(hwnd, &ps);
+            LRESULT(0)
+        }
+        WM_RENDER => {
+            // Process all pending render commands
+            let ptr = GetWindowLongPtrW(hwnd, GWLP_USERDATA) as *mut WndState;
+            if !ptr.is_null() {
+                let state = &mut *ptr;
+                while let Ok(cmd) = state.rx.try_recv() {
+                    match cmd {
+                        RenderCommand::Draw { mut texts, origin, backdrop, mut regions } => {
+                            // Bring the new frame into the frame of reference of what is shown
+                            let (dx, dy) = (state.anchor.0 - origin.0, state.anchor.1 - origin.1);
+                            for t in &mut texts {
+                                t.x += dx as f32;
+                                t.y += dy as f32;
+                            }
+                            let backdrop = backdrop.map(|b| (b, state.anchor.0 as f32, state.anchor.1 as f32));
+                            for r in &mut regions {
+                                r.left += dx as f32;
+                                r.right += dx as f32;
+                                r.top += dy as f32;
+                                r.bottom += dy as f32;
+                            }
+                            // The game's client area, placed at the origin the texts are aligned to
+                            let game = capture::client_rect(state.target_hwnd);
+                            let (left, top) = (state.anchor.0 as f32, state.anchor.1 as f32);
+                            let dim_area = (game.right > game.left && game.bottom > game.top).then_some(D2D_RECT_F {
+                                left,
+                                top,
+                                right: left + (game.right - game.left) as f32,
+                                bottom: top + (game.bottom - game.top) as f32,
+                            });
+                            for o in &mut state.overlays {
+                                o.overlay.set_backdrop(backdrop.clone());
+                                o.overlay.set_debug_regions(regions.clone());
+                                o.overlay.set_dim_area(dim_area);
+                            }
+                            state.draw(texts);
+                        }
+                        RenderCommand::Clear => {
+                            for o in &mut state.overlays {
+                                let _ = o.overlay.clear(o.hwnd);
+                            }
+                        }
+                        RenderCommand::Status(status) => {
+                            let o = state.game_overlay();
+                            let _ = o.overlay.set_status(status, o.hwnd);
+                        }
+                    }
+                }
+                for o in &state.overlays {
+                    if o.overlay.is_animating() {
+                        SetTimer(Some(o.hwnd), TIMER_FADE, FADE_TICK_MS, None);
+                    }
+                }
+            }
+            LRESULT(0)
+        }
+        WM_LBUTTONUP => {
+            // Only reachable in interactive mode; copy "source\ntranslation" of the clicked box,
+            // or with Ctrl hand it to the GUI's dictionary popup
+            let ptr = GetWindowLongPtrW(hwnd, GWLP_USERDATA) as *mut WndState;
+            if let Some(state) = ptr.as_mut() {
+                let controls = state.controls.clone();
+                if let Some(o) = state.overlay_for(hwnd) {
+                    let x = (lparam.0 & 0xFFFF) as i16 as f32;
+                    let y = ((lparam.0 >> 16) & 0xFFFF) as i16 as f32;
+                    let clicked = o.overlay.hit_test(x, y).and_then(|index| Some((index, o.overlay.text_at(index)?)));
+                    if let Some((index, text)) = clicked {
+                        if GetKeyState(VK_CONTROL.0 as i32) < 0 {
+                            log(&format!("[LOOKUP] \"{}\"", truncate_str(&text.source_text, 60)));
+                            *controls.lookup.lock().unwrap() = Some(LookupRequest {
+                                source: text.source_text.clone(),
+                                translation: ruby::strip(&text.translated_text),
+                            });
+                        } else {
+                            let content = format!("{}\n{}", text.source_text, ruby::strip(&text.translated_text));
+                            match clipboard::set_text(hwnd, &content) {
+                                Ok(()) => {
+                                    log(&format!("[COPY] \"{}\"", truncate_str(&text.translated_text, 60)));
+                                    if o.overlay.copy_flash_enabled() {
+                                        let _ = o.overlay.set_highlight(Some(index), hwnd);
+                                        SetTimer(Some(hwnd), TIMER_COPY_FLASH, COPY_FLASH_MS, None);
+                                    }
+                                }
+                                Err(e) => log_always(&format!("Clipboard error: {:?}", e)),
+                            }
+                        }
+                    }
+                }
+            }
+            LRESULT(0)
+        }
+        WM_MOUSEWHEEL => {
+            // Interactive mode: scroll the history panel under the cursor
+            let ptr = GetWindowLongPtrW(hwnd, GWLP_USERDATA) as *mut WndState;
+            if let Some(o) = ptr.as_mut().and_then(|state| state.overlay_for(hwnd)) {
+                let mut pt = POINT {
+                    x: (lparam.0 & 0xFFFF) as i16 as i32,
+                    y: ((lparam.0 >> 16) & 0xFFFF) as i16 as i32,
+                };
+                let _ = ScreenToClient(hwnd, &mut pt);
+                if o.overlay.history_hit_test(pt.x as f32, pt.y as f32) {
+                    let delta = ((wparam.0 >> 16) & 0xFFFF) as i16 as i32;
+                    let _ = o.overlay.scroll_history(delta / WHEEL_DELTA as i32, hwnd);
+                }
+            }
+            LRESULT(0)
+        }
+        WM_RBUTTONUP => {
+            // Interactive mode: hide the clicked region for the rest of the session,
+            // by its text or (with Shift, for HUD values that keep changing) by position
+            let ptr = GetWindowLongPtrW(hwnd, GWLP_USERDATA) as *mut WndState;
+            if let Some(state) = ptr.as_mut() {
+                let controls = state.controls.clone();
+                if let Some(o) = state.overlay_for(hwnd) {
+                    let x = (lparam.0 & 0xFFFF) as i16 as f32;
+                    let y = ((lparam.0 >> 16) & 0xFFFF) as i16 as f32;
+                    if let Some(index) = o.overlay.hit_test(x, y) {
+                        if let Some(text) = o.overlay.text_at(index) {
+                            let by_position = GetKeyState(VK_SHIFT.0 as i32) < 0;
+                            let mut hidden = controls.hidden.lock().unwrap();
+                            if by_position {
+                                hidden.points.push(text.source_center);
+                                log(&format!("[HIDE] 位置 {:?} の領域を非表示", text.source_center));
+                            } else {
+                                hidden.texts.insert(text.source_text.clone());
+                                log(&format!("[HIDE] \"{}\" を非表示", truncate_str(&text.source_text, 60)));
+                            }
+                        }
+                        let _ = o.overlay.dismiss(index, hwnd);
+                    }
+                }
+            }
+            LRESULT(0)
+        }
+        WM_TIMER if wparam.0 == TIMER_COPY_FLASH => {
+            let _ = KillTimer(Some(hwnd), TIMER_COPY_FLASH);
+            let ptr = GetWindowLongPtrW(hwnd, GWLP_USERDATA) as *mut WndState;
+            if let Some(o) = ptr.as_mut().and_then(|state| state.overlay_for(hwnd)) {
+                let _ = o.overlay.set_highlight(None, hwnd);
+            }
+            LRESULT(0)
+        }
+        WM_TIMER if wparam.0 == TIMER_TRACK => {
+            let ptr = GetWindowLongPtrW(hwnd, GWLP_USERDATA) as *mut WndState;
+            if !ptr.is_null() {
+                (*ptr).track_target();
+            }
+            LRESULT(0)
+        }
+        WM_TIMER if wparam.0 == TIMER_TOPMOST => {
+            let ptr = GetWindowLongPtrW(hwnd, GWLP_USERDATA) as *mut WndState;
+            if !ptr.is_null() {
+                (*ptr).keep_on_top();
+            }
+            LRESULT(0)
+        }
+        WM_TIMER if wparam.0 == TIMER_HUD => {
+            let ptr = GetWindowLongPtrW(hwnd, GWLP_USERDATA) as *mut WndState;
+            if !ptr.is_null() {
+                (*ptr).update_hud();
+            }
+            LRESULT(0)
+        }
+        WM_TIMER if wparam.0 == TIMER_FADE => {
+            let ptr = GetWindowLongPtrW(hwnd, GWLP_USERDATA) as *mut WndState;
+            let animating = match ptr.as_mut().and_then(|state| state.overlay_for(hwnd)) {
+                Some(o) => o.overlay.tick(hwnd).unwrap_or_else(|e| {
+                    log_always(&format!("Fade error: {:?}", e));
+                    false
+                }),
+                None => false,
+            };
+            if !animating {
+                let _ = KillTimer(Some(hwnd), TIMER_FADE);
+            }
+            LRESULT(0)
+        }
+        WM_MOUSEMOVE => {
+            // Interactive mode: expand a truncated box while the cursor is over it
+            let ptr = GetWindowLongPtrW(hwnd, GWLP_USERDATA) as *mut WndState;
+            if let Some(o) = ptr.as_mut().and_then(|state| state.overlay_for(hwnd)) {
+                let x = (lparam.0 & 0xFFFF) as i16 as f32;
+                let y = ((lparam.0 >> 16) & 0xFFFF) as i16 as f32;
+                let hovered = o.overlay.hit_test(x, y);
+                let _ = o.overlay.set_expanded(hovered, hwnd);
+                let mut tme = TRACKMOUSEEVENT {
+                    cbSize: std::mem::size_of::<TRACKMOUSEEVENT>() as u32,
+                    dwFlags: TME_LEAVE,
+                    hwndTrack: hwnd,
+                    dwHoverTime: 0,
+                };
+                let _ = TrackMouseEvent(&mut tme);
+            }
+            LRESULT(0)
+        }
+        WM_MOUSELEAVE => {
+            let ptr = GetWindowLongPtrW(hwnd, GWLP_USERDATA) as *mut WndState;
+            if let Some(o) = ptr.as_mut().and_then(|state| state.overlay_for(hwnd)) {
+                let _ = o.overlay.set_expanded(None, hwnd);
+            }
+            LRESULT(0)
+        }
+        WM_MOUSEACTIVATE => {
+            // Interactive mode: clicking a box must not steal focus from the game
+            LRESULT(MA_NOACTIVATE as isize)
+        }
+        WM_APPLY_CAPTURE_VISIBILITY => {
+            let ptr = GetWindowLongPtrW(hwnd, GWLP_USERDATA) as *mut WndState;
+            if let Some(state) = ptr.as_ref() {
+                let excluded = state.controls.exclude_from_capture.load(Ordering::SeqCst);
+                for o in &state.overlays {
+                    set_capture_excluded(o.hwnd, excluded);
+                }
+            }
+            LRESULT(0)
+        }
+        WM_APPLY_VISIBILITY => {
+            let ptr = GetWindowLongPtrW(hwnd, GWLP_USERDATA) as *mut WndState;
+            if let Some(state) = ptr.as_ref() {
+                let cmd = if state.controls.visible.load(Ordering::SeqCst) { SW_SHOWNOACTIVATE } else { SW_HIDE };
+                for o in &state.overlays {
+                    let _ = ShowWindow(o.hwnd, cmd);
+                }
+            }
+            LRESULT(0)
+        }
+        WM_APPLY_CONFIG => {
+            let ptr = GetWindowLongPtrW(hwnd, GWLP_USERDATA) as *mut WndState;
+            if !ptr.is_null() {
+                let state = &mut *ptr;
+                let pending = state.controls.pending_config.lock().unwrap().take();
+                if let Some(config) = pending {
+                    for o in &mut state.overlays {
+                        if let Err(e) = o.overlay.apply_config(config.clone(), o.hwnd) {
+                            log_always(&format!("Config update error: {:?}", e));
+                        }
+                    }
+                }
+            }
+            LRESULT(0)
+        }
+        WM_APPLY_FONT_SCALE => {
+            let ptr = GetWindowLongPtrW(hwnd, GWLP_USERDATA) as *mut WndState;
+            if !ptr.is_null() {
+                for o in &mut (*ptr).overlays {
+                    if let Err(e) = o.overlay.redraw(o.hwnd) {
+                        log_always(&format!("Render error: {:?}", e));
+                    }
+                }
+            }
+            LRESULT(0)
+        }
+        WM_APPLY_OPACITY | WM_APPLY_INTERACTIVE | WM_HOTKEY => {
+            let ptr = GetWindowLongPtrW(hwnd, GWLP_USERDATA) as *mut WndState;
+            if !ptr.is_null() {
+                let state = &mut *ptr;
+                let controls = &state.controls;
+                let (opacity_changed, interactive_changed) = match (msg, wparam.0 as i32) {
+                    (WM_APPLY_OPACITY, _) => (true, false),
+                    (WM_APPLY_INTERACTIVE, _) => (false, true),
+                    (_, HOTKEY_OPACITY_UP | HOTKEY_OPACITY_DOWN) => {
+                        let step = if wparam.0 as i32 == HOTKEY_OPACITY_UP { OPACITY_STEP } else { -OPACITY_STEP };
+                        controls.opacity.set(controls.opacity.get() + step);
+                        log(&format!("[HOTKEY] オーバーレイ不透明度: {:.0}%", controls.opacity.get() * 100.0));
+                        (true, false)
+                    }
+                    (_, HOTKEY_INTERACTIVE) => {
+                        let now = !controls.interactive.load(Ordering::SeqCst);
+                        controls.interactive.store(now, Ordering::SeqCst);
+                        log(&format!("[HOTKEY] 操作モード: {}", if now { "ON" } else { "OFF" }));
+                        (false, true)
+                    }
+                    (_, HOTKEY_PERF_HUD) => {
+                        // Shown on the next HUD tick
+                        let now = !controls.perf_hud.load(Ordering::SeqCst);
+                        controls.perf_hud.store(now, Ordering::SeqCst);
+                        log(&format!("[HOTKEY] パフォーマンス表示: {}", if now { "ON" } else { "OFF" }));
+                        (false, false)
+                    }
+                    (_, HOTKEY_GLOSS_TRANSLATION) => {
+                        // The capture loop notices the flip and redraws the screen
+                        let now = !controls.gloss_show_translation.load(Ordering::SeqCst);
+                        controls.gloss_show_translation.store(now, Ordering::SeqCst);
+                        log(&format!("[HOTKEY] 学習モードの表示: {}", if now { "訳文" } else { "訳語" }));
+                        (false, false)
+                    }
+                    _ => (false, false),
+                };
+                let click_through = !controls.interactive.load(Ordering::SeqCst);
+                for o in &state.overlays {
+                    if opacity_changed {
+                        if let Err(e) = o.overlay.apply_opacity() {
+                            log_always(&format!("Opacity update error: {:?}", e));
+                        }
+                    }
+                    if interactive_changed {
+                        set_click_through(o.hwnd, click_through);
+                        let _ = o.overlay.update_hit_region(o.hwnd);
+                    }
+                }
+            }
+            LRESULT(0)
+        }
+        _ => DefWindowProcW(hwnd, msg, wparam, lparam),
+    }
+}
+
+/// Bounds of the whole virtual screen, used when monitors can't be enumerated
+fn virtual_screen_rect() -> RECT {
+    unsafe {
+        let (x, y) = (GetSystemMetrics(SM_XVIRTUALSCREEN), GetSystemMetrics(SM_YVIRTUALSCREEN));
+        RECT {
+            left: x,
+            top: y,
+            right: x + GetSystemMetrics(SM_CXVIRTUALSCREEN),
+            bottom: y + GetSystemMetrics(SM_CYVIRTUALSCREEN),
+        }
+    }
+}
+
+/// Screen rectangles of all attached monitors
+fn list_monitors() -> Vec<(HMONITOR, RECT)> {
+    unsafe extern "system" fn collect(monitor: HMONITOR, _: HDC, rect: *mut RECT, data: LPARAM) -> BOOL {
+        let monitors = &mut *(data.0 as *mut Vec<(HMONITOR, RECT)>);
+        monitors.push((monitor, *rect));
+        TRUE
+    }
+    let mut monitors: Vec<(HMONITOR, RECT)> = Vec::new();
+    unsafe {
+        let _ = EnumDisplayMonitors(None, None, Some(collect), LPARAM(&mut monitors as *mut _ as isize));
+    }
+    monitors
+}
+
+/// Topmost, click-through window covering `rect` (a monitor or the game's client area)
+fn create_transparent_window(rect: &RECT) -> Result<HWND> {
+    unsafe {
+        let instance = GetModuleHandleW(None)?;
+
+        let class_name = w!("GameTranslatorOverlay");
+
+        let wc = WNDCLASSW {
+            style: CS_HREDRAW | CS_VREDRAW,
+            lpfnWndProc: Some(wndproc),
+            hInstance: HINSTANCE(instance.0),
+            lpszClassName: class_name,
+            hCursor: LoadCursorW(None, IDC_ARROW)?,
+            hbrBackground: HBRUSH(GetStockObject(NULL_BRUSH).0),
+            ..Default::default()
+        };
+
+        // Fails harmlessly for every window after the first
+        RegisterClassW(&wc);
+
+        let hwnd = CreateWindowExW(
+            // Content comes from a DirectComposition swap chain, so no redirection bitmap;
+            // WS_EX_LAYERED is kept only so WS_EX_TRANSPARENT makes it click-through
+            WS_EX_LAYERED | WS_EX_TRANSPARENT | WS_EX_TOPMOST | WS_EX_NOREDIRECTIONBITMAP,
+            class_name,
+            w!("Game Translator Overlay"),
+            WS_POPUP,
+            rect.left,
+            rect.top,
+            rect.right - rect.left,
+            rect.bottom - rect.top,
+            None,
+            None,
+            Some(HINSTANCE(instance.0)),
+            None,
+        )?;
+
+        // A layered window stays invisible until its attributes are set once
+        SetLayeredWindowAttributes(hwnd, COLORREF(0), 255, LWA_ALPHA)?;
+        let _ = ShowWindow(hwnd, SW_SHOW);
+        let _ = UpdateWindow(hwnd);
+
+        Ok(hwnd)
+    }
+}
+
+/// Screen X of the side-panel column, or None in overlay mode.
+/// Falls back to the inner edge of the game window when there is no room on that side
+/// of the virtual screen (e.g. fullscreen games).
+fn panel_column_x(mode: DisplayMode, panel_width: f32, win_x: i32, win_width: u32) -> Option<f32> {
+    const PANEL_GAP: f32 = 8.0;
+    let (screen_left, screen_right) = unsafe {
+        let left = GetSystemMetrics(SM_XVIRTUALSCREEN) as f32;
+        (left, left + GetSystemMetrics(SM_CXVIRTUALSCREEN) as f32)
+    };
+    let win_left = win_x as f32;
+    let win_right = win_left + win_width as f32;
+    match mode {
+        DisplayMode::Overlay => None,
+        DisplayMode::PanelRight => {
+            let x = win_right + PANEL_GAP;
+            Some(if x + panel_width <= screen_right { x } else { win_right - panel_width - PANEL_GAP })
+        }
+        DisplayMode::PanelLeft => {
+            let x = win_left - PANEL_GAP - panel_width;
+            Some(if x >= screen_left { x } else { win_left + PANEL_GAP })
+        }
+    }
+}
+
+/// Where a box starts relative to the game's client area when placed in the game window:
+/// on top of its source region, or next to it so the original stays visible.
+/// Centred and right-aligned text line up with the region's centre / right edge.
+fn adjacent_origin(
+    placement: Placement,
+    align: TextAlign,
+    region: &TextRegion,
+    max_width: f32,
+    win_width: u32,
+) -> (f32, f32) {
+    const GAP: f32 = 6.0;
+    let y = region.y as f32;
+    let x = match align {
+        TextAlign::Left => region.x as f32,
+        TextAlign::Center => region.x as f32 + (region.width as f32 - max_width) / 2.0,
+        TextAlign::Right => (region.x + region.width) as f32 - max_width,
+    };
+    match placement {
+        Placement::Cover => (x, y),
+        Placement::Below => (x, y + region.block_height as f32 + GAP),
+        Placement::Beside => {
+            let right = (region.x + region.width) as f32 + GAP;
+            if right + max_width <= win_width as f32 {
+                (right, y)
+            } else {
+                ((region.x as f32 - GAP - max_width).max(0.0), y)
+            }
+        }
+    }
+}
+
+/// Whether a region's translation should be set vertically.
+/// Auto mode only picks tall, narrow source blocks and only for languages set vertically.
+fn is_vertical(mode: VerticalText, target_supports_vertical: bool, region: &TextRegion) -> bool {
+    match mode {
+        VerticalText::Off => false,
+        VerticalText::Always => true,
+        VerticalText::Auto => target_supports_vertical && region.block_height as f32 >= region.width as f32 * 1.5,
+    }
+}
+
+/// Centre of a region as a fraction of the client area (the unit of zones, capture regions and masks)
+fn relative_center(region: &TextRegion, win_width: u32, win_height: u32) -> (f32, f32) {
+    let cx = (region.x + region.width / 2) as f32 / win_width.max(1) as f32;
+    let cy = (region.y + region.block_height / 2) as f32 / win_height.max(1) as f32;
+    (cx, cy)
+}
+
+/// First style zone containing the centre of a region
+fn style_zone(zones: &[StyleZone], region: &TextRegion, win_width: u32, win_height: u32) -> Option<usize> {
+    let (cx, cy) = relative_center(region, win_width, win_height);
+    zones.iter().position(|z| z.contains(cx, cy))
+}
+
+/// Whether a region lies in the configured capture regions (all of the window if none) and outside every mask
+fn in_capture_area(config: &AppConfig, region: &TextRegion, win_width: u32, win_height: u32) -> bool {
+    let (cx, cy) = relative_center(region, win_width, win_height);
+    (config.capture_regions.is_empty() || config.capture_regions.iter().any(|r| r.contains(cx, cy)))
+        && !config.capture_masks.iter().any(|m| m.contains(cx, cy))
+}
+
+/// Hand the GUI's OCR preview a thumbnail of this frame and the regions found in it
+fn publish_preview(controls: &OverlayControls, frame: &[u8], width: u32, height: u32, regions: &[TextRegion]) {
+    const PREVIEW_WIDTH: u32 = 480;
+    let factor = width.div_ceil(PREVIEW_WIDTH).max(1);
+    let (pixels, thumb_width, thumb_height) = WindowCapture::downscale(frame, width, height, factor);
+    let blank = pixels.chunks_exact(4).all(|p| p[0] < 8 && p[1] < 8 && p[2] < 8);
+    let mut preview = controls.preview.lock().unwrap();
+    *preview = Some(OcrPreview {
+        frame: preview.as_ref().map_or(0, |p| p.frame + 1),
+        pixels,
+        width: thumb_width,
+        height: thumb_height,
+        scale: 1.0 / factor as f32,
+        frame_width: width,
+        frame_height: height,
+        blank,
+        regions: regions.to_vec(),
+    });
+}
+
+/// Update the overlay's status badge; a closed overlay is noticed by the next Draw/Clear
+fn send_status(tx: &mpsc::Sender<RenderCommand>, overlay_hwnd: HWND, status: Option<PipelineStatus>) {
+    if tx.send(RenderCommand::Status(status)).is_ok() {
+        unsafe {
+            let _ = PostMessageW(Some(overlay_hwnd), WM_RENDER, WPARAM(0), LPARAM(0));
+        }
+    }
+}
+
+fn texts_changed(current: &[String], previous: &[String]) -> bool {
+    if current.len() != previous.len() {
+        return true;
+    }
+    current.iter().zip(previous.iter()).any(|(a, b)| a != b)
+}
+
+/// Cheap fingerprint of a frame, to skip OCR while the screen is static
+fn frame_hash(pixels: &[u8]) -> u64 {
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    hasher.write(pixels);
+    hasher.finish()
+}
+
+/// Frames that waited longer than this for OCR are dropped in favour of a fresh capture
+const MAX_FRAME_AGE: Duration = Duration::from_millis(500);
+
+/// One captured frame on its way to OCR
+struct Frame {
+    /// BGRA pixels of the client area
+    pixels: Vec<u8>,
+    width: u32,
+    height: u32,
+    /// Client-area origin on screen when captured
+    origin: (i32, i32),
+    captured_at: Instant,
+    /// Retranslate hotkey: OCR even if unchanged and bypass the cache
+    force: bool,
+}
+
+/// OCR result of one frame, handed to both the translate and the render stage
+struct Recognized {
+    /// Increases with every frame whose text changed
+    seq: u64,
+    frame: Frame,
+    regions: Vec<TextRegion>,
+    texts: Vec<String>,
+}
+
+/// What the input stage should do after the per-round session checks
+enum SessionState {
+    /// Stopped, or the game or overlay window is gone
+    Ended,
+    /// Paused or in the background; the overlay was cleared and the stage waited a while
+    Idle,
+    Active,
+}
+
+/// Lines from a text hook or speech waiting for the line stage; a source flooding lines waits
+const LINE_BUFFER: usize = 16;
+/// How often the line stage wakes up to check stop and pause while no line arrives
+const LINE_POLL: Duration = Duration::from_millis(100);
+/// Gloss mode: gloss font size relative to the source line's height
+const GLOSS_FONT_SCALE: f32 = 0.5;
+/// Gloss mode: narrowest gloss box in font sizes, so glosses under one character don't wrap every word
+const GLOSS_MIN_WIDTH: f32 = 5.0;
+/// Pixels of surroundings kept around a line's region in history screenshots
+const SCREENSHOT_MARGIN: i32 = 8;
+
+/// Latest OCR result (None = nothing to show). A watch channel keeps only the newest value,
+/// so a stage that falls behind skips straight to the current screen.
+type Latest = watch::Sender<Option<Arc<Recognized>>>;
+
+/// capture → preprocess → OCR → translate → render, each its own loop joined by channels.
+///
+/// Capture and preprocess wait for room in the next stage (backpressure), so at most one frame
+/// waits for a slow OCR, and frames that waited too long are dropped. OCR hands its result to
+/// translate and render at once: the overlay
+/// is redrawn with the cached lines right away and again when the missing ones are translated,
+/// so a slow API request no longer holds up capture or the overlay. All stages run on the
+/// capture thread and share state through cells.
+struct Stages<'a> {
+    config: &'a AppConfig,
+    controls: &'a OverlayControls,
+    stop_signal: &'a AtomicBool,
+    overlay_hwnd: HWND,
+    target_hwnd: HWND,
+    /// For the status badge outside the render stage (pause)
+    status_tx: mpsc::Sender<RenderCommand>,
+    /// Pause state the badge last showed
+    paused: Cell<bool>,
+    cache_scope: CacheScope,
+    cache: RefCell<TranslationCache>,
+    /// Texts of the last frame passed on by OCR, to skip frames whose text didn't change
+    prev_texts: RefCell<Vec<String>>,
+    /// Fingerprint of the last frame passed on to OCR
+    prev_frame_hash: Cell<Option<u64>>,
+    no_change_count: Cell<u32>,
+    /// Number of the last screen passed on
+    seq: Cell<u64>,
+    /// Gloss mode: dictionary that splits source lines into glossed words
+    gloss: Option<Arc<Dictionary>>,
+    /// Last state of `OverlayControls::gloss_show_translation`, to redraw when it flips
+    gloss_show_translation: Cell<bool>,
+    /// Dictionary that reads source lines for furigana or pinyin
+    readings: Option<Arc<Dictionary>>,
+}
+
+impl Stages<'_> {
+    /// Forget the last screen, so the next frame is read and drawn again
+    fn reset(&self) {
+        self.prev_texts.borrow_mut().clear();
+        self.prev_frame_hash.set(None);
+    }
+
+    fn count_no_change(&self) -> u32 {
+        let count = self.no_change_count.get() + 1;
+        self.no_change_count.set(count);
+        count
+    }
+
+    /// Send a command to the overlay thread; false once the overlay is gone
+    fn post_render(&self, tx: &mpsc::Sender<RenderCommand>, command: RenderCommand) -> bool {
+        if tx.send(command).is_err() {
+            log_always("[EXIT] Overlay receiver dropped");
+            return false;
+        }
+        unsafe {
+            let _ = PostMessageW(Some(self.overlay_hwnd), WM_RENDER, WPARAM(0), LPARAM(0));
+        }
+        true
+    }
+
+    /// Update the status badge; while paused it keeps saying so, whatever a request in flight reports
+    fn show_status(&self, tx: &mpsc::Sender<RenderCommand>, status: Option<PipelineStatus>) {
+        let status = if self.controls.paused.load(Ordering::SeqCst) { Some(PipelineStatus::Paused) } else { status };
+        send_status(tx, self.overlay_hwnd, status);
+    }
+
+    fn close_overlay(&self) {
+        unsafe {
+            let _ = PostMessageW(Some(self.overlay_hwnd), WM_CLOSE, WPARAM(0), LPARAM(0));
+        }
+    }
+
+    /// Checks made by the input stage every round: the history tab's pins, stop, a closed
+    /// game window, and pause / background
+    async fn check_session(&self, latest: &Latest) -> SessionState {
+        // 履歴タブで固定された訳をキャッシュに反映し、表示中の行も描き直す（停止時も失わないよう停止の確認より先に）
+        let pins = std::mem::take(&mut *self.controls.pending_pins.lock().unwrap());
+        if !pins.is_empty() {
+            log_always(&format!("[PIN] {}件の訳を固定", pins.len()));
+            let mut cache = self.cache.borrow_mut();
+            for entry in pins {
+                cache.insert_entry(entry);
+            }
+            cache.save();
+            latest.send_modify(|_| {});
+        }
+        let show_translation = self.controls.gloss_show_translation.load(Ordering::SeqCst);
+        if self.gloss.is_some() && show_translation != self.gloss_show_translation.replace(show_translation) {
+            latest.send_modify(|_| {});
+        }
+
+        // Check stop signal
+        if self.stop_signal.load(Ordering::SeqCst) {
+            log_always("[EXIT] 停止シグナル受信");
+            self.close_overlay();
+            return SessionState::Ended;
+        }
+
+        // 対象ウィンドウが閉じられたかチェック
+        if !unsafe { IsWindow(Some(self.target_hwnd)) }.as_bool() {
+            log_always("[EXIT] 対象ウィンドウが閉じられました");
+            self.close_overlay();
+            return SessionState::Ended;
+        }
+
+        let paused = self.controls.paused.load(Ordering::SeqCst);
+        if paused != self.paused.replace(paused) {
+            self.show_status(&self.status_tx, None);
+        }
+
+        // 対象ウィンドウが前面でない場合（前面のみの設定時）・一時停止中はオーバーレイを非表示
+        let in_background = self.config.foreground_only && unsafe { GetForegroundWindow() } != self.target_hwnd;
+        if in_background || paused {
+            latest.send_if_modified(|shown| shown.take().is_some());
+            self.reset();
+            tokio::time::sleep(Duration::from_millis(
+                self.config.background_interval_ms.max(crate::config::MIN_POLL_INTERVAL_MS) as u64,
+            ))
+            .await;
+            return SessionState::Idle;
+        }
+        SessionState::Active
+    }
+
+    /// Grab frames at the poll interval; also owns stop, pause and the history tab's pins
+    async fn capture(&self, mut capture: WindowCapture, frames: tokio_mpsc::Sender<Frame>, latest: &Latest) -> Result<()> {
+        loop {
+            match self.check_session(latest).await {
+                SessionState::Ended => return Ok(()),
+                SessionState::Idle => continue,
+                SessionState::Active => {}
+            }
+
+            // 再翻訳ホットキー: 変化がなくても読み直し、キャッシュを使わずに翻訳する
+            let force = self.controls.retranslate.swap(false, Ordering::SeqCst);
+            if force {
+                log_always("[RETRANSLATE] 画面を読み直して再翻訳");
+                self.reset();
+                self.no_change_count.set(0);
+            }
+
+            // Only capture once the next stage has room, so the frame is fresh when it gets there
+            let Ok(permit) = frames.reserve().await else {
+                return Ok(());
+            };
+            let frame_start = Instant::now();
+            let frame = capture.capture_frame();
+            {
+                let mut stats = self.controls.stats.lock().unwrap();
+                stats.capture_ok = Some(matches!(frame, Ok(Some(_))));
+                stats.last_capture = frame_start.elapsed();
+                if matches!(frame, Ok(Some(_))) {
+                    stats.frames += 1;
+                }
+            }
+            if let Some(pixels) = frame? {
+                let (width, height) = capture.get_dimensions();
+                permit.send(Frame {
+                    pixels,
+                    width,
+                    height,
+                    origin: capture.get_window_position(),
+                    captured_at: frame_start,
+                    force,
+                });
+            }
+
+            let interval = self.config.poll_interval_ms(self.no_change_count.get());
+            tokio::time::sleep(Duration::from_millis(interval as u64)).await;
+        }
+    }
+
+    /// Drop frames that went stale waiting for OCR, and frames identical to the last one
+    async fn preprocess(&self, mut frames: tokio_mpsc::Receiver<Frame>, ocr: tokio_mpsc::Sender<Frame>) -> Result<()> {
+        loop {
+            let Ok(permit) = ocr.reserve().await else {
+                return Ok(());
+            };
+            let frame = loop {
+                let Some(frame) = frames.recv().await else {
+                    return Ok(());
+                };
+                if frame.force || frame.captured_at.elapsed() <= MAX_FRAME_AGE {
+                    break frame;
+                }
+                self.controls.stats.lock().unwrap().frames_skipped += 1;
+            };
+            let hash = frame_hash(&frame.pixels);
+            if self.prev_frame_hash.replace(Some(hash)) == Some(hash) && !frame.force {
+                self.controls.stats.lock().unwrap().frames_skipped += 1;
+                self.count_no_change();
+                continue;
+            }
+            permit.send(frame);
+        }
+    }
+
+    /// Read the text of each frame and pass on frames whose text changed
+    async fn recognize(
+        &self,
+        ocr: OCREngine,
+        mut frames: tokio_mpsc::Receiver<Frame>,
+        to_translate: &Latest,
+        to_render: &Latest,
+    ) -> Result<()> {
+        while let Some(frame) = frames.recv().await {
+            let ocr_start = Instant::now();
+            let mut text_regions = ocr.detect_text(&frame.pixels, frame.width, frame.height).await?;
+            {
+                let mut stats = self.controls.stats.lock().unwrap();
+                stats.ocr_runs += 1;
+                stats.last_ocr = ocr_start.elapsed();
+            }
+            {
+                let hidden = self.controls.hidden.lock().unwrap();
+                text_regions.retain(|r| !hidden.hides(r) && in_capture_area(self.config, r, frame.width, frame.height));
+            }
+            if self.controls.preview_enabled.load(Ordering::SeqCst) {
+                publish_preview(self.controls, &frame.pixels, frame.width, frame.height, &text_regions);
+            }
+
+            if text_regions.is_empty() {
+                self.prev_texts.borrow_mut().clear();
+                to_translate.send_replace(None);
+                if to_render.send_if_modified(|shown| shown.take().is_some()) {
+                    log("[CLEAR] テキスト未検出 - オーバーレイクリア");
+                }
+                self.count_no_change();
+                continue;
+            }
+
+            let texts: Vec<String> = text_regions.iter().map(|r| r.text.clone()).collect();
+            if !frame.force && !texts_changed(&texts, &self.prev_texts.borrow()) {
+                if self.count_no_change() == 1 {
+                    log(&format!(
+                        "[NO CHANGE] 翻訳スキップ (間隔: {}ms)",
+                        self.config.poll_interval_ms(1)
+                    ));
+                }
+                continue;
+            }
+            self.no_change_count.set(0);
+
+            log(&format!("[OCR] {}個の領域検出", text_regions.len()));
+            for (i, r) in text_regions.iter().enumerate() {
+                log(&format!("  [{}] ({},{} {}x{}) \"{}\"", i, r.x, r.y, r.width, r.height, truncate_str(&r.text, 80)));
+            }
+            self.pass_on(frame, text_regions, texts, to_translate, to_render);
+        }
+        Ok(())
+    }
+
+    /// Hand a screen whose text changed to the translate and render stages
+    fn pass_on(&self, frame: Frame, regions: Vec<TextRegion>, texts: Vec<String>, to_translate: &Latest, to_render: &Latest) {
+        feed::publish(FeedEvent::Ocr {
+            lines: regions.iter().map(|r| FeedLine::new(r, None)).collect(),
+        });
+        *self.prev_texts.borrow_mut() = texts.clone();
+        let seq = self.seq.get() + 1;
+        self.seq.set(seq);
+        let recognized = Arc::new(Recognized { seq, frame, regions, texts });
+        to_translate.send_replace(Some(recognized.clone()));
+        to_render.send_replace(Some(recognized));
+    }
+
+    /// Line input in place of capture, preprocess and OCR: every line from a text hook
+    /// (Textractor / Agent) or transcribed from the game's audio becomes the screen.
+    /// Also owns stop, pause and pins like `capture`.
+    async fn receive_lines(
+        &self,
+        mut lines: tokio_mpsc::Receiver<String>,
+        to_translate: &Latest,
+        to_render: &Latest,
+    ) -> Result<()> {
+        let mut last: Option<String> = None;
+        // The overlay was cleared while idle; show the last line again on resume
+        let mut redraw = false;
+        loop {
+            match self.check_session(to_render).await {
+                SessionState::Ended => return Ok(()),
+                SessionState::Idle => {
+                    // Lines that arrive while paused or in the background are dropped
+                    while lines.try_recv().is_ok() {}
+                    redraw = true;
+                    continue;
+                }
+                SessionState::Active => {}
+            }
+
+            let force = self.controls.retranslate.swap(false, Ordering::SeqCst);
+            if force {
+                log_always("[RETRANSLATE] 最後の文を再翻訳");
+                self.reset();
+            }
+            let line = if force || std::mem::take(&mut redraw) {
+                last.clone()
+            } else {
+                match tokio::time::timeout(LINE_POLL, lines.recv()).await {
+                    Ok(Some(line)) => Some(line),
+                    Ok(None) => return Ok(()),
+                    Err(_) => None,
+                }
+            };
+            let Some(line) = line else {
+                continue;
+            };
+            last = Some(line.clone());
+
+            let (frame, region) = self.subtitle_screen(line, force);
+            // Every line lands in the same place, so only hiding by text applies
+            if self.controls.hidden.lock().unwrap().texts.contains(&region.text) {
+                continue;
+            }
+            let texts = vec![region.text.clone()];
+            if !force && !texts_changed(&texts, &self.prev_texts.borrow()) {
+                continue;
+            }
+            log(&format!("[LINE] \"{}\"", truncate_str(&region.text, 80)));
+            self.controls.stats.lock().unwrap().frames += 1;
+            self.pass_on(frame, vec![region], texts, to_translate, to_render);
+        }
+    }
+
+    /// A line has no position on screen, so it is laid out like a subtitle bar
+    /// across the bottom of the game's client area
+    fn subtitle_screen(&self, text: String, force: bool) -> (Frame, TextRegion) {
+        let rect = capture::client_rect(self.target_hwnd);
+        let width = (rect.right - rect.left).max(1) as u32;
+        let height = (rect.bottom - rect.top).max(1) as u32;
+        let line_height = (height as i32 / 24).clamp(16, 64);
+        // Room for three lines; longer text wraps further or is shrunk by auto-fit
+        let block_height = line_height * 3;
+        let region = TextRegion {
+            text,
+            x: width as i32 / 10,
+            y: height as i32 - height as i32 / 20 - block_height,
+            width: width as i32 * 8 / 10,
+            height: line_height,
+            block_height,
+            words: Vec::new(),
+        };
+        let frame = Frame {
+            pixels: Vec::new(),
+            width,
+            height,
+            origin: (rect.left, rect.top),
+            captured_at: Instant::now(),
+            force,
+        };
+        (frame, region)
+    }
+
+    /// Translate the lines of the latest screen missing from the cache, then have it redrawn.
+    /// Screens that changed while a request was in flight are skipped.
+    async fn translate(
+        &self,
+        translator: &Translator,
+        mut latest: watch::Receiver<Option<Arc<Recognized>>>,
+        to_render: &Latest,
+        tx: &mpsc::Sender<RenderCommand>,
+    ) -> Result<()> {
+        // Only the first failure of a streak is reported, so retries don't repeat the notification
+        let mut api_failing = false;
+        let mut retry = false;
+        loop {
+            let retrying = std::mem::take(&mut retry);
+            if !retrying && latest.changed().await.is_err() {
+                return Ok(());
+            }
+            let Some(recognized) = latest.borrow_and_update().clone() else {
+                continue;
+            };
+            let force = recognized.frame.force;
+            let uncached: Vec<String> = {
+                let cache = self.cache.borrow();
+                recognized
+                    .texts
+                    .iter()
+                    .filter(|t| !cache.is_pinned(&self.cache_scope, t) && (force || !cache.contains(&self.cache_scope, t)))
+                    .cloned()
+                    .collect()
+            };
+            if !retrying {
+                let mut stats = self.controls.stats.lock().unwrap();
+                stats.cache_hits += (recognized.texts.len() - uncached.len()) as u64;
+                stats.cache_misses += uncached.len() as u64;
+            }
+            if uncached.is_empty() {
+                log(&format!("[CACHE HIT] {}個すべてキャッシュ済み", recognized.texts.len()));
+                continue;
+            }
+
+            log(&format!(
+                "[TRANSLATE] {}個の未翻訳テキスト (キャッシュ: {}件)",
+                uncached.len(),
+                self.cache.borrow().scope_len(&self.cache_scope)
+            ));
+            for text in &uncached {
+                log(&format!("  src: \"{}\"", truncate_str(text, 80)));
+            }
+
+            self.show_status(tx, Some(PipelineStatus::Translating));
+            {
+                let mut stats = self.controls.stats.lock().unwrap();
+                stats.api_calls += 1;
+                stats.queue_depth = uncached.len();
+            }
+            let translate_start = Instant::now();
+            let result = translator
+                .translate_batch(uncached.clone(), &self.config.source_lang, &self.config.target_lang)
+                .await;
+            {
+                let mut stats = self.controls.stats.lock().unwrap();
+                stats.last_translation = Some(translate_start.elapsed());
+                stats.queue_depth = 0;
+            }
+            match result {
+                Ok(translations) => {
+                    self.show_status(tx, None);
+                    if api_failing {
+                        api_failing = false;
+                        self.controls.error.lock().unwrap().take();
+                        log_always("[TRANSLATE] API復旧");
+                    }
+                    let mut new_entries = false;
+                    let mut chars = 0;
+                    let mut cache = self.cache.borrow_mut();
+                    for (orig, trans) in uncached.iter().zip(translations.iter()) {
+                        if let Some(t) = trans {
+                            chars += orig.chars().count() as u64;
+                            log(&format!("  ok: \"{}\" -> \"{}\"", truncate_str(orig, 40), truncate_str(t, 60)));
+                            cache.insert(&self.cache_scope, orig.clone(), t.clone());
+                            new_entries = true;
+                        } else {
+                            log(&format!("  FAIL: \"{}\"", truncate_str(orig, 80)));
+                        }
+                    }
+                    if new_entries {
+                        cache.save();
+                        to_render.send_modify(|_| {});
+                    }
+                    self.controls.stats.lock().unwrap().chars_translated += chars;
+                }
+                Err(e) => {
+                    log(&format!("[TRANSLATE ERR] {} — retrying in 2s", e));
+                    self.controls.stats.lock().unwrap().api_errors += 1;
+                    let status = if translate::is_rate_limited(&e) {
+                        PipelineStatus::RateLimited
+                    } else {
+                        PipelineStatus::ApiError
+                    };
+                    self.show_status(tx, Some(status));
+                    if !api_failing {
+                        api_failing = true;
+                        log_always(&format!("[TRANSLATE ERR] {}", e));
+                        self.controls.report_error(format!("{:#}", e), false);
+                    }
+                    tokio::time::sleep(Duration::from_secs(2)).await;
+                    retry = true;
+                }
+            }
+        }
+    }
+
+    /// Draw the latest screen with whatever is translated so far; also feeds the history and readout
+    async fn render(
+        &self,
+        mut latest: watch::Receiver<Option<Arc<Recognized>>>,
+        tx: mpsc::Sender<RenderCommand>,
+        readout: readout::Readout,
+    ) -> Result<()> {
+        let target_supports_vertical = Language::parse(&self.config.target_lang).is_some_and(|l| l.supports_vertical());
+        // Lines drawn with a translation last time, so only new ones reach the history and readout
+        let mut shown: Vec<String> = Vec::new();
+        // History records still on screen, by source line, so they get an end time when they go away
+        let mut open: Vec<(String, usize)> = Vec::new();
+        let started = Instant::now();
+        // Last screen whose capture-to-render latency was recorded
+        let mut timed_seq = 0;
+        while latest.changed().await.is_ok() {
+            let Some(recognized) = latest.borrow_and_update().clone() else {
+                if !self.post_render(&tx, RenderCommand::Clear) {
+                    return Ok(());
+                }
+                feed::publish(FeedEvent::Clear);
+                shown.clear();
+                self.close_records(&mut open, &[], started.elapsed());
+                continue;
+            };
+
+            let (draw, drawn) = self.draw_command(&recognized, target_supports_vertical);
+            if !self.post_render(&tx, draw) {
+                return Ok(());
+            }
+            if drawn.len() == recognized.texts.len() && recognized.seq != timed_seq {
+                timed_seq = recognized.seq;
+                let mut stats = self.controls.stats.lock().unwrap();
+                stats.latency_total += recognized.frame.captured_at.elapsed();
+                stats.latency_samples += 1;
+            }
+
+            {
+                let time = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+                let cache = self.cache.borrow();
+                let mut session_log = self.controls.session_log.lock().unwrap();
+                let mut fresh = Vec::new();
+                let mut feed_lines = Vec::new();
+                for region in recognized.regions.iter().filter(|r| !shown.contains(&r.text)) {
+                    if let Some(translation) = cache.get(&self.cache_scope, &region.text) {
+                        fresh.push(ruby::strip(translation));
+                        feed_lines.push(FeedLine::new(region, Some(translation)));
+                        let id = session_log.push(SessionRecord {
+                            time: time.clone(),
+                            engine: self.config.translation_engine,
+                            source: region.text.clone(),
+                            region: (region.x, region.y, region.width, region.block_height),
+                            translation: translation.clone(),
+                            source_lang: self.config.source_lang.clone(),
+                            target_lang: self.config.target_lang.clone(),
+                            pinned: cache.is_pinned(&self.cache_scope, &region.text),
+                            shown_at: started.elapsed(),
+                            hidden_at: None,
+                            screenshot: self.screenshot(&recognized.frame, region),
+                        });
+                        open.push((region.text.clone(), id));
+                    }
+                }
+                readout.push(&fresh);
+                if !feed_lines.is_empty() {
+                    feed::publish(FeedEvent::Translation {
+                        engine: self.config.translation_engine.as_str(),
+                        source_lang: &self.config.source_lang,
+                        target_lang: &self.config.target_lang,
+                        lines: feed_lines,
+                    });
+                }
+            }
+            self.close_records(&mut open, &drawn, started.elapsed());
+            shown = drawn;
+        }
+        self.close_records(&mut open, &[], started.elapsed());
+        Ok(())
+    }
+
+    /// PNG crop of a line's region with a little of its surroundings, kept in the history when
+    /// enabled (Anki cards). Hooked and transcribed lines have no screen to crop.
+    fn screenshot(&self, frame: &Frame, region: &TextRegion) -> Option<Arc<Vec<u8>>> {
+        if !self.config.history_screenshots || frame.pixels.is_empty() {
+            return None;
+        }
+        let rect = (
+            region.x - SCREENSHOT_MARGIN,
+            region.y - SCREENSHOT_MARGIN,
+            region.width + SCREENSHOT_MARGIN * 2,
+            region.block_height + SCREENSHOT_MARGIN * 2,
+        );
+        match capture::encode_png_crop(&frame.pixels, frame.width, frame.height, rect) {
+            Ok(png) => png.map(Arc::new),
+            Err(e) => {
+                log(&format!("[HISTORY] 画像を保存できません: {:#}", e));
+                None
+            }
+        }
+    }
+
+    /// Give history records whose line is no longer drawn their end time
+    fn close_records(&self, open: &mut Vec<(String, usize)>, drawn: &[String], at: Duration) {
+        let mut session_log = self.controls.session_log.lock().unwrap();
+        open.retain(|(text, index)| {
+            if drawn.contains(text) {
+                return true;
+            }
+            if let Some(record) = session_log.get_mut(*index) {
+                record.hidden_at = Some(at);
+            }
+            false
+        });
+    }
+
+    /// Overlay draw command for a screen, and the source lines it includes (those with a translation)
+    fn draw_command(&self, recognized: &Recognized, target_supports_vertical: bool) -> (RenderCommand, Vec<String>) {
+        let config = self.config;
+        let frame = &recognized.frame;
+        let (width, height) = (frame.width, frame.height);
+        let (win_x, win_y) = frame.origin;
+
+        // DPI補正: ピクセル→DIP変換
+        let dpi = unsafe { GetDpiForWindow(self.target_hwnd) };
+        let dpi_scale = dpi as f32 / 96.0;
+
+        let panel_x = panel_column_x(config.display_mode, config.panel_width, win_x, width);
+
+        let cache = self.cache.borrow();
+        let mut translated_texts = Vec::new();
+        let mut drawn = Vec::new();
+        let gloss = self.gloss.as_deref().filter(|_| !self.controls.gloss_show_translation.load(Ordering::SeqCst));
+        // Redraws (a translation landing, a pin) reuse the last OCR result, so a region hidden
+        // since then must be dropped here too
+        let hidden = self.controls.hidden.lock().unwrap();
+        for region in recognized.regions.iter().filter(|r| !hidden.hides(r)) {
+            if let Some(dictionary) = gloss.filter(|_| !region.words.is_empty()) {
+                let zone = style_zone(&config.overlay_zones, region, width, height);
+                translated_texts.extend(gloss_texts(dictionary, region, frame, dpi_scale, zone));
+                if cache.get(&self.cache_scope, &region.text).is_some() {
+                    drawn.push(region.text.clone());
+                }
+                continue;
+            }
+            let translation = cache.get(&self.cache_scope, &region.text);
+            let shown = match (self.readings.as_deref(), translation) {
+                (Some(dictionary), _) if config.source_reading == SourceReading::Instead => {
+                    Some(dictionary.with_readings(&region.text))
+                }
+                (Some(dictionary), Some(translation)) => {
+                    Some(format!("{}\n{}", dictionary.with_readings(&region.text), translation))
+                }
+                (_, translation) => translation.cloned(),
+            };
+            if let Some(shown) = shown {
+                let (x, y, max_width) = match panel_x {
+                    Some(px) => (px, region.y as f32 + win_y as f32, config.panel_width),
+                    None => {
+                        let max_width = region.width as f32 * config.overlay_wrap_width_factor;
+                        let (x, y) = adjacent_origin(
+                            config.overlay_placement,
+                            config.overlay_text_align,
+                            region,
+                            max_width,
+                            width,
+                        );
+                        (x + win_x as f32, y + win_y as f32, max_width)
+                    }
+                };
+                // Side panels share one horizontal column, so vertical setting only applies in place
+                let vertical = panel_x.is_none()
+                    && is_vertical(config.overlay_vertical_text, target_supports_vertical, region);
+                if translation.is_some() {
+                    drawn.push(region.text.clone());
+                }
+                translated_texts.push(TranslatedText {
+                    source_text: region.text.clone(),
+                    translated_text: shown,
+                    x,
+                    y,
+                    max_width,
+                    font_size: region.height as f32 / dpi_scale,
+                    // DirectWrite line height is ~1.3x the glyph height OCR reports,
+                    // so allow some slack before auto-fit starts shrinking.
+                    // Vertical text wraps its columns at exactly the block's height.
+                    max_height: region.block_height as f32 / dpi_scale * if vertical { 1.0 } else { 1.5 },
+                    vertical,
+                    source_center: (region.x + region.width / 2, region.y + region.block_height / 2),
+                    anchor_height: region.block_height as f32,
+                    bg_luminance: WindowCapture::sample_luminance(
+                        &frame.pixels, width, height,
+                        region.x, region.y, region.width, region.height,
+                    ),
+                    zone: style_zone(&config.overlay_zones, region, width, height),
+                });
+            }
+        }
+
+        // Boxes only sit over the game in overlay mode, so only then is a backdrop useful
+        // Lines from a text hook or the game's audio come without a captured frame
+        let backdrop = (config.overlay_acrylic && panel_x.is_none() && !frame.pixels.is_empty()).then(|| {
+            const BACKDROP_SCALE: u32 = 4;
+            let (pixels, width, height) = WindowCapture::downscale(&frame.pixels, width, height, BACKDROP_SCALE);
+            Arc::new(Backdrop { pixels, width, height, scale: BACKDROP_SCALE as f32 })
+        });
+        let regions = if config.overlay_debug_regions {
+            recognized
+                .regions
+                .iter()
+                .map(|r| D2D_RECT_F {
+                    left: (r.x + win_x) as f32,
+                    top: (r.y + win_y) as f32,
+                    right: (r.x + r.width + win_x) as f32,
+                    bottom: (r.y + r.block_height + win_y) as f32,
+                })
+                .collect()
+        } else {
+            Vec::new()
+        };
+        let draw = RenderCommand::Draw {
+            texts: translated_texts,
+            origin: (win_x, win_y),
+            backdrop,
+            regions,
+        };
+        (draw, drawn)
+    }
+}
+
+/// Gloss mode: a small box under each word of `region` with the word's dictionary gloss.
+/// OCR only gives word boxes, so each word's box is split evenly between its characters.
+fn gloss_texts(
+    dictionary: &Dictionary,
+    region: &TextRegion,
+    frame: &Frame,
+    dpi_scale: f32,
+    zone: Option<usize>,
+) -> Vec<TranslatedText> {
+    let (win_x, win_y) = frame.origin;
+    let mut texts = Vec::new();
+    let last_line = region.words.iter().map(|w| w.line).max().unwrap_or(0);
+    for line in 0..=last_line {
+        // (character, left, width, bottom) in captured-frame pixels
+        let mut chars: Vec<(char, i32, i32, i32)> = Vec::new();
+        for word in region.words.iter().filter(|w| w.line == line) {
+            let n = word.text.chars().count().max(1) as i32;
+            for (k, c) in word.text.chars().enumerate() {
+                chars.push((c, word.x + word.width * k as i32 / n, word.width / n, word.y + word.height));
+            }
+        }
+        let line_height = region.words.iter().filter(|w| w.line == line).map(|w| w.height).max().unwrap_or(region.height);
+        let font_size = line_height as f32 * GLOSS_FONT_SCALE / dpi_scale;
+        let text: String = chars.iter().map(|c| c.0).collect();
+        for segment in dictionary.segment(&text) {
+            let Some(gloss) = segment.gloss else {
+                continue;
+            };
+            let part = &chars[segment.start..segment.start + segment.len];
+            let left = part[0].1;
+            let right = part.iter().map(|c| c.1 + c.2).max().unwrap_or(left);
+            let bottom = part.iter().map(|c| c.3).max().unwrap_or(region.y);
+            texts.push(TranslatedText {
+                source_text: region.text.clone(),
+                translated_text: gloss,
+                x: (left + win_x) as f32,
+                y: (bottom + win_y) as f32,
+                max_width: ((right - left) as f32).max(font_size * GLOSS_MIN_WIDTH),
+                font_size,
+                max_height: 0.0,
+                vertical: false,
+                source_center: (region.x + region.width / 2, region.y + region.block_height / 2),
+                anchor_height: 0.0,
+                bg_luminance: WindowCapture::sample_luminance(
+                    &frame.pixels, frame.width, frame.height,
+                    left, bottom, right - left, line_height / 2,
+                ),
+                zone,
+            });
+        }
+    }
+    texts
+}
+
+/// Dictionary for gloss mode, if it is on and usable for the source language
+fn gloss_dictionary(config: &AppConfig, controls: &OverlayControls) -> Option<Arc<Dictionary>> {
+    if !config.gloss_mode || config.input_source != InputSource::Ocr {
+        return None;
+    }
+    let Some(kind) = DictionaryKind::for_language(&config.source_lang) else {
+        log_always("[GLOSS] 学習モードは翻訳元が日本語・中国語の時だけ使えます");
+        return None;
+    };
+    session_dictionary(kind, "学習モード", controls)
+}
+
+/// Dictionary for source readings (furigana / pinyin), if they are on and the source is Japanese or Chinese
+fn reading_dictionary(config: &AppConfig, controls: &OverlayControls) -> Option<Arc<Dictionary>> {
+    if config.source_reading == SourceReading::Off {
+        return None;
+    }
+    let Some(kind) = DictionaryKind::for_language(&config.source_lang) else {
+        log_always("[READING] 読みは翻訳元が日本語・中国語の時だけ付けられます");
+        return None;
+    };
+    session_dictionary(kind, "読みの表示", controls)
+}
+
+/// Downloaded dictionary for a session feature; a missing one is reported, not fetched
+fn session_dictionary(kind: DictionaryKind, feature: &str, controls: &OverlayControls) -> Option<Arc<Dictionary>> {
+    if !kind.is_downloaded() {
+        controls.report_error(format!("{}には {} が必要です（辞書のポップアップからダウンロードしてください）", feature, kind.name()), false);
+        return None;
+    }
+    match Dictionary::shared(kind) {
+        Ok(dictionary) => Some(dictionary),
+        Err(e) => {
+            controls.report_error(format!("{:#}", e), false);
+            None
+        }
+    }
+}
+
+async fn capture_and_translate_loop(
+    translator: Arc<Translator>,
+    tx: mpsc::Sender<RenderCommand>,
+    overlay_hwnd: HWND,
+    target_hwnd: HWND,
+    stop_signal: Arc<AtomicBool>,
+    config: AppConfig,
+    controls: Arc<OverlayControls>,
+) -> Result<()> {
+    // WinRT/COM initialization for OCR on this thread
+    unsafe {
+        let _ = CoInitializeEx(None, COINIT_MULTITHREADED);
+    }
+    // Ensure CoUninitialize is called when leaving this function
+    struct ComGuard;
+    impl Drop for ComGuard {
+        fn drop(&mut self) {
+            unsafe { CoUninitialize(); }
+        }
+    }
+    let _com_guard = ComGuard;
+
+    let cache_scope = CacheScope::from_config(&config);
+    let translation_cache = TranslationCache::load(&cache_scope);
+    log(&format!("キャッシュ読み込み: {}件", translation_cache.scope_len(&cache_scope)));
+    let readout = readout::Readout::new(&config, overlay_hwnd);
+
+    let stages = Stages {
+        config: &config,
+        controls: &controls,
+        stop_signal: &stop_signal,
+        overlay_hwnd,
+        target_hwnd,
+        status_tx: tx.clone(),
+        paused: Cell::new(false),
+        cache_scope,
+        cache: RefCell::new(translation_cache),
+        prev_texts: RefCell::new(Vec::new()),
+        prev_frame_hash: Cell::new(None),
+        no_change_count: Cell::new(0),
+        seq: Cell::new(0),
+        gloss: gloss_dictionary(&config, &controls),
+        gloss_show_translation: Cell::new(false),
+        readings: reading_dictionary(&config, &controls),
+    };
+    let (to_translate, translate_rx) = watch::channel(None);
+    let (to_render, render_rx) = watch::channel(None);
+
+    // Every stage loops until shutdown; the first one to finish (stop, closed window or
+    // overlay, or an error) ends the others
+    match config.input_source {
+        InputSource::Ocr => {
+            let capture = WindowCapture::new(target_hwnd)?;
+            let ocr = OCREngine::new(&ocr::preferred_language(&config.source_lang))?;
+            let (captured_tx, captured_rx) = tokio_mpsc::channel(1);
+            let (frames_tx, frames_rx) = tokio_mpsc::channel(1);
+
+            log("Starting capture loop...");
+            tokio::select! {
+                r = stages.capture(capture, captured_tx, &to_render) => r,
+                r = stages.preprocess(captured_rx, frames_tx) => r,
+                r = stages.recognize(ocr, frames_rx, &to_translate, &to_render) => r,
+                r = stages.translate(&translator, translate_rx, &to_render, &tx) => r,
+                r = stages.render(render_rx, tx.clone(), readout) => r,
+            }
+        }
+        source => {
+            let (lines_tx, lines_rx) = tokio_mpsc::channel(LINE_BUFFER);
+            let receive = async {
+                match source {
+                    InputSource::Clipboard => texthook::watch_clipboard(lines_tx).await,
+                    InputSource::Audio => return audio::transcribe_loopback(Whisper::from_config(&config)?, lines_tx).await,
+                    _ => texthook::listen(&config.texthook_url, lines_tx).await,
+                }
+                Ok(())
+            };
+
+            log_always(&format!("[LINE] 入力: {:?}", source));
+            tokio::select! {
+                r = receive => r,
+                r = stages.receive_lines(lines_rx, &to_translate, &to_render) => r,
+                r = stages.translate(&translator, translate_rx, &to_render, &tx) => r,
+                r = stages.render(render_rx, tx.clone(), readout) => r,
+            }
+        }
+    }
+}
+
+/// Run overlay window + capture loop on a dedicated thread.
+/// Called from the GUI's Start button.
+///
+/// Blocks until the overlay closes: set `stop_signal` and post `WM_CLOSE` to the window
+/// published in `overlay_hwnd_arc` to end the session. `controls` is shared with the
+/// frontend for live settings, history, statistics and errors.
+pub fn run_overlay_thread(
+    target_hwnd_raw: isize,
+    config: AppConfig,
+    overlay_config: OverlayConfig,
+    stop_signal: Arc<AtomicBool>,
+    overlay_hwnd_arc: Arc<AtomicIsize>,
+    controls: Arc<OverlayControls>,
+) -> Result<()> {
+    // DPI awareness
+    unsafe {
+        let _ = SetProcessDpiAwarenessContext(DPI_AWARENESS_CONTEXT_PER_MONITOR_AWARE_V2);
+    }
+
+    // Create translator based on config
+    let translator = Arc::new(Translator::from_config(&config)?);
+
+    // Regions hidden with a right click, and hotkey hide/pause, only last for one session
+    controls.hidden.lock().unwrap().clear();
+    controls.visible.store(true, Ordering::SeqCst);
+    controls.paused.store(false, Ordering::SeqCst);
+    controls.retranslate.store(false, Ordering::SeqCst);
+    controls.session_log.lock().unwrap().clear();
+    controls.gloss_show_translation.store(false, Ordering::SeqCst);
+    controls.perf_hud.store(config.overlay_perf_hud, Ordering::SeqCst);
+    *controls.stats.lock().unwrap() = SessionStats::default();
+    controls.pending_config.lock().unwrap().take();
+    controls.error.lock().unwrap().take();
+
+    // Overlay mode: one window covering just the game's client area that follows it, so
+    // boxes never spill over other apps. Side panels sit outside the game window, so they
+    // get one window per monitor instead, each sized to its monitor so mixed-DPI setups
+    // don't share one huge surface spanning the whole virtual screen.
+    let target_rect = capture::client_rect(HWND(target_hwnd_raw as *mut _));
+    let follow_target = config.display_mode == DisplayMode::Overlay
+        && target_rect.right > target_rect.left
+        && target_rect.bottom > target_rect.top;
+    let mut monitors = if follow_target {
+        vec![(HMONITOR::default(), target_rect)]
+    } else {
+        list_monitors()
+    };
+    if monitors.is_empty() {
+        monitors.push((HMONITOR::default(), virtual_screen_rect()));
+    }
+
+    let mut overlays = Vec::with_capacity(monitors.len());
+    for (monitor, rect) in monitors {
+        let hwnd = create_transparent_window(&rect)?;
+        let mut overlay = Overlay::new(overlay_config.clone(), controls.clone())?;
+        overlay.create_render_target(
+            hwnd,
+            (rect.right - rect.left) as u32,
+            (rect.bottom - rect.top) as u32,
+            rect.left,
+            rect.top,
+        )?;
+        // A previous session may have left interactive mode on
+        set_click_through(hwnd, !controls.interactive.load(Ordering::SeqCst));
+        if controls.exclude_from_capture.load(Ordering::SeqCst) {
+            set_capture_excluded(hwnd, true);
+        }
+        // Clear initial state (prevent black screen)
+        overlay.clear(hwnd)?;
+        overlays.push(MonitorOverlay { hwnd, monitor, overlay });
+    }
+    let overlay_hwnd = overlays[0].hwnd;
+    overlay_hwnd_arc.store(overlay_hwnd.0 as isize, Ordering::SeqCst);
+    log_always(&format!("Overlay windows created ({} monitor(s))", overlays.len()));
+
+    unsafe {
+        let mods = MOD_CONTROL | MOD_ALT | MOD_NOREPEAT;
+        let gloss_hotkey = config.gloss_mode.then_some((HOTKEY_GLOSS_TRANSLATION, VK_T, "Ctrl+Alt+T"));
+        for (id, vk, name) in OVERLAY_HOTKEYS.into_iter().chain(gloss_hotkey) {
+            if RegisterHotKey(Some(overlay_hwnd), id, mods, vk.0 as u32).is_err() {
+                log_always(&format!("[HOTKEY] {} を登録できませんでした（他のアプリが使用中）", name));
+            }
+        }
+    }
+
+    // Channel for render commands
+    let (tx, rx) = mpsc::channel::<RenderCommand>();
+
+    // Set up window state in GWLP_USERDATA for wndproc access (shared by all overlay windows)
+    let hwnds: Vec<HWND> = overlays.iter().map(|o| o.hwnd).collect();
+    let wnd_state = Box::into_raw(Box::new(WndState {
+        overlays,
+        rx,
+        controls: controls.clone(),
+        target_hwnd: HWND(target_hwnd_raw as *mut _),
+        anchor: capture::client_origin(HWND(target_hwnd_raw as *mut _)),
+        follow_target,
+        last_foreground: HWND::default(),
+        shown: HashSet::new(),
+        hud_frames: (0, Instant::now()),
+    }));
+    unsafe {
+        for hwnd in hwnds {
+            SetWindowLongPtrW(hwnd, GWLP_USERDATA, wnd_state as isize);
+        }
+        SetTimer(Some(overlay_hwnd), TIMER_TRACK, TRACK_INTERVAL_MS, None);
+        SetTimer(Some(overlay_hwnd), TIMER_TOPMOST, TOPMOST_INTERVAL_MS, None);
+        SetTimer(Some(overlay_hwnd), TIMER_HUD, HUD_INTERVAL_MS, None);
+    }
+
+    log_always("Starting translation service...");
+
+    let overlay_hwnd_raw = overlay_hwnd.0 as isize;
+
+    // Spawn capture thread
+    let capture_stop = stop_signal.clone();
+    let capture_controls = controls;
+    let capture_handle = std::thread::spawn(move || {
+        let overlay_hwnd = HWND(overlay_hwnd_raw as *mut _);
+        let target_hwnd = HWND(target_hwnd_raw as *mut _);
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async {
+            if let Err(e) = capture_and_translate_loop(
+                translator,
+                tx,
+                overlay_hwnd,
+                target_hwnd,
+                capture_stop,
+                config,
+                capture_controls.clone(),
+            )
+            .await
+            {
+                log_always(&format!("Error in capture loop: {}", e));
+                capture_controls.report_error(format!("{:#}", e), true);
+                // Don't leave a frozen overlay behind; closing it ends the session
+                unsafe {
+                    let _ = PostMessageW(Some(overlay_hwnd), WM_CLOSE, WPARAM(0), LPARAM(0));
+                }
+            }
+        });
+    });
+
+    // Windows message loop (overlay runs on this thread)
+    // WndState is freed in WM_NCDESTROY via Box::from_raw
+    unsafe {
+        let mut msg = MSG::default();
+        while GetMessageW(&mut msg, None, 0, 0).into() {
+            let _ = TranslateMessage(&msg);
+            DispatchMessageW(&msg);
+        }
+    }
+
+    // Wait for capture thread to finish
+    let _ = capture_handle.join();
+
+    overlay_hwnd_arc.store(0, Ordering::SeqCst);
+    Ok(())
+}
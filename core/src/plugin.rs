@@ -0,0 +1,232 @@
+//! 外部プロセスの翻訳エンジン（プラグイン）
+//!
+//! `<データフォルダ>/plugins/*.toml` にマニフェストを置くと、エンジン一覧に自動で並ぶ。
+//! プラグインは標準入出力で、1行に1つの JSON-RPC 2.0 メッセージをやり取りする:
+//!
+//! ```text
+//! → {"jsonrpc":"2.0","id":1,"method":"translate","params":{"texts":["Hello"],"from":"EN","to":"JA"}}
+//! ← {"jsonrpc":"2.0","id":1,"result":{"translations":["こんにちは"]}}
+//! ```
+//!
+//! `translations` は `texts` と同じ順・同じ数で、訳せなかった行は null。失敗時は
+//! `{"jsonrpc":"2.0","id":1,"error":{"code":1,"message":"..."}}` を返す。標準出力の JSON 以外の行は
+//! 無視するので、ログは標準エラー出力へ。プロセスは最初の翻訳で起動し、セッション中は使い回す
+//! （応答がない・終了した時は次の翻訳で起動し直す）。
+
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::process::{Child, ChildStdin, ChildStdout, Command};
+use windows::Win32::System::Threading::CREATE_NO_WINDOW;
+
+/// plugins フォルダのマニフェスト1つ
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct PluginManifest {
+    /// エンジン一覧に出る名前（設定の `plugin_name`）
+    pub name: String,
+    /// 実行ファイル（相対パスはマニフェストのあるフォルダから）
+    pub command: PathBuf,
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// 翻訳1回の制限時間（秒）
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+    /// マニフェストのあるフォルダ
+    #[serde(skip)]
+    dir: PathBuf,
+}
+
+fn default_timeout_secs() -> u64 {
+    30
+}
+
+impl PluginManifest {
+    /// 起動する実行ファイルのパス
+    pub fn command_path(&self) -> PathBuf {
+        if self.command.is_absolute() {
+            self.command.clone()
+        } else {
+            self.dir.join(&self.command)
+        }
+    }
+
+    fn load(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)?;
+        let mut manifest: Self = toml::from_str(&text)?;
+        anyhow::ensure!(!manifest.name.trim().is_empty(), "name is empty");
+        manifest.dir = path.parent().map(Path::to_path_buf).unwrap_or_default();
+        Ok(manifest)
+    }
+}
+
+pub fn plugin_dir() -> PathBuf {
+    crate::config::data_dir().join("plugins")
+}
+
+/// plugins フォルダのマニフェストを名前順に読む。読めないもの・名前が重複したものはログに残して飛ばす
+pub fn discover() -> Vec<PluginManifest> {
+    let Ok(entries) = std::fs::read_dir(plugin_dir()) else {
+        return Vec::new();
+    };
+    let mut paths: Vec<PathBuf> = entries
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("toml")))
+        .collect();
+    paths.sort();
+    let mut plugins: Vec<PluginManifest> = Vec::new();
+    for path in paths {
+        match PluginManifest::load(&path) {
+            Ok(manifest) if plugins.iter().any(|p| p.name == manifest.name) => {
+                crate::log_always(&format!("[PLUGIN] {} は名前が重複しているため無視: {}", manifest.name, path.display()));
+            }
+            Ok(manifest) => plugins.push(manifest),
+            Err(e) => crate::log_always(&format!("[PLUGIN] {} を読み込めません: {:#}", path.display(), e)),
+        }
+    }
+    plugins.sort_by(|a, b| a.name.cmp(&b.name));
+    plugins
+}
+
+pub fn find(name: &str) -> Option<PluginManifest> {
+    discover().into_iter().find(|p| p.name == name)
+}
+
+#[derive(Serialize)]
+struct RpcRequest<'a> {
+    jsonrpc: &'static str,
+    id: u64,
+    method: &'static str,
+    params: TranslateParams<'a>,
+}
+
+#[derive(Serialize)]
+struct TranslateParams<'a> {
+    texts: &'a [String],
+    from: &'a str,
+    to: &'a str,
+}
+
+#[derive(Deserialize)]
+struct RpcResponse {
+    id: Option<u64>,
+    result: Option<TranslateResult>,
+    error: Option<RpcError>,
+}
+
+#[derive(Deserialize)]
+struct TranslateResult {
+    translations: Vec<Option<String>>,
+}
+
+#[derive(Deserialize)]
+struct RpcError {
+    #[serde(default)]
+    code: i64,
+    message: String,
+}
+
+/// 起動中のプラグインプロセス（drop で終了させる）
+struct Process {
+    _child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+    next_id: u64,
+}
+
+impl Process {
+    fn spawn(manifest: &PluginManifest) -> Result<Self> {
+        let path = manifest.command_path();
+        let mut child = Command::new(&path)
+            .args(&manifest.args)
+            .current_dir(&manifest.dir)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .creation_flags(CREATE_NO_WINDOW.0)
+            .kill_on_drop(true)
+            .spawn()
+            .with_context(|| format!("Failed to start plugin {}", path.display()))?;
+        let stdin = child.stdin.take().context("Plugin stdin unavailable")?;
+        let stdout = child.stdout.take().context("Plugin stdout unavailable")?;
+        crate::log_always(&format!("[PLUGIN] {} を起動: {}", manifest.name, path.display()));
+        Ok(Self { _child: child, stdin, stdout: BufReader::new(stdout), next_id: 1 })
+    }
+
+    /// リクエストを1行送り、同じ id の応答を待つ
+    async fn call(&mut self, texts: &[String], from: &str, to: &str) -> Result<RpcResponse> {
+        let id = self.next_id;
+        self.next_id += 1;
+        let request = RpcRequest { jsonrpc: "2.0", id, method: "translate", params: TranslateParams { texts, from, to } };
+        let mut line = serde_json::to_string(&request)?;
+        line.push('\n');
+        self.stdin.write_all(line.as_bytes()).await.context("Failed to write to plugin")?;
+        self.stdin.flush().await.context("Failed to write to plugin")?;
+
+        let mut line = String::new();
+        loop {
+            line.clear();
+            if self.stdout.read_line(&mut line).await.context("Failed to read from plugin")? == 0 {
+                anyhow::bail!("Plugin exited");
+            }
+            match serde_json::from_str::<RpcResponse>(line.trim()) {
+                Ok(response) if response.id == Some(id) => return Ok(response),
+                Ok(_) => {}
+                Err(_) => crate::log(&format!("[PLUGIN] {}", line.trim_end())),
+            }
+        }
+    }
+}
+
+/// 1つのプラグインへの接続
+pub struct PluginClient {
+    manifest: PluginManifest,
+    process: tokio::sync::Mutex<Option<Process>>,
+}
+
+impl PluginClient {
+    pub fn new(manifest: PluginManifest) -> Self {
+        Self { manifest, process: tokio::sync::Mutex::new(None) }
+    }
+
+    pub async fn translate(&self, texts: &[String], from: &str, to: &str) -> Result<Vec<Option<String>>> {
+        let name = &self.manifest.name;
+        let mut process = self.process.lock().await;
+        let running = match process.as_mut() {
+            Some(running) => running,
+            None => process.insert(Process::spawn(&self.manifest)?),
+        };
+        let timeout = Duration::from_secs(self.manifest.timeout_secs.max(1));
+        let response = match tokio::time::timeout(timeout, running.call(texts, from, to)).await {
+            Ok(Ok(response)) => response,
+            // 応答の途中で止まったプロセスは使えないので、次の翻訳で起動し直す
+            Ok(Err(e)) => {
+                process.take();
+                return Err(e.context(format!("Plugin {} failed", name)));
+            }
+            Err(_) => {
+                process.take();
+                anyhow::bail!("Plugin {} timed out after {}s", name, timeout.as_secs());
+            }
+        };
+        if let Some(error) = response.error {
+            anyhow::bail!("Plugin {} error: {} ({})", name, error.message, error.code);
+        }
+        let translations = response
+            .result
+            .with_context(|| format!("Plugin {} returned neither result nor error", name))?
+            .translations;
+        anyhow::ensure!(
+            translations.len() == texts.len(),
+            "Plugin {} returned {} translations for {} lines",
+            name,
+            translations.len(),
+            texts.len()
+        );
+        Ok(translations)
+    }
+}
@@ -0,0 +1,71 @@
+//! 新しく表示された訳文をオーバーレイ以外へ出力する（アクセシビリティ）
+//!
+//! 画面を見られない・オーバーレイを置く場所がない人向けに、訳文を
+//! Windows の音声合成（SAPI）で読み上げるか、スクリーンリーダーが読み上げる
+//! クリップボードへ書き込む。
+
+use anyhow::{Context, Result};
+use windows::core::HSTRING;
+use windows::Win32::Foundation::HWND;
+use windows::Win32::Media::Speech::*;
+use windows::Win32::System::Com::{CoCreateInstance, CLSCTX_ALL};
+
+use crate::clipboard;
+use crate::config::{AppConfig, ReadoutMode};
+
+pub enum Readout {
+    Off,
+    Speech(ISpVoice),
+    /// クリップボードの持ち主になるウィンドウ
+    Clipboard(HWND),
+}
+
+impl Readout {
+    /// COM 初期化済みのスレッドで呼ぶ。読み上げを始められなければ Off にする。
+    pub fn new(config: &AppConfig, owner: HWND) -> Self {
+        match config.readout_mode {
+            ReadoutMode::Off => Self::Off,
+            ReadoutMode::Speech => match create_voice(config.speech_rate) {
+                Ok(voice) => Self::Speech(voice),
+                Err(e) => {
+                    crate::log_always(&format!("[READOUT] 音声合成を使えません: {:#}", e));
+                    Self::Off
+                }
+            },
+            ReadoutMode::Clipboard => Self::Clipboard(owner),
+        }
+    }
+
+    /// 1画面分の新しい訳文を出力する。読み上げ中の前の画面は打ち切る。
+    pub fn push(&self, lines: &[String]) {
+        if lines.is_empty() {
+            return;
+        }
+        let text = lines.join("\n");
+        let result = match self {
+            Self::Off => Ok(()),
+            Self::Speech(voice) => unsafe {
+                voice
+                    .Speak(
+                        &HSTRING::from(text.as_str()),
+                        (SPF_ASYNC.0 | SPF_PURGEBEFORESPEAK.0 | SPF_IS_NOT_XML.0) as u32,
+                        None,
+                    )
+                    .context("ISpVoice::Speak failed")
+            },
+            Self::Clipboard(owner) => clipboard::set_text(*owner, &text),
+        };
+        if let Err(e) = result {
+            crate::log(&format!("[READOUT ERR] {:#}", e));
+        }
+    }
+}
+
+/// 速度は -10（遅い）〜 10（速い）
+fn create_voice(rate: i32) -> Result<ISpVoice> {
+    unsafe {
+        let voice: ISpVoice = CoCreateInstance(&SpVoice, None, CLSCTX_ALL).context("Failed to create SpVoice")?;
+        voice.SetRate(rate.clamp(-10, 10))?;
+        Ok(voice)
+    }
+}
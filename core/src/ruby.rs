@@ -0,0 +1,118 @@
+//! ルビ（ふりがな）記法の解析
+//!
+//! 青空文庫形式に対応:
+//! - `｜東京《とうきょう》` — 「｜」から「《」までを親文字とする
+//! - `漢字《かんじ》` — 「《」の直前に連続する漢字を親文字とする
+
+/// ルビの付く範囲（位置・長さは DirectWrite に渡すため UTF-16 単位）
+#[derive(Debug, Clone, PartialEq)]
+pub struct RubySpan {
+    pub start: u32,
+    pub len: u32,
+    pub ruby: String,
+}
+
+pub(crate) fn is_kanji(c: char) -> bool {
+    matches!(c,
+        '\u{3400}'..='\u{4DBF}' | '\u{4E00}'..='\u{9FFF}' | '\u{F900}'..='\u{FAFF}'
+        | '\u{20000}'..='\u{2FA1F}' | '々' | '〆' | 'ヶ' | '〇')
+}
+
+/// ルビ記法を取り除いた本文と、ルビの範囲を返す
+pub fn parse(text: &str) -> (String, Vec<RubySpan>) {
+    let chars: Vec<char> = text.chars().collect();
+    let mut out: Vec<char> = Vec::with_capacity(chars.len());
+    // (親文字の開始, 終了, ルビ) — out 内の文字インデックス
+    let mut spans: Vec<(usize, usize, String)> = Vec::new();
+    let mut explicit_start: Option<usize> = None;
+
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let close = || chars[i + 1..].iter().position(|&c| c == '》').map(|p| i + 1 + p);
+
+        if c == '｜' {
+            // 後ろに《》が続く場合だけ記法として扱う（単独の縦線はそのまま表示）
+            if chars[i + 1..].contains(&'《') {
+                explicit_start = Some(out.len());
+                i += 1;
+                continue;
+            }
+        } else if c == '《' {
+            if let Some(end) = close() {
+                let base_start = explicit_start.take().unwrap_or_else(|| {
+                    let kanji_run = out.iter().rev().take_while(|&&c| is_kanji(c)).count();
+                    out.len() - kanji_run
+                });
+                if base_start < out.len() && end > i + 1 {
+                    let ruby: String = chars[i + 1..end].iter().collect();
+                    spans.push((base_start, out.len(), ruby));
+                    i = end + 1;
+                    continue;
+                }
+            }
+        }
+        out.push(c);
+        i += 1;
+    }
+
+    // 文字インデックス → UTF-16 オフセット
+    let mut utf16_offsets = Vec::with_capacity(out.len() + 1);
+    let mut offset = 0u32;
+    for c in &out {
+        utf16_offsets.push(offset);
+        offset += c.len_utf16() as u32;
+    }
+    utf16_offsets.push(offset);
+
+    let spans = spans
+        .into_iter()
+        .map(|(start, end, ruby)| RubySpan {
+            start: utf16_offsets[start],
+            len: utf16_offsets[end] - utf16_offsets[start],
+            ruby,
+        })
+        .collect();
+    (out.into_iter().collect(), spans)
+}
+
+/// ルビ記法を取り除いた本文（コピー・ログ用）
+pub fn strip(text: &str) -> String {
+    parse(text).0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn span(start: u32, len: u32, ruby: &str) -> RubySpan {
+        RubySpan { start, len, ruby: ruby.to_string() }
+    }
+
+    #[test]
+    fn explicit_base() {
+        assert_eq!(parse("｜東京《とうきょう》へ"), ("東京へ".to_string(), vec![span(0, 2, "とうきょう")]));
+    }
+
+    #[test]
+    fn implicit_base_is_the_kanji_run() {
+        assert_eq!(parse("今日は漢字《かんじ》"), ("今日は漢字".to_string(), vec![span(3, 2, "かんじ")]));
+    }
+
+    #[test]
+    fn offsets_are_utf16() {
+        assert_eq!(parse("𠮷野家《よしのや》"), ("𠮷野家".to_string(), vec![span(0, 4, "よしのや")]));
+    }
+
+    #[test]
+    fn ascii_pipe_is_plain_text() {
+        assert_eq!(parse("A|B《x》"), ("A|B《x》".to_string(), vec![]));
+    }
+
+    #[test]
+    fn lone_markers_are_kept() {
+        assert_eq!(parse("a｜b"), ("a｜b".to_string(), vec![]));
+        assert_eq!(parse("漢字《"), ("漢字《".to_string(), vec![]));
+        assert_eq!(strip("かな《よみ》"), "かな《よみ》");
+    }
+}
@@ -0,0 +1,106 @@
+//! テキストフック入力（Textractor / Agent）
+//!
+//! OCR の代わりに、フックツールがゲームから取り出した文を受け取る。画面から読まないので
+//! 誤認識がない。受け取り方は2つ:
+//!
+//! - クリップボード: Textractor の「Copy to Clipboard」拡張や Agent のクリップボード出力が書き込んだ文を読む
+//! - WebSocket: Textractor の WebSocket 拡張などのサーバーへ接続し、テキストメッセージ1つを1文として受け取る
+//!
+//! 文には画面上の位置がないので、オーバーレイはゲーム画面の下部に字幕として表示する。
+
+use std::time::Duration;
+
+use futures::StreamExt;
+use tokio::sync::mpsc;
+use tokio_tungstenite::tungstenite::Message;
+
+use crate::clipboard;
+
+const CLIPBOARD_POLL: Duration = Duration::from_millis(100);
+const RECONNECT_DELAY: Duration = Duration::from_secs(2);
+/// これより長い文は切り詰める（フックが拾った壊れたデータで翻訳 API を埋めないように）
+const MAX_LINE_CHARS: usize = 2000;
+
+/// 受け取った文を整える（空なら None）
+fn clean(text: &str) -> Option<String> {
+    let text = text.replace("\r\n", "\n");
+    let text = text.trim();
+    if text.is_empty() {
+        return None;
+    }
+    Some(text.chars().take(MAX_LINE_CHARS).collect())
+}
+
+/// クリップボードに新しく書き込まれた文を `lines` へ送る（受け手がいなくなるまで）。
+/// 開始前からあった内容と、このアプリ自身が書き込んだ訳文は読まない
+pub async fn watch_clipboard(lines: mpsc::Sender<String>) {
+    let mut seen = clipboard::sequence_number();
+    loop {
+        tokio::time::sleep(CLIPBOARD_POLL).await;
+        if lines.is_closed() {
+            return;
+        }
+        let seq = clipboard::sequence_number();
+        if seq == seen {
+            continue;
+        }
+        if clipboard::is_own() {
+            seen = seq;
+            continue;
+        }
+        match clipboard::get_text() {
+            Ok(text) => {
+                seen = seq;
+                if let Some(line) = text.as_deref().and_then(clean) {
+                    if lines.send(line).await.is_err() {
+                        return;
+                    }
+                }
+            }
+            // 他のアプリが開いている間は読めないので、次の確認で読み直す
+            Err(e) => crate::log(&format!("[HOOK] クリップボードを読めません: {:#}", e)),
+        }
+    }
+}
+
+/// `url` の WebSocket サーバーから受け取った文を `lines` へ送る。切れたらつなぎ直す
+pub async fn listen(url: &str, lines: mpsc::Sender<String>) {
+    // Only the first failure of a streak is logged, so a closed Textractor doesn't flood the log
+    let mut failing = false;
+    loop {
+        match tokio_tungstenite::connect_async(url).await {
+            Ok((mut ws, _)) => {
+                failing = false;
+                crate::log_always(&format!("[HOOK] {} に接続", url));
+                while let Some(message) = ws.next().await {
+                    match message {
+                        Ok(Message::Text(text)) => {
+                            if let Some(line) = clean(&text) {
+                                if lines.send(line).await.is_err() {
+                                    return;
+                                }
+                            }
+                        }
+                        Ok(Message::Close(_)) | Err(_) => break,
+                        Ok(_) => {}
+                    }
+                }
+                crate::log_always(&format!("[HOOK] {} から切断", url));
+            }
+            Err(e) if !failing => {
+                failing = true;
+                crate::log_always(&format!(
+                    "[HOOK] {} に接続できません（{}秒ごとに再試行）: {}",
+                    url,
+                    RECONNECT_DELAY.as_secs(),
+                    e
+                ));
+            }
+            Err(_) => {}
+        }
+        tokio::select! {
+            _ = lines.closed() => return,
+            _ = tokio::time::sleep(RECONNECT_DELAY) => {}
+        }
+    }
+}
@@ -0,0 +1,936 @@
+use anyhow::{Context, Result};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use reqwest::{Client, NoProxy, Proxy};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::config::{AppConfig, EchoStyle, LocalLlmApi, TranslationEngine};
+use crate::glossary::Glossary;
+use crate::lang::Language;
+use crate::plugin::{self, PluginClient, PluginManifest};
+
+/// Truncate a string to at most `max_chars` characters (safe for multi-byte UTF-8).
+fn truncate_str(s: &str, max_chars: usize) -> &str {
+    match s.char_indices().nth(max_chars) {
+        Some((idx, _)) => &s[..idx],
+        None => s,
+    }
+}
+
+fn tlog(msg: &str) {
+    crate::log(msg);
+}
+
+/// APIのレート制限（HTTP 429）で失敗したか
+pub fn is_rate_limited(e: &anyhow::Error) -> bool {
+    e.to_string().contains(&reqwest::StatusCode::TOO_MANY_REQUESTS.to_string())
+}
+
+/// サーバーにそのAPIがない（HTTP 404 / 405）ため失敗したか
+fn is_unsupported_endpoint(e: &anyhow::Error) -> bool {
+    let msg = e.to_string();
+    [reqwest::StatusCode::NOT_FOUND, reqwest::StatusCode::METHOD_NOT_ALLOWED]
+        .iter()
+        .any(|status| msg.contains(&status.to_string()))
+}
+
+// === DeepL API ===
+
+/// LLMプロンプト用の言語名（不明なコードはそのまま使う）
+fn llm_language_name(code: &str) -> String {
+    Language::parse(code)
+        .map(|l| l.english_name().to_string())
+        .unwrap_or_else(|| code.trim().to_string())
+}
+
+#[derive(Debug, Serialize)]
+struct DeepLRequest {
+    text: Vec<String>,
+    target_lang: String,
+    /// None = DeepL側で自動検出
+    #[serde(skip_serializing_if = "Option::is_none")]
+    source_lang: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DeepLResponse {
+    translations: Vec<DeepLTranslation>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DeepLTranslation {
+    text: String,
+}
+
+/// 今月の使用文字数と上限（/v2/usage）
+#[derive(Debug, Clone, Deserialize)]
+pub struct DeepLUsage {
+    pub character_count: u64,
+    pub character_limit: u64,
+}
+
+impl DeepLUsage {
+    pub fn remaining(&self) -> u64 {
+        self.character_limit.saturating_sub(self.character_count)
+    }
+}
+
+/// Free API keys end with ":fx", Pro keys don't
+fn deepl_base_url(api_key: &str) -> &'static str {
+    if api_key.ends_with(":fx") {
+        "https://api-free.deepl.com/v2"
+    } else {
+        "https://api.deepl.com/v2"
+    }
+}
+
+// === OpenAI Chat Completions API (Groq等) ===
+
+#[derive(Debug, Serialize)]
+struct ChatMessage {
+    role: String,
+    content: String,
+}
+
+#[derive(Debug, Serialize)]
+struct ChatCompletionRequest {
+    model: String,
+    messages: Vec<ChatMessage>,
+    temperature: f32,
+    max_tokens: u32,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatCompletionResponse {
+    choices: Vec<ChatCompletionChoice>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatCompletionChoice {
+    message: ChatMessageResponse,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatMessageResponse {
+    content: String,
+}
+
+// === OpenAI互換API (ExLlama3等) ===
+
+#[derive(Debug, Serialize)]
+struct CompletionRequest {
+    model: String,
+    prompt: String,
+    temperature: f32,
+    max_tokens: u32,
+}
+
+#[derive(Debug, Deserialize)]
+struct CompletionResponse {
+    choices: Vec<CompletionChoice>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CompletionChoice {
+    text: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ModelListResponse {
+    data: Vec<ModelInfo>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ModelInfo {
+    id: String,
+}
+
+/// Ollama の /api/tags
+#[derive(Debug, Deserialize)]
+struct OllamaTagsResponse {
+    models: Vec<OllamaModel>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OllamaModel {
+    name: String,
+}
+
+// === プロンプトテンプレート（Completions API用） ===
+
+/// モデル名から推定するチャットテンプレート
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum PromptTemplate {
+    Gemma,
+    Llama3,
+    ChatMl,
+    Mistral,
+}
+
+impl PromptTemplate {
+    /// 不明なモデルは従来通り Gemma 形式（TranslateGemma 想定）
+    fn detect(model: &str) -> Self {
+        let m = model.to_lowercase();
+        if m.contains("gemma") {
+            Self::Gemma
+        } else if m.contains("llama-3") || m.contains("llama3") {
+            Self::Llama3
+        } else if m.contains("qwen") || m.contains("chatml") || m.contains("hermes") || m.contains("yi-") {
+            Self::ChatMl
+        } else if m.contains("mistral") || m.contains("mixtral") {
+            Self::Mistral
+        } else {
+            Self::Gemma
+        }
+    }
+
+    fn format(&self, user: &str) -> String {
+        match self {
+            Self::Gemma => format!(
+                "<start_of_turn>user\n{}<end_of_turn>\n<start_of_turn>model\n",
+                user
+            ),
+            Self::Llama3 => format!(
+                "<|begin_of_text|><|start_header_id|>user<|end_header_id|>\n\n{}<|eot_id|><|start_header_id|>assistant<|end_header_id|>\n\n",
+                user
+            ),
+            Self::ChatMl => format!(
+                "<|im_start|>user\n{}<|im_end|>\n<|im_start|>assistant\n",
+                user
+            ),
+            Self::Mistral => format!("[INST] {} [/INST]", user),
+        }
+    }
+}
+
+// === 番号付きレスポンス解析 ===
+
+/// "N. テキスト" 形式のレスポンスを解析。
+/// 複数行にまたがる翻訳にも対応（次の番号行が来るまで結合）。
+fn parse_numbered_response(raw: &str, count: usize) -> Vec<Option<String>> {
+    let mut results: Vec<Option<String>> = vec![None; count];
+    let mut current_num: Option<usize> = None;
+    let mut current_text = String::new();
+
+    for line in raw.lines() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() { continue; }
+
+        // "N. テキスト" パターンをチェック
+        let mut matched_num = None;
+        if let Some(dot_pos) = trimmed.find(". ") {
+            if let Ok(num) = trimmed[..dot_pos].trim().parse::<usize>() {
+                if num >= 1 && num <= count {
+                    matched_num = Some((num, trimmed[dot_pos + 2..].to_string()));
+                }
+            }
+        }
+
+        if let Some((num, text)) = matched_num {
+            // 前の番号のテキストを確定
+            if let Some(prev) = current_num {
+                if prev >= 1 && prev <= count {
+                    results[prev - 1] = Some(current_text.trim().to_string());
+                }
+            }
+            current_num = Some(num);
+            current_text = text;
+        } else if current_num.is_some() {
+            // 続きの行を結合
+            current_text.push(' ');
+            current_text.push_str(trimmed);
+        }
+    }
+
+    // 最後の番号のテキストを確定
+    if let Some(prev) = current_num {
+        if prev >= 1 && prev <= count {
+            results[prev - 1] = Some(current_text.trim().to_string());
+        }
+    }
+
+    // 1件だけで番号なしの場合のフォールバック
+    if count == 1 && results[0].is_none() && !raw.trim().is_empty() {
+        results[0] = Some(raw.trim().to_string());
+    }
+
+    results
+}
+
+// === Echo (テスト用) ===
+
+fn translate_echo(texts: &[String], style: EchoStyle) -> Vec<Option<String>> {
+    texts
+        .iter()
+        .map(|t| {
+            Some(match style {
+                EchoStyle::Bracket => format!("[{}]", t),
+                EchoStyle::Reverse => t.chars().rev().collect(),
+            })
+        })
+        .collect()
+}
+
+// === プロンプト（設定で編集可能） ===
+
+/// LLMへの指示文の既定値。{from} / {to} は言語名に置き換える
+pub const DEFAULT_SYSTEM_PROMPT: &str = "You are a translator. Translate each numbered line from {from} to {to}. Output ONLY the translations, one per line, keeping the same numbering. No explanations.";
+/// ユーザーメッセージの既定値。{lines} は "1. 原文" 形式の番号付きの行に置き換える
+pub const DEFAULT_USER_TEMPLATE: &str = "{lines}";
+
+/// 「直訳」の指示文（組み込みの LLM プリセット用）
+pub const LITERAL_SYSTEM_PROMPT: &str = "You are a translator. Translate each numbered line from {from} to {to} as literally as possible, keeping the original terms and word order where the grammar allows. Output ONLY the translations, one per line, keeping the same numbering. No explanations.";
+/// 「意訳」の指示文（組み込みの LLM プリセット用）
+pub const LITERARY_SYSTEM_PROMPT: &str = "You are a professional game localizer. Translate each numbered line from {from} to {to} into natural, expressive prose that fits the characters and the scene, adapting idioms instead of translating them word for word. Output ONLY the translations, one per line, keeping the same numbering. No explanations.";
+
+/// 指示文とユーザーメッセージのテンプレート、生成パラメータ
+#[derive(Debug, Clone)]
+struct Prompts {
+    system: String,
+    user: String,
+    /// None = エンジンごとの既定値
+    temperature: Option<f32>,
+    /// 0 = 行数から決める
+    max_tokens: u32,
+}
+
+impl Default for Prompts {
+    fn default() -> Self {
+        Self {
+            system: DEFAULT_SYSTEM_PROMPT.to_string(),
+            user: DEFAULT_USER_TEMPLATE.to_string(),
+            temperature: None,
+            max_tokens: 0,
+        }
+    }
+}
+
+impl Prompts {
+    /// 空のテンプレートは既定値を使う
+    fn from_config(config: &AppConfig) -> Self {
+        let or_default = |s: &str, default: &str| {
+            if s.trim().is_empty() { default.to_string() } else { s.to_string() }
+        };
+        Self {
+            system: or_default(&config.llm_system_prompt, DEFAULT_SYSTEM_PROMPT),
+            user: or_default(&config.llm_user_template, DEFAULT_USER_TEMPLATE),
+            temperature: config.llm_temperature,
+            max_tokens: config.llm_max_tokens,
+        }
+    }
+
+    /// 最大トークン数（未指定なら1行あたり `per_line`、`cap` まで）
+    fn max_tokens(&self, lines: usize, per_line: u32, cap: u32) -> u32 {
+        if self.max_tokens > 0 {
+            self.max_tokens
+        } else {
+            (lines as u32 * per_line).min(cap)
+        }
+    }
+
+    /// プレースホルダーを置き換える。原文中の "{to}" 等を置き換えないよう {lines} は最後
+    fn render(template: &str, texts: &[String], from: &str, to: &str) -> String {
+        let numbered: Vec<String> = texts.iter().enumerate()
+            .map(|(i, t)| format!("{}. {}", i + 1, t))
+            .collect();
+        template
+            .replace("{from}", &llm_language_name(from))
+            .replace("{to}", &llm_language_name(to))
+            .replace("{lines}", &numbered.join("\n"))
+    }
+
+    fn chat_messages(&self, texts: &[String], from: &str, to: &str) -> Vec<ChatMessage> {
+        vec![
+            ChatMessage {
+                role: "system".to_string(),
+                content: Self::render(&self.system, texts, from, to),
+            },
+            ChatMessage {
+                role: "user".to_string(),
+                content: Self::render(&self.user, texts, from, to),
+            },
+        ]
+    }
+
+    /// Completions API 用: 指示文と入力を1つのユーザーターンにまとめ、モデルのテンプレートで包む
+    fn completion_prompt(&self, model: &str, texts: &[String], from: &str, to: &str) -> String {
+        PromptTemplate::detect(model).format(&format!(
+            "{}\n\n{}",
+            Self::render(&self.system, texts, from, to),
+            Self::render(&self.user, texts, from, to)
+        ))
+    }
+}
+
+// === Translator ===
+
+/// ローカルLLMサーバーの判定結果（初回リクエスト時に決定）
+#[derive(Debug, Clone)]
+struct LocalResolved {
+    use_chat: bool,
+    model: String,
+}
+
+#[allow(dead_code)]
+pub enum TranslatorBackend {
+    DeepL { api_key: String },
+    LocalLLM { endpoint: String, model: String, api: LocalLlmApi },
+    Groq { api_key: String, model: String },
+    Echo { style: EchoStyle },
+    Plugin { client: Box<PluginClient> },
+}
+
+/// 設定（プロキシ・カスタムヘッダー・TLS）を反映したHTTPクライアントを作成
+fn build_client(config: &AppConfig) -> Result<Client> {
+    let mut builder = Client::builder().timeout(Duration::from_secs(30));
+
+    let mut headers = HeaderMap::new();
+    for h in config.custom_headers.iter().filter(|h| h.engine == config.translation_engine) {
+        if h.name.trim().is_empty() {
+            continue;
+        }
+        let name = HeaderName::from_bytes(h.name.trim().as_bytes())
+            .with_context(|| format!("Invalid header name: {}", h.name))?;
+        let mut value = HeaderValue::from_str(h.value.trim())
+            .with_context(|| format!("Invalid value for header {}", h.name))?;
+        value.set_sensitive(true);
+        headers.append(name, value);
+    }
+    if !headers.is_empty() {
+        builder = builder.default_headers(headers);
+    }
+
+    if config.translation_engine == TranslationEngine::LocalLLM && config.local_llm_accept_invalid_certs {
+        builder = builder.danger_accept_invalid_certs(true);
+    }
+
+    if let Some(proxy) = proxy(config)? {
+        builder = builder.proxy(proxy);
+    }
+
+    builder.build().context("Failed to build HTTP client")
+}
+
+/// 設定のプロキシ（未設定なら None）。翻訳以外の通信（辞書のダウンロード等）でも使う
+pub fn proxy(config: &AppConfig) -> Result<Option<Proxy>> {
+    let proxy_url = config.proxy_url.trim();
+    if proxy_url.is_empty() {
+        return Ok(None);
+    }
+    let mut proxy = Proxy::all(proxy_url)
+        .with_context(|| format!("Invalid proxy URL: {}", proxy_url))?;
+    if !config.proxy_username.is_empty() {
+        proxy = proxy.basic_auth(&config.proxy_username, &config.proxy_password);
+    }
+    Ok(Some(proxy.no_proxy(NoProxy::from_string(&config.no_proxy))))
+}
+
+pub struct Translator {
+    client: Client,
+    backend: TranslatorBackend,
+    prompts: Prompts,
+    glossary: Glossary,
+    local_resolved: Mutex<Option<LocalResolved>>,
+}
+
+impl Translator {
+    /// 設定で選択されているエンジンの翻訳器を作成
+    pub fn from_config(config: &AppConfig) -> Result<Self> {
+        let mut translator = match config.translation_engine {
+            TranslationEngine::DeepL => Self::new_deepl(config.deepl_api_key.clone()),
+            TranslationEngine::LocalLLM => Self::new_local(
+                config.local_llm_endpoint.clone(),
+                config.local_llm_model.clone(),
+                config.local_llm_api,
+            ),
+            TranslationEngine::Groq => {
+                Self::new_groq(config.groq_api_key.clone(), config.groq_model.clone())
+            }
+            TranslationEngine::Echo => Self::new_echo(config.echo_style),
+            TranslationEngine::Plugin => Self::new_plugin(
+                plugin::find(&config.plugin_name)
+                    .with_context(|| format!("Plugin not found: {}", config.plugin_name))?,
+            ),
+        };
+        translator.client = build_client(config)?;
+        translator.prompts = Prompts::from_config(config);
+        translator.glossary = Glossary::new(&config.glossary);
+        Ok(translator)
+    }
+
+    /// `from_config` が読む設定をまとめた比較用の値（変わったら翻訳器を作り直す）
+    pub fn settings_key(config: &AppConfig) -> String {
+        let backend = (
+            config.translation_engine,
+            &config.deepl_api_key,
+            (&config.local_llm_endpoint, &config.local_llm_model, config.local_llm_api, config.local_llm_accept_invalid_certs),
+            (&config.groq_api_key, &config.groq_model),
+            config.echo_style,
+            &config.plugin_name,
+        );
+        let client = (
+            &config.custom_headers,
+            (&config.proxy_url, &config.proxy_username, &config.proxy_password, &config.no_proxy),
+        );
+        let prompts = (
+            &config.llm_system_prompt,
+            &config.llm_user_template,
+            config.llm_temperature,
+            config.llm_max_tokens,
+            &config.glossary,
+        );
+        serde_json::to_string(&(backend, client, prompts)).unwrap_or_default()
+    }
+
+    pub fn new_deepl(api_key: String) -> Self {
+        Self {
+            client: Client::builder()
+                .timeout(Duration::from_secs(30))
+                .build()
+                .unwrap_or_else(|_| Client::new()),
+            backend: TranslatorBackend::DeepL { api_key },
+            prompts: Prompts::default(),
+            glossary: Glossary::default(),
+            local_resolved: Mutex::new(None),
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn new_local(endpoint: String, model: String, api: LocalLlmApi) -> Self {
+        Self {
+            client: Client::builder()
+                .timeout(Duration::from_secs(30))
+                .build()
+                .unwrap_or_else(|_| Client::new()),
+            backend: TranslatorBackend::LocalLLM { endpoint, model, api },
+            prompts: Prompts::default(),
+            glossary: Glossary::default(),
+            local_resolved: Mutex::new(None),
+        }
+    }
+
+    pub fn new_groq(api_key: String, model: String) -> Self {
+        Self {
+            client: Client::builder()
+                .timeout(Duration::from_secs(30))
+                .build()
+                .unwrap_or_else(|_| Client::new()),
+            backend: TranslatorBackend::Groq { api_key, model },
+            prompts: Prompts::default(),
+            glossary: Glossary::default(),
+            local_resolved: Mutex::new(None),
+        }
+    }
+
+    /// キャプチャ・OCR・オーバーレイの位置合わせ確認用（API不要）
+    pub fn new_echo(style: EchoStyle) -> Self {
+        Self {
+            client: Client::new(),
+            backend: TranslatorBackend::Echo { style },
+            prompts: Prompts::default(),
+            glossary: Glossary::default(),
+            local_resolved: Mutex::new(None),
+        }
+    }
+
+    /// plugins フォルダのマニフェストで登録された外部プロセス
+    pub fn new_plugin(manifest: PluginManifest) -> Self {
+        Self {
+            client: Client::new(),
+            backend: TranslatorBackend::Plugin { client: Box::new(PluginClient::new(manifest)) },
+            prompts: Prompts::default(),
+            glossary: Glossary::default(),
+            local_resolved: Mutex::new(None),
+        }
+    }
+
+    pub async fn translate_batch(&self, texts: Vec<String>, from: &str, to: &str) -> Result<Vec<Option<String>>> {
+        if texts.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        // Collect unique non-empty texts; repeated strings (e.g. identical button
+        // labels) are sent once and the translation is fanned back out.
+        let mut unique_texts: Vec<String> = Vec::new();
+        let mut unique_index: HashMap<&str, usize> = HashMap::new();
+        let slots: Vec<Option<usize>> = texts.iter()
+            .map(|t| {
+                if t.trim().is_empty() {
+                    return None;
+                }
+                Some(*unique_index.entry(t.as_str()).or_insert_with(|| {
+                    unique_texts.push(t.clone());
+                    unique_texts.len() - 1
+                }))
+            })
+            .collect();
+
+        if unique_texts.is_empty() {
+            return Ok(vec![None; texts.len()]);
+        }
+
+        if unique_texts.len() < slots.iter().flatten().count() {
+            tlog(&format!("[DEDUP] {} texts -> {} unique", slots.iter().flatten().count(), unique_texts.len()));
+        }
+
+        // Glossary: whole-line terms need no request; other terms are swapped for
+        // placeholders before sending and put back into the translations
+        let mut translated: Vec<Option<String>> = unique_texts.iter().map(|t| self.glossary.exact(t)).collect();
+        let pending: Vec<usize> = (0..unique_texts.len()).filter(|&i| translated[i].is_none()).collect();
+        let (to_send, replacements): (Vec<String>, Vec<Vec<String>>) =
+            pending.iter().map(|&i| self.glossary.protect(&unique_texts[i])).unzip();
+
+        if !to_send.is_empty() {
+            let results = self.send(&to_send, from, to).await?;
+            let mut broken = Vec::new();
+            for ((&i, reps), result) in pending.iter().zip(&replacements).zip(results) {
+                match result.map(|r| Glossary::restore(&r, reps)) {
+                    Some(None) => broken.push(i),
+                    restored => translated[i] = restored.flatten(),
+                }
+            }
+            // The engine dropped or mangled a placeholder: translate those lines again as they are,
+            // rather than keep (and cache) a translation with a term missing
+            if !broken.is_empty() {
+                tlog(&format!("[GLOSSARY] {}行でプレースホルダーが崩れたため用語集なしで再翻訳", broken.len()));
+                let originals: Vec<String> = broken.iter().map(|&i| unique_texts[i].clone()).collect();
+                for (&i, result) in broken.iter().zip(self.send(&originals, from, to).await?) {
+                    translated[i] = result;
+                }
+            }
+        }
+
+        // Map results back to original indices
+        let results = slots.iter()
+            .map(|slot| slot.and_then(|i| translated.get(i).cloned().flatten()))
+            .collect();
+
+        Ok(results)
+    }
+
+    /// 選択中のエンジンに送る（用語集の処理は済んだ前提）
+    async fn send(&self, texts: &[String], from: &str, to: &str) -> Result<Vec<Option<String>>> {
+        Ok(match &self.backend {
+            TranslatorBackend::DeepL { api_key } => self.translate_deepl(texts, from, to, api_key).await?,
+            TranslatorBackend::LocalLLM { endpoint, model, api } => {
+                self.translate_local(texts, from, to, endpoint, model, *api).await?
+            }
+            TranslatorBackend::Groq { api_key, model } => self.translate_groq(texts, from, to, api_key, model).await?,
+            TranslatorBackend::Echo { style } => translate_echo(texts, *style),
+            TranslatorBackend::Plugin { client } => client.translate(texts, from, to).await?,
+        })
+    }
+
+    fn chat_request(&self, model: &str, texts: &[String], from: &str, to: &str, temperature: f32) -> ChatCompletionRequest {
+        ChatCompletionRequest {
+            model: model.to_string(),
+            messages: self.prompts.chat_messages(texts, from, to),
+            temperature: self.prompts.temperature.unwrap_or(temperature),
+            max_tokens: self.prompts.max_tokens(texts.len(), 128, 2048),
+        }
+    }
+
+    fn completion_request(&self, model: &str, texts: &[String], from: &str, to: &str) -> CompletionRequest {
+        CompletionRequest {
+            model: model.to_string(),
+            prompt: self.prompts.completion_prompt(model, texts, from, to),
+            temperature: self.prompts.temperature.unwrap_or(0.1),
+            max_tokens: self.prompts.max_tokens(texts.len(), 64, 1024),
+        }
+    }
+
+    /// 送信するリクエスト本文（GUIのプロンプト編集のプレビュー用）。
+    /// ローカルの「自動」は Chat API として表示する（失敗時の Completions への切り替えは実行時）。
+    pub fn preview_request(&self, texts: &[String], from: &str, to: &str) -> Result<String> {
+        let protected: Vec<String> = texts.iter().map(|t| self.glossary.protect(t).0).collect();
+        let texts = protected.as_slice();
+        let json = match &self.backend {
+            TranslatorBackend::Groq { model, .. } => {
+                serde_json::to_string_pretty(&self.chat_request(model, texts, from, to, 0.3))?
+            }
+            TranslatorBackend::LocalLLM { model, api: LocalLlmApi::Completions, .. } => {
+                serde_json::to_string_pretty(&self.completion_request(model, texts, from, to))?
+            }
+            TranslatorBackend::LocalLLM { model, .. } => {
+                serde_json::to_string_pretty(&self.chat_request(model, texts, from, to, 0.1))?
+            }
+            TranslatorBackend::DeepL { .. } | TranslatorBackend::Echo { .. } | TranslatorBackend::Plugin { .. } => {
+                anyhow::bail!("This engine does not use prompts")
+            }
+        };
+        Ok(json)
+    }
+
+    async fn translate_deepl(&self, texts: &[String], from: &str, to: &str, api_key: &str) -> Result<Vec<Option<String>>> {
+        let target_lang = Language::parse(to)
+            .and_then(|l| l.deepl_target())
+            .map(str::to_string)
+            .unwrap_or_else(|| to.to_uppercase());
+        let source_lang = match Language::parse(from) {
+            Some(l) => l.deepl_source().map(str::to_string),
+            None => Some(from.to_uppercase()),
+        };
+        let request = DeepLRequest {
+            text: texts.to_vec(),
+            target_lang,
+            source_lang,
+        };
+
+        let response = self.client
+            .post(format!("{}/translate", deepl_base_url(api_key)))
+            .header("Authorization", format!("DeepL-Auth-Key {}", api_key))
+            .json(&request)
+            .send()
+            .await
+            .context("Failed to send DeepL request")?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            anyhow::bail!("DeepL API error: {} - {}", status, body);
+        }
+
+        let resp: DeepLResponse = response.json().await
+            .context("Failed to parse DeepL response")?;
+
+        Ok(resp.translations.iter().map(|t| Some(t.text.clone())).collect())
+    }
+
+    /// DeepL の今月の残り文字数（GUIの表示用）
+    pub async fn deepl_usage(&self) -> Result<DeepLUsage> {
+        let TranslatorBackend::DeepL { api_key } = &self.backend else {
+            anyhow::bail!("Usage is only available for DeepL");
+        };
+        let response = self.client
+            .get(format!("{}/usage", deepl_base_url(api_key)))
+            .header("Authorization", format!("DeepL-Auth-Key {}", api_key))
+            .send()
+            .await
+            .context("Failed to send DeepL usage request")?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            anyhow::bail!("DeepL API error: {} - {}", status, truncate_str(&body, 200));
+        }
+        response.json().await.context("Failed to parse DeepL usage")
+    }
+
+    /// サーバーで使えるモデル名の一覧（GUIのモデル選択用）。
+    /// ローカルは /v1/models、応答しなければ Ollama の /api/tags を試す。
+    pub async fn list_models(&self) -> Result<Vec<String>> {
+        let mut models = match &self.backend {
+            TranslatorBackend::LocalLLM { endpoint, .. } => {
+                let base = endpoint.trim_end_matches('/');
+                match self.fetch_json::<ModelListResponse>(self.client.get(format!("{}/v1/models", base))).await {
+                    Ok(list) => list.data.into_iter().map(|m| m.id).collect(),
+                    Err(e) => {
+                        tlog(&format!("[LOCAL] /v1/models failed ({:#}), trying /api/tags", e));
+                        self.fetch_json::<OllamaTagsResponse>(self.client.get(format!("{}/api/tags", base)))
+                            .await?
+                            .models
+                            .into_iter()
+                            .map(|m| m.name)
+                            .collect()
+                    }
+                }
+            }
+            TranslatorBackend::Groq { api_key, .. } => {
+                let request = self.client
+                    .get("https://api.groq.com/openai/v1/models")
+                    .header("Authorization", format!("Bearer {}", api_key));
+                self.fetch_json::<ModelListResponse>(request)
+                    .await?
+                    .data
+                    .into_iter()
+                    .map(|m| m.id)
+                    .collect::<Vec<_>>()
+            }
+            TranslatorBackend::DeepL { .. } | TranslatorBackend::Echo { .. } | TranslatorBackend::Plugin { .. } => {
+                anyhow::bail!("This engine has no model selection")
+            }
+        };
+        models.sort();
+        models.dedup();
+        Ok(models)
+    }
+
+    async fn fetch_json<T: serde::de::DeserializeOwned>(&self, request: reqwest::RequestBuilder) -> Result<T> {
+        let response = request.send().await.context("Failed to send model list request")?;
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            anyhow::bail!("HTTP {} - {}", status, truncate_str(&body, 200));
+        }
+        response.json().await.context("Failed to parse model list")
+    }
+
+    /// /v1/models を問い合わせてAPI形式とモデル名を決定（結果はキャッシュ）
+    async fn resolve_local(&self, endpoint: &str, model: &str, api: LocalLlmApi) -> LocalResolved {
+        if let Some(r) = self.local_resolved.lock().unwrap().clone() {
+            return r;
+        }
+
+        // 一覧が取れた時と、/v1/models がないと分かった時（404 / 405）だけ結果を覚える。
+        // 起動中・接続できない等で問い合わせ自体が失敗した時は次のリクエストで再判定する。
+        let url = format!("{}/v1/models", endpoint.trim_end_matches('/'));
+        let (models, settled): (Option<Vec<String>>, bool) = match self.client.get(&url).send().await {
+            Ok(resp) if resp.status().is_success() => {
+                let models = resp
+                    .json::<ModelListResponse>()
+                    .await
+                    .ok()
+                    .map(|m| m.data.into_iter().map(|d| d.id).collect());
+                let settled = models.is_some();
+                (models, settled)
+            }
+            Ok(resp) => (
+                None,
+                matches!(resp.status(), reqwest::StatusCode::NOT_FOUND | reqwest::StatusCode::METHOD_NOT_ALLOWED),
+            ),
+            Err(_) => (None, false),
+        };
+
+        let use_chat = match api {
+            LocalLlmApi::Auto => models.is_some(),
+            LocalLlmApi::Chat => true,
+            LocalLlmApi::Completions => false,
+        };
+
+        // モデル名が未指定なら、サーバーにロード済みの先頭モデルを使う
+        let model = match models.as_ref().and_then(|m| m.first()) {
+            Some(first) if model.trim().is_empty() || model == "default" => first.clone(),
+            _ => model.to_string(),
+        };
+
+        tlog(&format!(
+            "[LOCAL] api={:?} use_chat={} model={} models={:?} settled={}",
+            api, use_chat, model, models, settled
+        ));
+
+        let resolved = LocalResolved { use_chat, model };
+        if settled {
+            *self.local_resolved.lock().unwrap() = Some(resolved.clone());
+        }
+        resolved
+    }
+
+    async fn translate_local(&self, texts: &[String], from: &str, to: &str, endpoint: &str, model: &str, api: LocalLlmApi) -> Result<Vec<Option<String>>> {
+        let resolved = self.resolve_local(endpoint, model, api).await;
+
+        if resolved.use_chat {
+            match self.translate_local_chat(texts, from, to, endpoint, &resolved.model).await {
+                Ok(r) => return Ok(r),
+                Err(e) if api == LocalLlmApi::Auto && is_unsupported_endpoint(&e) => {
+                    // チャットAPI非対応のサーバー: 以降は Completions を使う
+                    tlog(&format!("[LOCAL] chat failed, falling back to completions: {}", e));
+                    *self.local_resolved.lock().unwrap() = Some(LocalResolved {
+                        use_chat: false,
+                        model: resolved.model.clone(),
+                    });
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        self.translate_local_completions(texts, from, to, endpoint, &resolved.model).await
+    }
+
+    async fn translate_local_chat(&self, texts: &[String], from: &str, to: &str, endpoint: &str, model: &str) -> Result<Vec<Option<String>>> {
+        let url = format!("{}/v1/chat/completions", endpoint.trim_end_matches('/'));
+
+        let request = self.chat_request(model, texts, from, to, 0.1);
+
+        let response = self.client
+            .post(&url)
+            .json(&request)
+            .send()
+            .await
+            .context("Failed to send chat request to local LLM")?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            anyhow::bail!("Local LLM chat error: {} - {}", status, body);
+        }
+
+        let resp: ChatCompletionResponse = response.json().await
+            .context("Failed to parse local LLM chat response")?;
+
+        let raw = resp.choices.first()
+            .map(|c| c.message.content.trim().to_string())
+            .unwrap_or_default();
+
+        Ok(parse_numbered_response(&raw, texts.len()))
+    }
+
+    async fn translate_local_completions(&self, texts: &[String], from: &str, to: &str, endpoint: &str, model: &str) -> Result<Vec<Option<String>>> {
+        let url = format!("{}/v1/completions", endpoint.trim_end_matches('/'));
+
+        // 全テキストを1リクエストにバッチ化（速度重視）
+        let request = self.completion_request(model, texts, from, to);
+
+        let response = self.client
+            .post(&url)
+            .json(&request)
+            .send()
+            .await
+            .context("Failed to send request to local LLM")?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            anyhow::bail!("Local LLM error: {} - {}", status, body);
+        }
+
+        let resp: CompletionResponse = response.json().await
+            .context("Failed to parse local LLM response")?;
+
+        let raw = resp.choices.first()
+            .map(|c| c.text.trim().to_string())
+            .unwrap_or_default();
+
+        Ok(parse_numbered_response(&raw, texts.len()))
+    }
+
+    async fn translate_groq(&self, texts: &[String], from: &str, to: &str, api_key: &str, model: &str) -> Result<Vec<Option<String>>> {
+        let request = self.chat_request(model, texts, from, to, 0.3);
+
+        let response = self.client
+            .post("https://api.groq.com/openai/v1/chat/completions")
+            .header("Authorization", format!("Bearer {}", api_key))
+            .json(&request)
+            .send()
+            .await
+            .context("Failed to send Groq request")?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            tlog(&format!("[GROQ ERR] HTTP {} - {}", status, truncate_str(&body, 200)));
+            anyhow::bail!("Groq API error: {} - {}", status, body);
+        }
+
+        let body_text = response.text().await
+            .context("Failed to read Groq response body")?;
+
+        let resp: ChatCompletionResponse = serde_json::from_str(&body_text)
+            .context("Failed to parse Groq response JSON")?;
+
+        let raw = resp.choices.first()
+            .map(|c| c.message.content.trim().to_string())
+            .unwrap_or_default();
+
+        tlog(&format!("[GROQ RAW] count={} raw={}", texts.len(), truncate_str(&raw, 300)));
+
+        let results = parse_numbered_response(&raw, texts.len());
+        let fail_count = results.iter().filter(|r| r.is_none()).count();
+        if fail_count > 0 {
+            tlog(&format!("[GROQ PARSE] {} fails out of {}. Full raw: {}", fail_count, texts.len(), truncate_str(&raw, 500)));
+        }
+
+        Ok(results)
+    }
+}
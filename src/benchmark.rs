@@ -0,0 +1,140 @@
+//! ベンチマーク: キャプチャ・OCR・翻訳を N 回ずつ実行し、段階ごとの所要時間の分布を出す
+//!
+//! 翻訳エンジンや OCR 言語、ウィンドウサイズなどの設定を体感ではなく数値で比べる用。
+//! 翻訳はキャッシュを通さず毎回エンジンを呼ぶ（API の使用量に数えられる）。
+
+use anyhow::{Context, Result};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use windows::Win32::Foundation::HWND;
+
+use crate::capture::{self, WindowCapture};
+use crate::config::AppConfig;
+use crate::ocr::{self, OCREngine};
+use crate::translate::Translator;
+
+/// 何を読むか
+pub enum BenchmarkSource {
+    /// 選択中のウィンドウを毎回キャプチャする
+    Window(isize),
+    /// 画像ファイル（キャプチャの段階は測らない）
+    Image(PathBuf),
+}
+
+enum Input {
+    Window(WindowCapture),
+    Image(Vec<u8>, u32, u32),
+}
+
+/// 1つの段階の測定値
+#[derive(Clone)]
+pub struct StageTimes {
+    pub stage: &'static str,
+    pub samples: Vec<Duration>,
+}
+
+impl StageTimes {
+    /// 分位点（0.0〜1.0）。測定値がなければ None
+    pub fn percentile(&self, p: f32) -> Option<Duration> {
+        let mut sorted = self.samples.clone();
+        sorted.sort();
+        let last = sorted.len().checked_sub(1)?;
+        Some(sorted[(last as f32 * p).round() as usize])
+    }
+
+    pub fn mean(&self) -> Option<Duration> {
+        (!self.samples.is_empty()).then(|| self.samples.iter().sum::<Duration>() / self.samples.len() as u32)
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct BenchmarkProgress {
+    pub done: usize,
+    pub total: usize,
+    pub stages: Vec<StageTimes>,
+    /// OCR で読めた領域の数（最後の回）
+    pub regions: usize,
+    pub error: Option<String>,
+}
+
+impl BenchmarkProgress {
+    fn record(&mut self, stage: &'static str, elapsed: Duration) {
+        match self.stages.iter_mut().find(|s| s.stage == stage) {
+            Some(times) => times.samples.push(elapsed),
+            None => self.stages.push(StageTimes { stage, samples: vec![elapsed] }),
+        }
+    }
+}
+
+/// `iterations` 回測る（COM の初期化が必要）。測定値は1回ごとに `progress` に足していく
+pub async fn run(
+    config: AppConfig,
+    source: BenchmarkSource,
+    iterations: usize,
+    progress: Arc<Mutex<BenchmarkProgress>>,
+    cancel: Arc<AtomicBool>,
+) -> Result<()> {
+    progress.lock().unwrap().total = iterations;
+    let translator = Translator::from_config(&config)?;
+    let engine = OCREngine::new(&ocr::preferred_language(&config.source_lang))?;
+    let mut input = match source {
+        BenchmarkSource::Window(hwnd_raw) => Input::Window(WindowCapture::new(HWND(hwnd_raw as *mut _))?),
+        BenchmarkSource::Image(path) => {
+            let (pixels, width, height) = capture::decode_image(&path)?;
+            Input::Image(pixels, width, height)
+        }
+    };
+    crate::log_always(&format!("[BENCH] {}回の測定を開始 (OCR: {})", iterations, engine.language()));
+
+    for _ in 0..iterations {
+        if cancel.load(Ordering::SeqCst) {
+            crate::log_always("[BENCH] 中断されました");
+            break;
+        }
+        let iteration_start = Instant::now();
+        let frame;
+        let (pixels, width, height) = match &mut input {
+            Input::Window(capture) => {
+                let start = Instant::now();
+                frame = capture.capture_frame()?.context("The window is minimized")?;
+                progress.lock().unwrap().record("キャプチャ", start.elapsed());
+                let (width, height) = capture.get_dimensions();
+                (frame.as_slice(), width, height)
+            }
+            Input::Image(pixels, width, height) => (pixels.as_slice(), *width, *height),
+        };
+
+        let start = Instant::now();
+        let regions = engine.detect_text(pixels, width, height).await?;
+        progress.lock().unwrap().record("OCR", start.elapsed());
+
+        let lines: Vec<String> = regions.into_iter().map(|r| r.text).collect();
+        progress.lock().unwrap().regions = lines.len();
+        if !lines.is_empty() {
+            let start = Instant::now();
+            translator.translate_batch(lines, &config.source_lang, &config.target_lang).await?;
+            progress.lock().unwrap().record("翻訳", start.elapsed());
+        }
+        let mut p = progress.lock().unwrap();
+        p.record("合計", iteration_start.elapsed());
+        p.done += 1;
+    }
+
+    let p = progress.lock().unwrap();
+    for times in &p.stages {
+        let ms = |p: f32| times.percentile(p).map_or(0, |d| d.as_millis());
+        crate::log_always(&format!(
+            "[BENCH] {}: 中央値 {} ms, p95 {} ms, 最小 {} ms, 最大 {} ms (n={})",
+            times.stage,
+            ms(0.5),
+            ms(0.95),
+            ms(0.0),
+            ms(1.0),
+            times.samples.len()
+        ));
+    }
+    Ok(())
+}
@@ -0,0 +1,873 @@
+//! 翻訳キャッシュの CSV / TMX 入出力、ファン翻訳ファイル（TMX / XLIFF / CSV）からのシード取り込み、
+//! セッション中の翻訳履歴の書き出し（字幕ファイル・Anki 用 TSV を含む）、用語集の CSV 入出力
+
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::cache::{CacheEntry, CacheScope, TranslationCache};
+use crate::config::{self, GlossaryEntry, GlossaryMatch, TranscriptFormat, TranslationEngine};
+use crate::overlay::SessionRecord;
+use crate::ruby;
+
+const CSV_HEADER: &str = "engine,source_lang,target_lang,text,translation,plugin";
+const GLOSSARY_CSV_HEADER: &str = "term,translation,match";
+/// 消えた時刻がない字幕（書き出し時にまだ表示中だった文）の表示時間
+const SUBTITLE_FALLBACK_DURATION: Duration = Duration::from_secs(5);
+/// 1080p の動画に合わせた ASS のヘッダーと既定スタイル（下中央、白文字に黒縁）
+const ASS_HEADER: &str = "[Script Info]\r
+ScriptType: v4.00+\r
+PlayResX: 1920\r
+PlayResY: 1080\r
+WrapStyle: 0\r
+ScaledBorderAndShadow: yes\r
+\r
+[V4+ Styles]\r
+Format: Name, Fontname, Fontsize, PrimaryColour, SecondaryColour, OutlineColour, BackColour, Bold, Italic, Underline, StrikeOut, ScaleX, ScaleY, Spacing, Angle, BorderStyle, Outline, Shadow, Alignment, MarginL, MarginR, MarginV, Encoding\r
+Style: Default,Arial,56,&H00FFFFFF,&H000000FF,&H00000000,&H80000000,0,0,0,0,100,100,0,0,1,3,1,2,60,60,50,1\r
+\r
+[Events]\r
+Format: Layer, Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text\r
+";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CacheFormat {
+    Csv,
+    Tmx,
+    Xliff,
+}
+
+impl CacheFormat {
+    /// 拡張子からフォーマットを判定
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+        match ext.as_str() {
+            "csv" => Some(Self::Csv),
+            "tmx" => Some(Self::Tmx),
+            "xlf" | "xliff" => Some(Self::Xliff),
+            _ => None,
+        }
+    }
+}
+
+/// キャッシュをファイルへエクスポート。書き出した件数を返す。
+pub fn export_cache(cache: &TranslationCache, path: &Path) -> Result<usize> {
+    let format = CacheFormat::from_path(path)
+        .context("対応していない拡張子です (.csv / .tmx)")?;
+    let entries = cache.entries();
+    let content = match format {
+        CacheFormat::Csv => write_csv(&entries),
+        CacheFormat::Tmx => write_tmx(&entries),
+        CacheFormat::Xliff => anyhow::bail!("XLIFFへのエクスポートは未対応です (.csv / .tmx)"),
+    };
+    std::fs::write(path, content)
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(entries.len())
+}
+
+/// ファイルからキャッシュへインポート。
+/// エンジン・言語の記載がない項目は `default_scope` のものとして扱う。取り込んだ件数を返す。
+pub fn import_cache(cache: &mut TranslationCache, path: &Path, default_scope: &CacheScope) -> Result<usize> {
+    let format = CacheFormat::from_path(path)
+        .context("対応していない拡張子です (.csv / .tmx)")?;
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let entries = match format {
+        CacheFormat::Csv => read_csv(&content, default_scope),
+        CacheFormat::Tmx => read_tmx(&content, default_scope),
+        CacheFormat::Xliff => read_xliff(&content, default_scope),
+    };
+    let count = entries.len();
+    for entry in entries {
+        cache.insert_entry(entry);
+    }
+    Ok(count)
+}
+
+/// ファン翻訳ファイル（TMX / XLIFF / CSV）を人手翻訳として取り込む。
+/// 取り込んだ訳はエンジンに関係なくAPI翻訳より優先される。取り込んだ件数を返す。
+pub fn seed_from_file(cache: &mut TranslationCache, path: &Path, source_lang: &str, target_lang: &str) -> Result<usize> {
+    let format = CacheFormat::from_path(path)
+        .context("対応していない拡張子です (.tmx / .xlf / .xliff / .csv)")?;
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let scope = CacheScope::seed(source_lang, target_lang);
+
+    let pairs: Vec<(String, String)> = match format {
+        // 言語列のある行は、今の言語ペアと違えば取り込まない
+        CacheFormat::Csv => read_csv(&content, &scope)
+            .into_iter()
+            .filter(|e| language_matches(&e.source_lang, source_lang) && language_matches(&e.target_lang, target_lang))
+            .map(|e| (e.text, e.translation))
+            .collect(),
+        CacheFormat::Xliff => read_xliff(&content, &scope)
+            .into_iter()
+            .map(|e| (e.text, e.translation))
+            .collect(),
+        CacheFormat::Tmx => read_tmx_units(&content)
+            .into_iter()
+            .filter_map(|unit| pick_language_pair(&unit.variants, source_lang, target_lang))
+            .collect(),
+    };
+
+    let count = pairs.len();
+    for (text, translation) in pairs {
+        cache.insert(&scope, text, translation);
+    }
+    Ok(count)
+}
+
+/// "en-US" / "EN" / "en_GB" 等を主言語サブタグ（"en"）で比較
+fn same_language(a: &str, b: &str) -> bool {
+    let primary = |s: &str| s.split(['-', '_']).next().unwrap_or("").trim().to_ascii_lowercase();
+    primary(a) == primary(b)
+}
+
+/// ファイル側の言語（空欄は不明）がセッションの言語（"AUTO" は何でも可）に合うか
+fn language_matches(tag: &str, lang: &str) -> bool {
+    tag.trim().is_empty() || lang.eq_ignore_ascii_case("auto") || same_language(tag, lang)
+}
+
+/// TMX の翻訳単位から指定言語ペアの原文・訳文を選ぶ。
+/// 言語タグが1つもない単位だけ先頭2つを原文・訳文とみなし、タグがあってペアが揃わない単位は捨てる。
+fn pick_language_pair(variants: &[(Option<String>, String)], source_lang: &str, target_lang: &str) -> Option<(String, String)> {
+    let (source, target) = if variants.iter().all(|(l, _)| l.is_none()) {
+        if variants.len() < 2 {
+            return None;
+        }
+        (0, 1)
+    } else {
+        let tagged = |accept: &dyn Fn(&str) -> bool| {
+            variants.iter().position(|(l, _)| l.as_deref().is_some_and(accept))
+        };
+        // 原文が "AUTO" の時は訳文の言語以外のタグを原文とする
+        let target = tagged(&|l| same_language(l, target_lang))?;
+        let source = tagged(&|l| language_matches(l, source_lang) && !same_language(l, target_lang))?;
+        (source, target)
+    };
+    let pair = (variants[source].1.clone(), variants[target].1.clone());
+    if pair.0.trim().is_empty() || pair.1.trim().is_empty() {
+        return None;
+    }
+    Some(pair)
+}
+
+/// 翻訳履歴をファイルへ書き出す（.csv / .json、.srt / .ass の字幕、それ以外は原文・訳文を並べたテキスト）。
+/// 書き出した件数を返す。
+pub fn export_session(records: &[SessionRecord], path: &Path) -> Result<usize> {
+    let mut out = String::new();
+    let ext = path.extension().and_then(|e| e.to_str()).map(|e| e.to_ascii_lowercase());
+    if ext.as_deref() == Some("srt") {
+        for (i, r) in records.iter().enumerate() {
+            let (start, end) = subtitle_span(r);
+            out.push_str(&format!(
+                "{}\r\n{} --> {}\r\n{}\r\n\r\n",
+                i + 1,
+                srt_time(start),
+                srt_time(end),
+                subtitle_lines(r).join("\r\n")
+            ));
+        }
+    } else if ext.as_deref() == Some("ass") {
+        out.push_str(ASS_HEADER);
+        for r in records {
+            let (start, end) = subtitle_span(r);
+            let text: Vec<String> = subtitle_lines(r).iter().map(|l| l.replace('{', "(").replace('}', ")")).collect();
+            out.push_str(&format!(
+                "Dialogue: 0,{},{},Default,,0,0,0,,{}\r\n",
+                ass_time(start),
+                ass_time(end),
+                text.join("\\N")
+            ));
+        }
+    } else if ext.as_deref() == Some("json") {
+        let lines: Vec<serde_json::Value> = records
+            .iter()
+            .map(|r| {
+                let (x, y, width, height) = r.region;
+                serde_json::json!({
+                    "time": r.time,
+                    "engine": r.engine.as_str(),
+                    "source_lang": r.source_lang,
+                    "target_lang": r.target_lang,
+                    "text": r.source,
+                    "translation": r.translation,
+                    "pinned": r.pinned,
+                    "region": { "x": x, "y": y, "width": width, "height": height },
+                    "shown_at_ms": r.shown_at.as_millis() as u64,
+                    "hidden_at_ms": r.hidden_at.map(|t| t.as_millis() as u64),
+                })
+            })
+            .collect();
+        out = serde_json::to_string_pretty(&lines)?;
+    } else if CacheFormat::from_path(path) == Some(CacheFormat::Csv) {
+        out.push_str("time,engine,text,translation,x,y,width,height\r\n");
+        for r in records {
+            let (x, y, width, height) = r.region;
+            let fields = [
+                r.time.clone(),
+                r.engine.as_str().to_string(),
+                csv_field(&r.source),
+                csv_field(&r.translation),
+                x.to_string(),
+                y.to_string(),
+                width.to_string(),
+                height.to_string(),
+            ];
+            out.push_str(&fields.join(","));
+            out.push_str("\r\n");
+        }
+    } else {
+        for r in records {
+            let (x, y, width, height) = r.region;
+            out.push_str(&format!(
+                "[{}] {} ({}, {}, {}x{})\r\n{}\r\n{}\r\n\r\n",
+                r.time,
+                r.engine.as_str(),
+                x,
+                y,
+                width,
+                height,
+                r.source,
+                r.translation
+            ));
+        }
+    }
+    std::fs::write(path, out)
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(records.len())
+}
+
+/// 履歴の文を Anki で読み込める TSV（原文・訳文・画像）として書き出す。同じ原文は1枚にまとめる。
+/// 画像は TSV の隣の "<ファイル名>_media" フォルダに置く（Anki の collection.media へコピーして使う）。
+/// 書き出したカードの枚数を返す。
+pub fn export_anki(records: &[SessionRecord], path: &Path) -> Result<usize> {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy().into_owned();
+    let media_dir = path.with_file_name(format!("{}_media", stem));
+    let mut out = String::from("#separator:tab\r\n#html:true\r\n#tags:game-translator\r\n#columns:Source\tTranslation\tScreenshot\r\n");
+    let mut seen = std::collections::HashSet::new();
+    for (i, r) in records.iter().enumerate() {
+        if !seen.insert(r.source.as_str()) {
+            continue;
+        }
+        let image = match &r.screenshot {
+            Some(png) => {
+                let digits: String = r.time.chars().filter(char::is_ascii_digit).collect();
+                let name = format!("game-translator-{}-{}.png", digits, i);
+                std::fs::create_dir_all(&media_dir)
+                    .with_context(|| format!("Failed to create {}", media_dir.display()))?;
+                let image_path = media_dir.join(&name);
+                std::fs::write(&image_path, png.as_slice())
+                    .with_context(|| format!("Failed to write {}", image_path.display()))?;
+                format!("<img src=\"{}\">", name)
+            }
+            None => String::new(),
+        };
+        let fields = [anki_field(&r.source), anki_field(&ruby::strip(&r.translation)), image];
+        out.push_str(&fields.join("\t"));
+        out.push_str("\r\n");
+    }
+    std::fs::write(path, out)
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(seen.len())
+}
+
+/// Anki の HTML フィールド（タブは区切りなので空白に、改行は <br> に）
+fn anki_field(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('\t', " ")
+        .replace("\r\n", "<br>")
+        .replace('\n', "<br>")
+}
+
+/// 停止時に自動で書き出すセッション記録の保存先
+pub fn transcript_dir() -> PathBuf {
+    config::data_dir().join("transcripts")
+}
+
+/// セッション記録を transcripts フォルダへ "session-YYYYMMDD-HHMMSS.<拡張子>" として書き出し、そのパスを返す
+pub fn export_transcript(records: &[SessionRecord], format: TranscriptFormat) -> Result<PathBuf> {
+    let dir = transcript_dir();
+    std::fs::create_dir_all(&dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    let name = format!("session-{}.{}", chrono::Local::now().format("%Y%m%d-%H%M%S"), format.extension());
+    let path = dir.join(name);
+    export_session(records, &path)?;
+    Ok(path)
+}
+
+/// 字幕の表示区間（セッション開始からの時間）
+fn subtitle_span(record: &SessionRecord) -> (Duration, Duration) {
+    let end = record.hidden_at.unwrap_or(record.shown_at + SUBTITLE_FALLBACK_DURATION);
+    (record.shown_at, end.max(record.shown_at))
+}
+
+/// 字幕にする訳文の行（ルビを外し、空行は詰める。空行は SRT では字幕の区切りになるため）
+fn subtitle_lines(record: &SessionRecord) -> Vec<String> {
+    ruby::strip(&record.translation)
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// SRT の時刻（HH:MM:SS,mmm）
+fn srt_time(t: Duration) -> String {
+    let ms = t.as_millis();
+    format!("{:02}:{:02}:{:02},{:03}", ms / 3_600_000, ms / 60_000 % 60, ms / 1000 % 60, ms % 1000)
+}
+
+/// ASS の時刻（H:MM:SS.cc）
+fn ass_time(t: Duration) -> String {
+    let cs = t.as_millis() / 10;
+    format!("{}:{:02}:{:02}.{:02}", cs / 360_000, cs / 6000 % 60, cs / 100 % 60, cs % 100)
+}
+
+/// 用語集を CSV へ書き出す。書き出した件数を返す。
+pub fn export_glossary(entries: &[GlossaryEntry], path: &Path) -> Result<usize> {
+    let mut out = String::from(GLOSSARY_CSV_HEADER);
+    out.push_str("\r\n");
+    for e in entries {
+        let fields = [e.term.as_str(), &e.translation, e.mode.as_str()];
+        let line: Vec<String> = fields.iter().map(|f| csv_field(f)).collect();
+        out.push_str(&line.join(","));
+        out.push_str("\r\n");
+    }
+    std::fs::write(path, out)
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(entries.len())
+}
+
+/// CSV の用語集を読み込む（term,translation[,match]）。match が無い・不明なら単語単位で一致させる。
+pub fn import_glossary(path: &Path) -> Result<Vec<GlossaryEntry>> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let mut entries = Vec::new();
+    for record in parse_csv_records(&content) {
+        if record.first().is_none_or(|t| t.trim().is_empty()) || (record[0] == "term" && record.get(1).is_some_and(|f| f == "translation")) {
+            continue;
+        }
+        entries.push(GlossaryEntry {
+            term: record[0].clone(),
+            translation: record.get(1).cloned().unwrap_or_default(),
+            mode: record.get(2).and_then(|m| GlossaryMatch::from_name(m)).unwrap_or(GlossaryMatch::Word),
+        });
+    }
+    Ok(entries)
+}
+
+// === CSV ===
+
+fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+fn write_csv(entries: &[CacheEntry]) -> String {
+    let mut out = String::from(CSV_HEADER);
+    out.push_str("\r\n");
+    for e in entries {
+        let fields = [
+            e.engine.map_or("", |en| en.as_str()),
+            &e.source_lang,
+            &e.target_lang,
+            &e.text,
+            &e.translation,
+            &e.plugin,
+        ];
+        let line: Vec<String> = fields.iter().map(|f| csv_field(f)).collect();
+        out.push_str(&line.join(","));
+        out.push_str("\r\n");
+    }
+    out
+}
+
+/// RFC 4180 形式のCSVをレコード単位に分解（引用符内の改行・カンマに対応）
+fn parse_csv_records(content: &str) -> Vec<Vec<String>> {
+    let content = content.trim_start_matches('\u{feff}');
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = content.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if in_quotes {
+            if c == '"' {
+                if chars.peek() == Some(&'"') {
+                    field.push('"');
+                    chars.next();
+                } else {
+                    in_quotes = false;
+                }
+            } else {
+                field.push(c);
+            }
+            continue;
+        }
+        match c {
+            '"' => in_quotes = true,
+            ',' => record.push(std::mem::take(&mut field)),
+            '\r' => {}
+            '\n' => {
+                record.push(std::mem::take(&mut field));
+                records.push(std::mem::take(&mut record));
+            }
+            _ => field.push(c),
+        }
+    }
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+    records
+}
+
+fn read_csv(content: &str, default_scope: &CacheScope) -> Vec<CacheEntry> {
+    let mut entries = Vec::new();
+    for record in parse_csv_records(content) {
+        let entry = match record.len() {
+            // engine,source_lang,target_lang,text,translation[,plugin]
+            5.. => {
+                if record[0] == "engine" && record[3] == "text" {
+                    continue;
+                }
+                // 空欄は人手翻訳（シード）、未知の名前は既定のエンジン扱い
+                let engine = if record[0].trim().is_empty() {
+                    None
+                } else {
+                    TranslationEngine::from_name(&record[0]).or(default_scope.engine)
+                };
+                CacheEntry {
+                    engine,
+                    plugin: record.get(5).cloned().unwrap_or_default(),
+                    source_lang: record[1].clone(),
+                    target_lang: record[2].clone(),
+                    text: record[3].clone(),
+                    translation: record[4].clone(),
+                }
+            }
+            // text,translation（手編集用の簡易形式）
+            2..=4 => CacheEntry {
+                engine: default_scope.engine,
+                plugin: default_scope.plugin.clone(),
+                source_lang: default_scope.source_lang.clone(),
+                target_lang: default_scope.target_lang.clone(),
+                text: record[0].clone(),
+                translation: record[1].clone(),
+            },
+            _ => continue,
+        };
+        if !entry.text.trim().is_empty() && !entry.translation.trim().is_empty() {
+            entries.push(entry);
+        }
+    }
+    entries
+}
+
+// === TMX ===
+
+fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn xml_unescape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(amp) = rest.find('&') {
+        out.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+        let Some(semi) = rest.find(';') else { break };
+        let entity = &rest[1..semi];
+        let decoded = match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ => {
+                let code = if let Some(hex) = entity.strip_prefix("#x") {
+                    u32::from_str_radix(hex, 16).ok()
+                } else if let Some(dec) = entity.strip_prefix('#') {
+                    dec.parse::<u32>().ok()
+                } else {
+                    None
+                };
+                code.and_then(char::from_u32)
+            }
+        };
+        match decoded {
+            Some(c) => {
+                out.push(c);
+                rest = &rest[semi + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+fn write_tmx(entries: &[CacheEntry]) -> String {
+    // 原文の言語が混ざる時は "*all*"（各 <tu> の先頭の <tuv> が原文）
+    let srclang = match entries.first() {
+        Some(first) if entries.iter().all(|e| e.source_lang == first.source_lang) => first.source_lang.as_str(),
+        _ => "*all*",
+    };
+    let mut out = String::new();
+    out.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    out.push_str("<tmx version=\"1.4\">\n");
+    out.push_str(&format!(
+        "  <header creationtool=\"game_translator\" creationtoolversion=\"{}\" datatype=\"plaintext\" segtype=\"block\" adminlang=\"en\" srclang=\"{}\" o-tmf=\"game_translator\"/>\n",
+        env!("CARGO_PKG_VERSION"),
+        xml_escape(srclang)
+    ));
+    out.push_str("  <body>\n");
+    for e in entries {
+        out.push_str("    <tu>\n");
+        if let Some(engine) = e.engine {
+            out.push_str(&format!(
+                "      <prop type=\"x-engine\">{}</prop>\n",
+                engine.as_str()
+            ));
+        }
+        if !e.plugin.is_empty() {
+            out.push_str(&format!(
+                "      <prop type=\"x-plugin\">{}</prop>\n",
+                xml_escape(&e.plugin)
+            ));
+        }
+        out.push_str(&format!(
+            "      <tuv xml:lang=\"{}\"><seg>{}</seg></tuv>\n",
+            xml_escape(&e.source_lang),
+            xml_escape(&e.text)
+        ));
+        out.push_str(&format!(
+            "      <tuv xml:lang=\"{}\"><seg>{}</seg></tuv>\n",
+            xml_escape(&e.target_lang),
+            xml_escape(&e.translation)
+        ));
+        out.push_str("    </tu>\n");
+    }
+    out.push_str("  </body>\n</tmx>\n");
+    out
+}
+
+/// `<tag ...>` 開始タグ内の属性値を取得
+fn xml_attr(tag: &str, name: &str) -> Option<String> {
+    let pat = format!("{}=\"", name);
+    let start = tag.find(&pat)? + pat.len();
+    let end = tag[start..].find('"')? + start;
+    Some(xml_unescape(&tag[start..end]))
+}
+
+/// `open` で始まり `close` で終わる要素を順に切り出す（ネストは考慮しない）
+fn xml_elements<'a>(content: &'a str, open: &str, close: &str) -> Vec<&'a str> {
+    let mut out = Vec::new();
+    let mut rest = content;
+    while let Some(start) = rest.find(open) {
+        // "<tu" が "<tuv" にマッチしないよう、直後の文字を確認
+        let after = rest[start + open.len()..].chars().next();
+        if !matches!(after, Some('>') | Some(' ') | Some('\t') | Some('\r') | Some('\n') | Some('/')) {
+            rest = &rest[start + open.len()..];
+            continue;
+        }
+        // 自己終了タグ（`<target/>` 等）は空要素として扱う
+        let Some(gt) = rest[start..].find('>') else { break };
+        if rest[start..start + gt].ends_with('/') {
+            out.push(&rest[start..start + gt + 1]);
+            rest = &rest[start + gt + 1..];
+            continue;
+        }
+        let Some(end) = rest[start..].find(close) else { break };
+        out.push(&rest[start..start + end + close.len()]);
+        rest = &rest[start + end + close.len()..];
+    }
+    out
+}
+
+/// 要素の内側テキスト（開始タグの `>` から終了タグまで）
+fn xml_inner<'a>(element: &'a str, close: &str) -> &'a str {
+    let start = element.find('>').map_or(0, |i| i + 1);
+    let end = element.rfind(close).unwrap_or(element.len());
+    if start <= end { &element[start..end] } else { "" }
+}
+
+/// 中身が元文書の書式コード（`{1}` や `<b>` 等）で、訳文に含めないインライン要素
+const CODE_ELEMENTS: [&str; 5] = ["bpt", "ept", "ph", "it", "ut"];
+
+/// `<seg>` 内のインラインタグを除去してテキスト化する。
+/// `<bpt>` / `<ept>` / `<ph>` 等は中身ごと捨て、`<hi>` 等は中のテキストを残す。
+fn strip_inline_tags(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(lt) = rest.find('<') {
+        out.push_str(&rest[..lt]);
+        let Some(gt) = rest[lt..].find('>') else {
+            rest = "";
+            break;
+        };
+        let tag = &rest[lt + 1..lt + gt];
+        rest = &rest[lt + gt + 1..];
+        let name = tag.split(|c: char| c.is_whitespace() || c == '/').next().unwrap_or("");
+        if CODE_ELEMENTS.contains(&name) && !tag.ends_with('/') {
+            let close = format!("</{}>", name);
+            rest = rest.find(&close).map_or("", |end| &rest[end + close.len()..]);
+        }
+    }
+    out.push_str(rest);
+    xml_unescape(&out)
+}
+
+/// TMX の翻訳単位（`<tu>`）
+struct TmxUnit {
+    engine: Option<TranslationEngine>,
+    /// x-plugin のプロパティ（なければ空）
+    plugin: String,
+    /// (xml:lang, seg) の並び
+    variants: Vec<(Option<String>, String)>,
+}
+
+fn read_tmx_units(content: &str) -> Vec<TmxUnit> {
+    xml_elements(content, "<tu", "</tu>")
+        .into_iter()
+        .map(|tu| {
+            let props = xml_elements(tu, "<prop", "</prop>");
+            let prop = |kind: &str| {
+                props
+                    .iter()
+                    .find(|p| xml_attr(p, "type").as_deref() == Some(kind))
+                    .map(|p| xml_unescape(xml_inner(p, "</prop>")))
+            };
+            let engine = prop("x-engine").and_then(|name| TranslationEngine::from_name(&name));
+            let plugin = prop("x-plugin").unwrap_or_default();
+
+            let variants = xml_elements(tu, "<tuv", "</tuv>")
+                .into_iter()
+                .map(|tuv| {
+                    let open_end = tuv.find('>').unwrap_or(tuv.len());
+                    let lang = xml_attr(&tuv[..open_end], "xml:lang")
+                        .or_else(|| xml_attr(&tuv[..open_end], "lang"));
+                    let seg = xml_elements(tuv, "<seg", "</seg>")
+                        .first()
+                        .map(|s| strip_inline_tags(xml_inner(s, "</seg>")))
+                        .unwrap_or_default();
+                    (lang, seg)
+                })
+                .collect();
+            TmxUnit { engine, plugin, variants }
+        })
+        .collect()
+}
+
+/// ヘッダーの `srclang`（"*all*" や記載なしなら None）
+fn tmx_srclang(content: &str) -> Option<String> {
+    let header = xml_elements(content, "<header", "</header>").into_iter().next()?;
+    let open_end = header.find('>').unwrap_or(header.len());
+    xml_attr(&header[..open_end], "srclang").filter(|l| !l.trim().is_empty() && l != "*all*")
+}
+
+/// 翻訳単位ごとに、ヘッダーの `srclang` の言語を原文、それ以外の各言語を訳文とする
+/// （`srclang` がない・一致しない単位は先頭の `<tuv>` が原文）
+fn read_tmx(content: &str, default_scope: &CacheScope) -> Vec<CacheEntry> {
+    let srclang = tmx_srclang(content);
+    let mut entries = Vec::new();
+    for unit in read_tmx_units(content) {
+        if unit.variants.len() < 2 {
+            continue;
+        }
+        let source_index = srclang
+            .as_deref()
+            .and_then(|src| unit.variants.iter().position(|(l, _)| l.as_deref().is_some_and(|l| same_language(l, src))))
+            .unwrap_or(0);
+        let (src_lang, text) = &unit.variants[source_index];
+        if text.trim().is_empty() {
+            continue;
+        }
+        for (i, (tgt_lang, translation)) in unit.variants.iter().enumerate() {
+            if i == source_index || translation.trim().is_empty() {
+                continue;
+            }
+            entries.push(CacheEntry {
+                engine: unit.engine.or(default_scope.engine),
+                plugin: if unit.engine.is_some() { unit.plugin.clone() } else { default_scope.plugin.clone() },
+                source_lang: src_lang.clone().unwrap_or_else(|| default_scope.source_lang.clone()),
+                target_lang: tgt_lang.clone().unwrap_or_else(|| default_scope.target_lang.clone()),
+                text: text.clone(),
+                translation: translation.clone(),
+            });
+        }
+    }
+    entries
+}
+
+// === XLIFF ===
+
+/// XLIFF 1.2（`<trans-unit>`）と 2.x（`<segment>`）の原文・訳文を読み込む
+fn read_xliff(content: &str, default_scope: &CacheScope) -> Vec<CacheEntry> {
+    let mut units = xml_elements(content, "<trans-unit", "</trans-unit>");
+    if units.is_empty() {
+        units = xml_elements(content, "<segment", "</segment>");
+    }
+
+    let mut entries = Vec::new();
+    for unit in units {
+        let text = xml_elements(unit, "<source", "</source>")
+            .first()
+            .map(|s| strip_inline_tags(xml_inner(s, "</source>")))
+            .unwrap_or_default();
+        let translation = xml_elements(unit, "<target", "</target>")
+            .first()
+            .map(|s| strip_inline_tags(xml_inner(s, "</target>")))
+            .unwrap_or_default();
+        if text.trim().is_empty() || translation.trim().is_empty() {
+            continue;
+        }
+        entries.push(CacheEntry {
+            engine: default_scope.engine,
+            plugin: default_scope.plugin.clone(),
+            source_lang: default_scope.source_lang.clone(),
+            target_lang: default_scope.target_lang.clone(),
+            text,
+            translation,
+        });
+    }
+    entries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scope() -> CacheScope {
+        CacheScope::new(TranslationEngine::DeepL, "EN", "JA")
+    }
+
+    fn entry(engine: Option<TranslationEngine>, plugin: &str, langs: (&str, &str), text: &str, translation: &str) -> CacheEntry {
+        CacheEntry {
+            engine,
+            plugin: plugin.to_string(),
+            source_lang: langs.0.to_string(),
+            target_lang: langs.1.to_string(),
+            text: text.to_string(),
+            translation: translation.to_string(),
+        }
+    }
+
+    /// (engine, plugin, source_lang, target_lang, text, translation)
+    type Fields<'a> = (Option<TranslationEngine>, &'a str, &'a str, &'a str, &'a str, &'a str);
+
+    /// Every field of each entry, as CacheEntry has no PartialEq
+    fn fields(entries: &[CacheEntry]) -> Vec<Fields<'_>> {
+        entries
+            .iter()
+            .map(|e| (e.engine, e.plugin.as_str(), e.source_lang.as_str(), e.target_lang.as_str(), e.text.as_str(), e.translation.as_str()))
+            .collect()
+    }
+
+    fn sample_entries() -> Vec<CacheEntry> {
+        vec![
+            entry(Some(TranslationEngine::DeepL), "", ("EN", "JA"), "Hello, \"world\"\nSecond line", "こんにちは"),
+            entry(Some(TranslationEngine::LocalLLM), "", ("JA", "EN"), "<b>&</b>", "訳"),
+        ]
+    }
+
+    #[test]
+    fn csv_round_trip() {
+        let entries = sample_entries();
+        assert_eq!(fields(&read_csv(&write_csv(&entries), &scope())), fields(&entries));
+    }
+
+    #[test]
+    fn csv_engine_column() {
+        let content = "\u{feff}engine,source_lang,target_lang,text,translation\r\nunknown,EN,JA,a,b\r\n,EN,JA,c,d\r\nDeepL,EN,JA,e,\r\n";
+        assert_eq!(
+            fields(&read_csv(content, &scope())),
+            vec![
+                (Some(TranslationEngine::DeepL), "", "EN", "JA", "a", "b"),
+                (None, "", "EN", "JA", "c", "d"),
+            ]
+        );
+    }
+
+    #[test]
+    fn csv_two_columns_use_the_default_scope() {
+        assert_eq!(
+            fields(&read_csv("Hello,こんにちは\nBlank,\n", &scope())),
+            vec![(Some(TranslationEngine::DeepL), "", "EN", "JA", "Hello", "こんにちは")]
+        );
+    }
+
+    #[test]
+    fn tmx_round_trip() {
+        // Mixed source languages are written with srclang="*all*", so each unit's first variant is the source
+        let entries = sample_entries();
+        let tmx = write_tmx(&entries);
+        assert!(tmx.contains("srclang=\"*all*\""));
+        assert_eq!(fields(&read_tmx(&tmx, &scope())), fields(&entries));
+    }
+
+    #[test]
+    fn tmx_source_follows_srclang() {
+        let content = r#"<tmx version="1.4"><header srclang="ja"/><body>
+<tu><prop type="x-engine">Groq</prop>
+<tuv xml:lang="en"><seg>Press <bpt i="1">{b}</bpt>A<ept i="1">{/b}</ept> &amp; <hi>go</hi></seg></tuv>
+<tuv xml:lang="ja"><seg>進む</seg></tuv>
+<tuv xml:lang="fr"><seg>Appuyez</seg></tuv>
+</tu>
+<tu><tuv xml:lang="ja"><seg>only one</seg></tuv></tu>
+</body></tmx>"#;
+        assert_eq!(
+            fields(&read_tmx(content, &scope())),
+            vec![
+                (Some(TranslationEngine::Groq), "", "ja", "en", "進む", "Press A & go"),
+                (Some(TranslationEngine::Groq), "", "ja", "fr", "進む", "Appuyez"),
+            ]
+        );
+    }
+
+    #[test]
+    fn language_pair_selection() {
+        let variants = vec![
+            (Some("en-US".to_string()), "Hi".to_string()),
+            (Some("ja".to_string()), "やあ".to_string()),
+            (Some("fr".to_string()), "Salut".to_string()),
+        ];
+        let pair = |src: &str, tgt: &str| pick_language_pair(&variants, src, tgt);
+        assert_eq!(pair("EN", "JA"), Some(("Hi".to_string(), "やあ".to_string())));
+        assert_eq!(pair("AUTO", "FR"), Some(("Hi".to_string(), "Salut".to_string())));
+        assert_eq!(pair("DE", "JA"), None);
+
+        let untagged = vec![(None, "Hi".to_string()), (None, "やあ".to_string())];
+        assert_eq!(pick_language_pair(&untagged, "DE", "FR"), Some(("Hi".to_string(), "やあ".to_string())));
+    }
+
+    #[test]
+    fn plugin_name_round_trip() {
+        let entries = vec![
+            entry(Some(TranslationEngine::Plugin), "my-plugin", ("EN", "JA"), "Bye", "さようなら"),
+            entry(Some(TranslationEngine::Plugin), "other", ("EN", "JA"), "Bye", "またね"),
+        ];
+        assert_eq!(fields(&read_csv(&write_csv(&entries), &scope())), fields(&entries));
+        assert_eq!(fields(&read_tmx(&write_tmx(&entries), &scope())), fields(&entries));
+    }
+
+    #[test]
+    fn subtitle_times() {
+        let t = Duration::from_millis(3_723_045);
+        assert_eq!(srt_time(t), "01:02:03,045");
+        assert_eq!(ass_time(t), "1:02:03.04");
+        assert_eq!(srt_time(Duration::ZERO), "00:00:00,000");
+    }
+}
@@ -0,0 +1,108 @@
+//! コマンドライン引数。ランチャー（Playnite・Steam のショートカットなど）から
+//! ゲームと一緒に起動し、そのまま翻訳を始められるようにする。
+
+use regex::Regex;
+use std::path::PathBuf;
+
+use crate::config;
+use crate::startup::MINIMIZED_ARG;
+
+const USAGE: &str =
+    "game_translator [--window-title <regex>] [--profile <name>] [--config <path>] [--autostart] [--minimized]";
+
+#[derive(Default)]
+pub struct CliArgs {
+    /// 対象ウィンドウのタイトル（正規表現）
+    pub window_title: Option<Regex>,
+    /// 読み書きする設定ファイル（--config、または --profile の保存先）
+    pub config_path: Option<PathBuf>,
+    /// 対象ウィンドウが見つかったら翻訳を開始する（--window-title がなければ前回のウィンドウ）
+    pub autostart: bool,
+    /// ウィンドウを隠して通知領域のアイコンだけで起動する
+    pub minimized: bool,
+}
+
+/// `--profile <名前>` の設定ファイル（保存先フォルダの profiles 以下）
+pub fn profile_path(name: &str) -> PathBuf {
+    config::data_dir().join("profiles").join(format!("{}.toml", name))
+}
+
+/// profiles フォルダにある設定ファイルの名前（拡張子なし、名前順）
+pub fn list_profiles() -> Vec<String> {
+    let Ok(dir) = std::fs::read_dir(config::data_dir().join("profiles")) else {
+        return Vec::new();
+    };
+    let mut names: Vec<String> = dir
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.extension().is_some_and(|ext| ext == "toml"))
+        .filter_map(|p| p.file_stem().map(|s| s.to_string_lossy().into_owned()))
+        .collect();
+    names.sort();
+    names
+}
+
+/// 引数を解析する（プログラム名は含めない）。不明な引数・値の欠けた引数はエラー
+/// （ログと GUI に出すだけなので英語の固定文）。
+pub fn parse(args: impl IntoIterator<Item = String>) -> Result<CliArgs, String> {
+    let mut parsed = CliArgs::default();
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| args.next().ok_or_else(|| format!("{} needs a value", name));
+        match arg.as_str() {
+            "--window-title" => {
+                let pattern = value("--window-title")?;
+                let regex = Regex::new(&pattern).map_err(|e| format!("Invalid --window-title regex: {}", e))?;
+                parsed.window_title = Some(regex);
+            }
+            "--profile" => {
+                let name = value("--profile")?;
+                if name.contains(['/', '\\']) {
+                    return Err(format!("Profile names can't contain path separators: {}", name));
+                }
+                parsed.config_path = Some(profile_path(&name));
+            }
+            "--config" => parsed.config_path = Some(PathBuf::from(value("--config")?)),
+            "--autostart" => parsed.autostart = true,
+            a if a == MINIMIZED_ARG => parsed.minimized = true,
+            _ => return Err(format!("Unknown argument {} (usage: {})", arg, USAGE)),
+        }
+    }
+    Ok(parsed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_args(args: &[&str]) -> Result<CliArgs, String> {
+        parse(args.iter().map(|a| a.to_string()))
+    }
+
+    #[test]
+    fn parses_flags_and_values() {
+        let Ok(args) = parse_args(&["--window-title", "^Game.*$", "--autostart", MINIMIZED_ARG, "--config", "game.toml"]) else {
+            panic!("arguments should parse");
+        };
+        assert!(args.window_title.is_some_and(|r| r.is_match("Game Window")));
+        assert!(args.autostart && args.minimized);
+        assert_eq!(args.config_path, Some(PathBuf::from("game.toml")));
+    }
+
+    #[test]
+    fn no_arguments_is_the_default() {
+        let Ok(args) = parse_args(&[]) else {
+            panic!("no arguments should parse");
+        };
+        assert!(args.window_title.is_none() && args.config_path.is_none() && !args.autostart && !args.minimized);
+    }
+
+    #[test]
+    fn rejects_bad_arguments() {
+        let error = |args: &[&str]| parse_args(args).err().unwrap_or_default();
+        assert_eq!(error(&["--window-title"]), "--window-title needs a value");
+        assert!(error(&["--window-title", "("]).starts_with("Invalid --window-title regex"));
+        assert!(error(&["--profile", "../other"]).starts_with("Profile names can't contain path separators"));
+        assert!(error(&["--bogus"]).starts_with("Unknown argument --bogus"));
+    }
+}
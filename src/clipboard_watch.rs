@@ -0,0 +1,118 @@
+//! クリップボード監視の翻訳
+//!
+//! コピーされた文を翻訳して小さなポップアップに出す（テキストをコピーできるゲームや、
+//! ランチャー・チャットの文を OCR なしで訳す用）。翻訳のセッションとは別に動き、対象ウィンドウはいらない。
+//! キャッシュは開始時に読むだけで、新しい訳はファイルに書かない（セッションの保存と競合しないように）。
+
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+
+use anyhow::Result;
+use eframe::egui;
+use tokio::sync::{mpsc, oneshot};
+
+use crate::cache::{CacheScope, TranslationCache};
+use crate::config::AppConfig;
+use crate::i18n::tr;
+use crate::translate::Translator;
+use crate::{ruby, texthook};
+
+/// 翻訳した1件
+pub struct ClipboardTranslation {
+    pub source: String,
+    /// 訳文（ルビは外す）、または失敗の理由
+    pub result: Result<String, String>,
+}
+
+/// 監視に使っている翻訳の設定（翻訳器の設定と言語ペア）。変わったら作り直す
+pub type WatchKey = (String, String, String);
+
+pub fn watch_key(config: &AppConfig) -> WatchKey {
+    (
+        Translator::settings_key(config),
+        config.source_lang.clone(),
+        config.target_lang.clone(),
+    )
+}
+
+/// 監視中のスレッド。drop で止める
+pub struct ClipboardWatch {
+    key: WatchKey,
+    shutdown: Option<oneshot::Sender<()>>,
+    handle: Option<JoinHandle<()>>,
+    latest: Arc<Mutex<Option<ClipboardTranslation>>>,
+}
+
+impl ClipboardWatch {
+    pub fn start(config: &AppConfig, ctx: egui::Context) -> Result<Self> {
+        let translator = Translator::from_config(config)?;
+        let scope = CacheScope::from_config(config);
+        let (source_lang, target_lang) = (config.source_lang.clone(), config.target_lang.clone());
+        let latest = Arc::new(Mutex::new(None));
+        let thread_latest = latest.clone();
+        let (shutdown, shutdown_rx) = oneshot::channel();
+        let handle = std::thread::spawn(move || {
+            let rt = match tokio::runtime::Builder::new_current_thread().enable_all().build() {
+                Ok(rt) => rt,
+                Err(e) => {
+                    crate::log_always(&format!("[CLIPBOARD] 監視を開始できません: {}", e));
+                    return;
+                }
+            };
+            let mut cache = TranslationCache::load(&scope);
+            let (lines_tx, mut lines_rx) = mpsc::channel::<String>(4);
+            let translate = async {
+                while let Some(text) = lines_rx.recv().await {
+                    let result = match cache.get(&scope, &text) {
+                        Some(translation) => Ok(translation.clone()),
+                        None => match translator.translate_batch(vec![text.clone()], &source_lang, &target_lang).await {
+                            Ok(mut translations) => match translations.pop().flatten() {
+                                Some(translation) => {
+                                    cache.insert(&scope, text.clone(), translation.clone());
+                                    Ok(translation)
+                                }
+                                None => Err(tr("翻訳できませんでした").to_string()),
+                            },
+                            Err(e) => Err(format!("{:#}", e)),
+                        },
+                    };
+                    *thread_latest.lock().unwrap() = Some(ClipboardTranslation {
+                        source: text,
+                        result: result.map(|t| ruby::strip(&t)),
+                    });
+                    ctx.request_repaint();
+                }
+            };
+            rt.block_on(async {
+                tokio::select! {
+                    _ = shutdown_rx => {}
+                    _ = texthook::watch_clipboard(lines_tx) => {}
+                    _ = translate => {}
+                }
+            });
+        });
+        crate::log_always("[CLIPBOARD] 監視を開始");
+        Ok(Self { key: watch_key(config), shutdown: Some(shutdown), handle: Some(handle), latest })
+    }
+
+    pub fn key(&self) -> &WatchKey {
+        &self.key
+    }
+
+    /// 前回から新しく翻訳した文があれば取り出す
+    pub fn take(&self) -> Option<ClipboardTranslation> {
+        self.latest.lock().unwrap().take()
+    }
+}
+
+impl Drop for ClipboardWatch {
+    fn drop(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+        crate::log_always("[CLIPBOARD] 監視を停止");
+    }
+}
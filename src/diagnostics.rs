@@ -0,0 +1,258 @@
+//! クラッシュレポートと診断情報の ZIP（不具合報告用）
+//!
+//! パニックと構造化例外（SEH）を捕まえて、メッセージ・ログの末尾・秘密情報を除いた設定・
+//! OS / GPU の情報を `crash_reports` フォルダに書き出す（構造化例外は例外コードだけ記録し、
+//! レポートは次の起動時に作る）。次の起動時に GUI がそれを知らせ、
+//! ログ・クラッシュレポート・設定・システム情報を1つの ZIP にまとめられるようにする。
+
+use anyhow::{Context, Result};
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use windows::core::{w, PCWSTR};
+use windows::Win32::Graphics::Dxgi::{CreateDXGIFactory1, IDXGIFactory1};
+use windows::Win32::System::Diagnostics::Debug::{SetUnhandledExceptionFilter, EXCEPTION_POINTERS};
+use windows::Win32::System::Registry::*;
+
+use crate::config::{self, AppConfig};
+
+const CRASH_DIR: &str = "crash_reports";
+/// 次の起動時にまだ知らせていないレポートのファイル名
+const UNSEEN_MARKER: &str = "unseen.txt";
+/// 構造化例外の記録先（起動時に開いておき、例外時は書き込むだけにする）
+const EXCEPTION_RECORD: &str = "exception.txt";
+/// クラッシュレポートに入れるログの行数
+const LOG_TAIL_LINES: usize = 200;
+/// ZIP に入れる新しい順のログ・クラッシュレポートの数
+const BUNDLE_LOGS: usize = 3;
+const BUNDLE_CRASH_REPORTS: usize = 5;
+/// 例外を既定の処理（Windows のエラー報告）に回す
+const EXCEPTION_CONTINUE_SEARCH: i32 = 0;
+
+pub fn crash_dir() -> PathBuf {
+    config::data_dir().join(CRASH_DIR)
+}
+
+/// 起動時に集めたシステム情報（クラッシュ時にレジストリ・DXGI を触らない）
+static SYSTEM_INFO: OnceLock<String> = OnceLock::new();
+/// 構造化例外の記録先
+static EXCEPTION_FILE: OnceLock<File> = OnceLock::new();
+
+/// パニックフックと SEH のフィルターを入れる（起動直後に1回）
+pub fn install_crash_handler() {
+    SYSTEM_INFO.get_or_init(system_info);
+    // 前回の実行が構造化例外で落ちていれば、その記録からレポートを作る
+    let record_path = crash_dir().join(EXCEPTION_RECORD);
+    if let Ok(record) = std::fs::read_to_string(&record_path) {
+        if !record.trim().is_empty() {
+            // この時点では今回のログはまだないので、いちばん新しいログが前回の実行のもの
+            let log = newest(config::data_dir(), "debug_", ".log", 1).pop().unwrap_or_default();
+            write_crash_report(&format!("前回の実行: {}", record.trim()), &log, true);
+        }
+    }
+    if std::fs::create_dir_all(crash_dir()).is_ok() {
+        if let Ok(file) = File::create(&record_path) {
+            let _ = EXCEPTION_FILE.set(file);
+        }
+    }
+
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let thread = std::thread::current();
+        let summary = format!(
+            "パニック（スレッド: {}）\n{}\n\n{}",
+            thread.name().unwrap_or("<unnamed>"),
+            info,
+            std::backtrace::Backtrace::force_capture()
+        );
+        // 他のスレッドのパニックは処理ごとに拾われて続行できるので、次の起動では知らせない
+        write_crash_report(&summary, game_translator_core::debug_log_path(), thread.name() == Some("main"));
+        default_hook(info);
+    }));
+    unsafe {
+        SetUnhandledExceptionFilter(Some(unhandled_exception));
+    }
+}
+
+/// 壊れたプロセスの中で動くので、開いてあるファイルへ例外コードを書くだけにする
+/// （レポートは次の起動時に `install_crash_handler` が作る）
+unsafe extern "system" fn unhandled_exception(info: *const EXCEPTION_POINTERS) -> i32 {
+    if let Some(mut file) = EXCEPTION_FILE.get() {
+        let _ = match info.as_ref().and_then(|info| info.ExceptionRecord.as_ref()) {
+            Some(record) => writeln!(
+                file,
+                "構造化例外 0x{:08X}（アドレス {:?}）",
+                record.ExceptionCode.0 as u32, record.ExceptionAddress
+            ),
+            None => writeln!(file, "構造化例外（詳細不明）"),
+        };
+        let _ = file.sync_all();
+    }
+    EXCEPTION_CONTINUE_SEARCH
+}
+
+/// クラッシュレポートを書く。`notify` なら次の起動で知らせるよう印を付ける
+fn write_crash_report(summary: &str, log: &Path, notify: bool) {
+    crate::log_always(&format!("[CRASH] {}", summary.lines().next().unwrap_or_default()));
+    let config = AppConfig::load_from(&AppConfig::config_path())
+        .and_then(|c| Ok(toml::to_string_pretty(&c.without_secrets())?))
+        .unwrap_or_else(|e| format!("(読めません: {:#})", e));
+    let report = format!(
+        "# クラッシュレポート {}\n\n{}\n\n## システム\n{}\n## 設定（APIキー等を除く）\n{}\n## ログの末尾\n{}",
+        chrono::Local::now().format("%Y-%m-%d %H:%M:%S"),
+        summary,
+        SYSTEM_INFO.get_or_init(system_info),
+        config,
+        log_tail(log, LOG_TAIL_LINES)
+    );
+    let dir = crash_dir();
+    let name = chrono::Local::now().format("crash_%Y%m%d-%H%M%S.txt").to_string();
+    if std::fs::create_dir_all(&dir).is_ok() && std::fs::write(dir.join(&name), report).is_ok() && notify {
+        let _ = std::fs::write(dir.join(UNSEEN_MARKER), &name);
+    }
+}
+
+/// 前回の実行がクラッシュしていれば、そのレポート（1回だけ返す）
+pub fn take_unseen_crash() -> Option<PathBuf> {
+    let marker = crash_dir().join(UNSEEN_MARKER);
+    let name = std::fs::read_to_string(&marker).ok()?;
+    let _ = std::fs::remove_file(&marker);
+    Some(crash_dir().join(name.trim())).filter(|path| path.exists())
+}
+
+/// ログ・クラッシュレポート・設定（APIキー等を除く）・システム情報をデータフォルダの ZIP にまとめる
+pub fn write_bundle(config: &AppConfig) -> Result<PathBuf> {
+    let path = config::data_dir().join(chrono::Local::now().format("diagnostics_%Y%m%d-%H%M%S.zip").to_string());
+    let file = std::fs::File::create(&path).with_context(|| format!("Failed to create {}", path.display()))?;
+    let mut zip = zip::ZipWriter::new(file);
+    let options = zip::write::SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+
+    zip.start_file("system.txt", options)?;
+    zip.write_all(system_info().as_bytes())?;
+    zip.start_file("config.toml", options)?;
+    zip.write_all(toml::to_string_pretty(&config.without_secrets())?.as_bytes())?;
+    let files = newest(config::data_dir(), "debug_", ".log", BUNDLE_LOGS)
+        .into_iter()
+        .map(|p| ("logs", p))
+        .chain(newest(&crash_dir(), "crash_", ".txt", BUNDLE_CRASH_REPORTS).into_iter().map(|p| (CRASH_DIR, p)));
+    for (folder, file) in files {
+        let Ok(content) = std::fs::read(&file) else {
+            continue;
+        };
+        let name = file.file_name().unwrap_or_default().to_string_lossy();
+        zip.start_file(format!("{}/{}", folder, name), options)?;
+        zip.write_all(&content)?;
+    }
+    zip.finish()?;
+    crate::log_always(&format!("[DIAG] 診断情報を書き出し: {}", path.display()));
+    Ok(path)
+}
+
+/// `dir` 直下の `prefix*suffix` のファイルを新しい順に `count` 個（名前に日時が入っている前提）
+fn newest(dir: &Path, prefix: &str, suffix: &str, count: usize) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut files: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| {
+            path.file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|n| n.starts_with(prefix) && n.ends_with(suffix))
+        })
+        .collect();
+    files.sort();
+    files.into_iter().rev().take(count).collect()
+}
+
+fn log_tail(path: &Path, lines: usize) -> String {
+    let Ok(log) = std::fs::read_to_string(path) else {
+        return "(ログなし)\n".to_string();
+    };
+    let all: Vec<&str> = log.lines().collect();
+    all[all.len().saturating_sub(lines)..].iter().map(|line| format!("{}\n", line)).collect()
+}
+
+/// バージョン・OS・GPU
+fn system_info() -> String {
+    const CURRENT_VERSION: PCWSTR = w!("SOFTWARE\\Microsoft\\Windows NT\\CurrentVersion");
+    let mut info = format!("Game Translator {}\n", env!("CARGO_PKG_VERSION"));
+    info.push_str(&format!(
+        "OS: {} {} (build {}.{}, {})\n",
+        registry_string(CURRENT_VERSION, w!("ProductName")).unwrap_or_default(),
+        registry_string(CURRENT_VERSION, w!("DisplayVersion")).unwrap_or_default(),
+        registry_string(CURRENT_VERSION, w!("CurrentBuild")).unwrap_or_default(),
+        registry_dword(CURRENT_VERSION, w!("UBR")).unwrap_or_default(),
+        std::env::consts::ARCH
+    ));
+    match gpus() {
+        Ok(gpus) => {
+            for gpu in gpus {
+                info.push_str(&format!("GPU: {}\n", gpu));
+            }
+        }
+        Err(e) => info.push_str(&format!("GPU: (取得できません: {:#})\n", e)),
+    }
+    info.push_str(&format!("データフォルダ: {}\n", config::data_dir().display()));
+    info
+}
+
+fn registry_string(key: PCWSTR, value: PCWSTR) -> Option<String> {
+    let mut buffer = [0u16; 256];
+    let mut size = std::mem::size_of_val(&buffer) as u32;
+    unsafe {
+        RegGetValueW(
+            HKEY_LOCAL_MACHINE,
+            key,
+            value,
+            RRF_RT_REG_SZ,
+            None,
+            Some(buffer.as_mut_ptr().cast()),
+            Some(&mut size),
+        )
+    }
+    .ok()
+    .ok()?;
+    let len = buffer.iter().position(|&c| c == 0).unwrap_or(buffer.len());
+    Some(String::from_utf16_lossy(&buffer[..len]))
+}
+
+fn registry_dword(key: PCWSTR, value: PCWSTR) -> Option<u32> {
+    let mut data = 0u32;
+    let mut size = std::mem::size_of::<u32>() as u32;
+    unsafe {
+        RegGetValueW(
+            HKEY_LOCAL_MACHINE,
+            key,
+            value,
+            RRF_RT_REG_DWORD,
+            None,
+            Some((&mut data as *mut u32).cast()),
+            Some(&mut size),
+        )
+    }
+    .ok()
+    .ok()?;
+    Some(data)
+}
+
+/// アダプターごとに「名前 (ベンダー ID, 専用メモリ)」
+fn gpus() -> Result<Vec<String>> {
+    let factory: IDXGIFactory1 = unsafe { CreateDXGIFactory1()? };
+    let mut gpus = Vec::new();
+    let mut index = 0;
+    while let Ok(adapter) = unsafe { factory.EnumAdapters1(index) } {
+        let desc = unsafe { adapter.GetDesc1()? };
+        let len = desc.Description.iter().position(|&c| c == 0).unwrap_or(desc.Description.len());
+        gpus.push(format!(
+            "{} (vendor 0x{:04X}, {} MB)",
+            String::from_utf16_lossy(&desc.Description[..len]),
+            desc.VendorId,
+            desc.DedicatedVideoMemory / (1024 * 1024)
+        ));
+        index += 1;
+    }
+    Ok(gpus)
+}
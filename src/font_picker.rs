@@ -0,0 +1,104 @@
+//! Font picker for the overlay: searchable list of installed fonts with a sample
+//! rendered by DirectWrite in the chosen font, size and colors.
+
+use eframe::egui::{self, Color32};
+
+use crate::config::AppConfig;
+use crate::i18n::tr;
+use crate::overlay::{self, OverlayConfig};
+
+const PREVIEW_WIDTH: u32 = 480;
+const PREVIEW_HEIGHT: u32 = 120;
+
+pub struct FontPicker {
+    search: String,
+    sample: String,
+    /// Size the sample is drawn at; the overlay itself sizes text from the OCR line height
+    font_size: f32,
+    texture: Option<egui::TextureHandle>,
+    /// Inputs the texture was rendered from, so it is only redrawn when they change
+    rendered: Option<(OverlayConfig, String, f32)>,
+    error: Option<String>,
+    /// Set up when the picker opens; rendering reuses its factories
+    renderer: Result<overlay::FontPreviewRenderer, String>,
+}
+
+impl FontPicker {
+    pub fn open() -> Self {
+        Self {
+            search: String::new(),
+            sample: tr("翻訳の見本 Translation sample 123").to_string(),
+            font_size: 24.0,
+            texture: None,
+            rendered: None,
+            error: None,
+            renderer: overlay::FontPreviewRenderer::new().map_err(|e| format!("{:#}", e)),
+        }
+    }
+
+    /// Show the picker, editing the overlay font and colors in `config`. Returns false once it is closed.
+    pub fn show(&mut self, ctx: &egui::Context, config: &mut AppConfig, families: &[String]) -> bool {
+        let mut open = true;
+        egui::Window::new(tr("フォントの選択"))
+            .open(&mut open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label(tr("検索:"));
+                    ui.add(egui::TextEdit::singleline(&mut self.search).desired_width(200.0));
+                    ui.label(tr("選択中:"));
+                    ui.strong(&config.overlay_font_family);
+                });
+                let query = self.search.trim().to_lowercase();
+                egui::ScrollArea::vertical().max_height(200.0).show(ui, |ui| {
+                    ui.set_min_width(PREVIEW_WIDTH as f32);
+                    for family in families.iter().filter(|f| query.is_empty() || f.to_lowercase().contains(&query)) {
+                        ui.selectable_value(&mut config.overlay_font_family, family.clone(), family);
+                    }
+                });
+                ui.separator();
+
+                ui.horizontal(|ui| {
+                    ui.label(tr("見本:"));
+                    ui.add(egui::TextEdit::singleline(&mut self.sample).desired_width(PREVIEW_WIDTH as f32 - 60.0));
+                });
+                ui.horizontal(|ui| {
+                    ui.label(tr("大きさ:"));
+                    ui.add(egui::Slider::new(&mut self.font_size, 10.0..=72.0).suffix(" pt"));
+                    ui.label(tr("テキスト色:"));
+                    ui.color_edit_button_rgba_unmultiplied(&mut config.overlay_text_color);
+                    ui.label(tr("背景色:"));
+                    ui.color_edit_button_rgba_unmultiplied(&mut config.overlay_bg_color);
+                });
+
+                self.update_preview(ctx, OverlayConfig::from_config(config));
+                if let Some(err) = &self.error {
+                    ui.colored_label(Color32::RED, err);
+                }
+                if let Some(texture) = &self.texture {
+                    ui.image((texture.id(), texture.size_vec2()));
+                }
+            });
+        open
+    }
+
+    fn update_preview(&mut self, ctx: &egui::Context, config: OverlayConfig) {
+        let key = (config, self.sample.clone(), self.font_size);
+        if self.rendered.as_ref() == Some(&key) {
+            return;
+        }
+        let rendered = match &self.renderer {
+            Ok(renderer) => renderer.render(&key.0, &key.1, key.2, PREVIEW_WIDTH, PREVIEW_HEIGHT).map_err(|e| format!("{:#}", e)),
+            Err(e) => Err(e.clone()),
+        };
+        match rendered {
+            Ok(rgba) => {
+                let image = egui::ColorImage::from_rgba_unmultiplied([PREVIEW_WIDTH as usize, PREVIEW_HEIGHT as usize], &rgba);
+                self.texture = Some(ctx.load_texture("font_preview", image, egui::TextureOptions::LINEAR));
+                self.error = None;
+            }
+            Err(e) => self.error = Some(e),
+        }
+        self.rendered = Some(key);
+    }
+}
@@ -1,12 +1,100 @@
 use eframe::egui;
-use std::sync::atomic::{AtomicBool, Ordering};
+use raw_window_handle::{HasWindowHandle, RawWindowHandle};
+use regex::Regex;
+use std::collections::BTreeSet;
+use std::sync::atomic::{AtomicBool, AtomicIsize, Ordering};
 use std::sync::{Arc, Mutex};
 use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
 
-use crate::capture::list_windows;
-use crate::config::{AppConfig, TranslationEngine};
-use crate::overlay::OverlayConfig;
-use crate::translate::Translator;
+use crate::cache::{CacheEntry, CacheScope, TranslationCache};
+use crate::benchmark::{self, BenchmarkProgress, BenchmarkSource};
+use crate::cache_io;
+use crate::capture::{self, list_windows, WindowCapture};
+use crate::cli::{self, CliArgs};
+use crate::diagnostics;
+use crate::dictionary::DictionaryKind;
+use crate::lookup::{DictionaryLoader, DictionaryState};
+use crate::clipboard_watch::{self, ClipboardTranslation, ClipboardWatch, WatchKey};
+use crate::config::{
+    AppConfig, ConfigLoadError, CustomHeader, DisplayMode, EchoStyle, GlossaryEntry, GlossaryMatch, InputSource, LlmPreset, LocalLlmApi, Placement, ReadoutMode, SourceReading, StylePreset, StyleZone, TextAlign,
+    TranscriptFormat, TranslationEngine, VerticalAnchor, VerticalText,
+};
+use crate::hotkey::{self, HotkeyAction, HotkeyListener};
+use crate::i18n::{self, tr, trf, UiLanguage};
+use crate::lang;
+use crate::notify::Notifier;
+use crate::ocr::{self, OCREngine};
+use crate::overlay::{LookupRequest, OcrPreview, OverlayConfig, OverlayControls, SessionRecord, FONT_SCALE_RANGE};
+use crate::pretranslate::{self, PretranslateProgress};
+use crate::font_picker::FontPicker;
+use crate::region_editor::RegionEditor;
+use crate::screenshot_batch::{self, BatchProgress};
+use crate::snip::{SnipJob, SnipOutcome, SnipSelection};
+use crate::startup;
+use crate::feed::FeedServer;
+use crate::plugin::{self, PluginManifest};
+use crate::translate::{DeepLUsage, Translator};
+use crate::window_picker::WindowPicker;
+use windows::Win32::Foundation::HWND;
+use windows::Win32::System::Com::{CoInitializeEx, CoUninitialize, COINIT_MULTITHREADED};
+
+/// How often the window list is scanned for auto-start games
+const AUTO_START_POLL: Duration = Duration::from_secs(2);
+/// How long closing the window waits for running sessions before exiting anyway
+/// (a translation request in flight only notices the stop signal once it returns)
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// How often the DeepL quota is re-read while translating
+const DEEPL_USAGE_REFRESH: Duration = Duration::from_secs(60);
+
+/// How often the GUI checks for a Ctrl+click on the overlay while interactive mode is on
+const LOOKUP_POLL: Duration = Duration::from_millis(200);
+
+/// How long a clipboard watcher that failed to start waits before trying the same settings again
+const CLIPBOARD_WATCH_RETRY: Duration = Duration::from_secs(5);
+
+/// Model ids fetched from a server, tagged with the engine they were fetched for
+type FetchedModels = (TranslationEngine, Result<Vec<String>, String>);
+/// DeepL usage, tagged with the API key it was fetched for and when
+type FetchedUsage = (String, Instant, Result<DeepLUsage, String>);
+/// Port probe result, tagged with the endpoint it was run for
+type EndpointProbe = (String, Result<String, String>);
+
+/// Connection test stage name (an i18n key) and its outcome
+type TestStage = (&'static str, Result<String, String>);
+
+/// Sample lines sent with the edited prompt, each with its translation (None = missing from the reply)
+type PromptTestResult = Result<Vec<(String, Option<String>)>, String>;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Tab {
+    Settings,
+    /// Lines translated this session
+    History,
+}
+
+/// A pipeline running next to the main one (e.g. a chat window beside the game), with its
+/// own settings snapshot and overlay. The GUI's live controls only act on the main session.
+struct ExtraSession {
+    hwnd_raw: isize,
+    title: String,
+    /// Profile it was started with (None = the settings at that time)
+    profile: Option<String>,
+    stop_signal: Arc<AtomicBool>,
+    overlay_hwnd_raw: Arc<AtomicIsize>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl ExtraSession {
+    fn stop(&self) {
+        signal_stop(&self.stop_signal, &self.overlay_hwnd_raw);
+    }
+
+    fn stopping(&self) -> bool {
+        self.stop_signal.load(Ordering::SeqCst)
+    }
+}
 
 /// Status message displayed in the GUI
 #[derive(Clone)]
@@ -29,14 +117,145 @@ pub struct GameTranslatorApp {
     overlay_thread: Option<JoinHandle<()>>,
     /// Overlay HWND for sending WM_DESTROY
     overlay_hwnd_raw: Arc<std::sync::atomic::AtomicIsize>,
-    /// API test result (None = not tested / in progress, Some = result message)
-    api_test_result: Arc<Mutex<Option<String>>>,
+    /// Connection test results so far, one per stage (API, OCR, capture)
+    api_test_result: Arc<Mutex<Vec<TestStage>>>,
     api_testing: Arc<AtomicBool>,
+    /// Benchmark: iterations to run, image to read instead of the selected window (empty = window)
+    benchmark_iterations: usize,
+    benchmark_image: String,
+    benchmark_progress: Arc<Mutex<BenchmarkProgress>>,
+    benchmark_running: Arc<AtomicBool>,
+    benchmark_cancel: Arc<AtomicBool>,
+    model_list: Arc<Mutex<Option<FetchedModels>>>,
+    model_list_fetching: Arc<AtomicBool>,
+    endpoint_probe: Arc<Mutex<Option<EndpointProbe>>>,
+    endpoint_probing: Arc<AtomicBool>,
+    deepl_usage: Arc<Mutex<Option<FetchedUsage>>>,
+    deepl_usage_fetching: Arc<AtomicBool>,
+    /// The key is being typed; partial keys are not sent to the usage endpoint
+    deepl_key_editing: bool,
+    /// The low-quota notification was shown (cleared once the quota is above the threshold)
+    deepl_quota_warned: bool,
+    /// CSV file for glossary import / export
+    glossary_io_path: String,
+    glossary_io_result: Option<Result<String, String>>,
+    /// Lines (one per line) used for the prompt preview and test
+    prompt_sample: String,
+    /// Rendered preview request, keyed on the settings and sample it was built from
+    prompt_preview: Option<(String, Result<String, String>)>,
+    prompt_test_result: Arc<Mutex<Option<PromptTestResult>>>,
+    prompt_testing: Arc<AtomicBool>,
     debug_log: bool,
+    /// Path for cache export/import (.csv / .tmx)
+    cache_io_path: String,
+    /// Result of the last cache export/import
+    cache_io_result: Option<Result<String, String>>,
+    /// Path for sharing the whole configuration
+    settings_io_path: String,
+    /// Also write API keys, the proxy password and custom header values
+    settings_include_secrets: bool,
+    settings_io_result: Option<Result<String, String>>,
+    /// Crash report left by the previous run, until the banner is dismissed
+    crash_report: Option<std::path::PathBuf>,
+    diagnostics_result: Option<Result<String, String>>,
+    /// Text file of game strings for pre-translation (one per line)
+    pretranslate_path: String,
+    pretranslate_progress: Arc<Mutex<PretranslateProgress>>,
+    pretranslate_running: Arc<AtomicBool>,
+    pretranslate_cancel: Arc<AtomicBool>,
+    /// Folder of screenshots to OCR and translate in one go
+    batch_dir: String,
+    batch_progress: Arc<Mutex<BatchProgress>>,
+    batch_running: Arc<AtomicBool>,
+    batch_cancel: Arc<AtomicBool>,
+    /// Installed font families for the overlay font picker
+    font_families: Vec<String>,
+    /// Live overlay state (opacity / interactive mode), also changed by the overlay's hotkeys
+    overlay_controls: Arc<OverlayControls>,
+    /// Appearance the running overlay was last given, to spot edits that need pushing
+    applied_overlay_config: Option<OverlayConfig>,
+    /// Name for saving the current appearance as a style preset
+    preset_name: String,
+    /// Name for saving the current model, parameters and prompts as an LLM preset
+    llm_preset_name: String,
+    /// System-wide hotkeys, re-registered when their settings change
+    hotkeys: Option<HotkeyListener>,
+    /// Auto-start game window already acted on (0 = none); it isn't restarted after a manual stop
+    auto_start_hwnd: isize,
+    last_auto_start_check: Instant,
+    /// Still waiting for last session's window to resume it (cleared by any start)
+    resume_pending: bool,
+    /// Target of the main session while its thread is alive
+    main_session_hwnd: Option<isize>,
+    /// Pipelines started next to the main one
+    extra_sessions: Vec<ExtraSession>,
+    /// Profile the next extra session starts with (None = the current settings)
+    extra_session_profile: Option<String>,
+    extra_session_error: Option<String>,
+    /// Window title pattern given with --window-title
+    cli_window: Option<Regex>,
+    /// --autostart with --window-title: start once a matching window appears (cleared by any start)
+    cli_autostart: bool,
+    /// Capture region / mask editor, while open
+    region_editor: Option<RegionEditor>,
+    /// Overlay font picker, while open
+    font_picker: Option<FontPicker>,
+    /// Crosshair window picker, while waiting for the click
+    window_picker: Option<WindowPicker>,
+    /// Why the last picked window could not be selected
+    window_pick_error: Option<String>,
+    /// Tray icon used for error notifications, created on the first one (or at startup when
+    /// starting hidden in the tray)
+    notifier: Option<Notifier>,
+    /// Main window, brought back by clicking the tray icon
+    main_hwnd_raw: isize,
+    /// `PipelineError::seq` of the last error notified
+    notified_error: u64,
+    /// The config file could not be parsed; offer its last good backup until answered
+    config_recovery: Option<ConfigLoadError>,
+    /// Asking whether to stop the running sessions and exit
+    exit_confirm: bool,
+    /// Set once exit was confirmed: close the window when every session ends or at this time
+    exit_deadline: Option<Instant>,
+    /// Uploaded OCR preview thumbnail and the capture it came from
+    preview_texture: Option<(u64, egui::TextureHandle)>,
+    /// WebSocket feed of OCR results and translations, while enabled
+    feed_server: Option<FeedServer>,
+    /// Port the feed failed to listen on, and why (not retried until the port changes)
+    feed_error: Option<(u16, String)>,
+    /// Clipboard watcher translating copied text, while enabled
+    clipboard_watch: Option<ClipboardWatch>,
+    /// Settings the watcher failed to start with, when, and why (retried once they change or after a while)
+    clipboard_watch_error: Option<(WatchKey, Instant, String)>,
+    /// Last copied text and its translation, shown in the popup until closed
+    clipboard_popup: Option<ClipboardTranslation>,
+    /// Screen being snipped with the snip hotkey, until a range is dragged
+    snip: Option<SnipSelection>,
+    /// OCR and translation of the snipped range in progress
+    snip_job: Option<SnipJob>,
+    /// Snipped text and its translation, shown in a popup until closed
+    snip_popup: Option<ClipboardTranslation>,
+    /// Line Ctrl+clicked on the overlay, shown in the dictionary popup, and the character clicked in it
+    lookup: Option<(LookupRequest, Option<usize>)>,
+    /// Dictionaries downloaded or loaded so far (loading JMdict takes seconds, so they stay loaded)
+    dictionaries: Vec<DictionaryLoader>,
+    /// Translation plugins found in the plugins folder, listed as engines
+    plugins: Vec<PluginManifest>,
+    tab: Tab,
+    /// Filter for the history tab (matches source or translation)
+    history_search: String,
+    /// Path for exporting the session history (.csv, or plain text otherwise)
+    history_export_path: String,
+    history_export_result: Option<Result<String, String>>,
+    /// History line being corrected: its id in the session log and the edited translation
+    history_edit: Option<(usize, String)>,
+    /// History lines ticked for the Anki export, by id in the session log
+    history_selected: BTreeSet<usize>,
+    anki_export_path: String,
 }
 
 impl GameTranslatorApp {
-    pub fn new(cc: &eframe::CreationContext<'_>) -> Self {
+    pub fn new(cc: &eframe::CreationContext<'_>, args: CliArgs, args_error: Option<String>) -> Self {
         // Load Japanese font
         let mut fonts = egui::FontDefinitions::default();
         let font_data = include_bytes!("../makinas4/Makinas-4-Square.otf");
@@ -56,8 +275,46 @@ impl GameTranslatorApp {
             .or_default()
             .push("Makinas4".to_owned());
         cc.egui_ctx.set_fonts(fonts);
+        let main_hwnd_raw = match cc.window_handle().map(|h| h.as_raw()) {
+            Ok(RawWindowHandle::Win32(handle)) => handle.hwnd.get(),
+            _ => 0,
+        };
+        // Start hidden with only the tray icon, which brings the window back when clicked
+        let mut notifier = None;
+        if args.minimized {
+            match Notifier::new(HWND(main_hwnd_raw as *mut _)).and_then(|mut n| n.show_icon().map(|_| n)) {
+                Ok(tray) if main_hwnd_raw != 0 => {
+                    notifier = Some(tray);
+                    cc.egui_ctx.send_viewport_cmd(egui::ViewportCommand::Visible(false));
+                }
+                result => {
+                    if let Err(e) = result {
+                        crate::log_always(&format!("Failed to set up the tray icon: {:#}", e));
+                    }
+                    cc.egui_ctx.send_viewport_cmd(egui::ViewportCommand::Minimized(true));
+                }
+            }
+        }
 
-        let config = AppConfig::load();
+        let (config, config_recovery) = AppConfig::load();
+        if let Some(e) = &config_recovery {
+            crate::log_always(&format!("[CONFIG] 設定ファイルを読み込めません: {}", e.message));
+        }
+        i18n::set_language(config.ui_language);
+        // Re-register so the Run key follows the exe if it was moved
+        if config.launch_at_login {
+            if let Err(e) = startup::set_launch_at_login(true, config.start_minimized) {
+                crate::log_always(&format!("Failed to update the Run key: {:#}", e));
+            }
+        }
+        // --autostart without --window-title resumes the last window, like the resume option
+        let resume_pending = (config.resume_last_window || (args.autostart && args.window_title.is_none()))
+            && !(config.last_window_title.is_empty() && config.last_window_exe.is_empty());
+        let overlay_controls = Arc::new(OverlayControls::new(
+            config.overlay_opacity,
+            config.overlay_font_scale,
+            config.overlay_exclude_from_capture,
+        ));
         let mut app = Self {
             config,
             window_list: Vec::new(),
@@ -66,49 +323,475 @@ impl GameTranslatorApp {
             stop_signal: Arc::new(AtomicBool::new(false)),
             overlay_thread: None,
             overlay_hwnd_raw: Arc::new(std::sync::atomic::AtomicIsize::new(0)),
-            api_test_result: Arc::new(Mutex::new(None)),
+            api_test_result: Arc::new(Mutex::new(Vec::new())),
             api_testing: Arc::new(AtomicBool::new(false)),
+            benchmark_iterations: 10,
+            benchmark_image: String::new(),
+            benchmark_progress: Arc::new(Mutex::new(BenchmarkProgress::default())),
+            benchmark_running: Arc::new(AtomicBool::new(false)),
+            benchmark_cancel: Arc::new(AtomicBool::new(false)),
+            model_list: Arc::new(Mutex::new(None)),
+            model_list_fetching: Arc::new(AtomicBool::new(false)),
+            endpoint_probe: Arc::new(Mutex::new(None)),
+            endpoint_probing: Arc::new(AtomicBool::new(false)),
+            deepl_usage: Arc::new(Mutex::new(None)),
+            deepl_usage_fetching: Arc::new(AtomicBool::new(false)),
+            deepl_key_editing: false,
+            deepl_quota_warned: false,
+            glossary_io_path: "glossary.csv".to_string(),
+            glossary_io_result: None,
+            prompt_sample: "Hello, traveler!\nThe door is locked.".to_string(),
+            prompt_preview: None,
+            prompt_test_result: Arc::new(Mutex::new(None)),
+            prompt_testing: Arc::new(AtomicBool::new(false)),
             debug_log: false,
+            cache_io_path: "translation_cache.csv".to_string(),
+            cache_io_result: None,
+            settings_io_path: "game_translator_settings.toml".to_string(),
+            settings_include_secrets: false,
+            settings_io_result: None,
+            crash_report: diagnostics::take_unseen_crash(),
+            diagnostics_result: None,
+            pretranslate_path: "strings.txt".to_string(),
+            pretranslate_progress: Arc::new(Mutex::new(PretranslateProgress::default())),
+            pretranslate_running: Arc::new(AtomicBool::new(false)),
+            pretranslate_cancel: Arc::new(AtomicBool::new(false)),
+            batch_dir: String::new(),
+            batch_progress: Arc::new(Mutex::new(BatchProgress::default())),
+            batch_running: Arc::new(AtomicBool::new(false)),
+            batch_cancel: Arc::new(AtomicBool::new(false)),
+            font_families: crate::overlay::list_font_families().unwrap_or_else(|e| {
+                crate::log_always(&format!("Failed to enumerate fonts: {}", e));
+                Vec::new()
+            }),
+            overlay_controls,
+            applied_overlay_config: None,
+            preset_name: String::new(),
+            llm_preset_name: String::new(),
+            hotkeys: None,
+            auto_start_hwnd: 0,
+            last_auto_start_check: Instant::now(),
+            resume_pending,
+            main_session_hwnd: None,
+            extra_sessions: Vec::new(),
+            extra_session_profile: None,
+            extra_session_error: None,
+            cli_autostart: args.autostart && args.window_title.is_some(),
+            cli_window: args.window_title,
+            region_editor: None,
+            font_picker: None,
+            window_picker: None,
+            window_pick_error: None,
+            notifier,
+            main_hwnd_raw,
+            notified_error: 0,
+            config_recovery,
+            exit_confirm: false,
+            exit_deadline: None,
+            preview_texture: None,
+            feed_server: None,
+            feed_error: None,
+            clipboard_watch: None,
+            clipboard_watch_error: None,
+            clipboard_popup: None,
+            snip: None,
+            snip_job: None,
+            snip_popup: None,
+            lookup: None,
+            dictionaries: Vec::new(),
+            plugins: plugin::discover(),
+            tab: Tab::Settings,
+            history_search: String::new(),
+            history_export_path: "translation_history.csv".to_string(),
+            history_export_result: None,
+            history_edit: None,
+            history_selected: BTreeSet::new(),
+            anki_export_path: "anki_cards.tsv".to_string(),
         };
         app.refresh_windows();
+        if let Some(pattern) = &app.cli_window {
+            app.selected_window_index = app.window_list.iter().position(|(_, title)| pattern.is_match(title));
+        }
+        if let Some(e) = args_error {
+            app.status = AppStatus::Error(trf("コマンドライン引数が不正です: {}", &[&e]));
+        }
         app
     }
 
+    /// Reload the window list, pre-selecting last session's window if it is open
     fn refresh_windows(&mut self) {
         self.window_list = list_windows();
-        self.selected_window_index = None;
+        self.selected_window_index = self.find_last_window(&self.window_list);
     }
 
-    fn start(&mut self) {
-        // Validate config
-        match self.config.translation_engine {
-            TranslationEngine::DeepL => {
-                if self.config.deepl_api_key.trim().is_empty() {
-                    self.status = AppStatus::Error("DeepL APIキーが未設定です".to_string());
-                    return;
+    /// Select the window clicked with the crosshair picker
+    fn select_picked_window(&mut self, hwnd_raw: isize) {
+        if capture::is_own_window(hwnd_raw) {
+            self.window_pick_error = Some(tr("Game Translator 自身のウィンドウは選べません").to_string());
+            return;
+        }
+        self.window_list = list_windows();
+        match self.window_list.iter().position(|(hwnd, _)| *hwnd == hwnd_raw) {
+            Some(i) => {
+                self.selected_window_index = Some(i);
+                self.window_pick_error = None;
+            }
+            None => self.window_pick_error = Some(tr("タイトルのないウィンドウは選べません").to_string()),
+        }
+    }
+
+    /// Start, stop or move the WebSocket feed to match the settings
+    fn sync_feed_server(&mut self) {
+        let Some(port) = self.config.feed_enabled.then_some(self.config.feed_port) else {
+            self.feed_server = None;
+            self.feed_error = None;
+            return;
+        };
+        if self.feed_server.as_ref().is_some_and(|s| s.port() == port)
+            || self.feed_error.as_ref().is_some_and(|(failed, _)| *failed == port)
+        {
+            return;
+        }
+        self.feed_server = None;
+        match FeedServer::start(port) {
+            Ok(server) => {
+                self.feed_server = Some(server);
+                self.feed_error = None;
+            }
+            Err(e) => self.feed_error = Some((port, format!("{:#}", e))),
+        }
+    }
+
+    /// Run the clipboard watcher while enabled, except while a session takes copied text
+    /// itself (clipboard input shows it on the overlay instead)
+    fn sync_clipboard_watch(&mut self, ctx: &egui::Context) {
+        let session_reads_clipboard = self.is_running() && self.config.input_source == InputSource::Clipboard;
+        if !self.config.clipboard_watch || session_reads_clipboard {
+            self.clipboard_watch = None;
+            self.clipboard_watch_error = None;
+            return;
+        }
+        let key = clipboard_watch::watch_key(&self.config);
+        if self.clipboard_watch.as_ref().is_some_and(|w| *w.key() == key)
+            || self
+                .clipboard_watch_error
+                .as_ref()
+                .is_some_and(|(failed, at, _)| *failed == key && at.elapsed() < CLIPBOARD_WATCH_RETRY)
+        {
+            return;
+        }
+        self.clipboard_watch = None;
+        // The watcher only translates, so the session's input source settings don't matter here
+        let started = validate_translation(&self.config)
+            .and_then(|_| ClipboardWatch::start(&self.config, ctx.clone()).map_err(|e| format!("{:#}", e)));
+        match started {
+            Ok(watch) => {
+                self.clipboard_watch = Some(watch);
+                self.clipboard_watch_error = None;
+            }
+            Err(e) => {
+                self.clipboard_watch_error = Some((key, Instant::now(), e));
+                ctx.request_repaint_after(CLIPBOARD_WATCH_RETRY);
+            }
+        }
+    }
+
+    /// Always-on-top popup with the last copied text and its translation
+    fn clipboard_popup_ui(&mut self, ctx: &egui::Context) {
+        if let Some(translation) = self.clipboard_watch.as_ref().and_then(|w| w.take()) {
+            self.clipboard_popup = Some(translation);
+        }
+        if self.clipboard_popup.is_some()
+            && Self::translation_popup(ctx, "clipboard_popup", tr("クリップボードの翻訳"), self.clipboard_popup.as_ref())
+        {
+            self.clipboard_popup = None;
+        }
+    }
+
+    /// Snip-and-translate: the full-screen range selection, then a popup with the result
+    fn snip_ui(&mut self, ctx: &egui::Context) {
+        if let Some(selection) = &mut self.snip {
+            match selection.show(ctx) {
+                SnipOutcome::Selecting => {}
+                SnipOutcome::Cancelled => self.snip = None,
+                SnipOutcome::Selected(x, y, width, height) => {
+                    self.snip_popup = None;
+                    match validate_config(&self.config) {
+                        Ok(()) => {
+                            let job = SnipJob::start(&self.config, selection.shot(), (x, y, width, height), ctx.clone());
+                            self.snip_job = Some(job);
+                        }
+                        Err(e) => self.snip_popup = Some(ClipboardTranslation { source: String::new(), result: Err(e) }),
+                    }
+                    self.snip = None;
                 }
             }
-            TranslationEngine::LocalLLM => {
-                if self.config.local_llm_endpoint.trim().is_empty() {
-                    self.status = AppStatus::Error("LLMエンドポイントが未設定です".to_string());
-                    return;
+        }
+        if let Some(translation) = self.snip_job.as_ref().and_then(|job| job.take()) {
+            self.snip_popup = Some(translation);
+            self.snip_job = None;
+        }
+        if (self.snip_job.is_some() || self.snip_popup.is_some())
+            && Self::translation_popup(ctx, "snip_popup", tr("範囲の翻訳"), self.snip_popup.as_ref())
+        {
+            // Closing while still reading drops the job; its thread finishes on its own
+            self.snip_job = None;
+            self.snip_popup = None;
+        }
+    }
+
+    /// Capture the whole screen for the snip hotkey's range selection
+    fn begin_snip(&mut self) {
+        match capture::capture_desktop() {
+            Ok(shot) => self.snip = Some(SnipSelection::new(shot)),
+            Err(e) => {
+                crate::log_always(&format!("[SNIP] 画面を撮れません: {:#}", e));
+                self.snip_popup = Some(ClipboardTranslation { source: String::new(), result: Err(format!("{:#}", e)) });
+            }
+        }
+    }
+
+    /// Always-on-top popup with a source text and its translation (`None` while it is still being read).
+    /// Returns true when closed.
+    fn translation_popup(ctx: &egui::Context, id: &str, title: &str, popup: Option<&ClipboardTranslation>) -> bool {
+        let mut close = false;
+        ctx.show_viewport_immediate(
+            egui::ViewportId::from_hash_of(id),
+            egui::ViewportBuilder::default()
+                .with_title(title)
+                .with_inner_size([420.0, 180.0])
+                .with_always_on_top(),
+            |ctx, _| {
+                egui::CentralPanel::default().show(ctx, |ui| {
+                    let Some(popup) = popup else {
+                        ui.horizontal(|ui| {
+                            ui.spinner();
+                            ui.label(tr("読み取り中..."));
+                        });
+                        if ui.button(tr("閉じる")).clicked() {
+                            close = true;
+                        }
+                        return;
+                    };
+                    egui::ScrollArea::vertical().max_height(110.0).show(ui, |ui| {
+                        ui.label(egui::RichText::new(&popup.source).weak());
+                        ui.separator();
+                        match &popup.result {
+                            Ok(translation) => {
+                                ui.label(egui::RichText::new(translation).size(18.0));
+                            }
+                            Err(e) => {
+                                ui.colored_label(egui::Color32::RED, e);
+                            }
+                        }
+                    });
+                    ui.horizontal(|ui| {
+                        if let Ok(translation) = &popup.result {
+                            if ui.button(tr("訳文をコピー")).clicked() {
+                                ctx.copy_text(translation.clone());
+                            }
+                        }
+                        if ui.button(tr("閉じる")).clicked() {
+                            close = true;
+                        }
+                    });
+                });
+                if ctx.input(|i| i.viewport().close_requested()) {
+                    close = true;
                 }
+            },
+        );
+        close
+    }
+
+    /// Dictionary popup for a box Ctrl+clicked in interactive mode: click a character of the
+    /// source to look up the words starting there
+    fn lookup_popup_ui(&mut self, ctx: &egui::Context) {
+        if self.is_running() && self.overlay_controls.interactive.load(Ordering::SeqCst) {
+            if let Some(request) = self.overlay_controls.lookup.lock().unwrap().take() {
+                self.lookup = Some((request, None));
             }
-            TranslationEngine::Groq => {
-                if self.config.groq_api_key.trim().is_empty() {
-                    self.status = AppStatus::Error("Groq APIキーが未設定です".to_string());
-                    return;
+            // The overlay can't wake the GUI, so check for clicks while it takes them
+            ctx.request_repaint_after(LOOKUP_POLL);
+        }
+        let Some((request, pos)) = &mut self.lookup else {
+            return;
+        };
+        let kind = DictionaryKind::for_language(&self.config.source_lang);
+        if let Some(kind) = kind.filter(|k| !self.dictionaries.iter().any(|d| d.kind == *k)) {
+            self.dictionaries.push(DictionaryLoader::new(kind, ctx));
+        }
+        let loader = kind.and_then(|k| self.dictionaries.iter().find(|d| d.kind == k));
+        let state = loader.map(|l| l.state());
+        let matches = match (&state, *pos) {
+            (Some(DictionaryState::Ready(dictionary)), Some(start)) => dictionary.lookup(&request.source, start),
+            _ => Vec::new(),
+        };
+        let matched = pos.map(|start| start..start + matches.first().map_or(1, |m| m.len));
+        let mut close = false;
+        let mut download = false;
+        ctx.show_viewport_immediate(
+            egui::ViewportId::from_hash_of("lookup_popup"),
+            egui::ViewportBuilder::default()
+                .with_title(tr("辞書"))
+                .with_inner_size([460.0, 380.0])
+                .with_always_on_top(),
+            |ctx, _| {
+                egui::CentralPanel::default().show(ctx, |ui| {
+                    ui.horizontal_wrapped(|ui| {
+                        ui.spacing_mut().item_spacing.x = 0.0;
+                        for (i, c) in request.source.chars().enumerate() {
+                            let mut text = egui::RichText::new(c.to_string()).size(22.0);
+                            if matched.as_ref().is_some_and(|m| m.contains(&i)) {
+                                text = text.background_color(ui.visuals().selection.bg_fill);
+                            }
+                            if ui.add(egui::Label::new(text).sense(egui::Sense::click())).clicked() {
+                                *pos = Some(i);
+                            }
+                        }
+                    });
+                    ui.label(egui::RichText::new(&request.translation).weak());
+                    ui.separator();
+                    egui::ScrollArea::vertical().max_height(230.0).show(ui, |ui| match &state {
+                        None => {
+                            ui.label(tr("辞書を引けるのは翻訳元が日本語・中国語の時だけです"));
+                        }
+                        Some(DictionaryState::Missing) => {
+                            let name = kind.map_or("", |k| k.name());
+                            ui.label(trf("{} をダウンロードして保存先に置きます（初回のみ）", &[&name]));
+                            download = ui.button(tr("ダウンロード")).clicked();
+                        }
+                        Some(DictionaryState::Downloading) => {
+                            ui.horizontal(|ui| {
+                                ui.spinner();
+                                ui.label(tr("辞書をダウンロード中..."));
+                            });
+                        }
+                        Some(DictionaryState::Loading) => {
+                            ui.horizontal(|ui| {
+                                ui.spinner();
+                                ui.label(tr("辞書を読み込み中..."));
+                            });
+                        }
+                        Some(DictionaryState::Failed(e)) => {
+                            ui.colored_label(egui::Color32::RED, e);
+                            download = ui.button(tr("ダウンロードし直す")).clicked();
+                        }
+                        Some(DictionaryState::Ready(_)) if pos.is_none() => {
+                            ui.weak(tr("原文の文字をクリックすると、そこから始まる語を引きます"));
+                        }
+                        Some(DictionaryState::Ready(_)) if matches.is_empty() => {
+                            ui.weak(tr("見つかりませんでした"));
+                        }
+                        Some(DictionaryState::Ready(_)) => {
+                            for m in &matches {
+                                for entry in &m.entries {
+                                    let headword = entry.headwords.first().or(entry.readings.first()).cloned().unwrap_or_default();
+                                    ui.horizontal_wrapped(|ui| {
+                                        ui.label(egui::RichText::new(headword).size(18.0).strong());
+                                        if !entry.headwords.is_empty() && !entry.readings.is_empty() {
+                                            ui.label(format!("【{}】", entry.readings.join("・")));
+                                        }
+                                        if m.form != request.source.chars().skip(pos.unwrap_or(0)).take(m.len).collect::<String>() {
+                                            ui.weak(format!("← {}", m.form));
+                                        }
+                                    });
+                                    for (n, sense) in entry.senses.iter().enumerate() {
+                                        ui.label(format!("{}. {}", n + 1, sense));
+                                    }
+                                    ui.add_space(6.0);
+                                }
+                            }
+                        }
+                    });
+                    ui.separator();
+                    ui.horizontal(|ui| {
+                        if let Some(kind) = kind {
+                            ui.hyperlink_to(kind.name(), kind.homepage()).on_hover_text(tr("辞書のライセンス (CC BY-SA)"));
+                        }
+                        if ui.button(tr("閉じる")).clicked() {
+                            close = true;
+                        }
+                    });
+                });
+                if ctx.input(|i| i.viewport().close_requested()) {
+                    close = true;
+                }
+            },
+        );
+        if download {
+            if let Some(loader) = loader {
+                loader.download(&self.config, ctx);
+            }
+        }
+        if close {
+            self.lookup = None;
+        }
+    }
+
+    fn poll_window_picker(&mut self, ctx: &egui::Context) {
+        let Some(picker) = &self.window_picker else {
+            return;
+        };
+        match picker.take_result() {
+            Some(picked) => {
+                self.window_picker = None;
+                if let Some(hwnd_raw) = picked {
+                    self.select_picked_window(hwnd_raw);
                 }
             }
+            None => ctx.request_repaint_after(Duration::from_millis(100)),
+        }
+    }
+
+    /// Last session's window in `windows`: same executable (the exact title breaks ties),
+    /// or the same title when the executable is unknown or no longer matches
+    fn find_last_window(&self, windows: &[(isize, String)]) -> Option<usize> {
+        let exe = &self.config.last_window_exe;
+        let title = &self.config.last_window_title;
+        if !exe.is_empty() {
+            let same_exe: Vec<usize> = (0..windows.len())
+                .filter(|&i| capture::window_exe_name(windows[i].0).is_some_and(|e| e.eq_ignore_ascii_case(exe)))
+                .collect();
+            if let Some(&i) = same_exe.iter().find(|&&i| windows[i].1 == *title).or(same_exe.first()) {
+                return Some(i);
+            }
+        }
+        if title.is_empty() {
+            return None;
+        }
+        windows.iter().position(|(_, t)| t == title)
+    }
+
+    fn start(&mut self) {
+        if self.is_pretranslating() {
+            self.status = AppStatus::Error(tr("事前翻訳の実行中は開始できません").to_string());
+            return;
+        }
+        if let Err(msg) = validate_config(&self.config) {
+            self.status = AppStatus::Error(msg);
+            return;
         }
 
         let target_hwnd_raw = match self.selected_window_index {
             Some(idx) if idx < self.window_list.len() => self.window_list[idx].0,
             _ => {
-                self.status = AppStatus::Error("ウィンドウを選択してください".to_string());
+                self.status = AppStatus::Error(tr("ウィンドウを選択してください").to_string());
                 return;
             }
         };
+        if self.extra_sessions.iter().any(|s| s.hwnd_raw == target_hwnd_raw) {
+            self.status = AppStatus::Error(tr("このウィンドウはすでに翻訳中です").to_string());
+            return;
+        }
+        self.resume_pending = false;
+        self.history_selected.clear();
+        self.cli_autostart = false;
+        self.main_session_hwnd = Some(target_hwnd_raw);
+        if let Some(idx) = self.selected_window_index {
+            self.config.last_window_title = self.window_list[idx].1.clone();
+            self.config.last_window_exe = capture::window_exe_name(target_hwnd_raw).unwrap_or_default();
+        }
 
         // Save config
         if let Err(e) = self.config.save() {
@@ -117,46 +800,127 @@ impl GameTranslatorApp {
 
         // Reset stop signal
         self.stop_signal.store(false, Ordering::SeqCst);
-        let stop_signal = self.stop_signal.clone();
-        let overlay_hwnd_arc = self.overlay_hwnd_raw.clone();
+        self.applied_overlay_config = Some(OverlayConfig::from_config(&self.config));
+        let handle = spawn_session(
+            target_hwnd_raw,
+            self.config.clone(),
+            self.stop_signal.clone(),
+            self.overlay_hwnd_raw.clone(),
+            self.overlay_controls.clone(),
+        );
+
+        self.overlay_thread = Some(handle);
+        self.status = AppStatus::Running;
+    }
 
-        let overlay_config = OverlayConfig {
-            text_color: self.config.overlay_text_color,
-            bg_color: self.config.overlay_bg_color,
+    /// Start another pipeline on the selected window, with the chosen profile or the current settings
+    fn start_extra_session(&mut self) {
+        self.extra_session_error = None;
+        let Some((hwnd_raw, title)) = self.selected_window_index.and_then(|idx| self.window_list.get(idx)).cloned() else {
+            self.extra_session_error = Some(tr("ウィンドウを選択してください").to_string());
+            return;
+        };
+        if self.main_session_hwnd == Some(hwnd_raw) || self.extra_sessions.iter().any(|s| s.hwnd_raw == hwnd_raw) {
+            self.extra_session_error = Some(tr("このウィンドウはすでに翻訳中です").to_string());
+            return;
+        }
+        let config = match &self.extra_session_profile {
+            Some(name) => match AppConfig::load_from(&cli::profile_path(name)) {
+                Ok(config) => config,
+                Err(e) => {
+                    self.extra_session_error = Some(trf("プロファイルを読み込めません: {}", &[&format!("{:#}", e)]));
+                    return;
+                }
+            },
+            None => self.config.clone(),
         };
+        if let Err(msg) = validate_config(&config) {
+            self.extra_session_error = Some(msg);
+            return;
+        }
 
-        let config = self.config.clone();
+        let stop_signal = Arc::new(AtomicBool::new(false));
+        let overlay_hwnd_raw = Arc::new(AtomicIsize::new(0));
+        let controls = Arc::new(OverlayControls::new(
+            config.overlay_opacity,
+            config.overlay_font_scale,
+            config.overlay_exclude_from_capture,
+        ));
+        crate::log_always(&format!("[SESSION] 追加セッション開始: {}", title));
+        let thread = spawn_session(hwnd_raw, config, stop_signal.clone(), overlay_hwnd_raw.clone(), controls);
+        self.extra_sessions.push(ExtraSession {
+            hwnd_raw,
+            title,
+            profile: self.extra_session_profile.clone(),
+            stop_signal,
+            overlay_hwnd_raw,
+            thread: Some(thread),
+        });
+    }
 
-        let handle = std::thread::spawn(move || {
-            if let Err(e) = crate::run_overlay_thread(
-                target_hwnd_raw,
-                config,
-                overlay_config,
-                stop_signal,
-                overlay_hwnd_arc,
-            ) {
-                crate::log_always(&format!("Overlay thread error: {}", e));
+    /// Running extra sessions, a button to add one and the profile it starts with
+    fn sessions_ui(&mut self, ui: &mut egui::Ui) {
+        ui.weak(tr("ゲームとは別のウィンドウ（チャット欄など）も同時に翻訳できます。スライダー・プレビュー・履歴はメインのセッションのみ対象です。"));
+        let mut stopped = None;
+        for (i, session) in self.extra_sessions.iter().enumerate() {
+            ui.horizontal(|ui| {
+                let profile = session.profile.as_deref().unwrap_or(tr("現在の設定"));
+                ui.label(format!("{} [{}]", session.title, profile));
+                if session.stopping() {
+                    ui.colored_label(egui::Color32::YELLOW, tr("停止中..."));
+                } else if ui.button(tr("停止")).clicked() {
+                    stopped = Some(i);
+                }
+            });
+        }
+        if let Some(i) = stopped {
+            self.extra_sessions[i].stop();
+        }
+        ui.horizontal(|ui| {
+            ui.label(tr("プロファイル:"));
+            egui::ComboBox::from_id_salt("extra_session_profile")
+                .selected_text(self.extra_session_profile.as_deref().unwrap_or(tr("現在の設定")))
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut self.extra_session_profile, None, tr("現在の設定"));
+                    for name in cli::list_profiles() {
+                        ui.selectable_value(&mut self.extra_session_profile, Some(name.clone()), name);
+                    }
+                });
+            if ui.button(tr("選択中のウィンドウで追加")).clicked() {
+                self.start_extra_session();
             }
         });
-
-        self.overlay_thread = Some(handle);
-        self.status = AppStatus::Running;
+        if let Some(msg) = &self.extra_session_error {
+            ui.colored_label(egui::Color32::RED, msg);
+        }
     }
 
-    fn stop(&mut self) {
-        self.stop_signal.store(true, Ordering::SeqCst);
-
-        // Send WM_CLOSE to overlay window to break the message loop
+    /// Notify the running overlay that shared controls changed (no-op when stopped)
+    fn post_overlay_message(&self, msg: u32) {
         let hwnd_raw = self.overlay_hwnd_raw.load(Ordering::SeqCst);
         if hwnd_raw != 0 {
             unsafe {
                 use windows::Win32::Foundation::*;
                 use windows::Win32::UI::WindowsAndMessaging::*;
                 let hwnd = HWND(hwnd_raw as *mut _);
-                let _ = PostMessageW(Some(hwnd), WM_CLOSE, WPARAM(0), LPARAM(0));
+                let _ = PostMessageW(Some(hwnd), msg, WPARAM(0), LPARAM(0));
             }
         }
+    }
+
+    /// Send appearance edits (colors, fonts, box style...) to the running overlay
+    fn push_overlay_config(&mut self) {
+        let overlay_config = OverlayConfig::from_config(&self.config);
+        if self.applied_overlay_config.as_ref() == Some(&overlay_config) {
+            return;
+        }
+        *self.overlay_controls.pending_config.lock().unwrap() = Some(overlay_config.clone());
+        self.applied_overlay_config = Some(overlay_config);
+        self.post_overlay_message(crate::pipeline::WM_APPLY_CONFIG);
+    }
 
+    fn stop(&mut self) {
+        signal_stop(&self.stop_signal, &self.overlay_hwnd_raw);
         self.status = AppStatus::Stopping;
     }
 
@@ -168,264 +932,2853 @@ impl GameTranslatorApp {
                     let _ = handle.join();
                 }
                 self.overlay_hwnd_raw.store(0, Ordering::SeqCst);
+                self.main_session_hwnd = None;
                 self.status = AppStatus::Idle;
+                self.export_transcript();
             }
         }
+        self.extra_sessions.retain_mut(|session| {
+            let finished = session.thread.as_ref().is_none_or(|t| t.is_finished());
+            if finished {
+                if let Some(thread) = session.thread.take() {
+                    let _ = thread.join();
+                }
+                crate::log_always(&format!("[SESSION] 追加セッション終了: {}", session.title));
+            }
+            !finished
+        });
     }
 
-    fn is_running(&self) -> bool {
-        matches!(self.status, AppStatus::Running | AppStatus::Stopping)
-    }
-
-    fn start_api_test(&self) {
-        if self.api_testing.load(Ordering::SeqCst) {
+    /// Save the finished session's lines to the transcripts folder, if enabled
+    fn export_transcript(&mut self) {
+        if !self.config.transcript_auto_export {
             return;
         }
-        self.api_testing.store(true, Ordering::SeqCst);
-        *self.api_test_result.lock().unwrap() = None;
+        let records = self.overlay_controls.session_log.lock().unwrap().records.clone();
+        if records.is_empty() {
+            return;
+        }
+        self.history_export_result = Some(match cache_io::export_transcript(&records, self.config.transcript_format) {
+            Ok(path) => {
+                crate::log_always(&format!("[HISTORY] セッションの記録を書き出し: {}", path.display()));
+                Ok(trf("{}件を {} に書き出しました", &[&records.len(), &path.display()]))
+            }
+            Err(e) => {
+                crate::log_always(&format!("[HISTORY] セッションの記録を書き出せません: {:#}", e));
+                Err(trf("エクスポート失敗: {}", &[&format!("{:#}", e)]))
+            }
+        });
+    }
 
-        let translator = match self.config.translation_engine {
-            TranslationEngine::DeepL => Translator::new_deepl(self.config.deepl_api_key.clone()),
-            TranslationEngine::LocalLLM => Translator::new_local(
-                self.config.local_llm_endpoint.clone(),
-                self.config.local_llm_model.clone(),
-            ),
-            TranslationEngine::Groq => Translator::new_groq(
-                self.config.groq_api_key.clone(),
-                self.config.groq_model.clone(),
-            ),
+    /// Dialog shown at startup when config.toml was unreadable and defaults were loaded instead
+    fn config_recovery_ui(&mut self, ctx: &egui::Context) {
+        let Some(error) = self.config_recovery.clone() else {
+            return;
         };
-
-        let source = self.config.source_lang.clone();
-        let target = self.config.target_lang.clone();
-        let result = self.api_test_result.clone();
-        let testing = self.api_testing.clone();
-
-        std::thread::spawn(move || {
-            let rt = tokio::runtime::Runtime::new().unwrap();
-            let start = std::time::Instant::now();
-            let res = rt.block_on(translator.translate_batch(
-                vec!["Hello".to_string()],
-                &source,
-                &target,
-            ));
-            let elapsed = start.elapsed();
-
-            let msg = match res {
-                Ok(translations) => {
-                    let translated = translations.first()
-                        .and_then(|t| t.clone())
-                        .unwrap_or_else(|| "(empty)".to_string());
-                    format!("OK: \"{}\" ({:.0}ms)", translated, elapsed.as_millis())
+        let mut close = false;
+        egui::Modal::new(egui::Id::new("config_recovery")).show(ctx, |ui| {
+            ui.heading(tr("設定ファイルを読み込めませんでした"));
+            ui.label(tr("既定の設定で起動しています。"));
+            ui.add(egui::Label::new(egui::RichText::new(&error.message).monospace().weak()).wrap());
+            if let Some(copy) = &error.broken_copy {
+                ui.label(trf("壊れたファイルは {} に残しました", &[&copy.display()]));
+            }
+            ui.horizontal(|ui| {
+                if let Some(backup) = &error.backup {
+                    let name = backup.file_name().unwrap_or_default().to_string_lossy();
+                    if ui.button(trf("バックアップ {} から復元", &[&name])).clicked() {
+                        match AppConfig::restore_backup(backup) {
+                            Ok(config) => {
+                                crate::log_always(&format!("[CONFIG] バックアップから復元: {}", backup.display()));
+                                self.config = config;
+                                i18n::set_language(self.config.ui_language);
+                                close = true;
+                            }
+                            Err(e) => {
+                                self.config_recovery = Some(ConfigLoadError {
+                                    message: format!("{:#}", e),
+                                    backup: None,
+                                    ..error.clone()
+                                });
+                            }
+                        }
+                    }
+                } else {
+                    ui.weak(tr("読み込めるバックアップがありません"));
                 }
-                Err(e) => format!("NG: {}", e),
-            };
-
-            *result.lock().unwrap() = Some(msg);
-            testing.store(false, Ordering::SeqCst);
+                if ui.button(tr("既定の設定で続ける")).clicked() {
+                    close = true;
+                }
+            });
         });
+        if close {
+            self.config_recovery = None;
+        }
     }
-}
 
-impl eframe::App for GameTranslatorApp {
-    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        // Poll overlay thread completion without blocking
-        if matches!(self.status, AppStatus::Stopping) {
+    /// Hold the window open while sessions run: ask first, then stop them and close once
+    /// they have ended or SHUTDOWN_TIMEOUT has passed
+    fn handle_close(&mut self, ctx: &egui::Context) {
+        let sessions_running = self.is_running() || !self.extra_sessions.is_empty();
+        if let Some(deadline) = self.exit_deadline {
             self.poll_thread_completion();
-            ctx.request_repaint();
+            if !sessions_running || Instant::now() >= deadline {
+                if sessions_running {
+                    crate::log_always("[EXIT] セッションが時間内に終了しないため待たずに終了");
+                }
+                ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+            }
+            ctx.request_repaint_after(Duration::from_millis(50));
+        } else if ctx.input(|i| i.viewport().close_requested()) && sessions_running {
+            ctx.send_viewport_cmd(egui::ViewportCommand::CancelClose);
+            self.exit_confirm = true;
+        }
+        if !self.exit_confirm {
+            return;
         }
 
-        egui::CentralPanel::default().show(ctx, |ui| {
-            ui.heading("Game Translator");
-            ui.separator();
-
-            // === Window Selection ===
-            ui.group(|ui| {
-                ui.label("対象ウィンドウ");
+        let mut cancel = false;
+        egui::Modal::new(egui::Id::new("exit_confirm")).show(ctx, |ui| {
+            ui.heading(tr("翻訳中です。終了しますか？"));
+            if self.exit_deadline.is_some() {
                 ui.horizontal(|ui| {
-                    if ui.button("更新").clicked() {
-                        self.refresh_windows();
-                    }
-                    let selected_label = self
-                        .selected_window_index
-                        .and_then(|idx| self.window_list.get(idx))
-                        .map(|(_, title)| title.as_str())
-                        .unwrap_or("-- 選択してください --");
-
-                    egui::ComboBox::from_id_salt("window_select")
-                        .selected_text(selected_label)
-                        .width(400.0)
-                        .show_ui(ui, |ui| {
-                            for (i, (_, title)) in self.window_list.iter().enumerate() {
-                                ui.selectable_value(
-                                    &mut self.selected_window_index,
-                                    Some(i),
-                                    title,
-                                );
-                            }
-                        });
+                    ui.spinner();
+                    ui.label(tr("セッションを停止しています..."));
                 });
+                return;
+            }
+            ui.label(trf("実行中のセッション（{}件）を停止してから閉じます", &[&(self.is_running() as usize + self.extra_sessions.len())]));
+            ui.horizontal(|ui| {
+                if ui.button(tr("終了")).clicked() {
+                    if self.is_running() && !matches!(self.status, AppStatus::Stopping) {
+                        self.stop();
+                    }
+                    for session in &self.extra_sessions {
+                        session.stop();
+                    }
+                    self.exit_deadline = Some(Instant::now() + SHUTDOWN_TIMEOUT);
+                }
+                if ui.button(tr("キャンセル")).clicked() {
+                    cancel = true;
+                }
             });
+        });
+        if cancel {
+            self.exit_confirm = false;
+        }
+    }
+
+    /// Raise a Windows notification for a new pipeline error, so it is noticed mid-game
+    fn poll_errors(&mut self) {
+        let Some(error) = self.overlay_controls.error.lock().unwrap().clone() else {
+            return;
+        };
+        if error.seq == self.notified_error {
+            return;
+        }
+        self.notified_error = error.seq;
+        if !self.config.error_notifications {
+            return;
+        }
+        let title = if error.fatal { tr("翻訳が停止しました") } else { tr("翻訳エラー") };
+        self.notify(title, &error.message);
+    }
 
-            ui.add_space(8.0);
+    /// Show a Windows notification, setting up the tray icon on first use
+    fn notify(&mut self, title: &str, message: &str) {
+        if self.notifier.is_none() {
+            match Notifier::new(HWND(self.main_hwnd_raw as *mut _)) {
+                Ok(notifier) => self.notifier = Some(notifier),
+                Err(e) => {
+                    crate::log_always(&format!("Failed to set up notifications: {:#}", e));
+                    return;
+                }
+            }
+        }
+        if let Some(Err(e)) = self.notifier.as_mut().map(|n| n.show(title, message)) {
+            crate::log_always(&format!("Failed to show a notification: {:#}", e));
+        }
+    }
 
-            // === Translation Settings ===
-            ui.group(|ui| {
-                ui.label("翻訳設定");
+    /// Write the diagnostics ZIP and show it in Explorer
+    fn save_diagnostics(&mut self) {
+        self.diagnostics_result = Some(match diagnostics::write_bundle(&self.config) {
+            Ok(path) => {
+                let _ = std::process::Command::new("explorer").arg("/select,").arg(&path).spawn();
+                Ok(trf("{} に書き出しました", &[&path.display()]))
+            }
+            Err(e) => Err(trf("エクスポート失敗: {}", &[&format!("{:#}", e)])),
+        });
+    }
 
+    /// Banner shown after the previous run crashed, offering the diagnostics ZIP for a bug report
+    fn crash_banner_ui(&mut self, ui: &mut egui::Ui) {
+        let Some(report) = self.crash_report.clone() else {
+            return;
+        };
+        egui::Frame::new()
+            .fill(egui::Color32::from_rgb(110, 70, 10))
+            .inner_margin(8.0)
+            .corner_radius(4.0)
+            .show(ui, |ui| {
+                ui.set_width(ui.available_width());
+                ui.colored_label(egui::Color32::WHITE, egui::RichText::new(format!("⚠ {}", tr("前回は異常終了しました"))).strong());
+                ui.colored_label(
+                    egui::Color32::LIGHT_GRAY,
+                    tr("不具合を報告する時は、診断情報の ZIP を添付してください（APIキー等は含みません）"),
+                );
                 ui.horizontal(|ui| {
-                    ui.radio_value(
-                        &mut self.config.translation_engine,
-                        TranslationEngine::DeepL,
-                        "DeepL",
-                    );
-                    ui.radio_value(
-                        &mut self.config.translation_engine,
-                        TranslationEngine::LocalLLM,
-                        "Local LLM",
+                    if ui.button(tr("診断情報を ZIP に保存")).clicked() {
+                        self.save_diagnostics();
+                    }
+                    if ui.button(tr("レポートを開く")).clicked() {
+                        let _ = std::process::Command::new("notepad").arg(&report).spawn();
+                    }
+                    if ui.small_button(tr("閉じる")).clicked() {
+                        self.crash_report = None;
+                    }
+                });
+                if let Some(Err(msg)) = &self.diagnostics_result {
+                    ui.colored_label(egui::Color32::RED, msg);
+                }
+            });
+        ui.add_space(4.0);
+    }
+
+    /// Banner with the latest pipeline error, until it recovers or is dismissed
+    fn error_banner_ui(&mut self, ui: &mut egui::Ui) {
+        let Some(error) = self.overlay_controls.error.lock().unwrap().clone() else {
+            return;
+        };
+        egui::Frame::new()
+            .fill(egui::Color32::from_rgb(120, 20, 20))
+            .inner_margin(8.0)
+            .corner_radius(4.0)
+            .show(ui, |ui| {
+                ui.set_width(ui.available_width());
+                ui.horizontal(|ui| {
+                    let title = if error.fatal { tr("翻訳が停止しました") } else { tr("翻訳エラー") };
+                    ui.colored_label(egui::Color32::WHITE, egui::RichText::new(format!("⚠ {} ({})", title, error.time)).strong());
+                    if ui.small_button(tr("閉じる")).clicked() {
+                        self.overlay_controls.error.lock().unwrap().take();
+                    }
+                });
+                ui.colored_label(egui::Color32::WHITE, &error.message);
+                if !error.fatal {
+                    ui.colored_label(egui::Color32::LIGHT_GRAY, tr("自動で再試行中です。APIキー・利用上限・接続を確認してください"));
+                }
+            });
+        ui.add_space(4.0);
+    }
+
+    /// Start when a registered game window (or last session's window when resuming, or the
+    /// --window-title match with --autostart) appears. It always starts with the current settings;
+    /// profiles only apply to extra sessions and --profile.
+    /// Stopping when it closes needs nothing here: the capture loop ends on its own and
+    /// `poll_thread_completion` picks that up.
+    fn poll_auto_start(&mut self) {
+        if self.last_auto_start_check.elapsed() < AUTO_START_POLL {
+            return;
+        }
+        self.last_auto_start_check = Instant::now();
+        let windows = list_windows();
+        let resumed = if self.resume_pending { self.find_last_window(&windows) } else { None };
+        let found = resumed.or_else(|| {
+            windows.iter().position(|(_, title)| {
+                (self.cli_autostart && self.cli_window.as_ref().is_some_and(|p| p.is_match(title)))
+                    || (self.config.auto_start
+                        && self.config.auto_start_titles.iter().any(|t| !t.trim().is_empty() && title.contains(t.trim())))
+            })
+        });
+        let Some(index) = found else {
+            self.auto_start_hwnd = 0;
+            return;
+        };
+        let (hwnd, title) = windows[index].clone();
+        if hwnd == self.auto_start_hwnd {
+            return;
+        }
+        self.auto_start_hwnd = hwnd;
+        if matches!(self.status, AppStatus::Idle | AppStatus::Error(_)) {
+            crate::log_always(&format!("[AUTO START] {}", title));
+            self.window_list = windows;
+            self.selected_window_index = Some(index);
+            self.start();
+        }
+    }
+
+    /// Launch-at-login options. The Run key is written as soon as they change.
+    fn startup_ui(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            let before = (self.config.launch_at_login, self.config.start_minimized);
+            ui.checkbox(&mut self.config.launch_at_login, tr("Windows のログイン時に起動"));
+            ui.add_enabled(
+                self.config.launch_at_login,
+                egui::Checkbox::new(&mut self.config.start_minimized, tr("通知領域に隠して起動")),
+            );
+            if (self.config.launch_at_login, self.config.start_minimized) != before {
+                if let Err(e) = startup::set_launch_at_login(self.config.launch_at_login, self.config.start_minimized) {
+                    self.status = AppStatus::Error(trf("スタートアップに登録できません: {}", &[&format!("{:#}", e)]));
+                    (self.config.launch_at_login, self.config.start_minimized) = before;
+                }
+            }
+            ui.checkbox(&mut self.config.resume_last_window, tr("前回のウィンドウで自動再開"))
+                .on_hover_text(trf(
+                    "起動後、前回翻訳していたウィンドウが現れたら開始する（前回: {}）",
+                    &[&format!("{} / {}", self.config.last_window_exe, self.config.last_window_title)],
+                ));
+        });
+    }
+
+    fn polling_ui(&mut self, ui: &mut egui::Ui) {
+        let min = crate::config::MIN_POLL_INTERVAL_MS;
+        let config = &mut self.config;
+        egui::Grid::new("polling").num_columns(2).show(ui, |ui| {
+            ui.label(tr("キャプチャ間隔:"))
+                .on_hover_text(tr("画面の文字が変わっている間、この間隔で読み取る。短いほど早く訳が出るがCPU負荷が上がる"));
+            ui.add(egui::Slider::new(&mut config.capture_interval_ms, min..=2000).suffix(" ms"));
+            ui.end_row();
+
+            ui.label(tr("変化なしで減速:"))
+                .on_hover_text(tr("同じ画面がこの回数続いたら、読み取り間隔を下の値まで延ばす"));
+            ui.horizontal(|ui| {
+                ui.add(egui::DragValue::new(&mut config.idle_after).range(1..=100).suffix(tr(" 回")));
+                ui.label("→");
+                ui.add(egui::Slider::new(&mut config.idle_interval_ms, min..=5000).suffix(" ms"));
+            });
+            ui.end_row();
+
+            ui.label(tr("さらに減速:"))
+                .on_hover_text(tr("さらにこの回数続いたら、読み取り間隔をこの値まで延ばす（会話の少ない場面の省電力）"));
+            ui.horizontal(|ui| {
+                ui.add(egui::DragValue::new(&mut config.deep_idle_after).range(1..=500).suffix(tr(" 回")));
+                ui.label("→");
+                ui.add(egui::Slider::new(&mut config.deep_idle_interval_ms, min..=10000).suffix(" ms"));
+            });
+            ui.end_row();
+
+            ui.label(tr("前面のみ:"));
+            ui.checkbox(&mut config.foreground_only, tr("対象ウィンドウが前面の時だけ翻訳する"))
+                .on_hover_text(tr("オフにすると別のウィンドウを操作中も翻訳と表示を続ける（2画面でのプレイ向け）"));
+            ui.end_row();
+
+            ui.label(tr("待機中の確認間隔:"))
+                .on_hover_text(tr("対象ウィンドウが前面にない間・一時停止中に、戻ったかを確かめる間隔"));
+            ui.add(egui::Slider::new(&mut config.background_interval_ms, min..=5000).suffix(" ms"));
+            ui.end_row();
+        });
+        if config.deep_idle_after < config.idle_after {
+            ui.colored_label(egui::Color32::YELLOW, tr("「さらに減速」の回数が「変化なしで減速」より小さいため、2段目がすぐ適用されます"));
+        }
+        ui.weak(tr("次回の開始から反映。設定ファイル（プロファイル）ごとに保存されます"));
+    }
+
+    /// Config field holding the key combination for a hotkey action
+    fn hotkey_spec(config: &mut AppConfig, action: HotkeyAction) -> &mut String {
+        match action {
+            HotkeyAction::StartStop => &mut config.hotkey_start_stop,
+            HotkeyAction::ToggleOverlay => &mut config.hotkey_toggle_overlay,
+            HotkeyAction::Retranslate => &mut config.hotkey_retranslate,
+            HotkeyAction::Pause => &mut config.hotkey_pause,
+            HotkeyAction::Snip => &mut config.hotkey_snip,
+        }
+    }
+
+    /// (Re)register the global hotkeys when their settings change.
+    /// Invalid combinations are left out so half-typed edits don't register anything.
+    fn sync_hotkeys(&mut self, ctx: &egui::Context) {
+        let bindings: Vec<(HotkeyAction, String)> = HotkeyAction::ALL
+            .iter()
+            .map(|&action| {
+                let spec = Self::hotkey_spec(&mut self.config, action).clone();
+                (action, if hotkey::parse(&spec).is_some() { spec } else { String::new() })
+            })
+            .collect();
+        if self.hotkeys.as_ref().is_some_and(|h| h.bindings() == bindings.as_slice()) {
+            return;
+        }
+        // Unregister the old set first so unchanged combinations can be registered again
+        self.hotkeys = None;
+        self.hotkeys = Some(HotkeyListener::spawn(bindings, ctx.clone()));
+    }
+
+    fn handle_hotkey(&mut self, action: HotkeyAction) {
+        let controls = &self.overlay_controls;
+        match action {
+            HotkeyAction::StartStop => match self.status {
+                AppStatus::Running => self.stop(),
+                AppStatus::Stopping => {}
+                AppStatus::Idle | AppStatus::Error(_) => {
+                    if self.selected_window_index.is_none() {
+                        self.select_foreground_window();
+                    }
+                    self.start();
+                }
+            },
+            HotkeyAction::Snip => self.begin_snip(),
+            // The rest act on a running overlay
+            _ if !matches!(self.status, AppStatus::Running) => {}
+            HotkeyAction::ToggleOverlay => {
+                let now = !controls.visible.load(Ordering::SeqCst);
+                controls.visible.store(now, Ordering::SeqCst);
+                crate::log(&format!("[HOTKEY] オーバーレイ: {}", if now { "表示" } else { "非表示" }));
+                self.post_overlay_message(crate::pipeline::WM_APPLY_VISIBILITY);
+            }
+            HotkeyAction::Retranslate => controls.retranslate.store(true, Ordering::SeqCst),
+            HotkeyAction::Pause => self.toggle_pause(),
+        }
+    }
+
+    /// Pause or resume OCR and translation. The capture thread, OCR engine and overlay stay alive,
+    /// so resuming is immediate and the cache and history are kept.
+    fn toggle_pause(&self) {
+        let now = !self.overlay_controls.paused.load(Ordering::SeqCst);
+        self.overlay_controls.paused.store(now, Ordering::SeqCst);
+        crate::log_always(&format!("[PAUSE] {}", if now { "一時停止" } else { "再開" }));
+    }
+
+    /// Select the window in front, i.e. the game when a hotkey is pressed in-game
+    fn select_foreground_window(&mut self) {
+        use windows::Win32::UI::WindowsAndMessaging::*;
+        self.refresh_windows();
+        let foreground = unsafe { GetForegroundWindow() };
+        let mut pid = 0;
+        unsafe { GetWindowThreadProcessId(foreground, Some(&mut pid)) };
+        if pid != std::process::id() {
+            self.selected_window_index = self.window_list.iter().position(|&(hwnd, _)| hwnd == foreground.0 as isize);
+        }
+    }
+
+    /// Latest capture with its OCR regions outlined and labelled
+    fn ocr_preview_ui(&mut self, ui: &mut egui::Ui) {
+        let preview = self.overlay_controls.preview.lock().unwrap();
+        let Some(preview) = preview.as_ref() else {
+            ui.weak(tr("実行中、ゲームが前面にある間に更新されます"));
+            return;
+        };
+        let texture = preview_texture(&mut self.preview_texture, ui.ctx(), preview);
+        paint_preview(ui, preview, &texture, 1.0, true);
+        ui.label(trf("検出: {}個の領域", &[&preview.regions.len()]));
+    }
+
+    /// Small live view of what is being captured, so a black frame, the wrong window or bad cropping shows at a glance
+    fn capture_thumbnail_ui(&mut self, ui: &mut egui::Ui) {
+        const THUMBNAIL_WIDTH: f32 = 200.0;
+        let preview = self.overlay_controls.preview.lock().unwrap();
+        let Some(preview) = preview.as_ref() else {
+            ui.weak(tr("キャプチャ待ち..."));
+            return;
+        };
+        let texture = preview_texture(&mut self.preview_texture, ui.ctx(), preview);
+        ui.horizontal(|ui| {
+            paint_preview(ui, preview, &texture, THUMBNAIL_WIDTH / preview.width as f32, false)
+                .on_hover_ui(|ui| {
+                    paint_preview(ui, preview, &texture, 1.0, true);
+                });
+            ui.vertical(|ui| {
+                ui.monospace(format!("{}×{}", preview.frame_width, preview.frame_height));
+                ui.label(trf("検出: {}個の領域", &[&preview.regions.len()]));
+                if preview.blank {
+                    ui.colored_label(egui::Color32::YELLOW, tr("画面が真っ黒です"))
+                        .on_hover_text(tr("最小化されているか、キャプチャを拒否するゲームの可能性があります。ウィンドウを表示したままにするか、管理者として実行してください"));
+                }
+            });
+        });
+    }
+
+    /// Live counters for the current (or last) session
+    fn stats_ui(&self, ui: &mut egui::Ui) {
+        let stats = self.overlay_controls.stats.lock().unwrap().clone();
+        let or_dash = |v: Option<String>| v.unwrap_or_else(|| "-".to_string());
+        egui::Grid::new("session_stats").num_columns(2).striped(true).show(ui, |ui| {
+            let rows = [
+                (tr("キャプチャしたフレーム"), stats.frames.to_string()),
+                (tr("OCR実行回数"), stats.ocr_runs.to_string()),
+                (tr("OCRを省略したフレーム"), stats.frames_skipped.to_string()),
+                (
+                    tr("キャッシュヒット率"),
+                    or_dash(stats.cache_hit_rate().map(|r| {
+                        format!("{:.0}% ({}/{})", r * 100.0, stats.cache_hits, stats.cache_hits + stats.cache_misses)
+                    })),
+                ),
+                (tr("API呼び出し"), stats.api_calls.to_string()),
+                (tr("APIエラー"), stats.api_errors.to_string()),
+                (
+                    tr("平均レイテンシ（キャプチャ→表示）"),
+                    or_dash(stats.average_latency().map(|d| format!("{} ms", d.as_millis()))),
+                ),
+                (tr("翻訳した文字数"), stats.chars_translated.to_string()),
+            ];
+            for (label, value) in rows {
+                ui.label(label);
+                ui.monospace(value);
+                ui.end_row();
+            }
+        });
+    }
+
+    /// One-line strip with the latest result of each pipeline stage; the slowest stage is highlighted
+    fn pipeline_status_ui(&self, ui: &mut egui::Ui) {
+        let stats = self.overlay_controls.stats.lock().unwrap().clone();
+        let ms = |d: std::time::Duration| format!("{} ms", d.as_millis());
+        let slowest = [stats.last_capture, stats.last_ocr, stats.last_translation.unwrap_or_default()]
+            .into_iter()
+            .max()
+            .unwrap_or_default();
+        let stage = |ui: &mut egui::Ui, label: &str, value: String, elapsed: Option<std::time::Duration>| {
+            ui.label(label);
+            if elapsed.is_some_and(|d| d == slowest && !d.is_zero()) {
+                ui.colored_label(egui::Color32::YELLOW, egui::RichText::new(value).monospace());
+            } else {
+                ui.monospace(value);
+            }
+            ui.separator();
+        };
+        ui.horizontal(|ui| {
+            ui.label(tr("キャプチャ:"));
+            match stats.capture_ok {
+                Some(true) => ui.colored_label(egui::Color32::GREEN, "OK"),
+                Some(false) => ui.colored_label(egui::Color32::RED, tr("失敗")),
+                None => ui.monospace("-"),
+            };
+            if stats.capture_ok.is_some() {
+                ui.monospace(ms(stats.last_capture));
+            }
+            ui.separator();
+            let ocr = (stats.ocr_runs > 0).then_some(stats.last_ocr);
+            stage(ui, "OCR:", ocr.map_or_else(|| "-".to_string(), ms), ocr);
+            stage(ui, tr("翻訳:"), stats.last_translation.map_or_else(|| "-".to_string(), ms), stats.last_translation);
+            stage(ui, tr("翻訳待ち:"), trf("{}行", &[&stats.queue_depth]), None);
+            ui.label(tr("キャッシュヒット率"));
+            ui.monospace(
+                stats
+                    .cache_hit_rate()
+                    .map_or_else(|| "-".to_string(), |r| format!("{:.0}%", r * 100.0)),
+            );
+        });
+    }
+
+    /// Session history: every line shown since the last start, newest first
+    fn history_ui(&mut self, ui: &mut egui::Ui) {
+        // Held for the frame: the rows below only borrow the records they draw
+        let controls = self.overlay_controls.clone();
+        let records = controls.session_log.lock().unwrap();
+        ui.horizontal(|ui| {
+            ui.label(tr("検索:"));
+            ui.add(egui::TextEdit::singleline(&mut self.history_search).desired_width(240.0));
+            ui.label(trf("{}件", &[&records.len()]));
+        });
+        ui.horizontal(|ui| {
+            ui.label(tr("書き出し先:"));
+            ui.add(egui::TextEdit::singleline(&mut self.history_export_path).desired_width(240.0));
+            if ui
+                .add_enabled(!records.is_empty(), egui::Button::new(tr("エクスポート")))
+                .on_hover_text(tr(".csv / .json ならCSV・JSON（位置・時刻も含む）、.srt / .ass なら訳文の字幕、それ以外は原文と訳文を並べたテキスト"))
+                .clicked()
+            {
+                let path = std::path::Path::new(self.history_export_path.trim());
+                self.history_export_result = Some(match cache_io::export_session(&records.records, path) {
+                    Ok(n) => Ok(trf("{}件をエクスポートしました", &[&n])),
+                    Err(e) => Err(trf("エクスポート失敗: {}", &[&format!("{:#}", e)])),
+                });
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut self.config.transcript_auto_export, tr("停止時に自動で書き出す"))
+                .on_hover_text(trf("保存先: {}", &[&cache_io::transcript_dir().display()]));
+            ui.radio_value(&mut self.config.transcript_format, TranscriptFormat::Txt, "TXT");
+            ui.radio_value(&mut self.config.transcript_format, TranscriptFormat::Json, "JSON");
+            ui.radio_value(&mut self.config.transcript_format, TranscriptFormat::Csv, "CSV");
+            if ui.button(tr("フォルダを開く")).clicked() {
+                let dir = cache_io::transcript_dir();
+                let _ = std::fs::create_dir_all(&dir);
+                let _ = std::process::Command::new("explorer").arg(&dir).spawn();
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.label("Anki:");
+            ui.add(egui::TextEdit::singleline(&mut self.anki_export_path).desired_width(180.0));
+            let selected: Vec<SessionRecord> =
+                self.history_selected.iter().filter_map(|&id| records.get(id).cloned()).collect();
+            if ui
+                .add_enabled(!selected.is_empty(), egui::Button::new(trf("選択した{}件を書き出し", &[&selected.len()])))
+                .on_hover_text(tr("チェックした文を Anki で読み込める TSV（原文・訳文・画像）にする。画像は隣の _media フォルダに書き出すので、Anki の collection.media へコピーする"))
+                .clicked()
+            {
+                let path = std::path::Path::new(self.anki_export_path.trim());
+                self.history_export_result = Some(match cache_io::export_anki(&selected, path) {
+                    Ok(n) => Ok(trf("{}件をエクスポートしました", &[&n])),
+                    Err(e) => Err(trf("エクスポート失敗: {}", &[&format!("{:#}", e)])),
+                });
+            }
+            if ui.small_button(tr("選択解除")).clicked() {
+                self.history_selected.clear();
+            }
+            ui.checkbox(&mut self.config.history_screenshots, tr("画像も残す"))
+                .on_hover_text(tr("原文の領域の画像を履歴に残し、カードに付ける（OCR のみ、次の開始から有効）"));
+        });
+        match &self.history_export_result {
+            Some(Ok(msg)) => {
+                ui.colored_label(egui::Color32::GREEN, msg);
+            }
+            Some(Err(msg)) => {
+                ui.colored_label(egui::Color32::RED, msg);
+            }
+            None => {}
+        }
+        ui.separator();
+
+        let query = self.history_search.trim().to_lowercase();
+        let matching: Vec<usize> = records
+            .iter()
+            .rev()
+            .filter(|(_, r)| {
+                query.is_empty() || r.source.to_lowercase().contains(&query) || r.translation.to_lowercase().contains(&query)
+            })
+            .map(|(id, _)| id)
+            .collect();
+
+        // The line being corrected gets a full editor here, so every row below has the same height
+        let mut pin = None;
+        let mut cancel = false;
+        if let Some((id, draft)) = &mut self.history_edit {
+            match records.get(*id) {
+                Some(record) => {
+                    ui.label(&record.source);
+                    ui.add(egui::TextEdit::multiline(draft).desired_rows(2).desired_width(f32::INFINITY));
+                    ui.horizontal(|ui| {
+                        if ui.add_enabled(!draft.trim().is_empty(), egui::Button::new(tr("固定"))).clicked() {
+                            pin = Some((record.clone(), draft.trim().to_string()));
+                        }
+                        cancel |= ui.button(tr("キャンセル")).clicked();
+                    });
+                    ui.separator();
+                }
+                None => cancel = true,
+            }
+        }
+
+        let text_height = ui.text_style_height(&egui::TextStyle::Body);
+        let spacing = ui.spacing().item_spacing.y;
+        // Header line, source, translation and the separator (6 px by default)
+        let row_height = ui.spacing().interact_size.y + text_height * 2.0 + spacing * 3.0 + 6.0;
+        let mut edit = None;
+        egui::ScrollArea::vertical().auto_shrink([false, false]).show_rows(ui, row_height, matching.len(), |ui, rows| {
+            for &id in &matching[rows] {
+                let Some(record) = records.get(id) else {
+                    continue;
+                };
+                ui.horizontal(|ui| {
+                    let mut selected = self.history_selected.contains(&id);
+                    if ui.checkbox(&mut selected, "").on_hover_text(tr("Anki に書き出す")).changed() {
+                        if selected {
+                            self.history_selected.insert(id);
+                        } else {
+                            self.history_selected.remove(&id);
+                        }
+                    }
+                    ui.weak(format!("{} {}", record.time, record.engine.as_str()));
+                    if record.pinned {
+                        ui.weak(tr("📌固定済み"));
+                    }
+                    if ui.small_button(tr("コピー")).clicked() {
+                        ui.ctx().copy_text(format!("{}\n{}", record.source, record.translation));
+                    }
+                    if ui
+                        .small_button(tr("編集"))
+                        .on_hover_text(tr("訳を修正して固定する（以後この訳を使い、APIの結果で上書きしない）"))
+                        .clicked()
+                    {
+                        edit = Some((id, record.translation.clone()));
+                    }
+                });
+                // One line each, the whole text on hover
+                ui.add(egui::Label::new(&record.source).truncate());
+                ui.add(egui::Label::new(egui::RichText::new(&record.translation).strong()).truncate());
+                ui.separator();
+            }
+        });
+        drop(records);
+        if let Some((record, translation)) = pin {
+            self.pin_translation(&record, translation);
+            self.history_edit = None;
+        } else if cancel {
+            self.history_edit = None;
+        }
+        if edit.is_some() {
+            self.history_edit = edit;
+        }
+    }
+
+    /// Save a corrected translation as a human translation, so it always wins over the engine's.
+    /// A running session merges it into its own cache; otherwise the cache file is updated directly.
+    fn pin_translation(&mut self, record: &SessionRecord, translation: String) {
+        let entry = CacheEntry {
+            engine: None,
+            plugin: String::new(),
+            source_lang: record.source_lang.clone(),
+            target_lang: record.target_lang.clone(),
+            text: record.source.clone(),
+            translation: translation.clone(),
+        };
+        if self.is_running() {
+            self.overlay_controls.pending_pins.lock().unwrap().push(entry);
+        } else {
+            let mut cache = TranslationCache::load(&self.current_cache_scope());
+            cache.insert_entry(entry);
+            cache.save();
+        }
+        let mut session_log = self.overlay_controls.session_log.lock().unwrap();
+        for r in session_log.records.iter_mut().filter(|r| {
+            r.source == record.source && r.source_lang == record.source_lang && r.target_lang == record.target_lang
+        }) {
+            r.translation = translation.clone();
+            r.pinned = true;
+        }
+    }
+
+    fn is_running(&self) -> bool {
+        matches!(self.status, AppStatus::Running | AppStatus::Stopping)
+    }
+
+    /// A pre-translation job holds its own copy of the cache for the language pair until it finishes
+    fn is_pretranslating(&self) -> bool {
+        self.pretranslate_running.load(Ordering::SeqCst)
+    }
+
+    fn start_api_test(&self) {
+        if self.api_testing.load(Ordering::SeqCst) {
+            return;
+        }
+        let translator = lang::validate_pair(
+            self.config.translation_engine,
+            &self.config.source_lang,
+            &self.config.target_lang,
+        )
+        .map_err(pair_error_message)
+        .and_then(|_| Translator::from_config(&self.config).map_err(|e| format!("{:#}", e)));
+        let preferred_ocr = ocr::preferred_language(&self.config.source_lang);
+        let hwnd_raw = self.selected_window_index.and_then(|idx| self.window_list.get(idx)).map(|&(hwnd, _)| hwnd);
+        self.api_testing.store(true, Ordering::SeqCst);
+        self.api_test_result.lock().unwrap().clear();
+
+        let source = self.config.source_lang.clone();
+        let target = self.config.target_lang.clone();
+        let result = self.api_test_result.clone();
+        let testing = self.api_testing.clone();
+
+        std::thread::spawn(move || {
+            let rt = tokio::runtime::Runtime::new().unwrap();
+            // Stages are published as they finish so a slow API doesn't hide the others
+            let report = |stage: &'static str, res: Result<String, String>| result.lock().unwrap().push((stage, res));
+
+            let api = translator.and_then(|translator| {
+                let start = std::time::Instant::now();
+                let translations = rt
+                    .block_on(translator.translate_batch(vec!["Hello".to_string()], &source, &target))
+                    .map_err(|e| e.to_string())?;
+                let translated = translations.first()
+                    .and_then(|t| t.clone())
+                    .unwrap_or_else(|| "(empty)".to_string());
+                Ok(format!("\"{}\" ({:.0}ms)", translated, start.elapsed().as_millis()))
+            });
+            report("翻訳API", api);
+
+            // WinRT OCR needs COM on this thread
+            unsafe {
+                let _ = CoInitializeEx(None, COINIT_MULTITHREADED);
+            }
+            let engine = OCREngine::new(&preferred_ocr);
+            let ocr_stage = match &engine {
+                Ok(engine) if engine.language().to_lowercase().starts_with(&preferred_ocr) => {
+                    Ok(engine.language().to_string())
+                }
+                Ok(engine) => Err(trf(
+                    "{} の OCR 言語がインストールされていません（{} で代用）",
+                    &[&preferred_ocr, &engine.language()],
+                )),
+                Err(e) => Err(format!("{:#}", e)),
+            };
+            report("OCR", ocr_stage);
+
+            let capture = match hwnd_raw {
+                Some(hwnd_raw) => test_capture(&rt, hwnd_raw, engine.as_ref().ok()),
+                None => Err(tr("ウィンドウを選択してください").to_string()),
+            };
+            report("キャプチャ", capture);
+            drop(engine);
+            unsafe {
+                CoUninitialize();
+            }
+
+            testing.store(false, Ordering::SeqCst);
+        });
+    }
+
+    fn start_benchmark(&self) {
+        if self.benchmark_running.load(Ordering::SeqCst) {
+            return;
+        }
+        let source = if self.benchmark_image.trim().is_empty() {
+            let hwnd_raw = self.selected_window_index.and_then(|idx| self.window_list.get(idx)).map(|&(hwnd, _)| hwnd);
+            match hwnd_raw {
+                Some(hwnd_raw) => BenchmarkSource::Window(hwnd_raw),
+                None => {
+                    *self.benchmark_progress.lock().unwrap() = BenchmarkProgress {
+                        error: Some(tr("ウィンドウを選択してください").to_string()),
+                        ..Default::default()
+                    };
+                    return;
+                }
+            }
+        } else {
+            BenchmarkSource::Image(std::path::PathBuf::from(self.benchmark_image.trim()))
+        };
+        self.benchmark_running.store(true, Ordering::SeqCst);
+        self.benchmark_cancel.store(false, Ordering::SeqCst);
+        *self.benchmark_progress.lock().unwrap() = BenchmarkProgress::default();
+
+        let config = self.config.clone();
+        let iterations = self.benchmark_iterations;
+        let progress = self.benchmark_progress.clone();
+        let running = self.benchmark_running.clone();
+        let cancel = self.benchmark_cancel.clone();
+
+        std::thread::spawn(move || {
+            // WinRT OCR and WIC need COM on this thread
+            unsafe {
+                let _ = CoInitializeEx(None, COINIT_MULTITHREADED);
+            }
+            let rt = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+            if let Err(e) = rt.block_on(benchmark::run(config, source, iterations, progress.clone(), cancel)) {
+                crate::log_always(&format!("[BENCH ERR] {:#}", e));
+                progress.lock().unwrap().error = Some(format!("{:#}", e));
+            }
+            unsafe {
+                CoUninitialize();
+            }
+            running.store(false, Ordering::SeqCst);
+        });
+    }
+
+    /// Per-stage latency distribution of the last benchmark
+    fn benchmark_ui(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label(tr("回数:"));
+            ui.add(egui::DragValue::new(&mut self.benchmark_iterations).range(1..=200));
+            ui.add(
+                egui::TextEdit::singleline(&mut self.benchmark_image)
+                    .hint_text(tr("画像ファイル（空欄なら選択中のウィンドウ）"))
+                    .desired_width(240.0),
+            );
+            if self.benchmark_running.load(Ordering::SeqCst) {
+                if ui.button(tr("中断")).clicked() {
+                    self.benchmark_cancel.store(true, Ordering::SeqCst);
+                }
+                ui.ctx().request_repaint();
+            } else if ui
+                .add_enabled(!self.is_running(), egui::Button::new(tr("実行")))
+                .on_hover_text(tr("キャプチャ・OCR・翻訳を指定した回数だけ実行し、段階ごとの所要時間を測る（翻訳はキャッシュを使わずに毎回APIを呼ぶ）"))
+                .clicked()
+            {
+                self.start_benchmark();
+            }
+        });
+        let progress = self.benchmark_progress.lock().unwrap().clone();
+        if progress.total > 0 {
+            ui.add(
+                egui::ProgressBar::new(progress.done as f32 / progress.total as f32)
+                    .desired_width(200.0)
+                    .text(format!("{}/{}", progress.done, progress.total)),
+            );
+        }
+        if let Some(err) = &progress.error {
+            ui.colored_label(egui::Color32::RED, err);
+        }
+        if progress.stages.is_empty() {
+            return;
+        }
+        let ms = |d: Option<std::time::Duration>| d.map_or_else(|| "-".to_string(), |d| format!("{} ms", d.as_millis()));
+        egui::Grid::new("benchmark_results").num_columns(6).striped(true).show(ui, |ui| {
+            for header in [tr("段階"), tr("中央値"), "p95", tr("平均"), tr("最小"), tr("最大")] {
+                ui.strong(header);
+            }
+            ui.end_row();
+            for times in &progress.stages {
+                ui.label(tr(times.stage));
+                ui.monospace(ms(times.percentile(0.5)));
+                ui.monospace(ms(times.percentile(0.95)));
+                ui.monospace(ms(times.mean()));
+                ui.monospace(ms(times.percentile(0.0)));
+                ui.monospace(ms(times.percentile(1.0)));
+                ui.end_row();
+            }
+        });
+        if !progress.stages.iter().any(|s| s.stage == "翻訳") && progress.done > 0 {
+            ui.weak(tr("テキストが見つからなかったため、翻訳は測っていません"));
+        }
+    }
+
+    fn start_model_fetch(&self, ctx: &egui::Context) {
+        if self.model_list_fetching.load(Ordering::SeqCst) {
+            return;
+        }
+        let engine = self.config.translation_engine;
+        let translator = match Translator::from_config(&self.config) {
+            Ok(t) => t,
+            Err(e) => {
+                *self.model_list.lock().unwrap() = Some((engine, Err(format!("{:#}", e))));
+                return;
+            }
+        };
+        self.model_list_fetching.store(true, Ordering::SeqCst);
+
+        let list = self.model_list.clone();
+        let fetching = self.model_list_fetching.clone();
+        let ctx = ctx.clone();
+        std::thread::spawn(move || {
+            let rt = tokio::runtime::Runtime::new().unwrap();
+            let res = rt.block_on(translator.list_models()).map_err(|e| format!("{:#}", e));
+            *list.lock().unwrap() = Some((engine, res));
+            fetching.store(false, Ordering::SeqCst);
+            ctx.request_repaint();
+        });
+    }
+
+    /// Open a TCP connection to the endpoint's host and port, without sending a request
+    fn start_endpoint_probe(&self, ctx: &egui::Context) {
+        if self.endpoint_probing.load(Ordering::SeqCst) {
+            return;
+        }
+        let endpoint = self.config.local_llm_endpoint.trim().to_string();
+        let url = match parse_endpoint(&endpoint) {
+            Ok(url) => url,
+            Err(e) => {
+                *self.endpoint_probe.lock().unwrap() = Some((endpoint, Err(e)));
+                return;
+            }
+        };
+        self.endpoint_probing.store(true, Ordering::SeqCst);
+
+        let probe = self.endpoint_probe.clone();
+        let probing = self.endpoint_probing.clone();
+        let ctx = ctx.clone();
+        std::thread::spawn(move || {
+            *probe.lock().unwrap() = Some((endpoint, probe_port(&url)));
+            probing.store(false, Ordering::SeqCst);
+            ctx.request_repaint();
+        });
+    }
+
+    /// Inline problems with the Local LLM endpoint, and the result of the last port probe
+    fn endpoint_ui(&self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            if self.endpoint_probing.load(Ordering::SeqCst) {
+                ui.spinner();
+            } else if ui
+                .small_button(tr("接続確認"))
+                .on_hover_text(tr("ホストとポートに接続できるか確かめる（リクエストは送らない）"))
+                .clicked()
+            {
+                self.start_endpoint_probe(ui.ctx());
+            }
+            let endpoint = self.config.local_llm_endpoint.trim();
+            if let Err(e) = parse_endpoint(endpoint) {
+                ui.colored_label(egui::Color32::RED, e);
+                return;
+            }
+            match &*self.endpoint_probe.lock().unwrap() {
+                Some((probed, Ok(msg))) if probed == endpoint => {
+                    ui.colored_label(egui::Color32::GREEN, msg);
+                }
+                Some((probed, Err(e))) if probed == endpoint => {
+                    ui.colored_label(egui::Color32::RED, e);
+                }
+                _ => {}
+            }
+        });
+    }
+
+    fn start_deepl_usage_fetch(&self, ctx: &egui::Context) {
+        if self.deepl_usage_fetching.load(Ordering::SeqCst) {
+            return;
+        }
+        let key = self.config.deepl_api_key.trim().to_string();
+        // Same proxy and custom headers as translation requests, even while another engine is selected
+        let config = AppConfig { translation_engine: TranslationEngine::DeepL, ..self.config.clone() };
+        let translator = Translator::from_config(&config);
+        self.deepl_usage_fetching.store(true, Ordering::SeqCst);
+
+        let usage = self.deepl_usage.clone();
+        let fetching = self.deepl_usage_fetching.clone();
+        let ctx = ctx.clone();
+        std::thread::spawn(move || {
+            let rt = tokio::runtime::Runtime::new().unwrap();
+            let res = match translator {
+                Ok(translator) => rt.block_on(translator.deepl_usage()),
+                Err(e) => Err(e),
+            }
+            .map_err(|e| format!("{:#}", e));
+            *usage.lock().unwrap() = Some((key, Instant::now(), res));
+            fetching.store(false, Ordering::SeqCst);
+            ctx.request_repaint();
+        });
+    }
+
+    /// Keep the DeepL quota current (once per key, then every minute while translating)
+    /// and notify once when it runs low during a session
+    fn poll_deepl_usage(&mut self, ctx: &egui::Context) {
+        let key = self.config.deepl_api_key.trim();
+        if self.config.translation_engine != TranslationEngine::DeepL || key.is_empty() || self.deepl_key_editing {
+            return;
+        }
+        let (stale, remaining) = match &*self.deepl_usage.lock().unwrap() {
+            Some((fetched_key, fetched_at, result)) if fetched_key == key => (
+                self.is_running() && fetched_at.elapsed() >= DEEPL_USAGE_REFRESH,
+                result.as_ref().ok().map(DeepLUsage::remaining),
+            ),
+            _ => (true, None),
+        };
+        if stale {
+            self.start_deepl_usage_fetch(ctx);
+        }
+        if self.is_running() {
+            ctx.request_repaint_after(DEEPL_USAGE_REFRESH);
+        }
+
+        let low = remaining.is_some_and(|r| r < self.config.deepl_quota_warning);
+        if !low {
+            self.deepl_quota_warned = false;
+        } else if self.is_running() && !self.deepl_quota_warned {
+            self.deepl_quota_warned = true;
+            let message = trf("DeepL の今月の残りは {} 文字です", &[&remaining.unwrap_or(0)]);
+            crate::log_always(&format!("[DEEPL] {}", message));
+            if self.config.error_notifications {
+                self.notify(tr("DeepL の残り文字数が少なくなっています"), &message);
+            }
+        }
+    }
+
+    /// Remaining DeepL characters this month, shown next to the API key
+    fn deepl_usage_ui(&self, ui: &mut egui::Ui) {
+        if self.deepl_usage_fetching.load(Ordering::SeqCst) {
+            ui.spinner();
+        } else if ui.small_button("↻").on_hover_text(tr("使用量を更新")).clicked() {
+            self.start_deepl_usage_fetch(ui.ctx());
+        }
+        let usage = self.deepl_usage.lock().unwrap();
+        let Some((_, _, result)) = usage.as_ref().filter(|(key, ..)| key == self.config.deepl_api_key.trim()) else {
+            return;
+        };
+        match result {
+            Ok(u) if u.remaining() < self.config.deepl_quota_warning => {
+                ui.colored_label(
+                    egui::Color32::RED,
+                    trf("⚠ 残り {} / {} 文字", &[&u.remaining(), &u.character_limit]),
+                )
+                .on_hover_text(tr("使い切ると月末まで翻訳できなくなります"));
+            }
+            Ok(u) => {
+                ui.label(trf("残り {} / {} 文字", &[&u.remaining(), &u.character_limit]));
+            }
+            Err(e) => {
+                ui.weak(tr("使用量を取得できません")).on_hover_text(e);
+            }
+        }
+    }
+
+    fn prompt_sample_lines(&self) -> Vec<String> {
+        self.prompt_sample.lines().map(str::trim).filter(|l| !l.is_empty()).map(str::to_string).collect()
+    }
+
+    /// LLM presets, temperature and response length
+    fn llm_params_ui(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label(tr("LLMプリセット:"));
+            let mut chosen = None;
+            egui::ComboBox::from_id_salt("llm_preset")
+                .selected_text(tr("適用…"))
+                .show_ui(ui, |ui| {
+                    for preset in LlmPreset::builtin().iter().chain(&self.config.llm_presets) {
+                        if ui.selectable_label(false, &preset.name).clicked() {
+                            chosen = Some(preset.clone());
+                        }
+                    }
+                });
+            if let Some(preset) = chosen {
+                preset.apply(&mut self.config);
+                self.llm_preset_name = preset.name;
+            }
+            ui.add(egui::TextEdit::singleline(&mut self.llm_preset_name).desired_width(100.0));
+            let name = self.llm_preset_name.trim().to_string();
+            let is_builtin = LlmPreset::builtin().iter().any(|p| p.name == name);
+            let existing = self.config.llm_presets.iter().position(|p| p.name == name);
+            if ui
+                .add_enabled(!name.is_empty() && !is_builtin, egui::Button::new(tr("保存")))
+                .on_hover_text(tr("現在のモデル・温度・最大トークン数・プロンプトをこの名前で保存（同名は上書き）"))
+                .clicked()
+            {
+                let preset = LlmPreset::from_config(&name, &self.config);
+                match existing {
+                    Some(i) => self.config.llm_presets[i] = preset,
+                    None => self.config.llm_presets.push(preset),
+                }
+            }
+            if let Some(i) = existing {
+                if ui.button(tr("削除")).clicked() {
+                    self.config.llm_presets.remove(i);
+                }
+            }
+        });
+        ui.horizontal(|ui| {
+            let mut custom = self.config.llm_temperature.is_some();
+            if ui
+                .checkbox(&mut custom, tr("温度:"))
+                .on_hover_text(tr("低いほど直訳的で安定、高いほど自由な訳（オフ = 既定）"))
+                .changed()
+            {
+                self.config.llm_temperature = custom.then_some(0.3);
+            }
+            if let Some(temperature) = &mut self.config.llm_temperature {
+                ui.add(egui::Slider::new(temperature, 0.0..=1.5).step_by(0.05));
+            }
+            ui.separator();
+            ui.label(tr("最大トークン数:"));
+            ui.add(egui::DragValue::new(&mut self.config.llm_max_tokens).range(0..=8192).speed(16));
+            ui.weak(tr("(0 = 自動)"));
+        });
+    }
+
+    /// Editor for the LLM system prompt and user template, with the exact request body
+    /// for the sample lines and a button to translate them with the edited prompt
+    fn prompt_ui(&mut self, ui: &mut egui::Ui) {
+        ui.label(tr("指示文 (system):"));
+        ui.add(egui::TextEdit::multiline(&mut self.config.llm_system_prompt).desired_rows(3).desired_width(f32::INFINITY));
+        ui.label(tr("ユーザーメッセージ:"));
+        ui.add(egui::TextEdit::multiline(&mut self.config.llm_user_template).desired_rows(2).desired_width(f32::INFINITY));
+        ui.horizontal(|ui| {
+            ui.weak(tr("{from} / {to} = 言語名、{lines} = 番号付きの原文（空欄 = 既定）"));
+            if ui.small_button(tr("既定に戻す")).clicked() {
+                self.config.llm_system_prompt = crate::translate::DEFAULT_SYSTEM_PROMPT.to_string();
+                self.config.llm_user_template = crate::translate::DEFAULT_USER_TEMPLATE.to_string();
+            }
+        });
+        // An empty user template falls back to the default, which is just {lines}
+        let user = &self.config.llm_user_template;
+        if !(user.trim().is_empty() || user.contains("{lines}") || self.config.llm_system_prompt.contains("{lines}")) {
+            ui.colored_label(egui::Color32::RED, tr("{lines} がないため原文が送られません"));
+        }
+        ui.colored_label(egui::Color32::GRAY, tr("訳文は「1. 訳文」のように番号付きで1行ずつ返すよう指示してください"));
+
+        ui.label(tr("見本の行（1行に1つ）:"));
+        ui.add(egui::TextEdit::multiline(&mut self.prompt_sample).desired_rows(3).desired_width(f32::INFINITY));
+        let lines = self.prompt_sample_lines();
+        egui::CollapsingHeader::new(tr("送信するリクエスト")).id_salt("prompt_preview").show(ui, |ui| {
+            // Building a Translator creates an HTTP client, so only rebuild when the inputs change
+            let key = format!(
+                "{}\n{}\n{}\n{}",
+                Translator::settings_key(&self.config),
+                self.config.source_lang,
+                self.config.target_lang,
+                self.prompt_sample
+            );
+            if self.prompt_preview.as_ref().is_none_or(|(cached, _)| *cached != key) {
+                let preview = Translator::from_config(&self.config)
+                    .and_then(|t| t.preview_request(&lines, &self.config.source_lang, &self.config.target_lang))
+                    .map_err(|e| format!("{:#}", e));
+                self.prompt_preview = Some((key, preview));
+            }
+            match self.prompt_preview.as_ref().map(|(_, preview)| preview) {
+                Some(Ok(json)) => {
+                    ui.add(egui::TextEdit::multiline(&mut json.as_str()).code_editor().desired_width(f32::INFINITY));
+                }
+                Some(Err(e)) => {
+                    ui.colored_label(egui::Color32::RED, e);
+                }
+                None => {}
+            }
+        });
+
+        let testing = self.prompt_testing.load(Ordering::SeqCst);
+        ui.horizontal(|ui| {
+            if ui
+                .add_enabled(!testing && !lines.is_empty(), egui::Button::new(tr("見本の行で試す")))
+                .clicked()
+            {
+                self.start_prompt_test(ui.ctx().clone(), lines);
+            }
+            if testing {
+                ui.spinner();
+            }
+        });
+        match &*self.prompt_test_result.lock().unwrap() {
+            Some(Ok(results)) => {
+                egui::Grid::new("prompt_test").num_columns(2).striped(true).show(ui, |ui| {
+                    for (source, translation) in results {
+                        ui.label(source);
+                        match translation {
+                            Some(t) => ui.strong(t),
+                            None => ui.colored_label(egui::Color32::RED, tr("（応答に含まれていません）")),
+                        };
+                        ui.end_row();
+                    }
+                });
+            }
+            Some(Err(e)) => {
+                ui.colored_label(egui::Color32::RED, e);
+            }
+            None => {}
+        }
+    }
+
+    fn start_prompt_test(&self, ctx: egui::Context, lines: Vec<String>) {
+        let translator = match Translator::from_config(&self.config) {
+            Ok(t) => t,
+            Err(e) => {
+                *self.prompt_test_result.lock().unwrap() = Some(Err(format!("{:#}", e)));
+                return;
+            }
+        };
+        self.prompt_testing.store(true, Ordering::SeqCst);
+        let source = self.config.source_lang.clone();
+        let target = self.config.target_lang.clone();
+        let result = self.prompt_test_result.clone();
+        let testing = self.prompt_testing.clone();
+        std::thread::spawn(move || {
+            let rt = tokio::runtime::Runtime::new().unwrap();
+            let outcome = rt
+                .block_on(translator.translate_batch(lines.clone(), &source, &target))
+                .map(|translations| lines.into_iter().zip(translations).collect())
+                .map_err(|e| format!("{:#}", e));
+            *result.lock().unwrap() = Some(outcome);
+            testing.store(false, Ordering::SeqCst);
+            ctx.request_repaint();
+        });
+    }
+
+    /// Selected plugin and the plugins folder (plugins are picked in the engine row)
+    fn plugin_ui(&mut self, ui: &mut egui::Ui) {
+        match self.plugins.iter().find(|p| p.name == self.config.plugin_name) {
+            Some(plugin) => {
+                ui.label(trf("実行ファイル: {}", &[&plugin.command_path().display()]));
+            }
+            None => {
+                ui.colored_label(
+                    egui::Color32::RED,
+                    trf("プラグイン「{}」が見つかりません", &[&self.config.plugin_name]),
+                );
+            }
+        }
+        ui.horizontal(|ui| {
+            if ui.button(tr("プラグインフォルダを開く")).clicked() {
+                let dir = plugin::plugin_dir();
+                let _ = std::fs::create_dir_all(&dir);
+                let _ = std::process::Command::new("explorer").arg(&dir).spawn();
+            }
+            if ui
+                .button(tr("再読み込み"))
+                .on_hover_text(tr("プラグインフォルダのマニフェスト（*.toml）を読み直す"))
+                .clicked()
+            {
+                self.plugins = plugin::discover();
+            }
+        });
+    }
+
+    /// Model id field with a dropdown of the ids fetched from the server
+    fn model_ui(&mut self, ui: &mut egui::Ui) {
+        let engine = self.config.translation_engine;
+        let fetched = self.model_list.lock().unwrap().clone().filter(|(e, _)| *e == engine);
+        let fetching = self.model_list_fetching.load(Ordering::SeqCst);
+        ui.horizontal(|ui| {
+            ui.label(tr("モデル:"));
+            let model = match engine {
+                TranslationEngine::LocalLLM => &mut self.config.local_llm_model,
+                TranslationEngine::Groq => &mut self.config.groq_model,
+                TranslationEngine::DeepL | TranslationEngine::Echo | TranslationEngine::Plugin => return,
+            };
+            ui.text_edit_singleline(model);
+            if let Some((_, Ok(models))) = &fetched {
+                egui::ComboBox::from_id_salt("model_list")
+                    .selected_text(trf("{} 件", &[&models.len()]))
+                    .show_ui(ui, |ui| {
+                        for m in models {
+                            if ui.selectable_label(m == model, m).clicked() {
+                                *model = m.clone();
+                            }
+                        }
+                    });
+            }
+            if fetching {
+                ui.add_enabled(false, egui::Button::new(tr("取得中...")));
+            } else if ui.button(tr("モデル一覧を取得")).clicked() {
+                self.start_model_fetch(ui.ctx());
+            }
+        });
+        if let Some((_, Err(e))) = &fetched {
+            ui.colored_label(egui::Color32::RED, trf("モデル一覧を取得できません: {}", &[e]));
+        }
+    }
+
+    /// 選択中エンジンのカスタムHTTPヘッダー編集
+    fn custom_headers_ui(&mut self, ui: &mut egui::Ui) {
+        let engine = self.config.translation_engine;
+        egui::CollapsingHeader::new(tr("カスタムヘッダー"))
+            .id_salt("custom_headers")
+            .show(ui, |ui| {
+                let mut remove = None;
+                for (i, h) in self.config.custom_headers.iter_mut().enumerate() {
+                    if h.engine != engine {
+                        continue;
+                    }
+                    ui.horizontal(|ui| {
+                        ui.add(
+                            egui::TextEdit::singleline(&mut h.name)
+                                .hint_text("Authorization")
+                                .desired_width(140.0),
+                        );
+                        ui.add(
+                            egui::TextEdit::singleline(&mut h.value)
+                                .hint_text("Bearer ...")
+                                .password(true)
+                                .desired_width(200.0),
+                        );
+                        if ui.button(tr("削除")).clicked() {
+                            remove = Some(i);
+                        }
+                    });
+                }
+                if let Some(i) = remove {
+                    self.config.custom_headers.remove(i);
+                }
+                if ui.button(tr("+ 追加")).clicked() {
+                    self.config.custom_headers.push(CustomHeader {
+                        engine,
+                        name: String::new(),
+                        value: String::new(),
+                    });
+                }
+            });
+    }
+
+    fn current_cache_scope(&self) -> CacheScope {
+        CacheScope::from_config(&self.config)
+    }
+
+    fn export_cache(&mut self) {
+        let cache = TranslationCache::load(&self.current_cache_scope());
+        let path = std::path::Path::new(self.cache_io_path.trim());
+        self.cache_io_result = Some(match cache_io::export_cache(&cache, path) {
+            Ok(n) => Ok(trf("{}件をエクスポートしました", &[&n])),
+            Err(e) => Err(trf("エクスポート失敗: {}", &[&format!("{:#}", e)])),
+        });
+    }
+
+    fn import_cache(&mut self) {
+        let scope = self.current_cache_scope();
+        let mut cache = TranslationCache::load(&scope);
+        let path = std::path::Path::new(self.cache_io_path.trim());
+        self.cache_io_result = Some(match cache_io::import_cache(&mut cache, path, &scope) {
+            Ok(n) => {
+                cache.save();
+                crate::log_always(&format!("[CACHE] {}件をインポート: {}", n, path.display()));
+                Ok(trf("{}件をインポートしました (合計 {}件)", &[&n, &cache.len()]))
+            }
+            Err(e) => Err(trf("インポート失敗: {}", &[&format!("{:#}", e)])),
+        });
+    }
+
+    /// Table editor for the glossary, with CSV import (merged by term) and export
+    fn glossary_ui(&mut self, ui: &mut egui::Ui) {
+        let mut removed = None;
+        egui::Grid::new("glossary").num_columns(4).striped(true).show(ui, |ui| {
+            ui.strong(tr("用語"));
+            ui.strong(tr("訳語"));
+            ui.strong(tr("一致"));
+            ui.end_row();
+            for (i, entry) in self.config.glossary.iter_mut().enumerate() {
+                ui.add(egui::TextEdit::singleline(&mut entry.term).desired_width(160.0));
+                ui.add(
+                    egui::TextEdit::singleline(&mut entry.translation)
+                        .hint_text(tr("空 = 翻訳しない"))
+                        .desired_width(160.0),
+                );
+                egui::ComboBox::from_id_salt(("glossary_match", i))
+                    .selected_text(glossary_match_label(entry.mode))
+                    .show_ui(ui, |ui| {
+                        for mode in GlossaryMatch::ALL {
+                            ui.selectable_value(&mut entry.mode, mode, glossary_match_label(mode));
+                        }
+                    });
+                if ui.small_button(tr("削除")).clicked() {
+                    removed = Some(i);
+                }
+                ui.end_row();
+            }
+        });
+        if let Some(i) = removed {
+            self.config.glossary.remove(i);
+        }
+        if ui.button(tr("追加")).clicked() {
+            self.config.glossary.push(GlossaryEntry {
+                term: String::new(),
+                translation: String::new(),
+                mode: GlossaryMatch::Word,
+            });
+        }
+        ui.weak(tr("原文の用語は訳語に置き換えて表示されます。キャッシュ済みの行には再翻訳ホットキーで反映（次回の開始から有効）"));
+
+        ui.horizontal(|ui| {
+            ui.label(tr("CSV:"));
+            ui.add(egui::TextEdit::singleline(&mut self.glossary_io_path).desired_width(240.0));
+            if ui.button(tr("エクスポート")).clicked() {
+                let path = std::path::Path::new(self.glossary_io_path.trim());
+                self.glossary_io_result = Some(match cache_io::export_glossary(&self.config.glossary, path) {
+                    Ok(n) => Ok(trf("{}件をエクスポートしました", &[&n])),
+                    Err(e) => Err(trf("エクスポート失敗: {}", &[&format!("{:#}", e)])),
+                });
+            }
+            if ui
+                .button(tr("インポート"))
+                .on_hover_text(tr("term,translation,match 形式。同じ用語は上書き"))
+                .clicked()
+            {
+                let path = std::path::Path::new(self.glossary_io_path.trim());
+                self.glossary_io_result = Some(match cache_io::import_glossary(path) {
+                    Ok(entries) => {
+                        let count = entries.len();
+                        for entry in entries {
+                            match self.config.glossary.iter_mut().find(|e| e.term == entry.term) {
+                                Some(existing) => *existing = entry,
+                                None => self.config.glossary.push(entry),
+                            }
+                        }
+                        Ok(trf("{}件をインポートしました (合計 {}件)", &[&count, &self.config.glossary.len()]))
+                    }
+                    Err(e) => Err(trf("インポート失敗: {}", &[&format!("{:#}", e)])),
+                });
+            }
+        });
+        match &self.glossary_io_result {
+            Some(Ok(msg)) => {
+                ui.colored_label(egui::Color32::GREEN, msg);
+            }
+            Some(Err(msg)) => {
+                ui.colored_label(egui::Color32::RED, msg);
+            }
+            None => {}
+        }
+    }
+
+    fn export_settings(&mut self) {
+        let path = std::path::Path::new(self.settings_io_path.trim());
+        self.settings_io_result = Some(match self.config.export(path, self.settings_include_secrets) {
+            Ok(()) => Ok(trf("{} に書き出しました", &[&path.display()])),
+            Err(e) => Err(trf("エクスポート失敗: {}", &[&format!("{:#}", e)])),
+        });
+    }
+
+    fn import_settings(&mut self) {
+        let path = std::path::Path::new(self.settings_io_path.trim());
+        self.settings_io_result = Some(match self.config.import(path) {
+            Ok(config) => {
+                self.config = config;
+                i18n::set_language(self.config.ui_language);
+                if let Err(e) = self.config.save() {
+                    crate::log_always(&format!("Failed to save config: {}", e));
+                }
+                crate::log_always(&format!("[CONFIG] 設定をインポート: {}", path.display()));
+                Ok(tr("設定を読み込みました").to_string())
+            }
+            Err(e) => Err(trf("インポート失敗: {}", &[&format!("{:#}", e)])),
+        });
+    }
+
+    fn start_pretranslate(&self) {
+        if self.pretranslate_running.load(Ordering::SeqCst) {
+            return;
+        }
+        self.pretranslate_running.store(true, Ordering::SeqCst);
+        self.pretranslate_cancel.store(false, Ordering::SeqCst);
+        *self.pretranslate_progress.lock().unwrap() = PretranslateProgress::default();
+
+        let config = self.config.clone();
+        let path = std::path::PathBuf::from(self.pretranslate_path.trim());
+        let progress = self.pretranslate_progress.clone();
+        let running = self.pretranslate_running.clone();
+        let cancel = self.pretranslate_cancel.clone();
+
+        std::thread::spawn(move || {
+            let rt = tokio::runtime::Runtime::new().unwrap();
+            if let Err(e) = rt.block_on(pretranslate::run(config, &path, progress.clone(), cancel)) {
+                crate::log_always(&format!("[PRETRANSLATE ERR] {:#}", e));
+                progress.lock().unwrap().error = Some(format!("{:#}", e));
+            }
+            running.store(false, Ordering::SeqCst);
+        });
+    }
+
+    fn start_screenshot_batch(&self) {
+        if self.batch_running.load(Ordering::SeqCst) {
+            return;
+        }
+        self.batch_running.store(true, Ordering::SeqCst);
+        self.batch_cancel.store(false, Ordering::SeqCst);
+        *self.batch_progress.lock().unwrap() = BatchProgress::default();
+
+        let config = self.config.clone();
+        let dir = std::path::PathBuf::from(self.batch_dir.trim());
+        let progress = self.batch_progress.clone();
+        let running = self.batch_running.clone();
+        let cancel = self.batch_cancel.clone();
+
+        std::thread::spawn(move || {
+            // WinRT OCR and WIC need COM on this thread
+            unsafe {
+                let _ = CoInitializeEx(None, COINIT_MULTITHREADED);
+            }
+            let rt = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+            if let Err(e) = rt.block_on(screenshot_batch::run(config, &dir, progress.clone(), cancel)) {
+                crate::log_always(&format!("[BATCH ERR] {:#}", e));
+                progress.lock().unwrap().error = Some(format!("{:#}", e));
+            }
+            unsafe {
+                CoUninitialize();
+            }
+            running.store(false, Ordering::SeqCst);
+        });
+    }
+
+    /// ファン翻訳ファイルを人手翻訳として取り込む
+    fn seed_cache(&mut self) {
+        let mut cache = TranslationCache::load(&self.current_cache_scope());
+        let path = std::path::Path::new(self.cache_io_path.trim());
+        self.cache_io_result = Some(match cache_io::seed_from_file(
+            &mut cache,
+            path,
+            &self.config.source_lang,
+            &self.config.target_lang,
+        ) {
+            Ok(n) => {
+                cache.save();
+                crate::log_always(&format!("[CACHE] ファン翻訳 {}件を取り込み: {}", n, path.display()));
+                Ok(trf("ファン翻訳 {}件を取り込みました", &[&n]))
+            }
+            Err(e) => Err(trf("取り込み失敗: {}", &[&format!("{:#}", e)])),
+        });
+    }
+
+    /// Start / stop buttons, status and the running session's readouts
+    fn controls_ui(&mut self, ui: &mut egui::Ui, thumbnail_open: bool) {
+        ui.add_space(6.0);
+        ui.horizontal(|ui| {
+            match &self.status {
+                AppStatus::Idle | AppStatus::Error(_) => {
+                    if ui
+                        .add_enabled_ui(!self.is_pretranslating(), |ui| {
+                            ui.add_sized([120.0, 30.0], egui::Button::new(tr("開始")))
+                        })
+                        .inner
+                        .on_disabled_hover_text(tr("事前翻訳の実行中は開始できません"))
+                        .clicked()
+                    {
+                        self.start();
+                    }
+                }
+                AppStatus::Running => {
+                    if ui
+                        .add_sized([120.0, 30.0], egui::Button::new(tr("停止")))
+                        .clicked()
+                    {
+                        self.stop();
+                    }
+                    let paused = self.overlay_controls.paused.load(Ordering::SeqCst);
+                    if ui
+                        .add_sized([120.0, 30.0], egui::Button::new(if paused { tr("再開") } else { tr("一時停止") }))
+                        .on_hover_text(tr("OCRと翻訳を止めてオーバーレイを隠す（キャプチャとOCRは起動したまま、すぐに再開できる）"))
+                        .clicked()
+                    {
+                        self.toggle_pause();
+                    }
+                }
+                AppStatus::Stopping => {
+                    ui.add_enabled(false, egui::Button::new(tr("停止中...")).min_size(egui::vec2(120.0, 30.0)));
+                }
+            }
+
+            ui.add_space(16.0);
+
+            match &self.status {
+                AppStatus::Idle => {
+                    ui.label(tr("待機中"));
+                }
+                AppStatus::Running if self.overlay_controls.paused.load(Ordering::SeqCst) => {
+                    ui.colored_label(egui::Color32::YELLOW, tr("一時停止中"));
+                }
+                AppStatus::Running => {
+                    ui.colored_label(egui::Color32::GREEN, tr("実行中"));
+                }
+                AppStatus::Stopping => {
+                    ui.colored_label(egui::Color32::YELLOW, tr("停止中..."));
+                }
+                AppStatus::Error(msg) => {
+                    ui.colored_label(egui::Color32::RED, msg.as_str());
+                }
+            }
+        });
+        if matches!(self.status, AppStatus::Running) {
+            self.pipeline_status_ui(ui);
+            if thumbnail_open {
+                self.capture_thumbnail_ui(ui);
+            }
+        }
+        ui.add_space(2.0);
+    }
+
+    /// Tab switch and the GUI language
+    fn tab_bar_ui(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.selectable_value(&mut self.tab, Tab::Settings, tr("設定"));
+            ui.selectable_value(&mut self.tab, Tab::History, tr("翻訳履歴"));
+            ui.separator();
+            ui.label(tr("表示言語:"));
+            let before = self.config.ui_language;
+            ui.radio_value(&mut self.config.ui_language, UiLanguage::Auto, tr("自動"));
+            ui.radio_value(&mut self.config.ui_language, UiLanguage::Japanese, "日本語");
+            ui.radio_value(&mut self.config.ui_language, UiLanguage::English, "English");
+            if self.config.ui_language != before {
+                i18n::set_language(self.config.ui_language);
+            }
+        });
+    }
+
+    /// Target window, capture region and startup settings
+    fn capture_settings_ui(&mut self, ui: &mut egui::Ui) {
+        ui.label(tr("対象ウィンドウ"));
+        ui.horizontal(|ui| {
+            if ui.button(tr("更新")).clicked() {
+                self.refresh_windows();
+            }
+            let selected_label = self
+                .selected_window_index
+                .and_then(|idx| self.window_list.get(idx))
+                .map(|(_, title)| title.as_str())
+                .unwrap_or(tr("-- 選択してください --"));
+
+            egui::ComboBox::from_id_salt("window_select")
+                .selected_text(selected_label)
+                .width(400.0)
+                .show_ui(ui, |ui| {
+                    for (i, (_, title)) in self.window_list.iter().enumerate() {
+                        ui.selectable_value(
+                            &mut self.selected_window_index,
+                            Some(i),
+                            title,
+                        );
+                    }
+                });
+            if self.window_picker.is_some() {
+                ui.spinner();
+                ui.label(tr("選ぶウィンドウをクリック（右クリックで中止）"));
+            } else if ui
+                .button(tr("クリックで選ぶ"))
+                .on_hover_text(tr("カーソルが十字になったら、翻訳するウィンドウをクリック"))
+                .clicked()
+            {
+                self.window_pick_error = None;
+                self.window_picker = Some(WindowPicker::spawn(ui.ctx().clone()));
+            }
+        });
+        if let Some(err) = &self.window_pick_error {
+            ui.colored_label(egui::Color32::RED, err);
+        }
+        ui.horizontal(|ui| {
+            let selected = self.selected_window_index.and_then(|idx| self.window_list.get(idx));
+            if ui
+                .add_enabled(selected.is_some(), egui::Button::new(tr("範囲を指定…")))
+                .on_hover_text(tr("画面をドラッグして翻訳する範囲・除外する範囲を指定"))
+                .clicked()
+            {
+                if let Some(&(hwnd, _)) = selected {
+                    self.region_editor = Some(RegionEditor::open(ui.ctx(), hwnd));
+                }
+            }
+            let regions = if self.config.capture_regions.is_empty() {
+                tr("画面全体").to_string()
+            } else {
+                trf("{}個", &[&self.config.capture_regions.len()])
+            };
+            ui.label(trf(
+                "翻訳する範囲: {} / 除外マスク: {}",
+                &[&regions, &self.config.capture_masks.len()],
+            ));
+        });
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut self.config.auto_start, tr("自動開始"))
+                .on_hover_text(tr("登録したゲームのウィンドウが現れたら自動で開始し、閉じたら停止する"));
+            if self.config.auto_start {
+                ui.weak(tr("（現在の設定で開始。プロファイルは使われない）"));
+            }
+            let selected = self
+                .selected_window_index
+                .and_then(|idx| self.window_list.get(idx))
+                .map(|(_, title)| title.clone());
+            if ui
+                .add_enabled(selected.is_some(), egui::Button::new(tr("選択中のウィンドウを登録")))
+                .clicked()
+            {
+                if let Some(title) = selected {
+                    if !self.config.auto_start_titles.contains(&title) {
+                        self.config.auto_start_titles.push(title);
+                    }
+                }
+            }
+        });
+        let mut removed = None;
+        for (i, title) in self.config.auto_start_titles.iter_mut().enumerate() {
+            ui.horizontal(|ui| {
+                ui.add(egui::TextEdit::singleline(title).desired_width(300.0))
+                    .on_hover_text(tr("タイトルの一部でも一致すれば対象になる"));
+                if ui.button(tr("削除")).clicked() {
+                    removed = Some(i);
+                }
+            });
+        }
+        if let Some(i) = removed {
+            self.config.auto_start_titles.remove(i);
+        }
+        self.startup_ui(ui);
+    }
+
+    /// OCR settings; returns whether the OCR preview is open
+    fn ocr_settings_ui(&mut self, ui: &mut egui::Ui) -> bool {
+        ui.horizontal(|ui| {
+            ui.label(tr("原文の入力:"));
+            ui.radio_value(&mut self.config.input_source, InputSource::Ocr, "OCR");
+            ui.radio_value(&mut self.config.input_source, InputSource::Clipboard, tr("クリップボード"))
+                .on_hover_text(tr("Textractor の「Copy to Clipboard」拡張や Agent がクリップボードに書き出した文を翻訳する"));
+            ui.radio_value(&mut self.config.input_source, InputSource::WebSocket, "WebSocket")
+                .on_hover_text(tr("Textractor の WebSocket 拡張などのサーバーから文を受け取って翻訳する"));
+            ui.radio_value(&mut self.config.input_source, InputSource::Audio, tr("音声 (Whisper)"))
+                .on_hover_text(tr("再生中の音声を whisper.cpp で文字に起こして翻訳する（字幕のないボイス付きムービーなど）"));
+        });
+        match self.config.input_source {
+            InputSource::WebSocket => {
+                ui.horizontal(|ui| {
+                    ui.label(tr("接続先:"));
+                    ui.add(egui::TextEdit::singleline(&mut self.config.texthook_url).desired_width(240.0));
+                });
+            }
+            InputSource::Audio => {
+                egui::Grid::new("whisper_paths").num_columns(2).show(ui, |ui| {
+                    ui.label("whisper-cli:");
+                    ui.add(egui::TextEdit::singleline(&mut self.config.whisper_command).desired_width(320.0))
+                        .on_hover_text(tr("whisper.cpp の whisper-cli.exe のパス"));
+                    ui.end_row();
+                    ui.label(tr("モデル:"));
+                    ui.add(egui::TextEdit::singleline(&mut self.config.whisper_model).desired_width(320.0))
+                        .on_hover_text(tr("ggml-small.bin などのモデルファイルのパス"));
+                    ui.end_row();
+                });
+                ui.label(tr("再生デバイスの音をすべて録るため、ゲーム以外の音も拾います"));
+            }
+            InputSource::Ocr | InputSource::Clipboard => {}
+        }
+        if self.config.input_source != InputSource::Ocr {
+            ui.label(tr("フック・音声の文には位置がないため、ゲーム画面の下部に字幕として表示します（次回の開始から反映）"));
+        }
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut self.config.clipboard_watch, tr("コピーした文を翻訳してポップアップ表示"))
+                .on_hover_text(tr("翻訳を開始していなくても、コピーした文をその場で翻訳する（ランチャー・チャットなど。入力がクリップボードのセッション中はオーバーレイに表示）"));
+            if self.clipboard_watch.is_some() {
+                ui.label(tr("監視中"));
+            } else if let Some((_, _, err)) = &self.clipboard_watch_error {
+                ui.colored_label(egui::Color32::RED, err);
+            }
+        });
+        ui.label(trf(
+            "OCR言語: {}（ソース言語に合わせて選択、自動検出なら英語）",
+            &[&crate::ocr::preferred_language(&self.config.source_lang)],
+        ));
+        ui.checkbox(&mut self.config.gloss_mode, tr("学習モード（語ごとの訳語を原文の下に表示）"))
+            .on_hover_text(tr("日本語・中国語の原文を辞書で語に区切り、各語の訳語を原文の下に表示する。訳文は Ctrl+Alt+T を押した時だけ表示（辞書は Ctrl+クリックのポップアップでダウンロード、次回の開始から反映）"));
+        ui.checkbox(&mut self.config.overlay_debug_regions, tr("検出領域の枠を表示"))
+            .on_hover_text(tr("OCRで検出したテキスト領域を枠線で囲む（次回の開始から反映）"));
+        ui.checkbox(&mut self.config.capture_thumbnail, tr("実行中にキャプチャを縮小表示"))
+            .on_hover_text(tr("下部にキャプチャ中の画面と検出領域を表示し、真っ黒・別のウィンドウ・切り抜きのずれにすぐ気付けるようにする"));
+        ui
+            .collapsing(tr("OCRプレビュー"), |ui| self.ocr_preview_ui(ui))
+            .body_returned
+            .is_some()
+    }
+
+    /// Translation engine, language pair and cache settings
+    fn translation_settings_ui(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.radio_value(
+                &mut self.config.translation_engine,
+                TranslationEngine::DeepL,
+                "DeepL",
+            );
+            ui.radio_value(
+                &mut self.config.translation_engine,
+                TranslationEngine::LocalLLM,
+                "Local LLM",
+            );
+            ui.radio_value(
+                &mut self.config.translation_engine,
+                TranslationEngine::Groq,
+                "Groq",
+            );
+            ui.radio_value(
+                &mut self.config.translation_engine,
+                TranslationEngine::Echo,
+                tr("Echo (テスト)"),
+            );
+            for plugin in &self.plugins {
+                let selected = self.config.translation_engine == TranslationEngine::Plugin
+                    && self.config.plugin_name == plugin.name;
+                if ui
+                    .radio(selected, &plugin.name)
+                    .on_hover_text(trf("プラグイン: {}", &[&plugin.command_path().display()]))
+                    .clicked()
+                {
+                    self.config.translation_engine = TranslationEngine::Plugin;
+                    self.config.plugin_name = plugin.name.clone();
+                }
+            }
+        });
+
+        match self.config.translation_engine {
+            TranslationEngine::DeepL => {
+                ui.horizontal(|ui| {
+                    ui.label(tr("APIキー:"));
+                    let key_edit = ui.add(
+                        egui::TextEdit::singleline(&mut self.config.deepl_api_key)
+                            .password(true)
+                            .desired_width(300.0),
+                    );
+                    self.deepl_key_editing = key_edit.has_focus();
+                    if !self.config.deepl_api_key.trim().is_empty() {
+                        self.deepl_usage_ui(ui);
+                    }
+                });
+            }
+            TranslationEngine::LocalLLM => {
+                ui.horizontal(|ui| {
+                    ui.label(tr("エンドポイント:"));
+                    ui.text_edit_singleline(&mut self.config.local_llm_endpoint);
+                });
+                self.endpoint_ui(ui);
+                self.model_ui(ui);
+                ui.horizontal(|ui| {
+                    ui.label(tr("API形式:"));
+                    ui.radio_value(&mut self.config.local_llm_api, LocalLlmApi::Auto, tr("自動"))
+                        .on_hover_text(tr("/v1/models に応答するサーバー（LM Studio, llama.cpp, TabbyAPI等）は Chat API を使用し、失敗時は Completions に切り替えます"));
+                    ui.radio_value(&mut self.config.local_llm_api, LocalLlmApi::Chat, "Chat");
+                    ui.radio_value(&mut self.config.local_llm_api, LocalLlmApi::Completions, "Completions")
+                        .on_hover_text(tr("プロンプトテンプレートをモデル名から推定します（Gemma / Llama 3 / ChatML / Mistral）"));
+                });
+                ui.checkbox(
+                    &mut self.config.local_llm_accept_invalid_certs,
+                    tr("自己署名証明書を許可 (LAN内サーバー用)"),
+                );
+                self.llm_params_ui(ui);
+                ui.collapsing(tr("プロンプト"), |ui| self.prompt_ui(ui));
+            }
+            TranslationEngine::Groq => {
+                ui.horizontal(|ui| {
+                    ui.label(tr("APIキー:"));
+                    ui.add(
+                        egui::TextEdit::singleline(&mut self.config.groq_api_key)
+                            .password(true)
+                            .desired_width(300.0),
+                    );
+                });
+                self.model_ui(ui);
+                self.llm_params_ui(ui);
+                ui.collapsing(tr("プロンプト"), |ui| self.prompt_ui(ui));
+            }
+            TranslationEngine::Echo => {
+                ui.horizontal(|ui| {
+                    ui.label(tr("出力:"));
+                    ui.radio_value(&mut self.config.echo_style, EchoStyle::Bracket, tr("[原文]"));
+                    ui.radio_value(&mut self.config.echo_style, EchoStyle::Reverse, tr("逆順"));
+                });
+                ui.label(tr("API不要。キャプチャ・OCR・オーバーレイ位置の確認用です。"));
+            }
+            TranslationEngine::Plugin => self.plugin_ui(ui),
+        }
+
+        if !matches!(self.config.translation_engine, TranslationEngine::Echo | TranslationEngine::Plugin) {
+            self.custom_headers_ui(ui);
+        }
+
+        egui::CollapsingHeader::new(tr("プロキシ"))
+            .id_salt("proxy_settings")
+            .show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("URL:");
+                    ui.add(
+                        egui::TextEdit::singleline(&mut self.config.proxy_url)
+                            .hint_text("http://host:8080 / socks5://host:1080")
+                            .desired_width(300.0),
+                    );
+                });
+                ui.horizontal(|ui| {
+                    ui.label(tr("ユーザー名:"));
+                    ui.add(
+                        egui::TextEdit::singleline(&mut self.config.proxy_username)
+                            .desired_width(120.0),
                     );
-                    ui.radio_value(
-                        &mut self.config.translation_engine,
-                        TranslationEngine::Groq,
-                        "Groq",
+                    ui.label(tr("パスワード:"));
+                    ui.add(
+                        egui::TextEdit::singleline(&mut self.config.proxy_password)
+                            .password(true)
+                            .desired_width(120.0),
+                    );
+                });
+                ui.horizontal(|ui| {
+                    ui.label(tr("除外:"));
+                    ui.add(
+                        egui::TextEdit::singleline(&mut self.config.no_proxy)
+                            .hint_text("localhost,127.0.0.1")
+                            .desired_width(300.0),
                     );
                 });
+            });
 
-                match self.config.translation_engine {
-                    TranslationEngine::DeepL => {
-                        ui.horizontal(|ui| {
-                            ui.label("APIキー:");
-                            ui.add(
-                                egui::TextEdit::singleline(&mut self.config.deepl_api_key)
-                                    .password(true)
-                                    .desired_width(300.0),
-                            );
-                        });
-                    }
-                    TranslationEngine::LocalLLM => {
-                        ui.horizontal(|ui| {
-                            ui.label("エンドポイント:");
-                            ui.text_edit_singleline(&mut self.config.local_llm_endpoint);
-                        });
-                        ui.horizontal(|ui| {
-                            ui.label("モデル:");
-                            ui.text_edit_singleline(&mut self.config.local_llm_model);
-                        });
-                    }
-                    TranslationEngine::Groq => {
-                        ui.horizontal(|ui| {
-                            ui.label("APIキー:");
-                            ui.add(
-                                egui::TextEdit::singleline(&mut self.config.groq_api_key)
-                                    .password(true)
-                                    .desired_width(300.0),
-                            );
-                        });
-                        ui.horizontal(|ui| {
-                            ui.label("モデル:");
-                            ui.text_edit_singleline(&mut self.config.groq_model);
-                        });
-                    }
+        ui.horizontal(|ui| {
+            let engine = self.config.translation_engine;
+            ui.label(tr("ソース言語:"));
+            language_combo(ui, "source_lang", &mut self.config.source_lang, engine, false);
+            ui.label(tr("ターゲット言語:"));
+            language_combo(ui, "target_lang", &mut self.config.target_lang, engine, true);
+        });
+
+        let testing = self.api_testing.load(Ordering::SeqCst);
+        if testing {
+            ui.add_enabled(false, egui::Button::new(tr("テスト中...")));
+            ui.ctx().request_repaint();
+        } else if ui.button(tr("接続テスト")).on_hover_text(tr("翻訳API・OCR言語・選択中ウィンドウのキャプチャを順に確認します")).clicked() {
+            self.start_api_test();
+        }
+        for (stage, res) in self.api_test_result.lock().unwrap().iter() {
+            ui.horizontal(|ui| {
+                ui.label(format!("{}:", tr(stage)));
+                match res {
+                    Ok(msg) => ui.colored_label(egui::Color32::GREEN, format!("OK {}", msg)),
+                    Err(msg) => ui.colored_label(egui::Color32::RED, format!("NG {}", msg)),
+                };
+            });
+        }
+        egui::CollapsingHeader::new(tr("ベンチマーク")).id_salt("benchmark").show(ui, |ui| self.benchmark_ui(ui));
+        ui.separator();
+        let title = if self.config.glossary.is_empty() {
+            tr("用語集").to_string()
+        } else {
+            trf("用語集 ({})", &[&self.config.glossary.len()])
+        };
+        egui::CollapsingHeader::new(title).id_salt("glossary").show(ui, |ui| self.glossary_ui(ui));
+        egui::CollapsingHeader::new(tr("翻訳キャッシュ")).id_salt("translation_cache").show(ui, |ui| {
+            ui.horizontal(|ui| {
+                ui.label(tr("ファイル:"));
+                ui.add(
+                    egui::TextEdit::singleline(&mut self.cache_io_path)
+                        .hint_text("cache.csv / cache.tmx / fan.xlf")
+                        .desired_width(240.0),
+                );
+                if ui.button(tr("エクスポート")).clicked() {
+                    self.export_cache();
+                }
+                // 実行中・事前翻訳中はそちらの保存が取り込んだ分を上書きしうるためインポート不可
+                let cache_busy = self.is_running() || self.is_pretranslating();
+                if ui
+                    .add_enabled(!cache_busy, egui::Button::new(tr("インポート")))
+                    .clicked()
+                {
+                    self.import_cache();
+                }
+                if ui
+                    .add_enabled(!cache_busy, egui::Button::new(tr("ファン翻訳取り込み")))
+                    .on_hover_text(tr("TMX / XLIFF / CSV の既存翻訳を取り込み、APIより優先して使用します"))
+                    .clicked()
+                {
+                    self.seed_cache();
+                }
+            });
+            match &self.cache_io_result {
+                Some(Ok(msg)) => {
+                    ui.colored_label(egui::Color32::GREEN, msg);
+                }
+                Some(Err(msg)) => {
+                    ui.colored_label(egui::Color32::RED, msg);
                 }
+                None => {}
+            }
 
+            // 事前翻訳: 1行1文字列のテキストファイルをまとめて翻訳しキャッシュへ
+            ui.horizontal(|ui| {
+                ui.label(tr("事前翻訳:"));
+                ui.add(
+                    egui::TextEdit::singleline(&mut self.pretranslate_path)
+                        .hint_text(tr("strings.txt (1行1文字列)"))
+                        .desired_width(240.0),
+                );
+                if self.is_pretranslating() {
+                    if ui.button(tr("中断")).clicked() {
+                        self.pretranslate_cancel.store(true, Ordering::SeqCst);
+                    }
+                    ui.ctx().request_repaint();
+                } else if ui
+                    .add_enabled(!self.is_running(), egui::Button::new(tr("実行")))
+                    .clicked()
+                {
+                    self.start_pretranslate();
+                }
+            });
+            let progress = self.pretranslate_progress.lock().unwrap().clone();
+            if progress.total > 0 || progress.error.is_some() {
                 ui.horizontal(|ui| {
-                    ui.label("ソース言語:");
-                    ui.text_edit_singleline(&mut self.config.source_lang);
-                    ui.label("ターゲット言語:");
-                    ui.text_edit_singleline(&mut self.config.target_lang);
+                    ui.add(
+                        egui::ProgressBar::new(progress.done as f32 / progress.total.max(1) as f32)
+                            .desired_width(200.0)
+                            .text(format!("{}/{}", progress.done, progress.total)),
+                    );
+                    if progress.failed > 0 {
+                        ui.label(trf("失敗 {}件", &[&progress.failed]));
+                    }
+                    if let Some(err) = &progress.error {
+                        ui.colored_label(egui::Color32::RED, err);
+                    }
                 });
+            }
 
+            // 画像の一括翻訳: フォルダ内のスクリーンショットを OCR・翻訳して訳文入りの画像とレポートを書き出す
+            ui.horizontal(|ui| {
+                ui.label(tr("画像フォルダ:"));
+                ui.add(
+                    egui::TextEdit::singleline(&mut self.batch_dir)
+                        .hint_text(tr("スクリーンショットのフォルダ"))
+                        .desired_width(240.0),
+                )
+                .on_hover_text(tr("フォルダ内の画像を OCR・翻訳し、訳文を描いた画像と report.txt を translated フォルダに書き出す"));
+                if self.batch_running.load(Ordering::SeqCst) {
+                    if ui.button(tr("中断")).clicked() {
+                        self.batch_cancel.store(true, Ordering::SeqCst);
+                    }
+                    ui.ctx().request_repaint();
+                } else if ui
+                    .add_enabled(!self.batch_dir.trim().is_empty(), egui::Button::new(tr("実行")))
+                    .clicked()
+                {
+                    self.start_screenshot_batch();
+                }
+            });
+            let progress = self.batch_progress.lock().unwrap().clone();
+            if progress.total > 0 || progress.error.is_some() {
                 ui.horizontal(|ui| {
-                    let testing = self.api_testing.load(Ordering::SeqCst);
-                    if testing {
-                        ui.add_enabled(false, egui::Button::new("テスト中..."));
-                        ui.ctx().request_repaint();
-                    } else if ui.button("接続テスト").clicked() {
-                        self.start_api_test();
+                    ui.add(
+                        egui::ProgressBar::new(progress.done as f32 / progress.total.max(1) as f32)
+                            .desired_width(200.0)
+                            .text(format!("{}/{}", progress.done, progress.total)),
+                    );
+                    if progress.failed > 0 {
+                        ui.label(trf("失敗 {}件", &[&progress.failed]));
                     }
-
-                    if let Some(msg) = self.api_test_result.lock().unwrap().as_ref() {
-                        if msg.starts_with("OK") {
-                            ui.colored_label(egui::Color32::GREEN, msg);
-                        } else {
-                            ui.colored_label(egui::Color32::RED, msg);
+                    if let Some(dir) = &progress.output {
+                        if ui.button(tr("フォルダを開く")).clicked() {
+                            let _ = std::process::Command::new("explorer").arg(dir).spawn();
                         }
                     }
+                    if let Some(err) = &progress.error {
+                        ui.colored_label(egui::Color32::RED, err);
+                    }
                 });
-            });
-
-            ui.add_space(8.0);
+            }
+        });
+    }
 
-            // === Overlay Appearance ===
-            ui.group(|ui| {
-                ui.label("オーバーレイ外観");
-                ui.horizontal(|ui| {
-                    ui.label("テキスト色:");
-                    ui.color_edit_button_rgba_unmultiplied(&mut self.config.overlay_text_color);
-                    ui.label("背景色:");
-                    ui.color_edit_button_rgba_unmultiplied(&mut self.config.overlay_bg_color);
-                });
+    /// Overlay appearance settings
+    fn overlay_settings_ui(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label(tr("表示位置:"));
+            ui.radio_value(&mut self.config.display_mode, DisplayMode::Overlay, tr("原文の上"));
+            ui.radio_value(&mut self.config.display_mode, DisplayMode::PanelRight, tr("右パネル"));
+            ui.radio_value(&mut self.config.display_mode, DisplayMode::PanelLeft, tr("左パネル"));
+        });
+        ui.horizontal(|ui| {
+            ui.label(tr("訳文の出力:"));
+            ui.radio_value(&mut self.config.readout_mode, ReadoutMode::Off, tr("オフ"));
+            ui.radio_value(&mut self.config.readout_mode, ReadoutMode::Speech, tr("読み上げ"))
+                .on_hover_text(tr("新しく表示された訳文を Windows の音声合成で読み上げる"));
+            ui.radio_value(&mut self.config.readout_mode, ReadoutMode::Clipboard, tr("クリップボード"))
+                .on_hover_text(tr("新しく表示された訳文をクリップボードに書き込む（スクリーンリーダーのクリップボード読み上げ機能用）"));
+            if self.config.readout_mode == ReadoutMode::Speech {
+                ui.label(tr("速さ:"));
+                ui.add(egui::Slider::new(&mut self.config.speech_rate, -10..=10));
+            }
+            ui.label(tr("(次回の開始から反映)"));
+        });
+        ui.horizontal(|ui| {
+            ui.label(tr("原文の読み:"))
+                .on_hover_text(tr("原文に読みを付けて表示する（日本語は JMdict のふりがな、中国語は CC-CEDICT の声調記号付きピンイン。辞書は Ctrl+クリックのポップアップでダウンロード）"));
+            ui.radio_value(&mut self.config.source_reading, SourceReading::Off, tr("オフ"));
+            ui.radio_value(&mut self.config.source_reading, SourceReading::WithTranslation, tr("訳文に添える"));
+            ui.radio_value(&mut self.config.source_reading, SourceReading::Instead, tr("訳文の代わりに表示"));
+            ui.label(tr("(次回の開始から反映)"));
+        });
+        if self.config.display_mode != DisplayMode::Overlay {
+            ui.horizontal(|ui| {
+                ui.label(tr("パネル幅:"));
+                ui.add(
+                    egui::Slider::new(&mut self.config.panel_width, 200.0..=800.0)
+                        .suffix(" px"),
+                );
             });
-
-            ui.add_space(12.0);
-
-            // === Controls ===
+        } else {
             ui.horizontal(|ui| {
-                match &self.status {
-                    AppStatus::Idle | AppStatus::Error(_) => {
-                        if ui
-                            .add_sized([120.0, 30.0], egui::Button::new("開始"))
-                            .clicked()
-                        {
-                            self.start();
+                ui.label(tr("縦書き:"));
+                ui.radio_value(&mut self.config.overlay_vertical_text, VerticalText::Off, tr("オフ"));
+                ui.radio_value(&mut self.config.overlay_vertical_text, VerticalText::Auto, tr("縦長の領域のみ"));
+                ui.radio_value(&mut self.config.overlay_vertical_text, VerticalText::Always, tr("常に"));
+            });
+            ui.horizontal(|ui| {
+                ui.label(tr("配置:"));
+                ui.radio_value(&mut self.config.overlay_placement, Placement::Cover, tr("原文に重ねる"));
+                ui.radio_value(&mut self.config.overlay_placement, Placement::Below, tr("原文の下"));
+                ui.radio_value(&mut self.config.overlay_placement, Placement::Beside, tr("原文の横"));
+            });
+        }
+        ui.horizontal(|ui| {
+            ui.label(tr("行揃え:"));
+            ui.radio_value(&mut self.config.overlay_text_align, TextAlign::Left, tr("左"));
+            ui.radio_value(&mut self.config.overlay_text_align, TextAlign::Center, tr("中央"));
+            ui.radio_value(&mut self.config.overlay_text_align, TextAlign::Right, tr("右"));
+            ui.separator();
+            ui.label(tr("原文の"));
+            ui.radio_value(&mut self.config.overlay_vertical_anchor, VerticalAnchor::Top, tr("上端"));
+            ui.radio_value(&mut self.config.overlay_vertical_anchor, VerticalAnchor::Middle, tr("中央"));
+            ui.label(tr("に合わせる"));
+        });
+        ui.horizontal(|ui| {
+            ui.label(tr("プリセット:"));
+            let mut chosen = None;
+            egui::ComboBox::from_id_salt("overlay_style_preset")
+                .selected_text(tr("適用…"))
+                .show_ui(ui, |ui| {
+                    let user = self.config.overlay_style_presets.iter();
+                    for preset in StylePreset::builtin().iter().chain(user) {
+                        if ui.selectable_label(false, &preset.name).clicked() {
+                            chosen = Some(preset.clone());
                         }
                     }
-                    AppStatus::Running => {
-                        if ui
-                            .add_sized([120.0, 30.0], egui::Button::new("停止"))
-                            .clicked()
-                        {
-                            self.stop();
-                        }
+                });
+            if let Some(preset) = chosen {
+                preset.apply(&mut self.config);
+                self.preset_name = preset.name;
+            }
+            ui.add(egui::TextEdit::singleline(&mut self.preset_name).desired_width(100.0));
+            let name = self.preset_name.trim().to_string();
+            let is_builtin = StylePreset::builtin().iter().any(|p| p.name == name);
+            let existing = self.config.overlay_style_presets.iter().position(|p| p.name == name);
+            if ui
+                .add_enabled(!name.is_empty() && !is_builtin, egui::Button::new(tr("保存")))
+                .on_hover_text(tr("現在のフォント・色・縁取り・背景をこの名前で保存（同名は上書き）"))
+                .clicked()
+            {
+                let preset = StylePreset::from_config(&name, &self.config);
+                match existing {
+                    Some(i) => self.config.overlay_style_presets[i] = preset,
+                    None => self.config.overlay_style_presets.push(preset),
+                }
+            }
+            if let Some(i) = existing {
+                if ui.button(tr("削除")).clicked() {
+                    self.config.overlay_style_presets.remove(i);
+                }
+            }
+        });
+        ui.collapsing(tr("ゾーン別スタイル"), |ui| {
+            ui.label(tr("原文の中心が範囲内にあるボックスだけプリセットの見た目にする（位置・サイズは画面に対する比率、上のゾーンが優先）"));
+            let presets: Vec<String> = StylePreset::builtin()
+                .iter()
+                .chain(&self.config.overlay_style_presets)
+                .map(|p| p.name.clone())
+                .collect();
+            let mut removed = None;
+            for (i, zone) in self.config.overlay_zones.iter_mut().enumerate() {
+                ui.horizontal(|ui| {
+                    ui.add(egui::TextEdit::singleline(&mut zone.name).hint_text(tr("名前")).desired_width(70.0));
+                    for (label, value) in [("X", &mut zone.x), ("Y", &mut zone.y), (tr("幅"), &mut zone.width), (tr("高さ"), &mut zone.height)] {
+                        ui.label(label);
+                        ui.add(egui::DragValue::new(value).speed(0.01).range(0.0..=1.0).max_decimals(2));
+                    }
+                    egui::ComboBox::from_id_salt(("overlay_zone_preset", i))
+                        .selected_text(if zone.preset.is_empty() { tr("（通常）") } else { zone.preset.as_str() })
+                        .show_ui(ui, |ui| {
+                            for name in &presets {
+                                ui.selectable_value(&mut zone.preset, name.clone(), name);
+                            }
+                        });
+                    ui.label(tr("文字×"));
+                    ui.add(egui::DragValue::new(&mut zone.font_scale).speed(0.05).range(0.5..=3.0).max_decimals(2));
+                    if ui.button(tr("削除")).clicked() {
+                        removed = Some(i);
                     }
-                    AppStatus::Stopping => {
-                        ui.add_enabled(false, egui::Button::new("停止中...").min_size(egui::vec2(120.0, 30.0)));
+                });
+            }
+            if let Some(i) = removed {
+                self.config.overlay_zones.remove(i);
+            }
+            if ui.button(tr("ゾーンを追加")).clicked() {
+                self.config.overlay_zones.push(StyleZone::default());
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.label(tr("テキスト色:"));
+            ui.color_edit_button_rgba_unmultiplied(&mut self.config.overlay_text_color);
+            ui.label(tr("背景色:"));
+            ui.color_edit_button_rgba_unmultiplied(&mut self.config.overlay_bg_color);
+            ui.checkbox(&mut self.config.overlay_bg_gradient, tr("グラデーション"));
+            ui.add_enabled_ui(self.config.overlay_bg_gradient, |ui| {
+                ui.color_edit_button_rgba_unmultiplied(&mut self.config.overlay_bg_color2);
+            });
+        });
+        ui.horizontal(|ui| {
+            ui.label(tr("スキン画像:"));
+            ui.add(
+                egui::TextEdit::singleline(&mut self.config.overlay_skin_path)
+                    .hint_text(tr("PNGのパス（空 = 使わない）"))
+                    .desired_width(220.0),
+            );
+            ui.label(tr("角:"));
+            ui.add(egui::DragValue::new(&mut self.config.overlay_skin_slice).range(1..=256).suffix(" px"));
+        });
+        ui.horizontal(|ui| {
+            ui.label(tr("フォント:"));
+            egui::ComboBox::from_id_salt("overlay_font")
+                .selected_text(&self.config.overlay_font_family)
+                .width(220.0)
+                .show_ui(ui, |ui| {
+                    for family in &self.font_families {
+                        ui.selectable_value(
+                            &mut self.config.overlay_font_family,
+                            family.clone(),
+                            family,
+                        );
                     }
+                });
+            if ui.button(tr("見本を見て選ぶ...")).clicked() {
+                self.font_picker = Some(FontPicker::open());
+            }
+        });
+        ui.checkbox(&mut self.config.overlay_avoid_overlap, tr("重なったボックスを上下にずらす"));
+        ui.checkbox(
+            &mut self.config.overlay_status_badge,
+            tr("処理状態を右上に表示（翻訳中… / APIエラー / レート制限中）"),
+        );
+        ui.horizontal(|ui| {
+            if ui
+                .checkbox(&mut self.config.overlay_perf_hud, tr("パフォーマンス表示を左上に出す（FPS・所要時間・APIエラー数）"))
+                .changed()
+            {
+                self.overlay_controls.perf_hud.store(self.config.overlay_perf_hud, Ordering::SeqCst);
+            }
+            ui.label(tr("(Ctrl+Alt+F で切替)"));
+        });
+        ui.horizontal(|ui| {
+            ui.label(tr("折り返し幅:"));
+            ui.add(
+                egui::Slider::new(&mut self.config.overlay_wrap_width_factor, 1.0..=2.5)
+                    .custom_formatter(|v, _| trf("原文の {}%", &[&format!("{:.0}", v * 100.0)])),
+            );
+            ui.checkbox(&mut self.config.overlay_cjk_line_breaking, tr("禁則処理"));
+        });
+        ui.horizontal(|ui| {
+            ui.label(tr("履歴パネル:"));
+            ui.add(egui::Slider::new(&mut self.config.overlay_history_lines, 0..=200).suffix(tr(" 行")));
+            ui.label(tr("(0 = 表示しない、操作モードではホイールでスクロール)"));
+        });
+        ui.horizontal(|ui| {
+            ui.label(tr("最大行数:"));
+            ui.add(egui::Slider::new(&mut self.config.overlay_max_lines, 0..=20));
+            ui.label(tr("(0 = 無制限、操作モードではホバーで全文表示)"));
+        });
+        ui.horizontal(|ui| {
+            ui.label(tr("フェード:"));
+            ui.add(egui::Slider::new(&mut self.config.overlay_fade_ms, 0..=500).suffix(" ms"));
+            ui.label(tr("(0 = なし)"));
+        });
+        ui.horizontal(|ui| {
+            ui.label(tr("文字サイズ:"));
+            let slider = egui::Slider::new(&mut self.config.overlay_font_scale, FONT_SCALE_RANGE)
+                .custom_formatter(|v, _| format!("{:.0}%", v * 100.0));
+            if ui.add(slider).changed() {
+                self.overlay_controls.font_scale.set(self.config.overlay_font_scale);
+                self.post_overlay_message(crate::pipeline::WM_APPLY_FONT_SCALE);
+            }
+            ui.label(tr("(実行中も反映)"));
+        });
+        ui.horizontal(|ui| {
+            ui.label(tr("最小:"));
+            ui.add(
+                egui::Slider::new(&mut self.config.overlay_min_font_size, 8.0..=24.0)
+                    .suffix(" pt"),
+            );
+            ui.label(tr("最大:"));
+            ui.add(
+                egui::Slider::new(&mut self.config.overlay_max_font_size, 24.0..=144.0)
+                    .suffix(" pt"),
+            );
+        });
+        ui.checkbox(&mut self.config.overlay_auto_fit, tr("原文の領域に収まるよう文字を縮小"));
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut self.config.overlay_outline_enabled, tr("縁取り"));
+            ui.add_enabled_ui(self.config.overlay_outline_enabled, |ui| {
+                ui.color_edit_button_rgba_unmultiplied(&mut self.config.overlay_outline_color);
+                ui.add(
+                    egui::Slider::new(&mut self.config.overlay_outline_width, 0.5..=6.0)
+                        .suffix(" px"),
+                );
+            });
+        });
+        ui.label(tr("※ 背景色の不透明度を0にすると縁取りだけの字幕表示になります"));
+        ui.horizontal(|ui| {
+            ui.label(tr("不透明度:"));
+            let slider = egui::Slider::new(&mut self.config.overlay_opacity, 0.0..=1.0)
+                .custom_formatter(|v, _| format!("{:.0}%", v * 100.0));
+            if ui.add(slider).changed() {
+                self.overlay_controls.opacity.set(self.config.overlay_opacity);
+                self.post_overlay_message(crate::pipeline::WM_APPLY_OPACITY);
+            }
+            ui.label(tr("(実行中は Ctrl+Alt+↑/↓ でも調整可)"));
+        });
+        ui.horizontal(|ui| {
+            let mut interactive = self.overlay_controls.interactive.load(Ordering::SeqCst);
+            if ui.checkbox(&mut interactive, tr("操作モード（翻訳ボックスをクリック可能にする）")).changed() {
+                self.overlay_controls.interactive.store(interactive, Ordering::SeqCst);
+                self.post_overlay_message(crate::pipeline::WM_APPLY_INTERACTIVE);
+            }
+            ui.label(tr("(Ctrl+Alt+I で切替)"));
+        });
+        if ui
+            .checkbox(
+                &mut self.config.overlay_exclude_from_capture,
+                tr("録画・配信に映さない（自分の画面にだけ表示）"),
+            )
+            .changed()
+        {
+            self.overlay_controls
+                .exclude_from_capture
+                .store(self.config.overlay_exclude_from_capture, Ordering::SeqCst);
+            self.post_overlay_message(crate::pipeline::WM_APPLY_CAPTURE_VISIBILITY);
+        }
+        ui.checkbox(
+            &mut self.config.overlay_copy_flash,
+            tr("コピー時にボックスを強調表示（操作モードでボックスをクリックすると原文と訳文をコピー）"),
+        );
+        ui.horizontal(|ui| {
+            ui.label(tr("画面を暗く:"));
+            ui.add(
+                egui::Slider::new(&mut self.config.overlay_dim, 0.0..=0.8)
+                    .custom_formatter(|v, _| format!("{:.0}%", v * 100.0)),
+            );
+            ui.label(tr("(訳文の表示中のみ、0% = なし)"));
+        });
+        ui.checkbox(
+            &mut self.config.overlay_acrylic,
+            tr("背景をぼかす（アクリル風、原文の上に表示する場合のみ）"),
+        );
+        ui.horizontal(|ui| {
+            ui.label(tr("角丸:"));
+            ui.add(
+                egui::Slider::new(&mut self.config.overlay_corner_radius, 0.0..=20.0)
+                    .suffix(" px"),
+            );
+        });
+        ui.horizontal(|ui| {
+            ui.label(tr("枠線:"));
+            ui.color_edit_button_rgba_unmultiplied(&mut self.config.overlay_border_color);
+            ui.add(
+                egui::Slider::new(&mut self.config.overlay_border_width, 0.0..=6.0)
+                    .suffix(" px"),
+            );
+        });
+        ui.checkbox(
+            &mut self.config.overlay_auto_contrast,
+            tr("自動コントラスト（背景が明るい/暗い場面で配色を自動調整）"),
+        );
+    }
+
+    /// Hotkeys and the remaining advanced settings
+    fn advanced_settings_ui(&mut self, ui: &mut egui::Ui) {
+        ui.label(tr("ホットキー（ゲーム中でも有効）"));
+        for action in HotkeyAction::ALL {
+            ui.horizontal(|ui| {
+                ui.add_sized([100.0, 18.0], egui::Label::new(action.label()));
+                let spec = Self::hotkey_spec(&mut self.config, action);
+                ui.add(egui::TextEdit::singleline(spec).hint_text(tr("例: Ctrl+Alt+S")).desired_width(140.0));
+                if spec.trim().is_empty() {
+                    ui.weak(tr("無効"));
+                } else if hotkey::parse(spec).is_none() {
+                    ui.colored_label(egui::Color32::RED, tr("不正な指定"));
                 }
+            });
+        }
+        ui.weak(tr("開始/停止はウィンドウ未選択なら前面のウィンドウを対象にします"));
+        ui.separator();
+        ui.collapsing(tr("キャプチャ間隔"), |ui| self.polling_ui(ui));
+        ui.separator();
+        ui.label(tr("設定の共有"));
+        ui.horizontal(|ui| {
+            ui.label(tr("ファイル:"));
+            ui.add(egui::TextEdit::singleline(&mut self.settings_io_path).desired_width(240.0));
+            if ui.button(tr("エクスポート")).clicked() {
+                self.export_settings();
+            }
+            if ui
+                .add_enabled(!self.is_running(), egui::Button::new(tr("インポート")))
+                .on_hover_text(tr("含まれていないAPIキー等は今の値を使います"))
+                .clicked()
+            {
+                self.import_settings();
+            }
+        });
+        ui.checkbox(&mut self.settings_include_secrets, tr("APIキー・パスワードも書き出す"));
+        match &self.settings_io_result {
+            Some(Ok(msg)) => {
+                ui.colored_label(egui::Color32::GREEN, msg);
+            }
+            Some(Err(msg)) => {
+                ui.colored_label(egui::Color32::RED, msg);
+            }
+            None => {}
+        }
+        ui.separator();
+        ui.horizontal(|ui| {
+            if ui
+                .checkbox(&mut self.debug_log, "Debug Log")
+                .on_hover_text(trf("保存先: {}", &[&crate::config::data_dir().display()]))
+                .changed()
+            {
+                crate::config::set_debug_log(self.debug_log);
+            }
+            ui.checkbox(&mut self.config.error_notifications, tr("エラーを通知"))
+                .on_hover_text(tr("APIキーの期限切れやレート制限で翻訳が止まった時、Windows の通知で知らせる"));
+        });
+        ui.horizontal(|ui| {
+            if ui
+                .button(tr("診断情報を ZIP に保存"))
+                .on_hover_text(tr("最近のログ・クラッシュレポート・設定（APIキー等を除く）・OS と GPU の情報をまとめる"))
+                .clicked()
+            {
+                self.save_diagnostics();
+            }
+            if ui.button(tr("クラッシュレポート")).on_hover_text(tr("フォルダを開く")).clicked() {
+                let dir = diagnostics::crash_dir();
+                let _ = std::fs::create_dir_all(&dir);
+                let _ = std::process::Command::new("explorer").arg(&dir).spawn();
+            }
+        });
+        match &self.diagnostics_result {
+            Some(Ok(msg)) => {
+                ui.colored_label(egui::Color32::GREEN, msg);
+            }
+            Some(Err(msg)) => {
+                ui.colored_label(egui::Color32::RED, msg);
+            }
+            None => {}
+        }
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut self.config.feed_enabled, tr("WebSocketで配信"))
+                .on_hover_text(tr("OCR結果と訳文をJSONで配信する（配信用オーバーレイ・ログ・読み上げツール向け、このPCからのみ接続可）"));
+            ui.label(tr("ポート:"));
+            ui.add(egui::DragValue::new(&mut self.config.feed_port).range(1024..=65535));
+            if let Some(server) = &self.feed_server {
+                ui.monospace(format!("ws://127.0.0.1:{}", server.port()));
+                let page = format!("http://127.0.0.1:{}/", server.port());
+                ui.hyperlink_to(tr("字幕ページ"), &page)
+                    .on_hover_text(trf("OBS のブラウザソースにこのURLを指定すると、訳文を字幕として表示できます: {}", &[&page]));
+            } else if let Some((_, err)) = self.feed_error.as_ref().filter(|_| self.config.feed_enabled) {
+                ui.colored_label(egui::Color32::RED, err);
+            }
+        });
+    }
+}
 
-                ui.add_space(16.0);
+/// Missing API keys / endpoint and bad language pairs, checked before a session starts
+fn validate_config(config: &AppConfig) -> Result<(), String> {
+    validate_translation(config)?;
+    match config.input_source {
+        InputSource::WebSocket if !config.texthook_url.trim().starts_with("ws://") => {
+            return Err(tr("テキストフックの接続先は ws:// で始めてください").to_string());
+        }
+        InputSource::Audio if !std::path::Path::new(config.whisper_command.trim()).is_file() => {
+            return Err(tr("whisper-cli.exe が見つかりません").to_string());
+        }
+        InputSource::Audio if !std::path::Path::new(config.whisper_model.trim()).is_file() => {
+            return Err(tr("Whisper のモデルファイルが見つかりません").to_string());
+        }
+        _ => {}
+    }
+    Ok(())
+}
 
-                if ui.checkbox(&mut self.debug_log, "Debug Log").changed() {
-                    crate::config::set_debug_log(self.debug_log);
-                }
+/// The translation half of `validate_config`: engine settings and the language pair
+fn validate_translation(config: &AppConfig) -> Result<(), String> {
+    match config.translation_engine {
+        TranslationEngine::DeepL if config.deepl_api_key.trim().is_empty() => {
+            return Err(tr("DeepL APIキーが未設定です").to_string());
+        }
+        TranslationEngine::LocalLLM => {
+            parse_endpoint(config.local_llm_endpoint.trim())?;
+        }
+        TranslationEngine::Groq if config.groq_api_key.trim().is_empty() => {
+            return Err(tr("Groq APIキーが未設定です").to_string());
+        }
+        TranslationEngine::Plugin if plugin::find(&config.plugin_name).is_none() => {
+            return Err(trf("プラグイン「{}」が見つかりません", &[&config.plugin_name]));
+        }
+        _ => {}
+    }
+    lang::validate_pair(config.translation_engine, &config.source_lang, &config.target_lang)
+        .map(|_| ())
+        .map_err(pair_error_message)
+}
 
-                ui.add_space(16.0);
+/// Message shown in the GUI for a language pair that failed validation
+fn pair_error_message(e: lang::PairError) -> String {
+    match e {
+        lang::PairError::UnknownCode { is_target, code, hint } => {
+            let label = tr(if is_target { "ターゲット言語" } else { "ソース言語" });
+            match hint {
+                Some(hint) => trf("{}「{}」は不明な言語コードです（{} の誤りでは？）", &[&label, &code, &hint]),
+                None => trf("{}「{}」は不明な言語コードです", &[&label, &code]),
+            }
+        }
+        lang::PairError::AutoTarget => tr("ターゲット言語に自動検出は指定できません").to_string(),
+        lang::PairError::SameLanguage => tr("ソース言語とターゲット言語が同じです").to_string(),
+        lang::PairError::UnsupportedByDeepL(src, tgt) => {
+            trf("DeepLは {} → {} に対応していません", &[&tr(src.display_name()), &tr(tgt.display_name())])
+        }
+    }
+}
 
-                match &self.status {
-                    AppStatus::Idle => {
-                        ui.label("待機中");
-                    }
-                    AppStatus::Running => {
-                        ui.colored_label(egui::Color32::GREEN, "実行中");
-                    }
-                    AppStatus::Stopping => {
-                        ui.colored_label(egui::Color32::YELLOW, "停止中...");
-                    }
-                    AppStatus::Error(msg) => {
-                        ui.colored_label(egui::Color32::RED, msg.as_str());
-                    }
+/// Check the Local LLM endpoint is a base URL the request paths can be appended to
+fn parse_endpoint(endpoint: &str) -> Result<reqwest::Url, String> {
+    if endpoint.is_empty() {
+        return Err(tr("LLMエンドポイントが未設定です").to_string());
+    }
+    let url = reqwest::Url::parse(endpoint).map_err(|e| trf("URLとして解釈できません: {}", &[&e]))?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(tr("http:// か https:// で始めてください").to_string());
+    }
+    if url.host_str().is_none_or(str::is_empty) {
+        return Err(tr("ホスト名がありません").to_string());
+    }
+    // "/v1/chat/completions" etc. are appended by the translator
+    let path = url.path().trim_end_matches('/');
+    if path.ends_with("/v1") || path.ends_with("/completions") || path.ends_with("/models") {
+        return Err(tr("/v1 以降は不要です（例: http://localhost:5000）").to_string());
+    }
+    Ok(url)
+}
+
+fn probe_port(url: &reqwest::Url) -> Result<String, String> {
+    let addrs = url
+        .socket_addrs(|| None)
+        .map_err(|e| trf("ホスト名を解決できません: {}", &[&e]))?;
+    let mut last_error = None;
+    for addr in addrs {
+        match std::net::TcpStream::connect_timeout(&addr, Duration::from_secs(3)) {
+            Ok(_) => return Ok(trf("{} に接続できました", &[&addr])),
+            Err(e) => last_error = Some(trf("{} に接続できません: {}", &[&addr, &e])),
+        }
+    }
+    Err(last_error.unwrap_or_else(|| tr("ホスト名を解決できません").to_string()))
+}
+
+/// Run the overlay and capture loop for one target window on its own thread
+fn spawn_session(
+    target_hwnd_raw: isize,
+    config: AppConfig,
+    stop_signal: Arc<AtomicBool>,
+    overlay_hwnd_arc: Arc<AtomicIsize>,
+    controls: Arc<OverlayControls>,
+) -> JoinHandle<()> {
+    let overlay_config = OverlayConfig::from_config(&config);
+    std::thread::spawn(move || {
+        if let Err(e) = crate::pipeline::run_overlay_thread(
+            target_hwnd_raw,
+            config,
+            overlay_config,
+            stop_signal,
+            overlay_hwnd_arc,
+            controls,
+        ) {
+            crate::log_always(&format!("Overlay thread error: {}", e));
+        }
+    })
+}
+
+fn glossary_match_label(mode: GlossaryMatch) -> &'static str {
+    match mode {
+        GlossaryMatch::Exact => tr("行全体"),
+        GlossaryMatch::Word => tr("単語"),
+        GlossaryMatch::Substring => tr("部分一致"),
+    }
+}
+
+/// Upload a new preview frame once and reuse the texture until the next one
+fn preview_texture(
+    slot: &mut Option<(u64, egui::TextureHandle)>,
+    ctx: &egui::Context,
+    preview: &OcrPreview,
+) -> egui::TextureHandle {
+    if let Some((frame, texture)) = slot {
+        if *frame == preview.frame {
+            return texture.clone();
+        }
+    }
+    let rgba: Vec<u8> = preview.pixels.chunks_exact(4).flat_map(|p| [p[2], p[1], p[0], 255]).collect();
+    let image = egui::ColorImage::from_rgba_unmultiplied([preview.width as usize, preview.height as usize], &rgba);
+    let texture = ctx.load_texture("ocr_preview", image, egui::TextureOptions::LINEAR);
+    *slot = Some((preview.frame, texture.clone()));
+    texture
+}
+
+/// Draw a preview frame at `zoom` times its thumbnail size with the OCR regions outlined
+fn paint_preview(
+    ui: &mut egui::Ui,
+    preview: &OcrPreview,
+    texture: &egui::TextureHandle,
+    zoom: f32,
+    labels: bool,
+) -> egui::Response {
+    let size = egui::vec2(preview.width as f32, preview.height as f32) * zoom;
+    let (rect, response) = ui.allocate_exact_size(size, egui::Sense::hover());
+    let painter = ui.painter_at(rect);
+    let uv = egui::Rect::from_min_max(egui::Pos2::ZERO, egui::pos2(1.0, 1.0));
+    painter.image(texture.id(), rect, uv, egui::Color32::WHITE);
+    let color = egui::Color32::from_rgb(255, 0, 255);
+    let scale = preview.scale * zoom;
+    for region in &preview.regions {
+        let min = rect.min + egui::vec2(region.x as f32, region.y as f32) * scale;
+        let max = min + egui::vec2(region.width as f32, region.block_height as f32) * scale;
+        painter.rect_stroke(egui::Rect::from_min_max(min, max), 0.0, egui::Stroke::new(1.5, color), egui::StrokeKind::Outside);
+        if labels {
+            let label = painter.layout_no_wrap(region.text.clone(), egui::FontId::proportional(11.0), egui::Color32::WHITE);
+            let label_rect = egui::Rect::from_min_size(egui::pos2(min.x, min.y - label.size().y), label.size());
+            painter.rect_filled(label_rect, 0.0, egui::Color32::from_black_alpha(180));
+            painter.galley(label_rect.min, label, egui::Color32::WHITE);
+        }
+    }
+    response
+}
+
+/// A top-level group of settings under a collapsible header
+fn settings_section(ui: &mut egui::Ui, id: &str, title: &str, default_open: bool, add_contents: impl FnOnce(&mut egui::Ui)) {
+    egui::CollapsingHeader::new(egui::RichText::new(title).strong())
+        .id_salt(id)
+        .default_open(default_open)
+        .show(ui, |ui| {
+            ui.group(|ui| {
+                ui.set_width(ui.available_width());
+                add_contents(ui);
+            });
+        });
+}
+
+/// Join a session thread unless it is still running at `deadline`; a thread left behind
+/// ends with the process
+fn join_until(thread: JoinHandle<()>, deadline: Instant) {
+    while !thread.is_finished() {
+        if Instant::now() >= deadline {
+            crate::log_always("[EXIT] 応答のないセッションを待たずに終了");
+            return;
+        }
+        std::thread::sleep(Duration::from_millis(20));
+    }
+    let _ = thread.join();
+}
+
+/// Ask a session to stop: raise its stop signal and close its overlay to end the message loop
+fn signal_stop(stop_signal: &AtomicBool, overlay_hwnd_raw: &AtomicIsize) {
+    stop_signal.store(true, Ordering::SeqCst);
+    let hwnd_raw = overlay_hwnd_raw.load(Ordering::SeqCst);
+    if hwnd_raw != 0 {
+        unsafe {
+            use windows::Win32::Foundation::*;
+            use windows::Win32::UI::WindowsAndMessaging::*;
+            let hwnd = HWND(hwnd_raw as *mut _);
+            let _ = PostMessageW(Some(hwnd), WM_CLOSE, WPARAM(0), LPARAM(0));
+        }
+    }
+}
+
+/// Capture one frame of the window and run OCR on it. Windows that PrintWindow
+/// can't read come back all black, which otherwise only shows up as "nothing detected".
+fn test_capture(rt: &tokio::runtime::Runtime, hwnd_raw: isize, ocr: Option<&OCREngine>) -> Result<String, String> {
+    let mut capture = WindowCapture::new(HWND(hwnd_raw as *mut _)).map_err(|e| format!("{:#}", e))?;
+    let frame = capture
+        .capture_frame()
+        .map_err(|e| format!("{:#}", e))?
+        .ok_or_else(|| tr("ウィンドウが最小化されています").to_string())?;
+    let (w, h) = capture.get_dimensions();
+    if frame.chunks_exact(4).all(|p| p[..3] == [0, 0, 0]) {
+        return Err(trf("{}×{} のフレームが真っ黒です（このウィンドウはキャプチャできない可能性があります）", &[&w, &h]));
+    }
+    let Some(ocr) = ocr else {
+        return Ok(format!("{}×{}", w, h));
+    };
+    let regions = rt.block_on(ocr.detect_text(&frame, w, h)).map_err(|e| format!("{:#}", e))?;
+    Ok(trf("{}×{}、テキスト領域 {} 個", &[&w, &h, &regions.len()]))
+}
+
+/// Dropdown of the languages `engine` supports, storing the canonical code.
+/// A code it doesn't know (e.g. a typo in config.toml) stays selected but is flagged.
+fn language_combo(ui: &mut egui::Ui, id: &str, code: &mut String, engine: TranslationEngine, as_target: bool) {
+    let current = lang::Language::parse(code);
+    let label = |l: lang::Language| format!("{} ({})", tr(l.display_name()), l.code());
+    let selected = current.map_or_else(|| code.clone(), label);
+    egui::ComboBox::from_id_salt(id)
+        .selected_text(selected)
+        .width(180.0)
+        .show_ui(ui, |ui| {
+            for language in lang::Language::ALL.into_iter().filter(|l| l.supported_by(engine, as_target)) {
+                if ui.selectable_label(current == Some(language), label(language)).clicked() {
+                    *code = language.code().to_string();
                 }
+            }
+        });
+    match current {
+        None => {
+            ui.colored_label(egui::Color32::RED, tr("不明"));
+        }
+        Some(l) if !l.supported_by(engine, as_target) => {
+            ui.colored_label(egui::Color32::RED, tr("非対応"));
+        }
+        _ => {}
+    }
+}
+
+impl eframe::App for GameTranslatorApp {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        // Poll overlay thread completion without blocking (it also ends by itself when the game closes)
+        if matches!(self.status, AppStatus::Stopping) {
+            self.poll_thread_completion();
+            ctx.request_repaint();
+        } else if matches!(self.status, AppStatus::Running) || !self.extra_sessions.is_empty() {
+            self.poll_thread_completion();
+        }
+        if !self.extra_sessions.is_empty() {
+            ctx.request_repaint_after(Duration::from_millis(500));
+        }
+
+        if self.config.auto_start || self.resume_pending || self.cli_autostart {
+            self.poll_auto_start();
+            ctx.request_repaint_after(AUTO_START_POLL);
+        }
+
+        self.config_recovery_ui(ctx);
+        self.poll_window_picker(ctx);
+        self.sync_feed_server();
+        self.sync_clipboard_watch(ctx);
+        self.clipboard_popup_ui(ctx);
+        self.snip_ui(ctx);
+        self.lookup_popup_ui(ctx);
+        self.handle_close(ctx);
+        self.poll_errors();
+        self.poll_deepl_usage(ctx);
+        self.sync_hotkeys(ctx);
+        let pressed = self.hotkeys.as_ref().map(|h| h.take_pressed()).unwrap_or_default();
+        for action in pressed {
+            self.handle_hotkey(action);
+        }
+
+        // Pick up opacity and HUD changes made via hotkeys so the controls and saved config follow
+        if matches!(self.status, AppStatus::Running) {
+            self.config.overlay_opacity = self.overlay_controls.opacity.get();
+            self.config.overlay_perf_hud = self.overlay_controls.perf_hud.load(Ordering::SeqCst);
+            ctx.request_repaint_after(std::time::Duration::from_millis(250));
+            self.push_overlay_config();
+        }
+
+        // Start / stop stays visible however far the settings are scrolled
+        let thumbnail_open = self.config.capture_thumbnail && matches!(self.status, AppStatus::Running);
+        egui::TopBottomPanel::bottom("controls").show(ctx, |ui| self.controls_ui(ui, thumbnail_open));
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.heading("Game Translator");
+            self.crash_banner_ui(ui);
+            self.error_banner_ui(ui);
+            self.tab_bar_ui(ui);
+            ui.separator();
+            if self.tab == Tab::History {
+                self.overlay_controls.preview_enabled.store(thumbnail_open, Ordering::SeqCst);
+                self.history_ui(ui);
+                return;
+            }
+            // The history scrolls its own rows, so only the settings sit in the scroll area
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                let mut preview_open = false;
+
+                // === Capture ===
+                settings_section(ui, "section_capture", tr("キャプチャ"), true, |ui| self.capture_settings_ui(ui));
+
+                // === OCR ===
+                settings_section(ui, "section_ocr", "OCR", false, |ui| preview_open = self.ocr_settings_ui(ui));
+
+                // === Translation ===
+                settings_section(ui, "section_translation", tr("翻訳"), true, |ui| self.translation_settings_ui(ui));
+
+                // === Overlay ===
+                settings_section(ui, "section_overlay", tr("オーバーレイ"), false, |ui| self.overlay_settings_ui(ui));
+
+                // === Advanced ===
+                settings_section(ui, "section_advanced", tr("詳細設定"), false, |ui| self.advanced_settings_ui(ui));
+
+                self.overlay_controls.preview_enabled.store(preview_open || thumbnail_open, Ordering::SeqCst);
+
+                ui.add_space(8.0);
+                ui.collapsing(tr("セッション統計"), |ui| self.stats_ui(ui));
+                let extra_title = if self.extra_sessions.is_empty() {
+                    tr("追加セッション").to_string()
+                } else {
+                    trf("追加セッション ({})", &[&self.extra_sessions.len()])
+                };
+                egui::CollapsingHeader::new(extra_title)
+                    .id_salt("extra_sessions")
+                    .show(ui, |ui| self.sessions_ui(ui));
             });
         });
+
+        if let Some(editor) = &mut self.region_editor {
+            if !editor.show(ctx, &mut self.config) {
+                self.region_editor = None;
+            }
+        }
+        if let Some(picker) = &mut self.font_picker {
+            if !picker.show(ctx, &mut self.config, &self.font_families) {
+                self.font_picker = None;
+            }
+        }
     }
 
     fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+        // Normally every session has already ended (see handle_close); this covers the timeout
+        // and exits that skip the dialog, without letting a stuck thread hang the process
         if self.is_running() {
             self.stop();
-            // Block on exit to ensure clean shutdown
-            if let Some(handle) = self.overlay_thread.take() {
-                let _ = handle.join();
-            }
+        }
+        for session in &self.extra_sessions {
+            session.stop();
+        }
+        let deadline = self.exit_deadline.unwrap_or_else(|| Instant::now() + SHUTDOWN_TIMEOUT);
+        let threads = self.overlay_thread.take().into_iter().chain(self.extra_sessions.iter_mut().filter_map(|s| s.thread.take()));
+        for thread in threads {
+            join_until(thread, deadline);
         }
     }
 }
@@ -0,0 +1,193 @@
+//! システム全体のホットキー（開始/停止・表示切替・再翻訳・一時停止・範囲を選んで翻訳）
+//!
+//! ゲームから Alt+Tab で抜けずに操作できるよう、専用スレッドで RegisterHotKey し、
+//! 押されたホットキーを GUI に渡す。GUI は次のフレームでまとめて処理する。
+
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+
+use eframe::egui;
+use windows::Win32::Foundation::*;
+use windows::Win32::System::Threading::GetCurrentThreadId;
+use windows::Win32::UI::Input::KeyboardAndMouse::*;
+use windows::Win32::UI::WindowsAndMessaging::*;
+
+use crate::i18n::tr;
+
+/// ホットキーで実行する操作
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HotkeyAction {
+    /// 翻訳の開始/停止
+    StartStop,
+    /// オーバーレイの表示/非表示
+    ToggleOverlay,
+    /// 画面を読み直し、キャッシュを使わずに翻訳し直す
+    Retranslate,
+    /// 一時停止/再開
+    Pause,
+    /// 画面の範囲をドラッグで選んで1回だけ翻訳
+    Snip,
+}
+
+impl HotkeyAction {
+    pub const ALL: [HotkeyAction; 5] = [Self::StartStop, Self::ToggleOverlay, Self::Retranslate, Self::Pause, Self::Snip];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::StartStop => tr("開始/停止"),
+            Self::ToggleOverlay => tr("表示/非表示"),
+            Self::Retranslate => tr("再翻訳"),
+            Self::Pause => tr("一時停止/再開"),
+            Self::Snip => tr("範囲を選んで翻訳"),
+        }
+    }
+}
+
+/// "Ctrl+Alt+S" 形式の指定を修飾キーと仮想キーコードに変換する（不正なら None）
+pub fn parse(spec: &str) -> Option<(HOT_KEY_MODIFIERS, u32)> {
+    let mut mods = HOT_KEY_MODIFIERS(0);
+    let mut key = None;
+    for part in spec.split('+').map(str::trim) {
+        match part.to_ascii_lowercase().as_str() {
+            "ctrl" | "control" => mods |= MOD_CONTROL,
+            "alt" => mods |= MOD_ALT,
+            "shift" => mods |= MOD_SHIFT,
+            "win" => mods |= MOD_WIN,
+            _ if key.is_some() => return None,
+            _ => key = Some(virtual_key(part)?),
+        }
+    }
+    key.map(|vk| (mods, vk))
+}
+
+fn virtual_key(name: &str) -> Option<u32> {
+    let upper = name.to_ascii_uppercase();
+    let vk = match upper.as_str() {
+        "SPACE" => VK_SPACE,
+        "TAB" => VK_TAB,
+        "ENTER" => VK_RETURN,
+        "PAUSE" => VK_PAUSE,
+        "INSERT" => VK_INSERT,
+        "DELETE" => VK_DELETE,
+        "HOME" => VK_HOME,
+        "END" => VK_END,
+        "PAGEUP" => VK_PRIOR,
+        "PAGEDOWN" => VK_NEXT,
+        "UP" => VK_UP,
+        "DOWN" => VK_DOWN,
+        "LEFT" => VK_LEFT,
+        "RIGHT" => VK_RIGHT,
+        _ => {
+            if let Some(n) = upper.strip_prefix('F').and_then(|n| n.parse::<u16>().ok()) {
+                return (1..=24).contains(&n).then(|| (VK_F1.0 + n - 1) as u32);
+            }
+            let mut chars = upper.chars();
+            return match (chars.next(), chars.next()) {
+                // 英数字の仮想キーコードは ASCII コードと同じ
+                (Some(c), None) if c.is_ascii_alphanumeric() => Some(c as u32),
+                _ => None,
+            };
+        }
+    };
+    Some(vk.0 as u32)
+}
+
+/// ホットキーを登録して待ち受けるスレッド。drop で登録を解除して終了する。
+pub struct HotkeyListener {
+    /// 登録した指定（設定が変わったら作り直す）
+    bindings: Vec<(HotkeyAction, String)>,
+    thread_id: u32,
+    handle: Option<JoinHandle<()>>,
+    pressed: Arc<Mutex<Vec<HotkeyAction>>>,
+}
+
+impl HotkeyListener {
+    /// 空・不正な指定は登録しない。他のアプリが使用中で登録できなかったものはログに残す。
+    pub fn spawn(bindings: Vec<(HotkeyAction, String)>, ctx: egui::Context) -> Self {
+        let pressed = Arc::new(Mutex::new(Vec::new()));
+        let (id_tx, id_rx) = mpsc::channel();
+        let thread_pressed = pressed.clone();
+        let thread_bindings = bindings.clone();
+        let handle = std::thread::spawn(move || unsafe {
+            // メッセージキューを作ってからスレッドIDを渡す（WM_QUIT を確実に受け取るため）
+            let mut msg = MSG::default();
+            let _ = PeekMessageW(&mut msg, None, WM_USER, WM_USER, PM_NOREMOVE);
+            let _ = id_tx.send(GetCurrentThreadId());
+
+            let mut registered = Vec::new();
+            for (i, (_, spec)) in thread_bindings.iter().enumerate() {
+                // 空・不正な指定は GUI 側で表示しているのでここでは飛ばすだけ
+                let Some((mods, vk)) = parse(spec) else {
+                    continue;
+                };
+                let id = i as i32 + 1;
+                if RegisterHotKey(None, id, mods | MOD_NOREPEAT, vk).is_ok() {
+                    registered.push(id);
+                } else {
+                    crate::log_always(&format!("[HOTKEY] {} を登録できませんでした（他のアプリが使用中）", spec));
+                }
+            }
+
+            while GetMessageW(&mut msg, None, 0, 0).into() {
+                if msg.message == WM_HOTKEY {
+                    if let Some((action, _)) = thread_bindings.get(msg.wParam.0.wrapping_sub(1)) {
+                        thread_pressed.lock().unwrap().push(*action);
+                        ctx.request_repaint();
+                    }
+                }
+            }
+            for id in registered {
+                let _ = UnregisterHotKey(None, id);
+            }
+        });
+        Self {
+            bindings,
+            thread_id: id_rx.recv().unwrap_or(0),
+            handle: Some(handle),
+            pressed,
+        }
+    }
+
+    pub fn bindings(&self) -> &[(HotkeyAction, String)] {
+        &self.bindings
+    }
+
+    /// 前回の呼び出し以降に押されたホットキー
+    pub fn take_pressed(&self) -> Vec<HotkeyAction> {
+        std::mem::take(&mut *self.pressed.lock().unwrap())
+    }
+}
+
+impl Drop for HotkeyListener {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = PostThreadMessageW(self.thread_id, WM_QUIT, WPARAM(0), LPARAM(0));
+        }
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_modifiers_and_keys() {
+        assert_eq!(parse("Ctrl+Alt+S"), Some((MOD_CONTROL | MOD_ALT, 'S' as u32)));
+        assert_eq!(parse("shift + f12"), Some((MOD_SHIFT, VK_F12.0 as u32)));
+        assert_eq!(parse("Win+PageUp"), Some((MOD_WIN, VK_PRIOR.0 as u32)));
+        assert_eq!(parse("1"), Some((HOT_KEY_MODIFIERS(0), '1' as u32)));
+    }
+
+    #[test]
+    fn rejects_invalid_specs() {
+        assert_eq!(parse(""), None);
+        assert_eq!(parse("Ctrl+Alt"), None);
+        assert_eq!(parse("Ctrl+A+B"), None);
+        assert_eq!(parse("F25"), None);
+        assert_eq!(parse("Ctrl+Esc"), None);
+    }
+}
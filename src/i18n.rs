@@ -0,0 +1,517 @@
+//! GUI の表示言語（日本語 / 英語）
+//!
+//! GUI の文字列は日本語のまま書き、`tr` / `trf` を通して表示する。英語表示のときは
+//! 下の対応表から英訳を引く（対応表にない文字列は日本語のまま表示される）。
+//! ログ出力は対象外。
+
+use std::fmt::Display;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+pub use crate::config::UiLanguage;
+
+/// `Auto` を OS の表示言語で解決する
+fn resolve(lang: UiLanguage) -> UiLanguage {
+    match lang {
+        UiLanguage::Auto => {
+            const LANG_JAPANESE: u16 = 0x11;
+            let langid = unsafe { windows::Win32::Globalization::GetUserDefaultUILanguage() };
+            // 下位10ビットが主言語
+            if langid & 0x3ff == LANG_JAPANESE { UiLanguage::Japanese } else { UiLanguage::English }
+        }
+        lang => lang,
+    }
+}
+
+/// 現在の表示言語（0 = 日本語, 1 = 英語）
+static ENGLISH: AtomicU8 = AtomicU8::new(0);
+
+pub fn set_language(lang: UiLanguage) {
+    let english = resolve(lang) == UiLanguage::English;
+    ENGLISH.store(english as u8, Ordering::SeqCst);
+}
+
+fn is_english() -> bool {
+    ENGLISH.load(Ordering::SeqCst) != 0
+}
+
+/// GUI 文字列を現在の表示言語で返す
+pub fn tr(ja: &'static str) -> &'static str {
+    if is_english() {
+        english(ja).unwrap_or(ja)
+    } else {
+        ja
+    }
+}
+
+/// `{}` を含む GUI 文字列を翻訳し、`{}` を順に `args` で置き換える
+pub fn trf(ja: &'static str, args: &[&dyn Display]) -> String {
+    let mut out = String::new();
+    let mut rest = tr(ja);
+    for arg in args {
+        let Some(pos) = rest.find("{}") else { break };
+        out.push_str(&rest[..pos]);
+        out.push_str(&arg.to_string());
+        rest = &rest[pos + 2..];
+    }
+    out.push_str(rest);
+    out
+}
+
+fn english(ja: &str) -> Option<&'static str> {
+    Some(match ja {
+        // タブ・全体
+        "設定" => "Settings",
+        "翻訳履歴" => "History",
+        "表示言語:" => "Language:",
+        "自動" => "Auto",
+        "開始" => "Start",
+        "停止" => "Stop",
+        "停止中..." => "Stopping...",
+        "待機中" => "Idle",
+        "実行中" => "Running",
+        "更新" => "Refresh",
+        "保存" => "Save",
+        "削除" => "Delete",
+        "実行" => "Run",
+        "中断" => "Cancel",
+        "コピー" => "Copy",
+        "インポート" => "Import",
+        "エクスポート" => "Export",
+        "無効" => "Off",
+        "オフ" => "Off",
+        "名前" => "Name",
+        "幅" => "W",
+        "高さ" => "H",
+        "左" => "Left",
+        "右" => "Right",
+        "中央" => "Center",
+        "上端" => "top",
+        "常に" => "Always",
+
+        // 対象ウィンドウ
+        "対象ウィンドウ" => "Target window",
+        "-- 選択してください --" => "-- Select a window --",
+        "ウィンドウを選択してください" => "Select a window",
+        "範囲を指定…" => "Set regions…",
+        "画面をドラッグして翻訳する範囲・除外する範囲を指定" => "Drag on the screen to set the regions to translate or skip",
+        "翻訳する範囲: {} / 除外マスク: {}" => "Capture regions: {} / Masks: {}",
+        "画面全体" => "whole window",
+        "{}個" => "{}",
+        "自動開始" => "Auto-start",
+        "登録したゲームのウィンドウが現れたら自動で開始し、閉じたら停止する" => "Start when a registered game window appears and stop when it closes",
+        "（現在の設定で開始。プロファイルは使われない）" => "(starts with the current settings; profiles are not applied)",
+        "選択中のウィンドウを登録" => "Register selected window",
+        "タイトルの一部でも一致すれば対象になる" => "Matches any window whose title contains this text",
+
+        // 翻訳設定
+        "翻訳" => "Translation",
+        "Echo (テスト)" => "Echo (test)",
+        "APIキー:" => "API key:",
+        "モデル:" => "Model:",
+        "📌固定済み" => "📌Pinned",
+        "編集" => "Edit",
+        "訳を修正して固定する（以後この訳を使い、APIの結果で上書きしない）" => "Correct the translation and pin it (always used from now on, never overwritten by the API)",
+        "固定" => "Pin",
+        "キャンセル" => "Cancel",
+        "再開" => "Resume",
+        "WebSocketで配信" => "Broadcast over WebSocket",
+        "ポート:" => "Port:",
+        "字幕ページ" => "Subtitle page",
+        "原文の入力:" => "Source text from:",
+        "Textractor の「Copy to Clipboard」拡張や Agent がクリップボードに書き出した文を翻訳する" => "Translate text that Textractor's Copy to Clipboard extension or Agent writes to the clipboard",
+        "Textractor の WebSocket 拡張などのサーバーから文を受け取って翻訳する" => "Translate text received from a server such as Textractor's WebSocket extension",
+        "接続先:" => "Server:",
+        "フック・音声の文には位置がないため、ゲーム画面の下部に字幕として表示します（次回の開始から反映）" => "Hooked or transcribed text has no position, so it is shown as subtitles at the bottom of the game window (applies from the next start)",
+        "音声 (Whisper)" => "Audio (Whisper)",
+        "再生中の音声を whisper.cpp で文字に起こして翻訳する（字幕のないボイス付きムービーなど）" => "Transcribe the playing audio with whisper.cpp and translate it (voiced cutscenes without subtitles, etc.)",
+        "whisper.cpp の whisper-cli.exe のパス" => "Path to whisper.cpp's whisper-cli.exe",
+        "ggml-small.bin などのモデルファイルのパス" => "Path to a model file such as ggml-small.bin",
+        "再生デバイスの音をすべて録るため、ゲーム以外の音も拾います" => "All audio on the output device is recorded, including sounds from other apps",
+        "whisper-cli.exe が見つかりません" => "whisper-cli.exe not found",
+        "Whisper のモデルファイルが見つかりません" => "Whisper model file not found",
+        "テキストフックの接続先は ws:// で始めてください" => "The text hook server must start with ws://",
+        "クリップボードの翻訳" => "Clipboard translation",
+        "訳文をコピー" => "Copy translation",
+        "翻訳できませんでした" => "Could not translate",
+        "コピーした文を翻訳してポップアップ表示" => "Translate copied text in a popup",
+        "翻訳を開始していなくても、コピーした文をその場で翻訳する（ランチャー・チャットなど。入力がクリップボードのセッション中はオーバーレイに表示）" => "Translate copied text right away, even without starting (launchers, chat, etc.; during a session with clipboard input it is shown on the overlay)",
+        "監視中" => "Watching",
+        "OBS のブラウザソースにこのURLを指定すると、訳文を字幕として表示できます: {}" => "Use this URL as an OBS Browser Source to show translations as subtitles: {}",
+        "OCR結果と訳文をJSONで配信する（配信用オーバーレイ・ログ・読み上げツール向け、このPCからのみ接続可）" => "Send OCR results and translations as JSON (for stream overlays, logging and TTS tools; only this PC can connect)",
+        "プラグイン「{}」が見つかりません" => "Plugin \"{}\" not found",
+        "プラグイン: {}" => "Plugin: {}",
+        "実行ファイル: {}" => "Executable: {}",
+        "プラグインフォルダを開く" => "Open plugins folder",
+        "再読み込み" => "Reload",
+        "プラグインフォルダのマニフェスト（*.toml）を読み直す" => "Re-read the manifests (*.toml) in the plugins folder",
+        "OCRを省略したフレーム" => "Frames skipped (unchanged or stale)",
+        "キャプチャ待ち..." => "Waiting for capture...",
+        "画面が真っ黒です" => "The capture is completely black",
+        "最小化されているか、キャプチャを拒否するゲームの可能性があります。ウィンドウを表示したままにするか、管理者として実行してください" => "The window may be minimized, or the game may refuse capture. Keep the window visible or run as administrator",
+        "実行中にキャプチャを縮小表示" => "Show a capture thumbnail while running",
+        "下部にキャプチャ中の画面と検出領域を表示し、真っ黒・別のウィンドウ・切り抜きのずれにすぐ気付けるようにする" => "Show the captured screen and detected regions at the bottom, so black frames, the wrong window or bad cropping are noticed right away",
+        "Game Translator 自身のウィンドウは選べません" => "Game Translator's own windows cannot be selected",
+        "タイトルのないウィンドウは選べません" => "Windows without a title cannot be selected",
+        "選ぶウィンドウをクリック（右クリックで中止）" => "Click the window to select (right-click to cancel)",
+        "クリックで選ぶ" => "Pick by clicking",
+        "カーソルが十字になったら、翻訳するウィンドウをクリック" => "When the cursor turns into a crosshair, click the window to translate",
+        "設定ファイルを読み込めませんでした" => "Could not read the settings file",
+        "既定の設定で起動しています。" => "Started with the default settings.",
+        "壊れたファイルは {} に残しました" => "The broken file was kept as {}",
+        "バックアップ {} から復元" => "Restore backup {}",
+        "読み込めるバックアップがありません" => "No readable backup",
+        "既定の設定で続ける" => "Continue with defaults",
+        "OCR言語: {}（ソース言語に合わせて選択、自動検出なら英語）" => "OCR language: {} (follows the source language; English when auto-detecting)",
+        "LLMプリセット:" => "LLM preset:",
+        "現在のモデル・温度・最大トークン数・プロンプトをこの名前で保存（同名は上書き）" => "Save the current model, temperature, max tokens and prompts under this name (overwrites the same name)",
+        "温度:" => "Temperature:",
+        "低いほど直訳的で安定、高いほど自由な訳（オフ = 既定）" => "Lower is more literal and stable, higher is freer (off = default)",
+        "最大トークン数:" => "Max tokens:",
+        "(0 = 自動)" => "(0 = auto)",
+        "接続確認" => "Check connection",
+        "ホストとポートに接続できるか確かめる（リクエストは送らない）" => "Check that the host and port accept connections (no request is sent)",
+        "URLとして解釈できません: {}" => "Not a valid URL: {}",
+        "http:// か https:// で始めてください" => "Start with http:// or https://",
+        "ホスト名がありません" => "The host name is missing",
+        "/v1 以降は不要です（例: http://localhost:5000）" => "Leave out /v1 and anything after it (e.g. http://localhost:5000)",
+        "ホスト名を解決できません: {}" => "Could not resolve the host name: {}",
+        "ホスト名を解決できません" => "Could not resolve the host name",
+        "{} に接続できました" => "Connected to {}",
+        "{} に接続できません: {}" => "Could not connect to {}: {}",
+        "DeepL の今月の残りは {} 文字です" => "{} DeepL characters left this month",
+        "DeepL の残り文字数が少なくなっています" => "DeepL quota is running low",
+        "使用量を更新" => "Refresh usage",
+        "⚠ 残り {} / {} 文字" => "⚠ {} / {} characters left",
+        "使い切ると月末まで翻訳できなくなります" => "Once it runs out, translation stops until the quota resets",
+        "残り {} / {} 文字" => "{} / {} characters left",
+        "使用量を取得できません" => "Could not read usage",
+        "翻訳中です。終了しますか？" => "Translation is running. Exit?",
+        "セッションを停止しています..." => "Stopping sessions...",
+        "実行中のセッション（{}件）を停止してから閉じます" => "Stops the running sessions ({}) before closing",
+        "終了" => "Exit",
+        "訳文の出力:" => "Also output to:",
+        "読み上げ" => "Speech",
+        "新しく表示された訳文を Windows の音声合成で読み上げる" => "Read new translations aloud with Windows text-to-speech",
+        "クリップボード" => "Clipboard",
+        "新しく表示された訳文をクリップボードに書き込む（スクリーンリーダーのクリップボード読み上げ機能用）" => "Write new translations to the clipboard (for a screen reader's clipboard reading feature)",
+        "速さ:" => "Rate:",
+        "(次回の開始から反映)" => "(from the next start)",
+        "キャプチャ:" => "Capture:",
+        "失敗" => "Failed",
+        "翻訳:" => "Translation:",
+        "翻訳待ち:" => "Queued:",
+        "{}行" => "{} lines",
+        "用語集" => "Glossary",
+        "追加" => "Add",
+        "用語集 ({})" => "Glossary ({})",
+        "用語" => "Term",
+        "訳語" => "Translation",
+        "一致" => "Match",
+        "空 = 翻訳しない" => "empty = don't translate",
+        "行全体" => "Whole line",
+        "単語" => "Word",
+        "部分一致" => "Anywhere",
+        "原文の用語は訳語に置き換えて表示されます。キャッシュ済みの行には再翻訳ホットキーで反映（次回の開始から有効）" => "Terms in the source are shown as their translation. Lines already cached pick it up with the retranslate hotkey (takes effect on the next start)",
+        "CSV:" => "CSV:",
+        "term,translation,match 形式。同じ用語は上書き" => "term,translation,match columns. Existing terms are overwritten",
+        "プロンプト" => "Prompt",
+        "指示文 (system):" => "Instructions (system):",
+        "ユーザーメッセージ:" => "User message:",
+        "{from} / {to} = 言語名、{lines} = 番号付きの原文（空欄 = 既定）" => "{from} / {to} = language names, {lines} = numbered source lines (empty = default)",
+        "既定に戻す" => "Reset to default",
+        "{lines} がないため原文が送られません" => "Without {lines} the source text is never sent",
+        "訳文は「1. 訳文」のように番号付きで1行ずつ返すよう指示してください" => "Ask for one numbered translation per line (\"1. translation\"), which is how replies are read",
+        "見本の行（1行に1つ）:" => "Sample lines (one per line):",
+        "送信するリクエスト" => "Request that will be sent",
+        "見本の行で試す" => "Test with sample lines",
+        "（応答に含まれていません）" => "(missing from the reply)",
+        "翻訳が停止しました" => "Translation stopped",
+        "翻訳エラー" => "Translation error",
+        "自動で再試行中です。APIキー・利用上限・接続を確認してください" => "Retrying automatically. Check the API key, usage limits and connection",
+        "エラーを通知" => "Notify on errors",
+        "閉じる" => "Dismiss",
+        "APIキーの期限切れやレート制限で翻訳が止まった時、Windows の通知で知らせる" => "Show a Windows notification when translation stops because of an expired API key, a rate limit and so on",
+        "見本を見て選ぶ..." => "Choose with preview...",
+        "フォントの選択" => "Choose font",
+        "選択中:" => "Selected:",
+        "見本:" => "Sample:",
+        "大きさ:" => "Size:",
+        "翻訳の見本 Translation sample 123" => "Translation sample 翻訳の見本 123",
+        "詳細設定" => "Advanced",
+        "キャプチャ間隔" => "Capture interval",
+        "キャプチャ間隔:" => "Capture interval:",
+        "画面の文字が変わっている間、この間隔で読み取る。短いほど早く訳が出るがCPU負荷が上がる" => "How often the screen is read while its text is changing. Shorter shows translations sooner but uses more CPU",
+        "変化なしで減速:" => "Slow down when idle:",
+        "同じ画面がこの回数続いたら、読み取り間隔を下の値まで延ばす" => "After this many unchanged captures, lengthen the interval to this value",
+        " 回" => " times",
+        "さらに減速:" => "Slow down further:",
+        "さらにこの回数続いたら、読み取り間隔をこの値まで延ばす（会話の少ない場面の省電力）" => "After this many unchanged captures, lengthen the interval to this value (saves power in quiet scenes)",
+        "前面のみ:" => "Foreground only:",
+        "対象ウィンドウが前面の時だけ翻訳する" => "Only translate while the target window is in front",
+        "オフにすると別のウィンドウを操作中も翻訳と表示を続ける（2画面でのプレイ向け）" => "When off, keeps translating and showing the overlay while you use other windows (for two-monitor setups)",
+        "待機中の確認間隔:" => "Check interval while waiting:",
+        "対象ウィンドウが前面にない間・一時停止中に、戻ったかを確かめる間隔" => "How often to check whether the target window is back in front, or the session is resumed",
+        "「さらに減速」の回数が「変化なしで減速」より小さいため、2段目がすぐ適用されます" => "\"Slow down further\" is below \"Slow down when idle\", so the second step applies right away",
+        "次回の開始から反映。設定ファイル（プロファイル）ごとに保存されます" => "Takes effect on the next start. Saved per settings file (profile)",
+        "一時停止" => "Pause",
+        "一時停止中" => "Paused",
+        "OCRと翻訳を止めてオーバーレイを隠す（キャプチャとOCRは起動したまま、すぐに再開できる）" => "Stop OCR and translation and hide the overlay (capture and OCR stay up, so resuming is instant)",
+        "このウィンドウはすでに翻訳中です" => "This window is already being translated",
+        "プロファイルを読み込めません: {}" => "Couldn't load the profile: {}",
+        "ゲームとは別のウィンドウ（チャット欄など）も同時に翻訳できます。スライダー・プレビュー・履歴はメインのセッションのみ対象です。" => "Translate another window (a chat window, say) alongside the game. Sliders, preview and history only apply to the main session.",
+        "現在の設定" => "Current settings",
+        "プロファイル:" => "Profile:",
+        "選択中のウィンドウで追加" => "Add for selected window",
+        "追加セッション" => "Extra sessions",
+        "追加セッション ({})" => "Extra sessions ({})",
+        "コマンドライン引数が不正です: {}" => "Invalid command-line arguments: {}",
+        "設定の共有" => "Share settings",
+        "{} に書き出しました" => "Exported to {}",
+        "設定を読み込みました" => "Settings imported",
+        "含まれていないAPIキー等は今の値を使います" => "API keys and passwords missing from the file keep their current values",
+        "APIキー・パスワードも書き出す" => "Include API keys and passwords",
+        "保存先: {}" => "Saved in: {}",
+        "Windows のログイン時に起動" => "Launch at Windows login",
+        "通知領域に隠して起動" => "Start hidden in the notification area",
+        "スタートアップに登録できません: {}" => "Couldn't update launch at login: {}",
+        "前回のウィンドウで自動再開" => "Resume last window",
+        "起動後、前回翻訳していたウィンドウが現れたら開始する（前回: {}）" => "After launch, start once the window translated last time appears (last: {})",
+        "セッション統計" => "Session statistics",
+        "キャプチャしたフレーム" => "Frames captured",
+        "OCR実行回数" => "OCR runs",
+        "キャッシュヒット率" => "Cache hit rate",
+        "APIエラー" => "API errors",
+        "API呼び出し" => "API calls",
+        "平均レイテンシ（キャプチャ→表示）" => "Average latency (capture → display)",
+        "翻訳した文字数" => "Characters translated",
+        "翻訳API" => "Translation API",
+        "キャプチャ" => "Capture",
+        "翻訳API・OCR言語・選択中ウィンドウのキャプチャを順に確認します" => "Checks the translation API, the OCR language and a capture of the selected window in turn",
+        "{} の OCR 言語がインストールされていません（{} で代用）" => "No OCR language installed for {} (using {} instead)",
+        "{}×{} のフレームが真っ黒です（このウィンドウはキャプチャできない可能性があります）" => "The {}×{} frame is completely black (this window may not be capturable)",
+        "{}×{}、テキスト領域 {} 個" => "{}×{}, {} text regions",
+        "{} 件" => "{} models",
+        "取得中..." => "Fetching...",
+        "モデル一覧を取得" => "Fetch models",
+        "モデル一覧を取得できません: {}" => "Couldn't fetch models: {}",
+        "エンドポイント:" => "Endpoint:",
+        "API形式:" => "API format:",
+        "/v1/models に応答するサーバー（LM Studio, llama.cpp, TabbyAPI等）は Chat API を使用し、失敗時は Completions に切り替えます" => "Servers that answer /v1/models (LM Studio, llama.cpp, TabbyAPI, ...) use the Chat API and fall back to Completions on failure",
+        "プロンプトテンプレートをモデル名から推定します（Gemma / Llama 3 / ChatML / Mistral）" => "The prompt template is inferred from the model name (Gemma / Llama 3 / ChatML / Mistral)",
+        "自己署名証明書を許可 (LAN内サーバー用)" => "Allow self-signed certificates (for LAN servers)",
+        "API不要。キャプチャ・OCR・オーバーレイ位置の確認用です。" => "No API needed. For checking capture, OCR and overlay placement.",
+        "出力:" => "Output:",
+        "[原文]" => "[source]",
+        "逆順" => "Reversed",
+        "ソース言語:" => "Source language:",
+        "不明" => "Unknown",
+        "非対応" => "Unsupported",
+        "ターゲット言語:" => "Target language:",
+        "接続テスト" => "Test connection",
+        "テスト中..." => "Testing...",
+        "DeepL APIキーが未設定です" => "DeepL API key is not set",
+        "LLMエンドポイントが未設定です" => "LLM endpoint is not set",
+        "Groq APIキーが未設定です" => "Groq API key is not set",
+        "プロキシ" => "Proxy",
+        "ユーザー名:" => "User:",
+        "パスワード:" => "Password:",
+        "除外:" => "Bypass:",
+        "カスタムヘッダー" => "Custom headers",
+        "+ 追加" => "+ Add",
+
+        // オーバーレイ外観
+        "オーバーレイ" => "Overlay",
+        "表示位置:" => "Position:",
+        "原文の上" => "Over source",
+        "右パネル" => "Right panel",
+        "左パネル" => "Left panel",
+        "パネル幅:" => "Panel width:",
+        "配置:" => "Placement:",
+        "原文に重ねる" => "Cover source",
+        "原文の下" => "Below source",
+        "原文の横" => "Beside source",
+        "縦書き:" => "Vertical text:",
+        "縦長の領域のみ" => "Tall regions only",
+        "行揃え:" => "Align:",
+        "原文の" => "Match source",
+        "に合わせる" => "edge",
+        "プリセット:" => "Preset:",
+        "適用…" => "Apply…",
+        "現在のフォント・色・縁取り・背景をこの名前で保存（同名は上書き）" => "Save the current font, colours, outline and background under this name (overwrites)",
+        "ゾーン別スタイル" => "Style zones",
+        "原文の中心が範囲内にあるボックスだけプリセットの見た目にする（位置・サイズは画面に対する比率、上のゾーンが優先）" => "Boxes whose source centre lies in a zone use its preset (position and size are fractions of the window; earlier zones win)",
+        "（通常）" => "(default)",
+        "文字×" => "Text ×",
+        "ゾーンを追加" => "Add zone",
+        "テキスト色:" => "Text colour:",
+        "背景色:" => "Background:",
+        "グラデーション" => "Gradient",
+        "スキン画像:" => "Skin image:",
+        "PNGのパス（空 = 使わない）" => "PNG path (empty = none)",
+        "角:" => "Corner:",
+        "フォント:" => "Font:",
+        "縁取り" => "Outline",
+        "※ 背景色の不透明度を0にすると縁取りだけの字幕表示になります" => "Tip: set the background opacity to 0 for outlined subtitle-style text",
+        "角丸:" => "Corner radius:",
+        "枠線:" => "Border:",
+        "(0 = なし)" => "(0 = none)",
+        "不透明度:" => "Opacity:",
+        "(実行中は Ctrl+Alt+↑/↓ でも調整可)" => "(Ctrl+Alt+↑/↓ while running)",
+        "画面を暗く:" => "Dim game:",
+        "(訳文の表示中のみ、0% = なし)" => "(only while translations are shown, 0% = off)",
+        "文字サイズ:" => "Text size:",
+        "(実行中も反映)" => "(applies live)",
+        "最小:" => "Min:",
+        "最大:" => "Max:",
+        "原文の領域に収まるよう文字を縮小" => "Shrink text to fit the source region",
+        "折り返し幅:" => "Wrap width:",
+        "原文の {}%" => "{}% of source",
+        "禁則処理" => "Japanese line breaking",
+        "最大行数:" => "Max lines:",
+        "(0 = 無制限、操作モードではホバーで全文表示)" => "(0 = unlimited; hover shows all in interactive mode)",
+        " 行" => " lines",
+        "履歴パネル:" => "History panel:",
+        "(0 = 表示しない、操作モードではホイールでスクロール)" => "(0 = off; scroll with the wheel in interactive mode)",
+        "フェード:" => "Fade:",
+        "重なったボックスを上下にずらす" => "Move overlapping boxes apart",
+        "処理状態を右上に表示（翻訳中… / APIエラー / レート制限中）" => "Show status in the corner (translating / API error / rate limited)",
+        "背景をぼかす（アクリル風、原文の上に表示する場合のみ）" => "Blur the background (acrylic, over-source mode only)",
+        "録画・配信に映さない（自分の画面にだけ表示）" => "Hide from recordings and streams (visible only on your screen)",
+        "操作モード（翻訳ボックスをクリック可能にする）" => "Interactive mode (boxes can be clicked)",
+        "(Ctrl+Alt+I で切替)" => "(toggle with Ctrl+Alt+I)",
+        "パフォーマンス表示を左上に出す（FPS・所要時間・APIエラー数）" => "Show a performance HUD in the top-left corner (FPS, timings, API errors)",
+        "(Ctrl+Alt+F で切替)" => "(toggle with Ctrl+Alt+F)",
+        "コピー時にボックスを強調表示（操作モードでボックスをクリックすると原文と訳文をコピー）" => "Flash a box when copied (click a box in interactive mode to copy source and translation)",
+        "自動コントラスト（背景が明るい/暗い場面で配色を自動調整）" => "Auto contrast (adjust colours on bright or dark scenes)",
+
+        // ホットキー
+        "ホットキー（ゲーム中でも有効）" => "Hotkeys (work in-game)",
+        "例: Ctrl+Alt+S" => "e.g. Ctrl+Alt+S",
+        "不正な指定" => "Invalid",
+        "開始/停止はウィンドウ未選択なら前面のウィンドウを対象にします" => "Start/stop uses the foreground window when none is selected",
+        "開始/停止" => "Start/stop",
+        "表示/非表示" => "Show/hide",
+        "再翻訳" => "Retranslate",
+        "一時停止/再開" => "Pause/resume",
+
+        // 翻訳キャッシュ・事前翻訳
+        "翻訳キャッシュ" => "Translation cache",
+        "ファイル:" => "File:",
+        "ファン翻訳取り込み" => "Import fan translation",
+        "TMX / XLIFF / CSV の既存翻訳を取り込み、APIより優先して使用します" => "Import existing TMX / XLIFF / CSV translations; they take priority over the API",
+        "{}件をエクスポートしました" => "Exported {} entries",
+        "エクスポート失敗: {}" => "Export failed: {}",
+        "{}件をインポートしました (合計 {}件)" => "Imported {} entries ({} total)",
+        "インポート失敗: {}" => "Import failed: {}",
+        "ファン翻訳 {}件を取り込みました" => "Imported {} fan translations",
+        "取り込み失敗: {}" => "Import failed: {}",
+        "事前翻訳の実行中は開始できません" => "Can't start while a pre-translation is running",
+        "事前翻訳:" => "Pre-translate:",
+        "strings.txt (1行1文字列)" => "strings.txt (one string per line)",
+        "失敗 {}件" => "{} failed",
+
+        // 実行・プレビュー
+        "検出領域の枠を表示" => "Outline detected regions",
+        "OCRで検出したテキスト領域を枠線で囲む（次回の開始から反映）" => "Outline the text regions found by OCR (from the next start)",
+        "OCRプレビュー" => "OCR preview",
+        "実行中、ゲームが前面にある間に更新されます" => "Updates while running with the game in front",
+        "検出: {}個の領域" => "Detected: {} regions",
+
+        // 翻訳履歴
+        "検索:" => "Search:",
+        "{}件" => "{} entries",
+        "書き出し先:" => "Export to:",
+        ".csv / .json ならCSV・JSON（位置・時刻も含む）、.srt / .ass なら訳文の字幕、それ以外は原文と訳文を並べたテキスト" => "CSV / JSON (with position and timing) for .csv / .json, subtitles of the translations for .srt / .ass, otherwise plain text with source and translation",
+        "停止時に自動で書き出す" => "Export automatically on stop",
+        "フォルダを開く" => "Open folder",
+        "選択した{}件を書き出し" => "Export {} selected",
+        "チェックした文を Anki で読み込める TSV（原文・訳文・画像）にする。画像は隣の _media フォルダに書き出すので、Anki の collection.media へコピーする" => "Write the ticked lines as a TSV Anki can import (source, translation, image). Images go to the _media folder next to it; copy them into Anki's collection.media",
+        "選択解除" => "Clear selection",
+        "画像も残す" => "Keep images",
+        "原文の領域の画像を履歴に残し、カードに付ける（OCR のみ、次の開始から有効）" => "Keep an image of each source region in the history for the cards (OCR only, from the next start)",
+        "Anki に書き出す" => "Export to Anki",
+        "辞書" => "Dictionary",
+        "辞書を引けるのは翻訳元が日本語・中国語の時だけです" => "The dictionary is only available when the source language is Japanese or Chinese",
+        "{} をダウンロードして保存先に置きます（初回のみ）" => "Download {} into the data folder (first time only)",
+        "ダウンロード" => "Download",
+        "辞書をダウンロード中..." => "Downloading dictionary...",
+        "辞書を読み込み中..." => "Loading dictionary...",
+        "ダウンロードし直す" => "Download again",
+        "原文の文字をクリックすると、そこから始まる語を引きます" => "Click a character of the source to look up the words starting there",
+        "見つかりませんでした" => "No entries found",
+        "辞書のライセンス (CC BY-SA)" => "Dictionary license (CC BY-SA)",
+        "前回は異常終了しました" => "The previous run crashed",
+        "不具合を報告する時は、診断情報の ZIP を添付してください（APIキー等は含みません）" => "When reporting the bug, please attach the diagnostics ZIP (API keys and the like are left out)",
+        "診断情報を ZIP に保存" => "Save diagnostics ZIP",
+        "レポートを開く" => "Open report",
+        "最近のログ・クラッシュレポート・設定（APIキー等を除く）・OS と GPU の情報をまとめる" => "Bundle recent logs, crash reports, settings (without API keys etc.) and OS/GPU info",
+        "クラッシュレポート" => "Crash reports",
+        "ベンチマーク" => "Benchmark",
+        "回数:" => "Iterations:",
+        "画像ファイル（空欄なら選択中のウィンドウ）" => "Image file (empty = selected window)",
+        "キャプチャ・OCR・翻訳を指定した回数だけ実行し、段階ごとの所要時間を測る（翻訳はキャッシュを使わずに毎回APIを呼ぶ）" => "Run capture, OCR and translation the given number of times and measure each stage (translation calls the API every time, bypassing the cache)",
+        "段階" => "Stage",
+        "中央値" => "Median",
+        "平均" => "Mean",
+        "最小" => "Min",
+        "最大" => "Max",
+        "合計" => "Total",
+        "テキストが見つからなかったため、翻訳は測っていません" => "No text was found, so translation was not measured",
+        "画像フォルダ:" => "Image folder:",
+        "スクリーンショットのフォルダ" => "Folder of screenshots",
+        "フォルダ内の画像を OCR・翻訳し、訳文を描いた画像と report.txt を translated フォルダに書き出す" => "OCR and translate the images in the folder, writing images with the translations drawn in and a report.txt to its translated folder",
+        "範囲を選んで翻訳" => "Snip and translate",
+        "翻訳する範囲をドラッグ（Esc・右クリックで取り消し）" => "Drag over the text to translate (Esc or right-click to cancel)",
+        "文字が見つかりませんでした" => "No text found",
+        "範囲の翻訳" => "Snip translation",
+        "読み取り中..." => "Reading...",
+        "原文の読み:" => "Source readings:",
+        "原文に読みを付けて表示する（日本語は JMdict のふりがな、中国語は CC-CEDICT の声調記号付きピンイン。辞書は Ctrl+クリックのポップアップでダウンロード）" => "Show the source text with readings (furigana from JMdict for Japanese, tone-marked pinyin from CC-CEDICT for Chinese; download the dictionary from the Ctrl+click popup)",
+        "訳文に添える" => "Above the translation",
+        "訳文の代わりに表示" => "Instead of the translation",
+        "学習モード（語ごとの訳語を原文の下に表示）" => "Learner mode (show word glosses under the source text)",
+        "日本語・中国語の原文を辞書で語に区切り、各語の訳語を原文の下に表示する。訳文は Ctrl+Alt+T を押した時だけ表示（辞書は Ctrl+クリックのポップアップでダウンロード、次回の開始から反映）" => "Splits Japanese/Chinese source text into words with the dictionary and shows each word's gloss under it. The full translation is shown only while toggled with Ctrl+Alt+T (download the dictionary from the Ctrl+click popup; applies from the next start)",
+        "{}件を {} に書き出しました" => "Exported {} lines to {}",
+
+        // 範囲の指定
+        "翻訳する範囲の指定" => "Capture regions",
+        "ドラッグで追加:" => "Drag to add:",
+        "翻訳する範囲" => "Capture region",
+        "除外マスク" => "Mask",
+        "撮り直す" => "Recapture",
+        "すべて消去" => "Clear all",
+        "右クリックで削除。翻訳する範囲がなければ画面全体が対象（次回の開始から反映）" => "Right-click to delete. Without capture regions the whole window is used (from the next start)",
+        "キャプチャできません: {}" => "Cannot capture: {}",
+        "ウィンドウが最小化されています" => "The window is minimised",
+
+        // 言語
+        "自動検出" => "Auto-detect",
+        "英語" => "English",
+        "日本語" => "Japanese",
+        "中国語（簡体字）" => "Chinese (Simplified)",
+        "中国語（繁体字）" => "Chinese (Traditional)",
+        "韓国語" => "Korean",
+        "フランス語" => "French",
+        "ドイツ語" => "German",
+        "スペイン語" => "Spanish",
+        "イタリア語" => "Italian",
+        "ポルトガル語（ブラジル）" => "Portuguese (Brazil)",
+        "ポルトガル語（ポルトガル）" => "Portuguese (Portugal)",
+        "ロシア語" => "Russian",
+        "ポーランド語" => "Polish",
+        "オランダ語" => "Dutch",
+        "トルコ語" => "Turkish",
+        "ウクライナ語" => "Ukrainian",
+        "インドネシア語" => "Indonesian",
+        "アラビア語" => "Arabic",
+        "ヘブライ語" => "Hebrew",
+        "ソース言語" => "Source language",
+        "ターゲット言語" => "Target language",
+        "{}「{}」は不明な言語コードです（{} の誤りでは？）" => "{} \"{}\" is not a known language code (did you mean {}?)",
+        "{}「{}」は不明な言語コードです" => "{} \"{}\" is not a known language code",
+        "ターゲット言語に自動検出は指定できません" => "The target language can't be auto-detect",
+        "ソース言語とターゲット言語が同じです" => "Source and target languages are the same",
+        "DeepLは {} → {} に対応していません" => "DeepL doesn't support {} → {}",
+        _ => return None,
+    })
+}
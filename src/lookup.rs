@@ -0,0 +1,73 @@
+//! 辞書引きのポップアップ用の辞書の準備
+//!
+//! 辞書のダウンロード（初回のみ、ボタンを押した時だけ）と読み込みを別スレッドで行う。
+//! JMdict の読み込みには数秒かかるので、読み込んだ辞書は学習モードのセッションとも共有し、
+//! アプリを閉じるまで使い回す。
+
+use std::sync::{Arc, Mutex};
+
+use eframe::egui;
+
+use crate::config::AppConfig;
+use crate::dictionary::{Dictionary, DictionaryKind};
+
+#[derive(Clone)]
+pub enum DictionaryState {
+    /// まだダウンロードしていない
+    Missing,
+    Downloading,
+    Loading,
+    Ready(Arc<Dictionary>),
+    Failed(String),
+}
+
+/// 1つの辞書の準備状況
+pub struct DictionaryLoader {
+    pub kind: DictionaryKind,
+    state: Arc<Mutex<DictionaryState>>,
+}
+
+impl DictionaryLoader {
+    /// ダウンロード済みならすぐ読み込みを始める
+    pub fn new(kind: DictionaryKind, ctx: &egui::Context) -> Self {
+        let loader = Self { kind, state: Arc::new(Mutex::new(DictionaryState::Missing)) };
+        if kind.is_downloaded() {
+            loader.spawn(None, ctx.clone());
+        }
+        loader
+    }
+
+    pub fn state(&self) -> DictionaryState {
+        self.state.lock().unwrap().clone()
+    }
+
+    /// ダウンロードしてから読み込む（失敗した後の再試行にも使う）
+    pub fn download(&self, config: &AppConfig, ctx: &egui::Context) {
+        self.spawn(Some(config.clone()), ctx.clone());
+    }
+
+    /// `download` = the settings to download with (proxy), or None to only load
+    fn spawn(&self, download: Option<AppConfig>, ctx: egui::Context) {
+        let kind = self.kind;
+        let state = self.state.clone();
+        *state.lock().unwrap() = if download.is_some() { DictionaryState::Downloading } else { DictionaryState::Loading };
+        std::thread::spawn(move || {
+            let result = (|| {
+                if let Some(config) = &download {
+                    kind.download(config)?;
+                    *state.lock().unwrap() = DictionaryState::Loading;
+                    ctx.request_repaint();
+                }
+                Dictionary::shared(kind)
+            })();
+            *state.lock().unwrap() = match result {
+                Ok(dictionary) => DictionaryState::Ready(dictionary),
+                Err(e) => {
+                    crate::log_always(&format!("[DICT] {} を使えません: {:#}", kind.name(), e));
+                    DictionaryState::Failed(format!("{:#}", e))
+                }
+            };
+            ctx.request_repaint();
+        });
+    }
+}
@@ -0,0 +1,151 @@
+//! Windows の通知（トースト）。ゲーム中に翻訳が止まっても気付けるようにエラーを知らせる
+//!
+//! パッケージ化していないアプリでも出せるよう、通知領域にアイコンを置き、
+//! そのバルーン（Windows 10 以降はトーストとして表示される）を使う。
+//! アイコン・通知をクリックするとメインウィンドウを前面に戻す（トレイに隠して起動した時の復帰にも使う）。
+
+use anyhow::{Context, Result};
+use windows::core::{w, PCWSTR};
+use windows::Win32::Foundation::{HWND, LPARAM, LRESULT, WPARAM};
+use windows::Win32::System::LibraryLoader::GetModuleHandleW;
+use windows::Win32::UI::Shell::*;
+use windows::Win32::UI::WindowsAndMessaging::*;
+
+const ICON_ID: u32 = 1;
+/// アイコン・通知の操作を知らせるメッセージ
+const WM_TRAY: u32 = WM_APP + 1;
+const CLASS_NAME: PCWSTR = w!("GameTranslatorTray");
+
+/// 通知領域のアイコンとその持ち主の隠しウィンドウ。drop でアイコンを消す。
+pub struct Notifier {
+    hwnd: HWND,
+    /// アイコンを登録済みか（2回目以降は NIM_MODIFY で通知を出し直す）
+    added: bool,
+}
+
+impl Notifier {
+    /// `main` はアイコン・通知のクリックで前面に戻すウィンドウ
+    pub fn new(main: HWND) -> Result<Self> {
+        unsafe {
+            let instance = GetModuleHandleW(None)?;
+            let class = WNDCLASSW {
+                lpfnWndProc: Some(wnd_proc),
+                hInstance: instance.into(),
+                lpszClassName: CLASS_NAME,
+                ..Default::default()
+            };
+            // 2回目以降は登録済みで失敗するが、そのまま使える
+            RegisterClassW(&class);
+            // アイコンのメッセージを受けるためだけのウィンドウ（表示しない）
+            let hwnd = CreateWindowExW(
+                WINDOW_EX_STYLE(0),
+                CLASS_NAME,
+                w!("Game Translator Notifications"),
+                WINDOW_STYLE(0),
+                0,
+                0,
+                0,
+                0,
+                None,
+                None,
+                Some(instance.into()),
+                None,
+            )
+            .context("Failed to create the notification window")?;
+            SetWindowLongPtrW(hwnd, GWLP_USERDATA, main.0 as isize);
+            Ok(Self { hwnd, added: false })
+        }
+    }
+
+    /// 通知を出さずにアイコンだけを置く
+    pub fn show_icon(&mut self) -> Result<()> {
+        let data = self.icon_data()?;
+        self.apply(&data)
+    }
+
+    /// 警告アイコン付きで通知する。長すぎるタイトル・本文は切り詰める。
+    pub fn show(&mut self, title: &str, message: &str) -> Result<()> {
+        let mut data = self.icon_data()?;
+        data.uFlags |= NIF_INFO;
+        data.dwInfoFlags = NIIF_WARNING;
+        copy_truncated(&mut data.szInfoTitle, title);
+        copy_truncated(&mut data.szInfo, message);
+        self.apply(&data)
+    }
+
+    fn icon_data(&self) -> Result<NOTIFYICONDATAW> {
+        let mut data = NOTIFYICONDATAW {
+            cbSize: std::mem::size_of::<NOTIFYICONDATAW>() as u32,
+            hWnd: self.hwnd,
+            uID: ICON_ID,
+            uFlags: NIF_ICON | NIF_TIP | NIF_MESSAGE,
+            uCallbackMessage: WM_TRAY,
+            hIcon: unsafe { LoadIconW(None, IDI_APPLICATION) }?,
+            ..Default::default()
+        };
+        copy_truncated(&mut data.szTip, "Game Translator");
+        Ok(data)
+    }
+
+    fn apply(&mut self, data: &NOTIFYICONDATAW) -> Result<()> {
+        let op = if self.added { NIM_MODIFY } else { NIM_ADD };
+        if !unsafe { Shell_NotifyIconW(op, data) }.as_bool() {
+            anyhow::bail!("Shell_NotifyIconW failed");
+        }
+        self.added = true;
+        Ok(())
+    }
+}
+
+unsafe extern "system" fn wnd_proc(hwnd: HWND, msg: u32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+    if msg != WM_TRAY {
+        return unsafe { DefWindowProcW(hwnd, msg, wparam, lparam) };
+    }
+    // 下位ワードがアイコン上のマウス操作、または通知のクリック
+    let event = (lparam.0 & 0xFFFF) as u32;
+    if matches!(event, WM_LBUTTONUP | WM_LBUTTONDBLCLK | WM_RBUTTONUP | NIN_BALLOONUSERCLICK) {
+        unsafe {
+            let main = HWND(GetWindowLongPtrW(hwnd, GWLP_USERDATA) as *mut _);
+            if !main.is_invalid() {
+                let _ = ShowWindow(main, SW_SHOW);
+                if IsIconic(main).as_bool() {
+                    let _ = ShowWindow(main, SW_RESTORE);
+                }
+                let _ = SetForegroundWindow(main);
+            }
+        }
+    }
+    LRESULT(0)
+}
+
+impl Drop for Notifier {
+    fn drop(&mut self) {
+        unsafe {
+            if self.added {
+                let data = NOTIFYICONDATAW {
+                    cbSize: std::mem::size_of::<NOTIFYICONDATAW>() as u32,
+                    hWnd: self.hwnd,
+                    uID: ICON_ID,
+                    ..Default::default()
+                };
+                let _ = Shell_NotifyIconW(NIM_DELETE, &data);
+            }
+            let _ = DestroyWindow(self.hwnd);
+        }
+    }
+}
+
+/// NUL 終端を残して収まる分だけ UTF-16 で書き込む（サロゲートペアは分割しない）
+fn copy_truncated(dst: &mut [u16], s: &str) {
+    let mut len = 0;
+    let mut buf = [0u16; 2];
+    for c in s.chars() {
+        let units = c.encode_utf16(&mut buf);
+        if len + units.len() >= dst.len() {
+            break;
+        }
+        dst[len..len + units.len()].copy_from_slice(units);
+        len += units.len();
+    }
+    dst[len] = 0;
+}
@@ -0,0 +1,128 @@
+//! ゲーム文字列リストの事前翻訳（プレイ前にキャッシュを埋めておく）
+
+use anyhow::{Context, Result};
+use std::collections::HashSet;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::cache::{CacheScope, TranslationCache};
+use crate::config::AppConfig;
+use crate::translate::Translator;
+
+/// 1リクエストあたりの行数
+const BATCH_SIZE: usize = 20;
+/// リクエスト間の待機時間（レート制限対策）
+const BATCH_INTERVAL: Duration = Duration::from_millis(1000);
+/// 失敗時のリトライ回数（待機時間は 2s, 4s, 8s... と倍増）
+const MAX_RETRIES: u32 = 4;
+
+#[derive(Clone, Default)]
+pub struct PretranslateProgress {
+    pub done: usize,
+    pub total: usize,
+    pub failed: usize,
+    pub error: Option<String>,
+}
+
+/// テキストファイル（1行1文字列）を読み込み、未キャッシュの行だけを重複なしで返す
+fn collect_pending(path: &Path, cache: &TranslationCache, scope: &CacheScope) -> Result<Vec<String>> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let mut seen = HashSet::new();
+    Ok(content
+        .lines()
+        .map(|l| l.trim())
+        .filter(|l| !l.is_empty() && !cache.contains(scope, l))
+        .filter(|l| seen.insert(l.to_string()))
+        .map(|l| l.to_string())
+        .collect())
+}
+
+/// レート制限などで失敗したら待ってやり直す（画像の一括翻訳でも使う）
+pub async fn translate_with_retry(
+    translator: &Translator,
+    batch: &[String],
+    from: &str,
+    to: &str,
+    cancel: &AtomicBool,
+) -> Result<Vec<Option<String>>> {
+    let mut delay = Duration::from_secs(2);
+    let mut attempt = 0;
+    loop {
+        match translator.translate_batch(batch.to_vec(), from, to).await {
+            Ok(r) => return Ok(r),
+            Err(e) if attempt < MAX_RETRIES && !cancel.load(Ordering::SeqCst) => {
+                crate::log(&format!("[PRETRANSLATE] {} — retrying in {}s", e, delay.as_secs()));
+                tokio::time::sleep(delay).await;
+                delay *= 2;
+                attempt += 1;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// ファイル内の全文字列を設定中のエンジンで翻訳し、キャッシュへ書き込む。
+/// バッチごとにキャッシュを保存するので、中断しても翻訳済みの分は残る。
+pub async fn run(
+    config: AppConfig,
+    path: &Path,
+    progress: Arc<Mutex<PretranslateProgress>>,
+    cancel: Arc<AtomicBool>,
+) -> Result<()> {
+    let scope = CacheScope::from_config(&config);
+    let mut cache = TranslationCache::load(&scope);
+    let pending = collect_pending(path, &cache, &scope)?;
+    progress.lock().unwrap().total = pending.len();
+    crate::log_always(&format!("[PRETRANSLATE] {}件を翻訳開始: {}", pending.len(), path.display()));
+
+    let translator = Translator::from_config(&config)?;
+
+    for (i, batch) in pending.chunks(BATCH_SIZE).enumerate() {
+        if cancel.load(Ordering::SeqCst) {
+            crate::log_always("[PRETRANSLATE] 中断されました");
+            break;
+        }
+        if i > 0 {
+            tokio::time::sleep(BATCH_INTERVAL).await;
+        }
+
+        let translations = translate_with_retry(
+            &translator,
+            batch,
+            &config.source_lang,
+            &config.target_lang,
+            &cancel,
+        )
+        .await;
+
+        let mut failed = 0;
+        match translations {
+            Ok(translations) => {
+                for (orig, trans) in batch.iter().zip(translations.iter()) {
+                    match trans {
+                        Some(t) => cache.insert(&scope, orig.clone(), t.clone()),
+                        None => failed += 1,
+                    }
+                }
+                failed += batch.len().saturating_sub(translations.len());
+                cache.save();
+            }
+            Err(e) => {
+                crate::log_always(&format!("[PRETRANSLATE ERR] {}", e));
+                failed = batch.len();
+                progress.lock().unwrap().error = Some(e.to_string());
+            }
+        }
+
+        let mut p = progress.lock().unwrap();
+        p.done += batch.len();
+        p.failed += failed;
+    }
+
+    let p = progress.lock().unwrap();
+    crate::log_always(&format!("[PRETRANSLATE] 完了: {}/{}件 (失敗 {}件)", p.done - p.failed, p.total, p.failed));
+    Ok(())
+}
@@ -0,0 +1,168 @@
+//! Drag-to-select editor for the capture regions and exclusion masks.
+//! Shows a dimmed screenshot of the target window; dragging on it adds a rectangle,
+//! right-clicking one removes it.
+
+use eframe::egui::{self, Color32, Pos2, Rect, Stroke, StrokeKind};
+use windows::Win32::Foundation::HWND;
+
+use crate::capture::WindowCapture;
+use crate::config::{AppConfig, RelativeRect};
+use crate::i18n::{tr, trf};
+
+const REGION_COLOR: Color32 = Color32::from_rgb(80, 220, 120);
+const MASK_COLOR: Color32 = Color32::from_rgb(230, 70, 70);
+/// Screenshots wider than this are scaled down to fit the editor
+const PREVIEW_WIDTH: f32 = 720.0;
+/// Drags smaller than this (preview pixels) are ignored
+const MIN_DRAG: f32 = 4.0;
+
+#[derive(Clone, Copy, PartialEq)]
+enum DragTarget {
+    Region,
+    Mask,
+}
+
+pub struct RegionEditor {
+    hwnd_raw: isize,
+    texture: Option<egui::TextureHandle>,
+    target: DragTarget,
+    /// Start and current pointer position of the drag in progress
+    drag: Option<(Pos2, Pos2)>,
+    error: Option<String>,
+}
+
+impl RegionEditor {
+    pub fn open(ctx: &egui::Context, hwnd_raw: isize) -> Self {
+        let mut editor = Self {
+            hwnd_raw,
+            texture: None,
+            target: DragTarget::Region,
+            drag: None,
+            error: None,
+        };
+        editor.capture(ctx);
+        editor
+    }
+
+    /// Take a fresh screenshot of the target window to draw on
+    fn capture(&mut self, ctx: &egui::Context) {
+        match screenshot(self.hwnd_raw) {
+            Ok(image) => {
+                self.texture = Some(ctx.load_texture("region_editor", image, egui::TextureOptions::LINEAR));
+                self.error = None;
+            }
+            Err(e) => self.error = Some(trf("キャプチャできません: {}", &[&e])),
+        }
+    }
+
+    /// Show the editor window, editing `config` in place. Returns false once it is closed.
+    pub fn show(&mut self, ctx: &egui::Context, config: &mut AppConfig) -> bool {
+        let mut open = true;
+        egui::Window::new(tr("翻訳する範囲の指定"))
+            .open(&mut open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label(tr("ドラッグで追加:"));
+                    ui.radio_value(&mut self.target, DragTarget::Region, tr("翻訳する範囲"));
+                    ui.radio_value(&mut self.target, DragTarget::Mask, tr("除外マスク"));
+                    ui.separator();
+                    if ui.button(tr("撮り直す")).clicked() {
+                        self.capture(ctx);
+                    }
+                    if ui.button(tr("すべて消去")).clicked() {
+                        config.capture_regions.clear();
+                        config.capture_masks.clear();
+                    }
+                });
+                ui.weak(tr("右クリックで削除。翻訳する範囲がなければ画面全体が対象（次回の開始から反映）"));
+                if let Some(err) = &self.error {
+                    ui.colored_label(Color32::RED, err);
+                }
+                let Some(texture) = &self.texture else {
+                    return;
+                };
+
+                let [w, h] = texture.size();
+                let scale = (PREVIEW_WIDTH / w as f32).min(1.0);
+                let (response, painter) =
+                    ui.allocate_painter(egui::vec2(w as f32 * scale, h as f32 * scale), egui::Sense::click_and_drag());
+                let frame = response.rect;
+                let uv = Rect::from_min_max(Pos2::ZERO, Pos2::new(1.0, 1.0));
+                painter.image(texture.id(), frame, uv, Color32::from_gray(160));
+
+                let to_screen = |r: &RelativeRect| {
+                    Rect::from_min_size(
+                        frame.min + egui::vec2(r.x * frame.width(), r.y * frame.height()),
+                        egui::vec2(r.width * frame.width(), r.height * frame.height()),
+                    )
+                };
+                let to_relative = |p: Pos2| ((p.x - frame.min.x) / frame.width(), (p.y - frame.min.y) / frame.height());
+                let outline = |rect: Rect, color: Color32| {
+                    painter.rect_filled(rect, 0.0, color.gamma_multiply(0.2));
+                    painter.rect_stroke(rect, 0.0, Stroke::new(2.0, color), StrokeKind::Inside);
+                };
+                for r in &config.capture_regions {
+                    outline(to_screen(r), REGION_COLOR);
+                }
+                for m in &config.capture_masks {
+                    outline(to_screen(m), MASK_COLOR);
+                }
+
+                let color = match self.target {
+                    DragTarget::Region => REGION_COLOR,
+                    DragTarget::Mask => MASK_COLOR,
+                };
+                if response.drag_started() {
+                    self.drag = response.interact_pointer_pos().map(|p| (p, p));
+                }
+                if let (Some((_, end)), Some(pos)) = (&mut self.drag, response.interact_pointer_pos()) {
+                    *end = pos;
+                }
+                if let Some((start, end)) = self.drag {
+                    let rect = Rect::from_two_pos(start, end).intersect(frame);
+                    outline(rect, color);
+                    if response.drag_stopped() {
+                        self.drag = None;
+                        if rect.width() >= MIN_DRAG && rect.height() >= MIN_DRAG {
+                            let (x, y) = to_relative(rect.min);
+                            let added = RelativeRect {
+                                x,
+                                y,
+                                width: rect.width() / frame.width(),
+                                height: rect.height() / frame.height(),
+                            };
+                            match self.target {
+                                DragTarget::Region => config.capture_regions.push(added),
+                                DragTarget::Mask => config.capture_masks.push(added),
+                            }
+                        }
+                    }
+                }
+
+                if response.secondary_clicked() {
+                    if let Some((x, y)) = response.interact_pointer_pos().map(to_relative) {
+                        // Masks are drawn on top, so they are removed first
+                        if let Some(i) = config.capture_masks.iter().rposition(|m| m.contains(x, y)) {
+                            config.capture_masks.remove(i);
+                        } else if let Some(i) = config.capture_regions.iter().rposition(|r| r.contains(x, y)) {
+                            config.capture_regions.remove(i);
+                        }
+                    }
+                }
+            });
+        open
+    }
+}
+
+/// Capture the window's client area as an RGBA image
+fn screenshot(hwnd_raw: isize) -> anyhow::Result<egui::ColorImage> {
+    let mut capture = WindowCapture::new(HWND(hwnd_raw as *mut _))?;
+    let frame = capture
+        .capture_frame()?
+        .ok_or_else(|| anyhow::anyhow!(tr("ウィンドウが最小化されています")))?;
+    let (w, h) = capture.get_dimensions();
+    // BGRA → RGBA; PrintWindow leaves the alpha channel undefined
+    let rgba: Vec<u8> = frame.chunks_exact(4).flat_map(|p| [p[2], p[1], p[0], 255]).collect();
+    Ok(egui::ColorImage::from_rgba_unmultiplied([w as usize, h as usize], &rgba))
+}
@@ -0,0 +1,169 @@
+//! スクリーンショットのフォルダの一括翻訳（攻略本の画像や過去のキャプチャの見直し用）
+//!
+//! フォルダ内の画像を1枚ずつ OCR して設定中のエンジンで翻訳し、訳文を原文の上に描いた画像と、
+//! 全画像の原文・訳文をまとめたレポートを `translated` フォルダに書き出す。
+//! 訳文はキャッシュにも入れるので、同じ文を含む画像が何枚あってもエンジンは1回しか呼ばない。
+
+use anyhow::{Context, Result};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+use crate::cache::{CacheScope, TranslationCache};
+use crate::capture;
+use crate::config::AppConfig;
+use crate::ocr::{self, OCREngine};
+use crate::overlay::{self, Annotation, OverlayConfig};
+use crate::pretranslate;
+use crate::ruby;
+use crate::translate::Translator;
+
+/// WIC で読める画像の拡張子
+const IMAGE_EXTENSIONS: [&str; 7] = ["png", "jpg", "jpeg", "bmp", "gif", "tif", "tiff"];
+/// 書き出し先（元のフォルダの中）
+const OUTPUT_DIR: &str = "translated";
+const REPORT_FILE: &str = "report.txt";
+
+#[derive(Clone, Default)]
+pub struct BatchProgress {
+    pub done: usize,
+    pub total: usize,
+    pub failed: usize,
+    pub error: Option<String>,
+    /// 書き出したフォルダ（終わってから設定）
+    pub output: Option<PathBuf>,
+}
+
+/// フォルダ直下の画像（名前順）
+fn list_images(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut images: Vec<PathBuf> = std::fs::read_dir(dir)
+        .with_context(|| format!("Failed to read {}", dir.display()))?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| {
+            path.extension()
+                .and_then(|e| e.to_str())
+                .is_some_and(|e| IMAGE_EXTENSIONS.contains(&e.to_ascii_lowercase().as_str()))
+        })
+        .collect();
+    images.sort();
+    Ok(images)
+}
+
+/// 設定中の OCR 言語とエンジンで、フォルダ内の画像をすべて翻訳して書き出す（COM の初期化が必要）。
+/// 1枚ごとにキャッシュを保存するので、中断しても翻訳済みの分は残る。
+pub async fn run(
+    config: AppConfig,
+    dir: &Path,
+    progress: Arc<Mutex<BatchProgress>>,
+    cancel: Arc<AtomicBool>,
+) -> Result<()> {
+    let images = list_images(dir)?;
+    anyhow::ensure!(!images.is_empty(), "No images in {}", dir.display());
+    progress.lock().unwrap().total = images.len();
+    let out_dir = dir.join(OUTPUT_DIR);
+    std::fs::create_dir_all(&out_dir).with_context(|| format!("Failed to create {}", out_dir.display()))?;
+    crate::log_always(&format!("[BATCH] {}枚を翻訳開始: {}", images.len(), dir.display()));
+
+    let scope = CacheScope::from_config(&config);
+    let mut cache = TranslationCache::load(&scope);
+    let translator = Translator::from_config(&config)?;
+    let engine = OCREngine::new(&ocr::preferred_language(&config.source_lang))?;
+    let overlay_config = OverlayConfig::from_config(&config);
+    let batch = Batch { config: &config, scope: &scope, translator: &translator, engine: &engine, overlay_config: &overlay_config };
+
+    let mut report = String::new();
+    for path in &images {
+        if cancel.load(Ordering::SeqCst) {
+            crate::log_always("[BATCH] 中断されました");
+            break;
+        }
+        let name = path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+        report.push_str(&format!("## {}\n", name));
+        match batch.translate_image(path, &out_dir, &mut cache, &cancel).await {
+            Ok(section) => report.push_str(&section),
+            Err(e) => {
+                crate::log_always(&format!("[BATCH ERR] {}: {:#}", name, e));
+                report.push_str(&format!("({:#})\n", e));
+                let mut p = progress.lock().unwrap();
+                p.failed += 1;
+                p.error = Some(format!("{}: {:#}", name, e));
+            }
+        }
+        report.push('\n');
+        cache.save();
+        progress.lock().unwrap().done += 1;
+    }
+
+    let report_path = out_dir.join(REPORT_FILE);
+    std::fs::write(&report_path, report).with_context(|| format!("Failed to write {}", report_path.display()))?;
+    let mut p = progress.lock().unwrap();
+    p.output = Some(out_dir);
+    crate::log_always(&format!("[BATCH] 完了: {}/{}枚 (失敗 {}枚)", p.done - p.failed, p.total, p.failed));
+    Ok(())
+}
+
+/// 全画像で共通の設定とエンジン
+struct Batch<'a> {
+    config: &'a AppConfig,
+    scope: &'a CacheScope,
+    translator: &'a Translator,
+    engine: &'a OCREngine,
+    overlay_config: &'a OverlayConfig,
+}
+
+impl Batch<'_> {
+    /// 1枚を OCR・翻訳して訳文入りの PNG を書き出し、レポートのその画像の部分を返す
+    async fn translate_image(
+        &self,
+        path: &Path,
+        out_dir: &Path,
+        cache: &mut TranslationCache,
+        cancel: &AtomicBool,
+    ) -> Result<String> {
+        let (pixels, width, height) = capture::decode_image(path)?;
+        let regions = self.engine.detect_text(&pixels, width, height).await?;
+
+        let mut seen = HashSet::new();
+        let pending: Vec<String> = regions
+            .iter()
+            .map(|r| r.text.clone())
+            .filter(|t| !cache.contains(self.scope, t) && seen.insert(t.clone()))
+            .collect();
+        if !pending.is_empty() {
+            let (from, to) = (&self.config.source_lang, &self.config.target_lang);
+            let translations = pretranslate::translate_with_retry(self.translator, &pending, from, to, cancel).await?;
+            for (source, translation) in pending.into_iter().zip(translations) {
+                if let Some(translation) = translation {
+                    cache.insert(self.scope, source, translation);
+                }
+            }
+        }
+
+        let mut section = String::new();
+        let mut annotations = Vec::new();
+        for region in &regions {
+            let translation = cache.get(self.scope, &region.text).map(|t| ruby::strip(t));
+            section.push_str(&format!("{}\n→ {}\n", region.text, translation.as_deref().unwrap_or("-")));
+            if let Some(text) = translation {
+                annotations.push(Annotation {
+                    rect: (region.x, region.y, region.width, region.block_height),
+                    text,
+                    font_size: region.height as f32,
+                });
+            }
+        }
+        if regions.is_empty() {
+            section.push_str("(文字なし)\n");
+        }
+
+        let annotated = overlay::render_annotated(self.overlay_config, &pixels, width, height, &annotations)?;
+        let png = capture::encode_png_crop(&annotated, width, height, (0, 0, width as i32, height as i32))?
+            .context("Empty image")?;
+        // 拡張子ごと残す（a.png と a.jpg が同じ名前にならないように）
+        let name = path.file_name().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+        let out_path = out_dir.join(format!("{}.png", name));
+        std::fs::write(&out_path, png).with_context(|| format!("Failed to write {}", out_path.display()))?;
+        Ok(section)
+    }
+}
@@ -0,0 +1,213 @@
+//! 範囲を選んで翻訳（ワンショット）
+//!
+//! ホットキーで画面全体を撮り、その上でドラッグした範囲を OCR して翻訳する。翻訳のセッションとは
+//! 別に動き、対象ウィンドウはいらない（ランチャー・説明書・他のアプリの文用）。
+//! 結果はクリップボード監視と同じポップアップに出す。
+
+use std::sync::{Arc, Mutex};
+
+use eframe::egui::{self, Color32, Pos2, Rect, Stroke, StrokeKind};
+use windows::Win32::System::Com::{CoInitializeEx, CoUninitialize, COINIT_MULTITHREADED};
+
+use crate::capture::DesktopShot;
+use crate::clipboard_watch::ClipboardTranslation;
+use crate::config::AppConfig;
+use crate::i18n::tr;
+use crate::ocr::{self, OCREngine};
+use crate::ruby;
+use crate::translate::Translator;
+
+const SELECTION_COLOR: Color32 = Color32::from_rgb(80, 160, 255);
+/// これより小さいドラッグ（画面のピクセル）は無視する
+const MIN_DRAG: i32 = 8;
+
+/// 範囲の選択の結果
+pub enum SnipOutcome {
+    /// 選択中
+    Selecting,
+    Cancelled,
+    /// 選んだ範囲 (x, y, 幅, 高さ)。スクリーンショット内のピクセル
+    Selected(i32, i32, i32, i32),
+}
+
+/// 撮った画面を全画面に出して、範囲をドラッグで選ばせる
+pub struct SnipSelection {
+    shot: DesktopShot,
+    texture: Option<egui::TextureHandle>,
+    drag: Option<(Pos2, Pos2)>,
+}
+
+impl SnipSelection {
+    pub fn new(shot: DesktopShot) -> Self {
+        Self { shot, texture: None, drag: None }
+    }
+
+    pub fn shot(&self) -> &DesktopShot {
+        &self.shot
+    }
+
+    /// 枠なしの最前面ウィンドウで仮想デスクトップ全体を覆う
+    pub fn show(&mut self, ctx: &egui::Context) -> SnipOutcome {
+        let shot = &self.shot;
+        let texture = self.texture.get_or_insert_with(|| {
+            // BGRA → RGBA; BitBlt leaves the alpha channel undefined
+            let rgba: Vec<u8> = shot.pixels.chunks_exact(4).flat_map(|p| [p[2], p[1], p[0], 255]).collect();
+            let image = egui::ColorImage::from_rgba_unmultiplied([shot.width as usize, shot.height as usize], &rgba);
+            ctx.load_texture("snip", image, egui::TextureOptions::LINEAR)
+        });
+        // Viewport geometry is in points; the screenshot is mapped onto whatever area the window gets
+        let ppp = ctx.native_pixels_per_point().unwrap_or(1.0);
+        let mut outcome = SnipOutcome::Selecting;
+        ctx.show_viewport_immediate(
+            egui::ViewportId::from_hash_of("snip"),
+            egui::ViewportBuilder::default()
+                .with_title(tr("範囲を選んで翻訳"))
+                .with_decorations(false)
+                .with_always_on_top()
+                .with_position([shot.origin.0 as f32 / ppp, shot.origin.1 as f32 / ppp])
+                .with_inner_size([shot.width as f32 / ppp, shot.height as f32 / ppp]),
+            |ctx, _| {
+                egui::CentralPanel::default().frame(egui::Frame::NONE).show(ctx, |ui| {
+                    let (response, painter) = ui.allocate_painter(ui.available_size(), egui::Sense::click_and_drag());
+                    let frame = response.rect;
+                    let full = Rect::from_min_max(Pos2::ZERO, Pos2::new(1.0, 1.0));
+                    painter.image(texture.id(), frame, full, Color32::from_gray(110));
+                    painter.text(
+                        frame.center_top() + egui::vec2(0.0, 24.0),
+                        egui::Align2::CENTER_TOP,
+                        tr("翻訳する範囲をドラッグ（Esc・右クリックで取り消し）"),
+                        egui::FontId::proportional(20.0),
+                        Color32::WHITE,
+                    );
+
+                    if response.drag_started() {
+                        self.drag = response.interact_pointer_pos().map(|p| (p, p));
+                    }
+                    if let (Some((_, end)), Some(pos)) = (&mut self.drag, response.interact_pointer_pos()) {
+                        *end = pos;
+                    }
+                    if let Some((start, end)) = self.drag {
+                        let rect = Rect::from_two_pos(start, end).intersect(frame);
+                        // The selection shows the screenshot undimmed
+                        let uv = Rect::from_min_max(
+                            Pos2::new((rect.min.x - frame.min.x) / frame.width(), (rect.min.y - frame.min.y) / frame.height()),
+                            Pos2::new((rect.max.x - frame.min.x) / frame.width(), (rect.max.y - frame.min.y) / frame.height()),
+                        );
+                        painter.image(texture.id(), rect, uv, Color32::WHITE);
+                        painter.rect_stroke(rect, 0.0, Stroke::new(2.0, SELECTION_COLOR), StrokeKind::Outside);
+                        if response.drag_stopped() {
+                            self.drag = None;
+                            let x = (uv.min.x * shot.width as f32) as i32;
+                            let y = (uv.min.y * shot.height as f32) as i32;
+                            let width = (uv.width() * shot.width as f32) as i32;
+                            let height = (uv.height() * shot.height as f32) as i32;
+                            if width >= MIN_DRAG && height >= MIN_DRAG {
+                                outcome = SnipOutcome::Selected(x, y, width, height);
+                            }
+                        }
+                    }
+                    if response.secondary_clicked() {
+                        outcome = SnipOutcome::Cancelled;
+                    }
+                });
+                if ctx.input(|i| i.key_pressed(egui::Key::Escape) || i.viewport().close_requested()) {
+                    outcome = SnipOutcome::Cancelled;
+                }
+            },
+        );
+        outcome
+    }
+}
+
+/// 選んだ範囲の OCR と翻訳（別スレッド）
+pub struct SnipJob {
+    result: Arc<Mutex<Option<ClipboardTranslation>>>,
+}
+
+impl SnipJob {
+    pub fn start(config: &AppConfig, shot: &DesktopShot, rect: (i32, i32, i32, i32), ctx: egui::Context) -> Self {
+        let result = Arc::new(Mutex::new(None));
+        let translator = match Translator::from_config(config) {
+            Ok(translator) => translator,
+            Err(e) => {
+                *result.lock().unwrap() = Some(ClipboardTranslation { source: String::new(), result: Err(format!("{:#}", e)) });
+                return Self { result };
+            }
+        };
+        let (pixels, width, height) = crop(shot, rect);
+        let ocr_lang = ocr::preferred_language(&config.source_lang);
+        let (source_lang, target_lang) = (config.source_lang.clone(), config.target_lang.clone());
+        let thread_result = result.clone();
+        std::thread::spawn(move || {
+            let translation = read_and_translate(&translator, &pixels, width, height, &ocr_lang, &source_lang, &target_lang);
+            if let Err(e) = &translation.result {
+                crate::log_always(&format!("[SNIP] 翻訳できません: {}", e));
+            }
+            *thread_result.lock().unwrap() = Some(translation);
+            ctx.request_repaint();
+        });
+        Self { result }
+    }
+
+    /// 終わっていれば結果を取り出す
+    pub fn take(&self) -> Option<ClipboardTranslation> {
+        self.result.lock().unwrap().take()
+    }
+}
+
+/// スクリーンショットから矩形を切り出す（BGRA のまま）
+fn crop(shot: &DesktopShot, (x, y, width, height): (i32, i32, i32, i32)) -> (Vec<u8>, u32, u32) {
+    let left = x.clamp(0, shot.width as i32) as usize;
+    let top = y.clamp(0, shot.height as i32) as usize;
+    let right = (x + width).clamp(0, shot.width as i32) as usize;
+    let bottom = (y + height).clamp(0, shot.height as i32) as usize;
+    let mut pixels = Vec::with_capacity((right - left) * (bottom - top) * 4);
+    for row in top..bottom {
+        let start = (row * shot.width as usize + left) * 4;
+        pixels.extend_from_slice(&shot.pixels[start..start + (right - left) * 4]);
+    }
+    (pixels, (right - left) as u32, (bottom - top) as u32)
+}
+
+fn read_and_translate(
+    translator: &Translator,
+    pixels: &[u8],
+    width: u32,
+    height: u32,
+    ocr_lang: &str,
+    source_lang: &str,
+    target_lang: &str,
+) -> ClipboardTranslation {
+    let rt = match tokio::runtime::Builder::new_current_thread().enable_all().build() {
+        Ok(rt) => rt,
+        Err(e) => return ClipboardTranslation { source: String::new(), result: Err(e.to_string()) },
+    };
+    // WinRT OCR needs COM on this thread
+    unsafe {
+        let _ = CoInitializeEx(None, COINIT_MULTITHREADED);
+    }
+    let lines = rt.block_on(async {
+        let engine = OCREngine::new(ocr_lang)?;
+        let regions = engine.detect_text(pixels, width, height).await?;
+        Ok::<_, anyhow::Error>(regions.into_iter().map(|r| r.text).collect::<Vec<String>>())
+    });
+    unsafe {
+        CoUninitialize();
+    }
+    let lines = match lines {
+        Ok(lines) if !lines.is_empty() => lines,
+        Ok(_) => return ClipboardTranslation { source: String::new(), result: Err(tr("文字が見つかりませんでした").to_string()) },
+        Err(e) => return ClipboardTranslation { source: String::new(), result: Err(format!("{:#}", e)) },
+    };
+    let source = lines.join("\n");
+    let result = match rt.block_on(translator.translate_batch(lines, source_lang, target_lang)) {
+        Ok(translations) if translations.iter().all(Option::is_none) => Err(tr("翻訳できませんでした").to_string()),
+        Ok(translations) => Ok(translations
+            .iter()
+            .map(|t| ruby::strip(t.as_deref().unwrap_or_default()))
+            .collect::<Vec<_>>()
+            .join("\n")),
+        Err(e) => Err(format!("{:#}", e)),
+    };
+    ClipboardTranslation { source, result }
+}
@@ -0,0 +1,44 @@
+//! Windows へのログイン時に起動する設定（HKCU の Run キー）
+
+use anyhow::{Context, Result};
+use windows::core::{w, PCWSTR};
+use windows::Win32::Foundation::ERROR_FILE_NOT_FOUND;
+use windows::Win32::System::Registry::*;
+
+const RUN_KEY: PCWSTR = w!("Software\\Microsoft\\Windows\\CurrentVersion\\Run");
+const VALUE_NAME: PCWSTR = w!("GameTranslator");
+
+/// ログイン時の起動で通知領域に隠して立ち上げるためのコマンドライン引数
+pub const MINIMIZED_ARG: &str = "--minimized";
+
+/// Run キーに登録・解除する。登録時は現在の実行ファイルのパスで上書きする。
+pub fn set_launch_at_login(enabled: bool, minimized: bool) -> Result<()> {
+    if !enabled {
+        let err = unsafe { RegDeleteKeyValueW(HKEY_CURRENT_USER, RUN_KEY, VALUE_NAME) };
+        // 未登録なら何もしなくてよい
+        if err == ERROR_FILE_NOT_FOUND {
+            return Ok(());
+        }
+        return err.ok().context("Failed to remove the Run key entry");
+    }
+
+    let exe = std::env::current_exe().context("Failed to get the executable path")?;
+    let mut command = format!("\"{}\"", exe.display());
+    if minimized {
+        command.push(' ');
+        command.push_str(MINIMIZED_ARG);
+    }
+    let wide: Vec<u16> = command.encode_utf16().chain(Some(0)).collect();
+    unsafe {
+        RegSetKeyValueW(
+            HKEY_CURRENT_USER,
+            RUN_KEY,
+            VALUE_NAME,
+            REG_SZ.0,
+            Some(wide.as_ptr().cast()),
+            (wide.len() * std::mem::size_of::<u16>()) as u32,
+        )
+    }
+    .ok()
+    .with_context(|| format!("Failed to write {} to the Run key", command))
+}
@@ -0,0 +1,121 @@
+//! クリックで対象ウィンドウを選ぶ
+//!
+//! カーソルを十字にして低レベルマウスフックでクリックを待ち、クリックした位置の
+//! トップレベルウィンドウを返す。クリックはゲームに届かないよう横取りする。右クリックで中止。
+
+use std::cell::Cell;
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+
+use eframe::egui;
+use windows::Win32::Foundation::*;
+use windows::Win32::System::Threading::GetCurrentThreadId;
+use windows::Win32::UI::WindowsAndMessaging::*;
+
+/// 十字にするシステムカーソル（テキストやリンクの上でも十字のままにする）
+const CROSSHAIR_CURSORS: [SYSTEM_CURSOR_ID; 3] = [OCR_NORMAL, OCR_IBEAM, OCR_HAND];
+
+thread_local! {
+    /// ボタンを押した時点の結果。離した時にループを抜ける（離す操作もゲームに渡さないため）
+    static PICKED: Cell<Option<Option<isize>>> = const { Cell::new(None) };
+}
+
+/// 選択待ちのスレッド。drop で中止してカーソルを戻す。
+pub struct WindowPicker {
+    thread_id: u32,
+    handle: Option<JoinHandle<()>>,
+    /// Some(Some(hwnd)) = 選択済み、Some(None) = 中止
+    result: Arc<Mutex<Option<Option<isize>>>>,
+}
+
+impl WindowPicker {
+    pub fn spawn(ctx: egui::Context) -> Self {
+        let result = Arc::new(Mutex::new(None));
+        let (id_tx, id_rx) = mpsc::channel();
+        let thread_result = result.clone();
+        let handle = std::thread::spawn(move || unsafe {
+            // メッセージキューを作ってからスレッドIDを渡す（WM_QUIT を確実に受け取るため）
+            let mut msg = MSG::default();
+            let _ = PeekMessageW(&mut msg, None, WM_USER, WM_USER, PM_NOREMOVE);
+            let _ = id_tx.send(GetCurrentThreadId());
+
+            let hook = match SetWindowsHookExW(WH_MOUSE_LL, Some(mouse_hook), None, 0) {
+                Ok(hook) => hook,
+                Err(e) => {
+                    crate::log_always(&format!("[PICK] マウスフックを設定できません: {}", e));
+                    *thread_result.lock().unwrap() = Some(None);
+                    ctx.request_repaint();
+                    return;
+                }
+            };
+            set_crosshair();
+            while GetMessageW(&mut msg, None, 0, 0).into() {
+                let _ = TranslateMessage(&msg);
+                DispatchMessageW(&msg);
+            }
+            let _ = UnhookWindowsHookEx(hook);
+            // システムカーソルを既定（ユーザーの設定）に戻す
+            let _ = SystemParametersInfoW(SPI_SETCURSORS, 0, None, SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS(0));
+
+            *thread_result.lock().unwrap() = Some(PICKED.get().flatten());
+            ctx.request_repaint();
+        });
+        Self {
+            thread_id: id_rx.recv().unwrap_or(0),
+            handle: Some(handle),
+            result,
+        }
+    }
+
+    /// 選択が終わっていれば結果（None = 中止）
+    pub fn take_result(&self) -> Option<Option<isize>> {
+        self.result.lock().unwrap().take()
+    }
+}
+
+impl Drop for WindowPicker {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = PostThreadMessageW(self.thread_id, WM_QUIT, WPARAM(0), LPARAM(0));
+        }
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+unsafe fn set_crosshair() {
+    let Ok(cross) = LoadCursorW(None, IDC_CROSS) else {
+        return;
+    };
+    for id in CROSSHAIR_CURSORS {
+        // SetSystemCursor は渡したカーソルを破棄するので、それぞれ複製を渡す
+        if let Ok(copy) = CopyIcon(HICON(cross.0)) {
+            let _ = SetSystemCursor(HCURSOR(copy.0), id);
+        }
+    }
+}
+
+unsafe extern "system" fn mouse_hook(code: i32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+    if code == HC_ACTION as i32 {
+        let info = &*(lparam.0 as *const MSLLHOOKSTRUCT);
+        match wparam.0 as u32 {
+            WM_LBUTTONDOWN => {
+                let hwnd = GetAncestor(WindowFromPoint(info.pt), GA_ROOT);
+                PICKED.set(Some((!hwnd.is_invalid()).then_some(hwnd.0 as isize)));
+                return LRESULT(1);
+            }
+            WM_RBUTTONDOWN => {
+                PICKED.set(Some(None));
+                return LRESULT(1);
+            }
+            WM_LBUTTONUP | WM_RBUTTONUP if PICKED.get().is_some() => {
+                PostQuitMessage(0);
+                return LRESULT(1);
+            }
+            _ => {}
+        }
+    }
+    CallNextHookEx(None, code, wparam, lparam)
+}